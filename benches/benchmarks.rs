@@ -24,6 +24,21 @@ fn gen_json_array(n: usize) -> String {
     format!("[{}]", entries.join(","))
 }
 
+/// Generate a JSON string representing an array of `n` objects of floats.
+fn gen_json_float_array(n: usize) -> String {
+    let mut entries = Vec::with_capacity(n);
+    for i in 0..n {
+        entries.push(format!(
+            r#"{{"lat":{:.6},"lon":{:.6},"price":{:.2},"ratio":{}}}"#,
+            (i as f64).sin() * 90.0,
+            (i as f64).cos() * 180.0,
+            i as f64 * 1.25 + 0.99,
+            1.0 / (i + 1) as f64
+        ));
+    }
+    format!("[{}]", entries.join(","))
+}
+
 /// Generate a CSV string with `n` rows (plus header).
 fn gen_csv(n: usize) -> String {
     let mut out = String::from("id,name,email,age,active\n");
@@ -98,6 +113,19 @@ fn bench_json_parse(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_json_parse_floats(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_json_floats");
+    for &count in &[100, 1_000, 10_000] {
+        let input = gen_json_float_array(count);
+        let size = input.len() as u64;
+        group.throughput(Throughput::Bytes(size));
+        group.bench_with_input(BenchmarkId::new("records", count), &input, |b, input| {
+            b.iter(|| parse_input(black_box(input), Format::Json).unwrap());
+        });
+    }
+    group.finish();
+}
+
 fn bench_csv_parse(c: &mut Criterion) {
     let mut group = c.benchmark_group("parse_csv");
     for &count in &[100, 1_000, 10_000] {
@@ -281,6 +309,7 @@ criterion_group!(
     benches,
     // Parsing
     bench_json_parse,
+    bench_json_parse_floats,
     bench_csv_parse,
     bench_yaml_parse,
     // Serialization
@@ -82,9 +82,31 @@ impl fmt::Display for Format {
 #[derive(Parser, Debug)]
 #[command(name = "morph", version, about = "Convert between data formats")]
 pub struct Cli {
-    /// Input file (reads from stdin if not specified)
-    #[arg(short = 'i', long = "input")]
-    pub input: Option<PathBuf>,
+    /// Input file (can be repeated with --merge or --concat; reads from stdin if not specified)
+    #[arg(short = 'i', long = "input", action = clap::ArgAction::Append)]
+    pub input: Vec<PathBuf>,
+
+    /// Merge multiple inputs into one document: deep (default) or shallow
+    #[arg(
+        long = "merge",
+        value_name = "MODE",
+        num_args = 0..=1,
+        default_missing_value = "deep",
+        conflicts_with = "concat"
+    )]
+    pub merge: Option<String>,
+
+    /// How --merge combines arrays: replace (default), append, or by-key:FIELD
+    #[arg(long = "merge-arrays", value_name = "STRATEGY", requires = "merge")]
+    pub merge_arrays: Option<String>,
+
+    /// Concatenate multiple inputs into one array of records
+    #[arg(long = "concat")]
+    pub concat: bool,
+
+    /// Add a `$source` field holding the input file name to each concatenated record
+    #[arg(long = "with-source", requires = "concat")]
+    pub with_source: bool,
 
     /// Output file (writes to stdout if not specified)
     #[arg(short = 'o', long = "output")]
@@ -122,6 +144,44 @@ pub struct Cli {
     #[arg(short = 'e', long = "expr", action = clap::ArgAction::Append)]
     pub expr: Vec<String>,
 
+    /// Load a lookup table for `join` statements (can be repeated)
+    #[arg(long = "lookup", value_name = "NAME=PATH", action = clap::ArgAction::Append)]
+    pub lookup: Vec<String>,
+
+    /// In --stream mode, let `distinct ... keep first` use a fixed-size
+    /// probabilistic set with this false-positive rate (e.g. 0.001)
+    #[arg(long = "distinct-fp-rate", value_name = "RATE")]
+    pub distinct_fp_rate: Option<f64>,
+
+    /// Number of distinct keys the --distinct-fp-rate set is sized for
+    #[arg(
+        long = "distinct-capacity",
+        value_name = "N",
+        default_value_t = 1_000_000,
+        requires = "distinct_fp_rate"
+    )]
+    pub distinct_capacity: usize,
+
+    /// Drop the first N records (applied after the mapping)
+    #[arg(long = "skip", value_name = "N")]
+    pub skip: Option<usize>,
+
+    /// Keep only the first N records (applied after the mapping and --skip)
+    #[arg(long = "limit", value_name = "N")]
+    pub limit: Option<usize>,
+
+    /// Keep only the last N records (applied after the mapping)
+    #[arg(long = "tail", value_name = "N")]
+    pub tail: Option<usize>,
+
+    /// Keep a uniform random sample of N records, in input order
+    #[arg(long = "sample", value_name = "N")]
+    pub sample: Option<usize>,
+
+    /// Seed for `sample` and the random functions so runs are reproducible
+    #[arg(long = "seed", value_name = "SEED")]
+    pub seed: Option<u64>,
+
     /// Parse and validate the mapping without executing
     #[arg(long = "dry-run")]
     pub dry_run: bool,
@@ -196,8 +256,45 @@ impl Cli {
         config
     }
 
+    /// Build the array strategy for `--merge` from the `--merge-arrays` flag.
+    pub fn array_merge(&self) -> crate::error::Result<crate::value::ArrayMerge> {
+        use crate::value::ArrayMerge;
+        match self.merge_arrays.as_deref() {
+            None | Some("replace") => Ok(ArrayMerge::Replace),
+            Some("append") => Ok(ArrayMerge::Append),
+            Some(other) => match other.strip_prefix("by-key:") {
+                Some(key) if !key.is_empty() => Ok(ArrayMerge::ByKey(key.to_string())),
+                _ => Err(crate::error::MorphError::cli(format!(
+                    "unknown array merge strategy: '{other}', expected replace, append, or by-key:FIELD"
+                ))),
+            },
+        }
+    }
+
     /// Resolve the input format from the `--from` flag or the input file extension.
+    ///
+    /// With several inputs this is the format of the first one.
     pub fn resolve_input_format(&self) -> crate::error::Result<Format> {
+        match self.input.first() {
+            Some(path) => self.input_format_for(path),
+            None => self.input_format_for_stdin(),
+        }
+    }
+
+    /// Resolve the format of one input file from `--from` or its extension.
+    pub fn input_format_for(&self, path: &std::path::Path) -> crate::error::Result<Format> {
+        if self.from.is_some() {
+            return self.input_format_for_stdin();
+        }
+        Format::from_path(path).ok_or_else(|| {
+            crate::error::MorphError::cli(format!(
+                "cannot detect format from '{}', use -f/--from to specify",
+                path.display()
+            ))
+        })
+    }
+
+    fn input_format_for_stdin(&self) -> crate::error::Result<Format> {
         if let Some(ref name) = self.from {
             return Format::from_name(name).ok_or_else(|| {
                 let mut msg = format!("unknown input format: '{name}'");
@@ -207,14 +304,6 @@ impl Cli {
                 crate::error::MorphError::cli(msg)
             });
         }
-        if let Some(ref path) = self.input {
-            return Format::from_path(path).ok_or_else(|| {
-                crate::error::MorphError::cli(format!(
-                    "cannot detect format from '{}', use -f/--from to specify",
-                    path.display()
-                ))
-            });
-        }
         Err(crate::error::MorphError::cli(
             "reading from stdin requires -f/--from to specify input format",
         ))
@@ -245,15 +334,10 @@ impl Cli {
     }
 }
 
-/// Read input data based on the CLI args.
+/// Read input data based on the CLI args (the first input when several are given).
 pub fn read_input(cli: &Cli) -> crate::error::Result<String> {
-    match &cli.input {
-        Some(path) => std::fs::read_to_string(path).map_err(|e| {
-            crate::error::MorphError::Io(std::io::Error::new(
-                e.kind(),
-                format!("{}: {e}", path.display()),
-            ))
-        }),
+    match cli.input.first() {
+        Some(path) => read_file(path),
         None => {
             use std::io::Read;
             let mut buf = String::new();
@@ -263,6 +347,86 @@ pub fn read_input(cli: &Cli) -> crate::error::Result<String> {
     }
 }
 
+fn read_file(path: &std::path::Path) -> crate::error::Result<String> {
+    std::fs::read_to_string(path).map_err(|e| {
+        crate::error::MorphError::Io(std::io::Error::new(
+            e.kind(),
+            format!("{}: {e}", path.display()),
+        ))
+    })
+}
+
+/// Read and parse every input, combining several according to `--merge` or
+/// `--concat`.
+pub fn read_inputs(cli: &Cli, in_fmt: Format) -> crate::error::Result<crate::value::Value> {
+    use crate::value::Value;
+
+    let combining = cli.merge.is_some() || cli.concat;
+    if cli.input.len() > 1 && !combining {
+        return Err(crate::error::MorphError::cli(
+            "multiple inputs require --merge or --concat",
+        ));
+    }
+    if !combining {
+        let input_data = read_input(cli)?;
+        return parse_input_with_cli(&input_data, in_fmt, Some(cli));
+    }
+
+    let mut documents = Vec::with_capacity(cli.input.len());
+    if cli.input.is_empty() {
+        let value = parse_input_with_cli(&read_input(cli)?, in_fmt, Some(cli))?;
+        documents.push(("-".to_string(), value));
+    }
+    for path in &cli.input {
+        let format = cli.input_format_for(path)?;
+        let value = parse_input_with_cli(&read_file(path)?, format, Some(cli))?;
+        documents.push((path.display().to_string(), value));
+    }
+
+    if cli.concat {
+        let mut records = Vec::new();
+        for (source, value) in documents {
+            let items = match value {
+                Value::Array(items) => items,
+                other => vec![other],
+            };
+            for mut item in items {
+                if let (true, Value::Map(map)) = (cli.with_source, &mut item) {
+                    map.insert("$source".to_string(), Value::String(source.clone()));
+                }
+                records.push(item);
+            }
+        }
+        return Ok(Value::Array(records));
+    }
+
+    let arrays = cli.array_merge()?;
+    let mut documents = documents.into_iter().map(|(_, value)| value);
+    let mut merged = documents.next().unwrap_or(Value::Null);
+    match cli.merge.as_deref() {
+        Some("deep") => {
+            for value in documents {
+                merged.merge_with(value, &arrays);
+            }
+        }
+        Some("shallow") => {
+            for value in documents {
+                match (&mut merged, value) {
+                    (Value::Map(lhs), Value::Map(rhs)) => lhs.extend(rhs),
+                    (this, other) => *this = other,
+                }
+            }
+        }
+        Some(other) => {
+            return Err(crate::error::MorphError::cli(format!(
+                "unknown merge mode: '{other}', expected deep or shallow"
+            )));
+        }
+        None => unreachable!("checked above"),
+    }
+    Ok(merged)
+}
+
 /// Parse input string according to format.
 pub fn parse_input(input: &str, format: Format) -> crate::error::Result<crate::value::Value> {
     parse_input_with_cli(input, format, None)
@@ -372,19 +536,29 @@ pub fn write_output(cli: &Cli, output: &str) -> crate::error::Result<()> {
     }
 }
 
-/// Build a combined mapping program from -m and -e flags.
+/// Build a combined mapping program from -m and -e flags, followed by the
+/// statements for --skip, --limit, --tail and --sample, together with the
+/// evaluation options from --lookup, --seed and the --distinct-* flags.
 /// Returns Ok(None) if no mapping flags were given.
 pub fn build_mapping_program(
     cli: &Cli,
-) -> crate::error::Result<Option<crate::mapping::ast::Program>> {
+) -> crate::error::Result<
+    Option<(
+        crate::mapping::ast::Program,
+        crate::mapping::eval::EvalOptions,
+    )>,
+> {
     let has_mapping = cli.mapping.is_some();
     let has_exprs = !cli.expr.is_empty();
+    let has_slicing =
+        cli.skip.is_some() || cli.limit.is_some() || cli.tail.is_some() || cli.sample.is_some();
 
-    if !has_mapping && !has_exprs {
+    if !has_mapping && !has_exprs && !has_slicing {
         return Ok(None);
     }
 
     let mut all_statements = Vec::new();
+    let mut on_error = crate::mapping::ast::OnError::default();
 
     // Load mapping file first
     if let Some(ref path) = cli.mapping {
@@ -395,18 +569,93 @@ pub fn build_mapping_program(
             ))
         })?;
         let program = crate::mapping::parser::parse_str(&source)?;
+        on_error = program.on_error;
         all_statements.extend(program.statements);
     }
 
-    // Then append inline expressions
+    // Then append inline expressions; an `on error` in one of them wins
     for expr_str in &cli.expr {
         let program = crate::mapping::parser::parse_str(expr_str)?;
+        if program.on_error != crate::mapping::ast::OnError::Fail {
+            on_error = program.on_error;
+        }
         all_statements.extend(program.statements);
     }
 
-    Ok(Some(crate::mapping::ast::Program {
+    all_statements.extend(slicing_statements(cli));
+
+    let mut lookups = crate::mapping::lookup::Lookups::new();
+    for spec in &cli.lookup {
+        let (name, table) = load_lookup(spec)?;
+        lookups.insert(name, table)?;
+    }
+    lookups.prepare(&all_statements)?;
+
+    let approx_distinct = cli
+        .distinct_fp_rate
+        .map(|rate| crate::mapping::distinct::ApproxDistinct::new(cli.distinct_capacity, rate))
+        .transpose()?;
+
+    let program = crate::mapping::ast::Program {
         statements: all_statements,
-    }))
+        ..Default::default()
+    };
+    let options = crate::mapping::eval::EvalOptions {
+        lookups,
+        approx_distinct,
+        seed: cli.seed,
+        on_error: Some(on_error),
+    };
+    Ok(Some((program, options)))
+}
+
+/// The statements equivalent to --skip, --limit, --tail and --sample, in
+/// that order.
+fn slicing_statements(cli: &Cli) -> Vec<crate::mapping::ast::Statement> {
+    use crate::mapping::ast::Statement;
+    let span = crate::mapping::lexer::Span::new(1, 1);
+    let mut statements = Vec::new();
+    if let Some(count) = cli.skip {
+        statements.push(Statement::Skip { count, span });
+    }
+    if let Some(count) = cli.limit {
+        statements.push(Statement::Limit { count, span });
+    }
+    if let Some(count) = cli.tail {
+        statements.push(Statement::Tail { count, span });
+    }
+    if let Some(count) = cli.sample {
+        statements.push(Statement::Sample {
+            count,
+            seed: None,
+            span,
+        });
+    }
+    statements
+}
+
+/// Load a `--lookup name=path` table, detecting its format from the extension.
+fn load_lookup(spec: &str) -> crate::error::Result<(String, crate::value::Value)> {
+    let (name, path) = spec
+        .split_once('=')
+        .filter(|(name, path)| !name.is_empty() && !path.is_empty())
+        .ok_or_else(|| {
+            crate::error::MorphError::cli(format!("invalid --lookup '{spec}', expected NAME=PATH"))
+        })?;
+    let path = std::path::Path::new(path);
+    let format = Format::from_path(path).ok_or_else(|| {
+        crate::error::MorphError::cli(format!(
+            "cannot detect format of lookup file '{}'",
+            path.display()
+        ))
+    })?;
+    let source = std::fs::read_to_string(path).map_err(|e| {
+        crate::error::MorphError::Io(std::io::Error::new(
+            e.kind(),
+            format!("{}: {e}", path.display()),
+        ))
+    })?;
+    Ok((name.to_string(), parse_input(&source, format)?))
 }
 
 /// Run the full pipeline based on CLI args.
@@ -447,17 +696,34 @@ pub fn run(cli: &Cli) -> crate::error::Result<()> {
     let in_fmt = cli.resolve_input_format()?;
     let out_fmt = cli.resolve_output_format()?;
 
-    // Streaming mode: process elements one at a time for large files
-    if cli.stream && crate::streaming::can_stream(in_fmt, out_fmt) {
-        return crate::streaming::run_streaming(cli, in_fmt, out_fmt, mapping_program.as_ref());
+    // Streaming mode: process elements one at a time for large files.
+    // --concat streams its inputs one after another; --merge needs every
+    // input in memory, so it cannot stream.
+    if cli.stream && cli.merge.is_some() {
+        return Err(crate::error::MorphError::cli(
+            "--stream cannot be used with --merge, which needs every input in memory",
+        ));
+    }
+    let mut streamable = crate::streaming::can_stream(in_fmt, out_fmt);
+    if cli.stream {
+        for path in &cli.input {
+            streamable &= crate::streaming::can_stream(cli.input_format_for(path)?, out_fmt);
+        }
+    }
+    if cli.stream && streamable {
+        let mapping = mapping_program
+            .as_ref()
+            .map(|(program, options)| (program, options));
+        return crate::streaming::run_streaming(cli, in_fmt, out_fmt, mapping);
     }
 
-    let input_data = read_input(cli)?;
-    let value = parse_input_with_cli(&input_data, in_fmt, Some(cli))?;
+    let value = read_inputs(cli, in_fmt)?;
 
     // Apply mapping if present
     let value = match mapping_program {
-        Some(ref program) => crate::mapping::eval::eval(program, &value)?,
+        Some((ref program, ref options)) => {
+            crate::mapping::eval::eval_with(program, options, &value)?
+        }
         None => value,
     };
 
@@ -592,11 +858,99 @@ fn print_functions() {
                 ),
             ],
         ),
+        (
+            "Case conversion",
+            &[
+                (
+                    "snake_case(value)",
+                    "string → string",
+                    "Convert to snake_case",
+                ),
+                (
+                    "camel_case(value)",
+                    "string → string",
+                    "Convert to camelCase",
+                ),
+                (
+                    "kebab_case(value)",
+                    "string → string",
+                    "Convert to kebab-case",
+                ),
+                (
+                    "pascal_case(value)",
+                    "string → string",
+                    "Convert to PascalCase",
+                ),
+                (
+                    "screaming_snake_case(value)",
+                    "string → string",
+                    "Convert to SCREAMING_SNAKE_CASE",
+                ),
+                (
+                    "title_case(value)",
+                    "string → string",
+                    "Capitalize each word",
+                ),
+                ("slugify(value)", "string → string", "URL-friendly slug"),
+            ],
+        ),
+        (
+            "Formatting",
+            &[
+                (
+                    "pad_left(value, width, [fill])",
+                    "string → string",
+                    "Pad on the left to width",
+                ),
+                (
+                    "pad_right(value, width, [fill])",
+                    "string → string",
+                    "Pad on the right to width",
+                ),
+                (
+                    "truncate(value, n, [ellipsis])",
+                    "string → string",
+                    "Cut to at most n characters",
+                ),
+                (
+                    "wrap(value, width)",
+                    "string → string",
+                    "Break lines between words",
+                ),
+                ("nfc(value)", "string → string", "Unicode NFC normalization"),
+                ("nfd(value)", "string → string", "Unicode NFD normalization"),
+                (
+                    "nfkc(value)",
+                    "string → string",
+                    "Unicode NFKC normalization",
+                ),
+                (
+                    "nfkd(value)",
+                    "string → string",
+                    "Unicode NFKD normalization",
+                ),
+                (
+                    "format_number(n, pattern, [locale])",
+                    "number → string",
+                    "Format with a pattern",
+                ),
+                (
+                    "parse_number(value, [locale])",
+                    "string → number",
+                    "Read locale-formatted number",
+                ),
+            ],
+        ),
         (
             "Type conversion",
             &[
                 ("to_int(value)", "any → int", "Convert to integer"),
                 ("to_float(value)", "any → float", "Convert to float"),
+                (
+                    "to_decimal(value)",
+                    "any → decimal",
+                    "Convert to an exact decimal",
+                ),
                 ("to_string(value)", "any → string", "Convert to string"),
                 ("to_bool(value)", "any → bool", "Convert to boolean"),
                 ("type_of(value)", "any → string", "Get type name"),
@@ -636,6 +990,217 @@ fn print_functions() {
                     "array → map",
                     "Group array elements by key",
                 ),
+                ("count(array)", "array → int", "Number of non-null elements"),
+                ("avg(array)", "array → number", "Mean of non-null numbers"),
+                ("median(array)", "array → number", "Middle value"),
+                (
+                    "percentile(array, p)",
+                    "array → number",
+                    "p-th percentile, interpolated",
+                ),
+                (
+                    "flatten(array, [depth])",
+                    "array → array",
+                    "Splice nested arrays",
+                ),
+                ("zip(a, b, ...)", "array → array", "Pair up elements"),
+                (
+                    "chunk(array, n)",
+                    "array → array",
+                    "Split into arrays of n elements",
+                ),
+                (
+                    "range([start], end, [step])",
+                    "int → array",
+                    "Integers from start up to end",
+                ),
+                (
+                    "slice(value, start, [end])",
+                    "string|array → same",
+                    "Part of a string or array",
+                ),
+                (
+                    "index_of(value, needle)",
+                    "string|array → int",
+                    "Position of a match, or null",
+                ),
+                (
+                    "entries(map)",
+                    "map → array",
+                    "[{key, value}, ...] for each field",
+                ),
+                (
+                    "from_entries(array)",
+                    "array → map",
+                    "Build a map from entries or pairs",
+                ),
+                (
+                    "merge(a, b, ...)",
+                    "map → map",
+                    "Deep-merge maps; later maps win",
+                ),
+                (
+                    "pick(map, keys...)",
+                    "map → map",
+                    "Keep only the named fields",
+                ),
+                ("omit(map, keys...)", "map → map", "Drop the named fields"),
+            ],
+        ),
+        (
+            "Higher-order",
+            &[
+                (
+                    "map(array, x => expr)",
+                    "array → array",
+                    "Transform each element",
+                ),
+                (
+                    "filter(array, x => cond)",
+                    "array → array",
+                    "Keep elements where cond is truthy",
+                ),
+                (
+                    "reduce(array, (acc, x) => expr, [init])",
+                    "array → any",
+                    "Fold elements into one value",
+                ),
+                (
+                    "any(array, x => cond)",
+                    "array → bool",
+                    "True if any element matches",
+                ),
+                (
+                    "all(array, x => cond)",
+                    "array → bool",
+                    "True if every element matches",
+                ),
+                (
+                    "find(array, x => cond)",
+                    "array → any",
+                    "First matching element",
+                ),
+                (
+                    "sort_by(array, x => key)",
+                    "array → array",
+                    "Stable sort by key",
+                ),
+                (
+                    "flat_map(array, x => expr)",
+                    "array → array",
+                    "Map and flatten one level",
+                ),
+                (
+                    "group_by(array, x => key)",
+                    "array → map",
+                    "Group elements by computed key",
+                ),
+            ],
+        ),
+        (
+            "Hashing and encoding",
+            &[
+                (
+                    "sha256(value)",
+                    "string|bytes → string",
+                    "SHA-256 digest as lowercase hex",
+                ),
+                (
+                    "sha1(value)",
+                    "string|bytes → string",
+                    "SHA-1 digest as lowercase hex",
+                ),
+                (
+                    "md5(value)",
+                    "string|bytes → string",
+                    "MD5 digest as lowercase hex",
+                ),
+                (
+                    "hmac_sha256(key, value)",
+                    "string → string",
+                    "Keyed HMAC-SHA-256 as lowercase hex",
+                ),
+                (
+                    "base64_encode(value)",
+                    "string|bytes → string",
+                    "Encode as base64",
+                ),
+                (
+                    "base64_decode(value)",
+                    "string → bytes",
+                    "Decode standard or URL-safe base64",
+                ),
+                ("hex(value)", "string|bytes → string", "Hex-encode"),
+                ("url_encode(value)", "string → string", "Percent-encode"),
+                (
+                    "url_decode(value)",
+                    "string → string",
+                    "Decode percent-encoding",
+                ),
+                ("uuid_v4()", "→ string", "Random UUID"),
+                (
+                    "uuid_v5(namespace, name)",
+                    "string → string",
+                    "Name-based UUID",
+                ),
+            ],
+        ),
+        (
+            "Embedded documents",
+            &[
+                ("parse_json(value)", "string → any", "Parse a JSON string"),
+                ("parse_jsonl(value)", "string → any", "Parse JSON lines"),
+                ("parse_yaml(value)", "string → any", "Parse a YAML string"),
+                ("parse_toml(value)", "string → any", "Parse a TOML string"),
+                ("parse_csv(value)", "string → any", "Parse CSV text"),
+                ("parse_xml(value)", "string → any", "Parse an XML string"),
+                ("parse_msgpack(value)", "string → any", "Parse MessagePack"),
+                (
+                    "parse_format(value, fmt)",
+                    "string → any",
+                    "Parse with a named format",
+                ),
+                (
+                    "to_json(value, [pretty])",
+                    "any → string",
+                    "Serialize as JSON",
+                ),
+                ("to_jsonl(value)", "any → string", "Serialize as JSON lines"),
+                ("to_yaml(value)", "any → string", "Serialize as YAML"),
+                ("to_toml(value)", "any → string", "Serialize as TOML"),
+                ("to_csv(value)", "any → string", "Serialize as CSV"),
+                ("to_xml(value)", "any → string", "Serialize as XML"),
+                (
+                    "to_msgpack(value)",
+                    "any → string",
+                    "Serialize as MessagePack",
+                ),
+                (
+                    "to_format(value, fmt, [pretty])",
+                    "any → string",
+                    "Serialize with a named format",
+                ),
+            ],
+        ),
+        (
+            "Random",
+            &[
+                (
+                    "random_int(lo, hi)",
+                    "int → int",
+                    "Integer between lo and hi, inclusive",
+                ),
+                ("random_choice(array)", "array → any", "Random element"),
+                (
+                    "shuffle(array)",
+                    "array → array",
+                    "Elements in random order",
+                ),
+                (
+                    "fake(kind)",
+                    "string → any",
+                    "Synthetic value such as \"email\"",
+                ),
             ],
         ),
         (
@@ -646,6 +1211,14 @@ fn print_functions() {
                 "Conditional expression",
             )],
         ),
+        (
+            "Error handling",
+            &[(
+                "try(expr, [fallback])",
+                "any → any",
+                "Value of expr, or fallback (null) if it fails",
+            )],
+        ),
     ];
 
     for (category, funcs) in categories {
@@ -741,7 +1314,7 @@ mod tests {
     #[test]
     fn arg_parsing_basic() {
         let cli = Cli::try_parse_from(["morph", "-i", "input.json", "-o", "output.yaml"]).unwrap();
-        assert_eq!(cli.input, Some(PathBuf::from("input.json")));
+        assert_eq!(cli.input, vec![PathBuf::from("input.json")]);
         assert_eq!(cli.output, Some(PathBuf::from("output.yaml")));
     }
 
@@ -948,7 +1521,7 @@ mod tests {
         .unwrap();
         let program = build_mapping_program(&cli).unwrap();
         assert!(program.is_some());
-        assert_eq!(program.unwrap().statements.len(), 1);
+        assert_eq!(program.unwrap().0.statements.len(), 1);
     }
 
     #[test]
@@ -967,7 +1540,7 @@ mod tests {
         .unwrap();
         let program = build_mapping_program(&cli).unwrap();
         assert!(program.is_some());
-        assert_eq!(program.unwrap().statements.len(), 2);
+        assert_eq!(program.unwrap().0.statements.len(), 2);
     }
 
     #[test]
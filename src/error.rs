@@ -339,10 +339,62 @@ pub fn suggest_function(input: &str) -> Option<String> {
         "split",
         "join",
         "reverse",
+        "snake_case",
+        "camel_case",
+        "kebab_case",
+        "pascal_case",
+        "screaming_snake_case",
+        "title_case",
+        "slugify",
+        "pad_left",
+        "lpad",
+        "pad_right",
+        "rpad",
+        "truncate",
+        "wrap",
+        "nfc",
+        "nfd",
+        "nfkc",
+        "nfkd",
+        "format_number",
+        "parse_number",
+        "sha256",
+        "sha1",
+        "md5",
+        "hmac_sha256",
+        "base64_encode",
+        "base64_decode",
+        "hex",
+        "url_encode",
+        "url_decode",
+        "uuid_v4",
+        "random_int",
+        "random_choice",
+        "shuffle",
+        "fake",
+        "uuid_v5",
+        "parse_json",
+        "parse_jsonl",
+        "parse_yaml",
+        "parse_toml",
+        "parse_csv",
+        "parse_xml",
+        "parse_msgpack",
+        "parse_format",
+        "to_json",
+        "to_jsonl",
+        "to_yaml",
+        "to_toml",
+        "to_csv",
+        "to_xml",
+        "to_msgpack",
+        "to_format",
         "to_int",
         "int",
         "to_float",
         "float",
+        "to_decimal",
+        "decimal",
         "to_string",
         "string",
         "str",
@@ -368,7 +420,32 @@ pub fn suggest_function(input: &str) -> Option<String> {
         "sum",
         "group_by",
         "groupby",
+        "zip",
+        "chunk",
+        "range",
+        "slice",
+        "index_of",
+        "entries",
+        "from_entries",
+        "merge",
+        "pick",
+        "omit",
+        "flatten",
+        "count",
+        "avg",
+        "mean",
+        "median",
+        "percentile",
         "if",
+        "try",
+        "map",
+        "filter",
+        "reduce",
+        "any",
+        "all",
+        "find",
+        "sort_by",
+        "flat_map",
     ];
     suggest_closest(input, &known, 3)
 }
@@ -224,7 +224,7 @@ pub fn to_string_with_config(value: &Value, config: &CsvConfig) -> error::Result
 }
 
 /// Try to parse a CSV field into the most specific type.
-fn parse_csv_field(field: &str) -> Value {
+pub(crate) fn parse_csv_field(field: &str) -> Value {
     if field.is_empty() {
         return Value::String(String::new());
     }
@@ -241,6 +241,10 @@ fn parse_csv_field(field: &str) -> Value {
         return Value::Int(i);
     }
 
+    if let Some(d) = super::exact_number(field) {
+        return Value::Decimal(d);
+    }
+
     // Try float
     if let Ok(f) = field.parse::<f64>() {
         return Value::Float(f);
@@ -249,7 +253,7 @@ fn parse_csv_field(field: &str) -> Value {
     Value::String(field.to_string())
 }
 
-fn csv_field_to_string(value: &Value) -> String {
+pub(crate) fn csv_field_to_string(value: &Value) -> String {
     match value {
         Value::Null => String::new(),
         Value::Bool(b) => b.to_string(),
@@ -269,8 +273,10 @@ fn csv_field_to_string(value: &Value) -> String {
                 format!("{s}.0")
             }
         }
+        Value::Decimal(d) => crate::value::decimal_to_text(d),
         Value::String(s) => s.clone(),
-        Value::Bytes(b) => format!("{b:?}"),
+        Value::Bytes(b) => super::bytes_to_base64(b),
+        Value::DateTime(dt) => dt.to_string(),
         Value::Array(_) | Value::Map(_) => {
             // Flatten complex types to JSON for CSV cells
             serde_json::to_string(&crate::formats::json::to_string(value).unwrap_or_default())
@@ -545,6 +551,23 @@ mod tests {
         assert_eq!(row.get_path(".val"), Some(&Value::Bool(false)));
     }
 
+    #[test]
+    fn type_detection_exact_numbers() {
+        let input = "id,amount,price\n12345678901234567890,0.10000000000000000001,19.99\n";
+        let val = from_str(input).unwrap();
+        let row = match &val {
+            Value::Array(a) => &a[0],
+            _ => panic!("expected array"),
+        };
+        assert_eq!(
+            row.get_path(".id"),
+            Some(&Value::Decimal("12345678901234567890".parse().unwrap()))
+        );
+        assert!(matches!(row.get_path(".amount"), Some(Value::Decimal(_))));
+        assert_eq!(row.get_path(".price"), Some(&Value::Float(19.99)));
+        assert_eq!(to_string(&val).unwrap(), input);
+    }
+
     // -----------------------------------------------------------------------
     // Empty fields: empty string representation
     // -----------------------------------------------------------------------
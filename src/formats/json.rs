@@ -35,16 +35,7 @@ pub(crate) fn json_to_value(json: serde_json::Value) -> Value {
     match json {
         serde_json::Value::Null => Value::Null,
         serde_json::Value::Bool(b) => Value::Bool(b),
-        serde_json::Value::Number(n) => {
-            if let Some(i) = n.as_i64() {
-                Value::Int(i)
-            } else if let Some(f) = n.as_f64() {
-                Value::Float(f)
-            } else {
-                // Fallback – shouldn't happen for normal JSON
-                Value::String(n.to_string())
-            }
-        }
+        serde_json::Value::Number(n) => number_to_value(&n),
         serde_json::Value::String(s) => Value::String(s),
         serde_json::Value::Array(arr) => Value::Array(arr.into_iter().map(json_to_value).collect()),
         serde_json::Value::Object(obj) => {
@@ -57,6 +48,24 @@ pub(crate) fn json_to_value(json: serde_json::Value) -> Value {
     }
 }
 
+/// Numbers keep their exact value: integers outside the `i64` range, and
+/// decimals that no `f64` represents exactly, become `Value::Decimal`.
+fn number_to_value(n: &serde_json::Number) -> Value {
+    if let Some(i) = n.as_i64() {
+        Value::Int(i)
+    } else if let Some(d) = super::exact_number(n.as_str()) {
+        Value::Decimal(d)
+    } else if let Some(f) = n.as_f64() {
+        Value::Float(f)
+    } else if let Ok(f) = n.as_str().parse::<f64>() {
+        // Past the float range (`1e400`), as the CSV reader does.
+        Value::Float(f)
+    } else {
+        // Fallback – shouldn't happen for normal JSON
+        Value::String(n.to_string())
+    }
+}
+
 pub(crate) fn value_to_json(value: &Value) -> serde_json::Value {
     match value {
         Value::Null => serde_json::Value::Null,
@@ -64,15 +73,12 @@ pub(crate) fn value_to_json(value: &Value) -> serde_json::Value {
         Value::Int(i) => serde_json::Value::Number((*i).into()),
         Value::Float(f) => serde_json::Number::from_f64(*f)
             .map_or(serde_json::Value::Null, serde_json::Value::Number),
+        Value::Decimal(d) => crate::value::decimal_to_text(d)
+            .parse()
+            .map_or(serde_json::Value::Null, serde_json::Value::Number),
         Value::String(s) => serde_json::Value::String(s.clone()),
-        Value::Bytes(b) => {
-            // Represent bytes as an array of numbers in JSON
-            serde_json::Value::Array(
-                b.iter()
-                    .map(|byte| serde_json::Value::from(*byte))
-                    .collect(),
-            )
-        }
+        Value::Bytes(b) => serde_json::Value::String(super::bytes_to_base64(b)),
+        Value::DateTime(dt) => serde_json::Value::String(dt.to_string()),
         Value::Array(arr) => serde_json::Value::Array(arr.iter().map(value_to_json).collect()),
         Value::Map(map) => {
             let mut obj = serde_json::Map::new();
@@ -194,6 +200,52 @@ mod tests {
 
     // -- Float precision --
 
+    #[test]
+    fn integers_beyond_i64_read_as_decimal() {
+        let input = "12345678901234567890";
+        let val = from_str(input).unwrap();
+        assert_eq!(val, Value::Decimal(input.parse().unwrap()));
+        assert_eq!(to_string(&val).unwrap(), input);
+    }
+
+    #[test]
+    fn inexact_floats_keep_their_digits() {
+        let input = r#"{"a":0.1,"b":1.00000000000000000001,"c":1e400}"#;
+        let val = from_str(input).unwrap();
+        assert_eq!(val.get_path(".a"), Some(&Value::Float(0.1)));
+        assert!(matches!(val.get_path(".b"), Some(Value::Decimal(_))));
+        assert!(matches!(val.get_path(".c"), Some(Value::Decimal(_))));
+        assert_eq!(
+            to_string(&val).unwrap(),
+            r#"{"a":0.1,"b":1.00000000000000000001,"c":1e+400}"#
+        );
+    }
+
+    #[test]
+    fn ordinary_floats_stay_floats() {
+        let input = r#"[1.5,-0.25,12.3456789,2.5e-8,6.02e19,0.0,-1e-19,0.1234567890123456]"#;
+        let Value::Array(items) = from_str(input).unwrap() else {
+            panic!("expected array");
+        };
+        let expected = [
+            1.5,
+            -0.25,
+            12.3456789,
+            2.5e-8,
+            6.02e19,
+            0.0,
+            -1e-19,
+            0.1234567890123456,
+        ];
+        for (item, want) in items.iter().zip(expected) {
+            assert_eq!(item, &Value::Float(want));
+        }
+        assert!(matches!(
+            from_str("0.12345678901234567").unwrap(),
+            Value::Decimal(_)
+        ));
+    }
+
     #[test]
     fn float_precision_preserved() {
         let input = "3.14159265358979";
@@ -381,4 +433,12 @@ mod tests {
         let val = from_str("-1.5").unwrap();
         assert_eq!(val, Value::Float(-1.5));
     }
+
+    // -- Bytes --
+
+    #[test]
+    fn bytes_serialize_as_base64() {
+        let out = to_string(&Value::Bytes(b"hi\xff".to_vec())).unwrap();
+        assert_eq!(out, "\"aGn/\"");
+    }
 }
@@ -5,3 +5,76 @@ pub mod msgpack;
 pub mod toml;
 pub mod xml;
 pub mod yaml;
+
+use crate::value::{
+    decimal_to_f64_exact, decimal_to_i64, decimal_to_text, parse_decimal, BigDecimal, Value,
+};
+
+/// The text form of `Value::Bytes` in formats with no binary type: standard,
+/// padded base64.
+pub(crate) fn bytes_to_base64(bytes: &[u8]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+/// Numeric text that neither `i64` nor `f64` holds exactly — an integer past
+/// the `i64` range, or digits a float would round — as an exact decimal.
+/// Text whose exponent implies more than
+/// [`MAX_DECIMAL_PADDING`](crate::value::MAX_DECIMAL_PADDING) zeros is
+/// left to the float parser.
+pub(crate) fn exact_number(text: &str) -> Option<BigDecimal> {
+    if fits_natively(text) {
+        return None;
+    }
+    let decimal = parse_decimal(text)?;
+    let fits = if text.contains(['.', 'e', 'E']) {
+        decimal_to_f64_exact(&decimal).is_some()
+    } else {
+        decimal_to_i64(&decimal).is_some()
+    };
+    (!fits).then_some(decimal)
+}
+
+/// The quick answer for most numbers, with no decimal built: text that is
+/// an `i64`, or a float with at most 15 significant digits between `1e-20`
+/// and `1e20`. Any decimal that short reads back unchanged from the nearest
+/// float. Other magnitudes take the exact check, which may keep them as
+/// decimals written in scientific notation.
+fn fits_natively(text: &str) -> bool {
+    if !text.contains(['.', 'e', 'E']) {
+        return text.parse::<i64>().is_ok();
+    }
+    let mantissa = text
+        .trim_start_matches(['+', '-'])
+        .split(['e', 'E'])
+        .next()
+        .unwrap_or_default();
+    let (mut first, mut last, mut count) = (None, 0, 0);
+    for b in mantissa.bytes().filter(|&b| b != b'.') {
+        if !b.is_ascii_digit() {
+            return false;
+        }
+        if b != b'0' {
+            first.get_or_insert(count);
+            last = count;
+        }
+        count += 1;
+    }
+    let significant = first.map_or(0, |first| last - first + 1);
+    significant <= 15
+        && text
+            .parse::<f64>()
+            .is_ok_and(|f| f == 0.0 || (1e-20..1e20).contains(&f.abs()))
+}
+
+/// `Value::Decimal` in formats whose numbers are `i64` or `f64`: the native
+/// number when the conversion is lossless, otherwise the exact decimal text.
+pub(crate) fn decimal_to_native(d: &BigDecimal) -> Value {
+    if let Some(i) = decimal_to_i64(d) {
+        Value::Int(i)
+    } else if let Some(f) = decimal_to_f64_exact(d) {
+        Value::Float(f)
+    } else {
+        Value::String(decimal_to_text(d))
+    }
+}
@@ -1,5 +1,6 @@
 use crate::error;
-use crate::value::Value;
+use crate::value::{DateTime, Value};
+use bigdecimal::ToPrimitive;
 use indexmap::IndexMap;
 use std::io::Read;
 
@@ -86,6 +87,47 @@ fn hex_nibble(b: u8) -> Option<u8> {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Timestamp extension
+// ---------------------------------------------------------------------------
+
+/// The extension type MessagePack reserves for timestamps.
+const TIMESTAMP_EXT: i8 = -1;
+
+/// Decode a timestamp extension payload (32, 64 or 96 bit) into a UTC
+/// instant.
+fn decode_timestamp(data: &[u8]) -> Option<DateTime> {
+    let (secs, nanos) = match data.len() {
+        4 => (i64::from(u32::from_be_bytes(data.try_into().ok()?)), 0),
+        8 => {
+            let packed = u64::from_be_bytes(data.try_into().ok()?);
+            ((packed & 0x3_ffff_ffff) as i64, (packed >> 34) as u32)
+        }
+        12 => (
+            i64::from_be_bytes(data[4..].try_into().ok()?),
+            u32::from_be_bytes(data[..4].try_into().ok()?),
+        ),
+        _ => return None,
+    };
+    let utc = chrono::DateTime::from_timestamp(secs, nanos)?;
+    Some(DateTime::Offset(utc.fixed_offset()))
+}
+
+/// Encode an instant in the smallest timestamp format that holds it.
+fn encode_timestamp(secs: i64, nanos: u32) -> Vec<u8> {
+    if secs >> 34 == 0 {
+        if nanos == 0 && secs <= i64::from(u32::MAX) {
+            return (secs as u32).to_be_bytes().to_vec();
+        }
+        return ((u64::from(nanos) << 34) | secs as u64)
+            .to_be_bytes()
+            .to_vec();
+    }
+    let mut data = nanos.to_be_bytes().to_vec();
+    data.extend_from_slice(&secs.to_be_bytes());
+    data
+}
+
 // ---------------------------------------------------------------------------
 // rmpv ↔ Value conversions
 // ---------------------------------------------------------------------------
@@ -98,8 +140,7 @@ fn rmpv_to_value(v: rmpv::Value) -> Value {
             if let Some(n) = i.as_i64() {
                 Value::Int(n)
             } else if let Some(n) = i.as_u64() {
-                // u64 values that don't fit in i64 – store as float to avoid data loss
-                Value::Float(n as f64)
+                Value::Decimal(n.into())
             } else {
                 Value::Int(0)
             }
@@ -128,6 +169,11 @@ fn rmpv_to_value(v: rmpv::Value) -> Value {
             Value::Map(map)
         }
         rmpv::Value::Ext(type_id, data) => {
+            if type_id == TIMESTAMP_EXT {
+                if let Some(dt) = decode_timestamp(&data) {
+                    return Value::DateTime(dt);
+                }
+            }
             // Store extension types as a map with metadata
             let mut map = IndexMap::new();
             map.insert("_ext_type".to_string(), Value::Int(type_id as i64));
@@ -143,8 +189,18 @@ fn value_to_rmpv(value: &Value) -> rmpv::Value {
         Value::Bool(b) => rmpv::Value::Boolean(*b),
         Value::Int(i) => rmpv::Value::Integer((*i).into()),
         Value::Float(f) => rmpv::Value::F64(*f),
+        Value::Decimal(d) => match d.to_u64().filter(|_| d.is_integer()) {
+            Some(n) => rmpv::Value::Integer(n.into()),
+            None => value_to_rmpv(&super::decimal_to_native(d)),
+        },
         Value::String(s) => rmpv::Value::String(s.clone().into()),
         Value::Bytes(b) => rmpv::Value::Binary(b.clone()),
+        Value::DateTime(DateTime::Offset(dt)) => rmpv::Value::Ext(
+            TIMESTAMP_EXT,
+            encode_timestamp(dt.timestamp(), dt.timestamp_subsec_nanos()),
+        ),
+        // The timestamp extension only holds instants.
+        Value::DateTime(dt) => rmpv::Value::String(dt.to_string().into()),
         Value::Array(arr) => rmpv::Value::Array(arr.iter().map(value_to_rmpv).collect()),
         Value::Map(map) => {
             let entries: Vec<(rmpv::Value, rmpv::Value)> = map
@@ -288,6 +344,76 @@ mod tests {
         assert_eq!(val, val2);
     }
 
+    // -----------------------------------------------------------------------
+    // Timestamps
+    // -----------------------------------------------------------------------
+
+    fn datetime(text: &str) -> Value {
+        Value::DateTime(DateTime::parse(text).unwrap())
+    }
+
+    #[test]
+    fn roundtrip_timestamps_in_each_width() {
+        for (text, width) in [
+            ("2024-01-15T10:30:00Z", 4),
+            ("2024-01-15T10:30:00.123456789Z", 8),
+            ("2600-01-01T00:00:00Z", 12),
+            ("1960-01-01T00:00:00.5Z", 12),
+        ] {
+            let val = datetime(text);
+            let bytes = to_bytes(&val).unwrap();
+            // fixext/ext header, type byte, then the payload
+            assert_eq!(
+                bytes[bytes.len() - width - 1] as i8,
+                TIMESTAMP_EXT,
+                "{text}"
+            );
+            assert_eq!(from_bytes(&bytes).unwrap(), val, "{text}");
+        }
+    }
+
+    #[test]
+    fn decode_timestamp_32() {
+        // fixext 4, type -1, 1 second after the epoch
+        let val = from_bytes(&[0xd6, 0xff, 0, 0, 0, 1]).unwrap();
+        assert_eq!(val, datetime("1970-01-01T00:00:01Z"));
+    }
+
+    #[test]
+    fn local_datetime_written_as_string() {
+        let bytes = to_bytes(&datetime("2024-01-15")).unwrap();
+        assert_eq!(
+            from_bytes(&bytes).unwrap(),
+            Value::String("2024-01-15".into())
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // Decimals
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn u64_beyond_i64_roundtrips_exactly() {
+        let val = Value::Decimal(u64::MAX.into());
+        let bytes = to_bytes(&val).unwrap();
+        assert_eq!(bytes[0], 0xcf, "written as uint 64");
+        assert_eq!(from_bytes(&bytes).unwrap(), val);
+    }
+
+    #[test]
+    fn decimal_fallbacks() {
+        let exact = Value::Decimal("2.5".parse().unwrap());
+        assert_eq!(
+            from_bytes(&to_bytes(&exact).unwrap()).unwrap(),
+            Value::Float(2.5)
+        );
+        let long = Value::Decimal("0.10000000000000000001".parse().unwrap());
+        assert_eq!(
+            from_bytes(&to_bytes(&long).unwrap()).unwrap(),
+            Value::String("0.10000000000000000001".into())
+        );
+    }
+
     // -----------------------------------------------------------------------
     // Nested structures
     // -----------------------------------------------------------------------
@@ -1,5 +1,5 @@
 use crate::error;
-use crate::value::Value;
+use crate::value::{DateTime, Value};
 use indexmap::IndexMap;
 use std::io::Read;
 
@@ -39,7 +39,10 @@ fn toml_to_value(t: toml::Value) -> Value {
         toml::Value::Integer(i) => Value::Int(i),
         toml::Value::Float(f) => Value::Float(f),
         toml::Value::Boolean(b) => Value::Bool(b),
-        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Datetime(dt) => {
+            let text = dt.to_string();
+            DateTime::parse(&text).map_or(Value::String(text), Value::DateTime)
+        }
         toml::Value::Array(arr) => Value::Array(arr.into_iter().map(toml_to_value).collect()),
         toml::Value::Table(table) => {
             let mut map = IndexMap::new();
@@ -57,14 +60,15 @@ fn value_to_toml(value: &Value) -> Option<toml::Value> {
         Value::Bool(b) => Some(toml::Value::Boolean(*b)),
         Value::Int(i) => Some(toml::Value::Integer(*i)),
         Value::Float(f) => Some(toml::Value::Float(*f)),
+        Value::Decimal(d) => value_to_toml(&super::decimal_to_native(d)),
         Value::String(s) => Some(toml::Value::String(s.clone())),
-        Value::Bytes(b) => {
-            // Represent as array of ints
-            Some(toml::Value::Array(
-                b.iter()
-                    .map(|byte| toml::Value::Integer(*byte as i64))
-                    .collect(),
-            ))
+        Value::Bytes(b) => Some(toml::Value::String(super::bytes_to_base64(b))),
+        Value::DateTime(dt) => {
+            let text = dt.to_string();
+            Some(
+                text.parse()
+                    .map_or(toml::Value::String(text), toml::Value::Datetime),
+            )
         }
         Value::Array(arr) => {
             let toml_arr: Vec<toml::Value> = arr.iter().filter_map(value_to_toml).collect();
@@ -262,58 +266,65 @@ age = 25
     }
 
     // -----------------------------------------------------------------------
-    // TOML datetime → string conversion and back
+    // TOML datetime → DateTime and back
     // -----------------------------------------------------------------------
 
-    #[test]
-    fn datetime_to_string() {
-        let input = "dt = 2024-01-15T10:30:00Z\n";
-        let val = from_str(input).unwrap();
-        match val.get_path(".dt") {
-            Some(Value::String(s)) => {
-                assert!(s.contains("2024"));
-                assert!(s.contains("01"));
-                assert!(s.contains("15"));
-            }
-            other => panic!("expected string datetime, got: {other:?}"),
+    fn datetime_at(input: &str, path: &str) -> DateTime {
+        match from_str(input).unwrap().get_path(path) {
+            Some(Value::DateTime(dt)) => *dt,
+            other => panic!("expected datetime, got: {other:?}"),
         }
     }
 
+    #[test]
+    fn offset_datetime() {
+        let dt = datetime_at("dt = 2024-01-15T10:30:00-05:00\n", ".dt");
+        assert!(matches!(dt, DateTime::Offset(_)));
+        assert_eq!(dt.to_string(), "2024-01-15T10:30:00-05:00");
+    }
+
     #[test]
     fn local_datetime() {
-        let input = "dt = 2024-01-15T10:30:00\n";
-        let val = from_str(input).unwrap();
-        match val.get_path(".dt") {
-            Some(Value::String(s)) => {
-                assert!(s.contains("2024-01-15"));
-                assert!(s.contains("10:30:00"));
-            }
-            other => panic!("expected string, got: {other:?}"),
-        }
+        let dt = datetime_at("dt = 2024-01-15T10:30:00.25\n", ".dt");
+        assert!(matches!(dt, DateTime::Local(_)));
+        assert_eq!(dt.to_string(), "2024-01-15T10:30:00.250");
     }
 
     #[test]
     fn local_date() {
-        let input = "d = 2024-01-15\n";
-        let val = from_str(input).unwrap();
-        match val.get_path(".d") {
-            Some(Value::String(s)) => {
-                assert_eq!(s, "2024-01-15");
-            }
-            other => panic!("expected string, got: {other:?}"),
-        }
+        let dt = datetime_at("d = 2024-01-15\n", ".d");
+        assert!(matches!(dt, DateTime::Date(_)));
+        assert_eq!(dt.to_string(), "2024-01-15");
     }
 
     #[test]
     fn local_time() {
-        let input = "t = 10:30:00\n";
-        let val = from_str(input).unwrap();
-        match val.get_path(".t") {
-            Some(Value::String(s)) => {
-                assert_eq!(s, "10:30:00");
-            }
-            other => panic!("expected string, got: {other:?}"),
-        }
+        let dt = datetime_at("t = 10:30:00\n", ".t");
+        assert!(matches!(dt, DateTime::Time(_)));
+        assert_eq!(dt.to_string(), "10:30:00");
+    }
+
+    #[test]
+    fn datetimes_written_natively() {
+        let input =
+            "a = 2024-01-15T10:30:00Z\nb = 2024-01-15T10:30:00\nc = 2024-01-15\nd = 10:30:00\n";
+        let out = to_string(&from_str(input).unwrap()).unwrap();
+        assert_eq!(out, input);
+    }
+
+    #[test]
+    fn decimals_written_as_numbers_when_exact() {
+        let mut map = IndexMap::new();
+        map.insert("a".to_string(), Value::Decimal("19.99".parse().unwrap()));
+        map.insert("b".to_string(), Value::Decimal("42".parse().unwrap()));
+        map.insert(
+            "c".to_string(),
+            Value::Decimal("12345678901234567890".parse().unwrap()),
+        );
+        assert_eq!(
+            to_string(&Value::Map(map)).unwrap(),
+            "a = 19.99\nb = 42\nc = \"12345678901234567890\"\n"
+        );
     }
 
     // -----------------------------------------------------------------------
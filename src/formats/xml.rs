@@ -65,12 +65,10 @@ pub fn from_str_with_config(input: &str, config: &XmlConfig) -> error::Result<Va
             Ok(Event::Text(_)) => {
                 // Ignore top-level whitespace text
             }
-            Ok(Event::CData(ref e)) => {
-                if !found_root {
-                    let text = String::from_utf8_lossy(e.as_ref()).to_string();
-                    result = Value::String(text);
-                    found_root = true;
-                }
+            Ok(Event::CData(ref e)) if !found_root => {
+                let text = String::from_utf8_lossy(e.as_ref()).to_string();
+                result = Value::String(text);
+                found_root = true;
             }
             Err(e) => {
                 return Err(error::MorphError::format(format!(
@@ -337,14 +335,25 @@ fn write_element(
                 .write_event(Event::End(BytesEnd::new(name)))
                 .map_err(|e| error::MorphError::format(format!("XML write error: {e}")))?;
         }
+        Value::Decimal(d) => {
+            write_element(
+                writer,
+                name,
+                &Value::String(crate::value::decimal_to_text(d)),
+                config,
+            )?;
+        }
+        Value::DateTime(dt) => {
+            write_element(writer, name, &Value::String(dt.to_string()), config)?;
+        }
         Value::Bytes(b) => {
-            let hex: String = b.iter().map(|byte| format!("{byte:02x}")).collect();
+            let text = super::bytes_to_base64(b);
             let elem = BytesStart::new(name);
             writer
                 .write_event(Event::Start(elem))
                 .map_err(|e| error::MorphError::format(format!("XML write error: {e}")))?;
             writer
-                .write_event(Event::Text(BytesText::new(&hex)))
+                .write_event(Event::Text(BytesText::new(&text)))
                 .map_err(|e| error::MorphError::format(format!("XML write error: {e}")))?;
             writer
                 .write_event(Event::End(BytesEnd::new(name)))
@@ -1,5 +1,6 @@
 use crate::error;
-use crate::value::Value;
+use crate::value::{DateTime, Value};
+use bigdecimal::ToPrimitive;
 use indexmap::IndexMap;
 use serde::Deserialize;
 use std::io::Read;
@@ -60,13 +61,15 @@ fn yaml_to_value(yaml: serde_yaml::Value) -> Value {
         serde_yaml::Value::Number(n) => {
             if let Some(i) = n.as_i64() {
                 Value::Int(i)
+            } else if let Some(u) = n.as_u64() {
+                Value::Decimal(u.into())
             } else if let Some(f) = n.as_f64() {
                 Value::Float(f)
             } else {
                 Value::String(n.to_string())
             }
         }
-        serde_yaml::Value::String(s) => Value::String(s),
+        serde_yaml::Value::String(s) => yaml_string_to_value(s),
         serde_yaml::Value::Sequence(seq) => {
             Value::Array(seq.into_iter().map(yaml_to_value).collect())
         }
@@ -132,21 +135,32 @@ fn yaml_to_value(yaml: serde_yaml::Value) -> Value {
     }
 }
 
+/// A plain scalar shaped like a YAML timestamp (`2001-12-14`,
+/// `2001-12-14t21:59:43.10-05:00`, `2001-12-14 21:59:43.10 -5`) becomes a
+/// `DateTime`. serde_yaml does not say whether a scalar was quoted, so a
+/// quoted timestamp is read as one too. A bare time of day is not a YAML
+/// timestamp and stays a string.
+fn yaml_string_to_value(s: String) -> Value {
+    match DateTime::parse(&s) {
+        Some(DateTime::Time(_)) | None => Value::String(s),
+        Some(dt) => Value::DateTime(dt),
+    }
+}
+
 fn value_to_yaml(value: &Value) -> serde_yaml::Value {
     match value {
         Value::Null => serde_yaml::Value::Null,
         Value::Bool(b) => serde_yaml::Value::Bool(*b),
         Value::Int(i) => serde_yaml::Value::Number(serde_yaml::Number::from(*i)),
         Value::Float(f) => serde_yaml::Value::Number(serde_yaml::Number::from(*f)),
+        Value::Decimal(d) => match d.to_u64().filter(|_| d.is_integer()) {
+            Some(n) => serde_yaml::Value::Number(serde_yaml::Number::from(n)),
+            None => value_to_yaml(&super::decimal_to_native(d)),
+        },
         Value::String(s) => serde_yaml::Value::String(s.clone()),
-        Value::Bytes(b) => {
-            // Represent bytes as a sequence of numbers
-            serde_yaml::Value::Sequence(
-                b.iter()
-                    .map(|byte| serde_yaml::Value::Number(serde_yaml::Number::from(*byte as u64)))
-                    .collect(),
-            )
-        }
+        Value::Bytes(b) => serde_yaml::Value::String(super::bytes_to_base64(b)),
+        // Written as a plain scalar, which YAML reads back as a timestamp.
+        Value::DateTime(dt) => serde_yaml::Value::String(dt.to_string()),
         Value::Array(arr) => serde_yaml::Value::Sequence(arr.iter().map(value_to_yaml).collect()),
         Value::Map(map) => {
             let mut m = serde_yaml::Mapping::new();
@@ -616,4 +630,39 @@ total: 2
         let val2 = from_str(&output).unwrap();
         assert_eq!(val, val2);
     }
+
+    // -- Timestamps --
+
+    #[test]
+    fn timestamps_read_as_datetime() {
+        let val = from_str("d: 2024-01-15\nts: 2001-12-14 21:59:43.10 -5\nt: 10:30:00\n").unwrap();
+        assert!(matches!(
+            val.get_path(".d"),
+            Some(Value::DateTime(DateTime::Date(_)))
+        ));
+        assert!(matches!(
+            val.get_path(".ts"),
+            Some(Value::DateTime(DateTime::Offset(_)))
+        ));
+        assert_eq!(val.get_path(".t"), Some(&Value::String("10:30:00".into())));
+    }
+
+    #[test]
+    fn timestamps_written_as_plain_scalars() {
+        let val = from_str("d: 2024-01-15\nts: 2024-01-15T10:30:00Z\n").unwrap();
+        assert_eq!(
+            to_string(&val).unwrap(),
+            "d: 2024-01-15\nts: 2024-01-15T10:30:00Z\n"
+        );
+    }
+
+    #[test]
+    fn decimals_and_large_unsigned_ints() {
+        let val = from_str("n: 18446744073709551615\n").unwrap();
+        assert_eq!(val.get_path(".n"), Some(&Value::Decimal(u64::MAX.into())));
+        assert_eq!(to_string(&val).unwrap(), "n: 18446744073709551615\n");
+
+        let val = Value::Decimal("0.10000000000000000001".parse().unwrap());
+        assert_eq!(to_string(&val).unwrap(), "'0.10000000000000000001'\n");
+    }
 }
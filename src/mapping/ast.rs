@@ -1,3 +1,4 @@
+use crate::cli::Format;
 use crate::mapping::lexer::Span;
 use std::fmt;
 
@@ -15,6 +16,16 @@ impl fmt::Display for Path {
                 PathSegment::Field(name) => write!(f, ".{name}")?,
                 PathSegment::Index(i) => write!(f, ".[{i}]")?,
                 PathSegment::Wildcard => write!(f, ".[*]")?,
+                PathSegment::Slice { start, end, step } => {
+                    let bound = |b: &Option<i64>| b.map(|i| i.to_string()).unwrap_or_default();
+                    write!(f, ".[{}:{}", bound(start), bound(end))?;
+                    if let Some(step) = step {
+                        write!(f, ":{step}")?;
+                    }
+                    write!(f, "]")?;
+                }
+                PathSegment::Descendant(name) => write!(f, "..{name}")?,
+                PathSegment::Filter(_) => write!(f, ".[?(...)]")?,
             }
         }
         Ok(())
@@ -28,8 +39,20 @@ pub enum PathSegment {
     Field(String),
     /// An array index: `.[0]`
     Index(i64),
-    /// A wildcard: `.[*]`
+    /// A wildcard over array elements or map values: `.[*]` or `.*`
     Wildcard,
+    /// A Python-style array slice: `.[1:3]`, `.[-2:]`, `.[::2]`.
+    /// Negative bounds count from the end; `step` is never zero.
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: Option<i64>,
+    },
+    /// Recursive descent to a key at any depth: `..name`
+    Descendant(String),
+    /// Elements (or map values) for which a predicate holds: `[?(.qty > 0)]`.
+    /// Paths inside the predicate are relative to each element.
+    Filter(Box<Expr>),
 }
 
 /// The target type for cast operations.
@@ -37,8 +60,58 @@ pub enum PathSegment {
 pub enum CastType {
     Int,
     Float,
+    Decimal,
     String,
     Bool,
+    /// `array` or `array split ","`: a string splits on the delimiter,
+    /// other scalars wrap in a one-element array.
+    Array {
+        split: Option<String>,
+    },
+    /// `datetime` or `datetime "%d/%m/%Y"`: RFC 3339 text unless a chrono
+    /// format is given; numbers are Unix seconds.
+    DateTime {
+        format: Option<String>,
+    },
+    /// `bytes`, `bytes base64` or `bytes hex`: how a string is decoded.
+    Bytes {
+        encoding: BytesEncoding,
+    },
+    /// `object` or `object yaml`: the format a string is parsed with.
+    Object {
+        format: Format,
+    },
+}
+
+/// How `cast ... as bytes` decodes a string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BytesEncoding {
+    /// The string's own UTF-8 bytes.
+    #[default]
+    Utf8,
+    Base64,
+    Hex,
+}
+
+/// What a `cast` yields for a value that does not convert.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CastFallback {
+    /// `or null`
+    Null,
+    /// `or default <expr>`, evaluated against the record
+    Default(Expr),
+}
+
+/// What to do when a statement fails, set by `on error skip|null|fail`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnError {
+    /// Stop with the error.
+    #[default]
+    Fail,
+    /// Failing expressions and casts yield `null`.
+    Null,
+    /// Drop the record the statement failed on.
+    Skip,
 }
 
 /// A binary operator.
@@ -57,6 +130,14 @@ pub enum BinOp {
     LtEq,
     And,
     Or,
+    /// `a ?? b` — `a` unless it is null.
+    Coalesce,
+    /// `x in xs` — membership in an array, substring of a string, or key of a map.
+    In,
+    /// `x not in xs`
+    NotIn,
+    /// `s =~ "pattern"` — regex match.
+    Match,
 }
 
 /// A unary operator.
@@ -87,8 +168,31 @@ pub enum Expr {
     },
     /// A unary operation: `not .active`, `-.value`.
     UnaryOp { op: UnaryOp, expr: Box<Expr> },
+    /// An array literal: `[1, .a, "x"]`.
+    Array(Vec<Expr>),
+    /// A conditional expression: `.age >= 18 ? "adult" : "minor"`. Only the
+    /// selected branch is evaluated.
+    Conditional {
+        condition: Box<Expr>,
+        then_expr: Box<Expr>,
+        else_expr: Box<Expr>,
+    },
     /// A string interpolation: `"Hello, {.name}!"`
     StringInterpolation { parts: Vec<InterpolationPart> },
+    /// A lambda passed to a higher-order function: `x => x.price * x.qty`,
+    /// `(acc, x) => acc + x`.
+    Lambda {
+        params: Vec<String>,
+        body: Box<Expr>,
+        span: Span,
+    },
+    /// A reference to a lambda parameter, optionally followed by a path into
+    /// its value: `x`, `x.price`, `item.tags.[0]`.
+    Variable {
+        name: String,
+        segments: Vec<PathSegment>,
+        span: Span,
+    },
 }
 
 /// A part of an interpolated string.
@@ -103,18 +207,42 @@ pub enum InterpolationPart {
 pub enum Statement {
     /// `rename .old -> .new`
     Rename { from: Path, to: Path, span: Span },
+    /// `rename keys recursive using snake_case` or
+    /// `rename keys .payload matching "^x_(.*)$" -> "$1"` — rename the keys
+    /// of the record (or of the map at `path`), and with `recursive` of
+    /// every map nested inside it.
+    RenameKeys {
+        path: Option<Path>,
+        recursive: bool,
+        rename: KeyRename,
+        span: Span,
+    },
     /// `select .a, .b, .c`
     Select { paths: Vec<Path>, span: Span },
     /// `drop .x, .y`
     Drop { paths: Vec<Path>, span: Span },
     /// `set .x = <expr>`
     Set { path: Path, expr: Expr, span: Span },
+    /// `set .cum = running_sum(.amount) partition by .account order by .date`
+    /// — a `set` whose expression uses window functions such as `lag` or
+    /// `row_number`, which see the neighbouring records of the root array.
+    /// Records are grouped by `partition` and visited in `order` (input
+    /// order when empty).
+    Window {
+        path: Path,
+        expr: Expr,
+        partition: Vec<Path>,
+        order: Vec<SortKey>,
+        span: Span,
+    },
     /// `default .x = <expr>`
     Default { path: Path, expr: Expr, span: Span },
-    /// `cast .x as int`
+    /// `cast .x as int`, optionally `or null` / `or default <expr>` for
+    /// values that do not convert
     Cast {
         path: Path,
         target_type: CastType,
+        fallback: Option<CastFallback>,
         span: Span,
     },
     /// `flatten .address` or `flatten .address -> prefix "addr"`
@@ -123,12 +251,93 @@ pub enum Statement {
         prefix: Option<String>,
         span: Span,
     },
+    /// `flatten all separator "." arrays index` — flatten every nested map
+    /// and array of the record into top-level keys such as `user.address.city`
+    /// and `tags.0`.
+    FlattenAll {
+        separator: String,
+        arrays: ArrayFlatten,
+        span: Span,
+    },
+    /// `unflatten all separator "."` — the inverse of `flatten all`.
+    UnflattenAll {
+        separator: String,
+        arrays: ArrayFlatten,
+        span: Span,
+    },
     /// `nest .a_x, .a_y -> .a`
     Nest {
         paths: Vec<Path>,
         target: Path,
         span: Span,
     },
+    /// `explode .items as .item` (or `unnest`) — one record per element of
+    /// the array at `path`, stored at `target`. Without `as`, `target` is
+    /// `path` itself.
+    Explode {
+        path: Path,
+        target: Path,
+        span: Span,
+    },
+    /// `aggregate by .country { set .orders = count(), set .revenue = sum(.total) }`
+    /// — one record per distinct combination of `keys`, holding the keys
+    /// and the aggregated `fields`. Without `by`, all records form one group.
+    Aggregate {
+        keys: Vec<Path>,
+        fields: Vec<AggregateField>,
+        span: Span,
+    },
+    /// `pivot key .metric value .value by .date` — long to wide: one record
+    /// per distinct combination of `by`, with one column per distinct value
+    /// of `key` holding the matching `value`. Missing cells are null.
+    Pivot {
+        key: Path,
+        value: Path,
+        by: Vec<Path>,
+        span: Span,
+    },
+    /// `unpivot .jan, .feb into .month, .amount` — wide to long: one record
+    /// per listed field present on the record, holding the field name at
+    /// `name` and its value at `value`.
+    Unpivot {
+        paths: Vec<Path>,
+        name: Path,
+        value: Path,
+        span: Span,
+    },
+    /// `distinct by .email, .tenant keep last` — drop records whose `by`
+    /// values (the whole record without `by`) repeat an earlier record's.
+    Distinct {
+        by: Vec<Path>,
+        keep: DistinctKeep,
+        span: Span,
+    },
+    /// `limit 100` (or `head 100`) — keep only the first `count` records
+    Limit { count: usize, span: Span },
+    /// `skip 10` — drop the first `count` records
+    Skip { count: usize, span: Span },
+    /// `tail 10` — keep only the last `count` records
+    Tail { count: usize, span: Span },
+    /// `sample 100 seed 42` — keep a uniform random sample of `count`
+    /// records, in input order. Without `seed`, the program's seed is used,
+    /// or a random one.
+    Sample {
+        count: usize,
+        seed: Option<u64>,
+        span: Span,
+    },
+    /// `join users on .user_id = .id as .user` — look up the record in the
+    /// named lookup table whose `lookup_key` equals this record's `key`.
+    /// The match is stored at `target`, or merged into the record when
+    /// `target` is absent.
+    Join {
+        kind: JoinKind,
+        table: String,
+        key: Path,
+        lookup_key: Path,
+        target: Option<Path>,
+        span: Span,
+    },
     /// `where <condition>` — filter array elements by condition
     Where { condition: Expr, span: Span },
     /// `sort .field asc, .field2 desc` — sort array elements
@@ -139,14 +348,132 @@ pub enum Statement {
         body: Vec<Statement>,
         span: Span,
     },
-    /// `when <condition> { ... }` — conditional block
+    /// `when <condition> { ... } else when <condition> { ... } else { ... }`
+    /// — conditional block. An `else when` chain is stored as an `else_body`
+    /// holding a single nested `When`.
     When {
         condition: Expr,
         body: Vec<Statement>,
+        else_body: Option<Vec<Statement>>,
+        span: Span,
+    },
+    /// `match .field { "a" => { ... }, "b" | "c" => { ... }, _ => { ... } }`
+    /// — run the first arm whose pattern equals the subject
+    Match {
+        subject: Expr,
+        arms: Vec<MatchArm>,
         span: Span,
     },
 }
 
+/// A `set .path = <expr>` entry inside an `aggregate` block. Calls to
+/// `count`, `sum`, `avg`, `min` and `max` in `expr` are computed over the
+/// group; other paths refer to the output record built so far.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregateField {
+    pub path: Path,
+    pub expr: Expr,
+}
+
+/// A single arm of a `match` statement: one or more `|`-separated patterns
+/// and the block to run when any of them matches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    pub patterns: Vec<MatchPattern>,
+    pub body: Vec<Statement>,
+}
+
+/// A pattern in a `match` arm.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchPattern {
+    /// A literal value compared for equality: `"a"`, `42`, `null`.
+    Literal(crate::value::Value),
+    /// The catch-all pattern: `_`
+    Wildcard,
+}
+
+/// How `flatten all` and `unflatten all` represent arrays.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArrayFlatten {
+    /// One key per element: `tags.0`, `tags.1` (the default).
+    Index,
+    /// Arrays of scalars become one string under a `[]`-suffixed key,
+    /// e.g. `tags[]` = `"a,b"`. Other arrays fall back to index keys.
+    Join(String),
+}
+
+/// How `rename keys` computes a new key from an old one.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyRename {
+    /// Convert to a naming convention.
+    Case(KeyCase),
+    /// Replace every match of `pattern`; `$1` or `${name}` in `replacement`
+    /// refer to capture groups.
+    Pattern {
+        pattern: CompiledRegex,
+        replacement: String,
+    },
+}
+
+/// A naming convention for keys and strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCase {
+    /// `snake_case`
+    Snake,
+    /// `camelCase`
+    Camel,
+    /// `kebab-case`
+    Kebab,
+    /// `PascalCase`
+    Pascal,
+    /// `SCREAMING_SNAKE_CASE`
+    ScreamingSnake,
+}
+
+impl KeyCase {
+    /// The convention with the same name as its conversion function, such
+    /// as `snake_case` or `camel_case`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "snake_case" => Some(KeyCase::Snake),
+            "camel_case" => Some(KeyCase::Camel),
+            "kebab_case" => Some(KeyCase::Kebab),
+            "pascal_case" => Some(KeyCase::Pascal),
+            "screaming_snake_case" => Some(KeyCase::ScreamingSnake),
+            _ => None,
+        }
+    }
+}
+
+/// A regular expression compiled when the mapping is parsed. Two are equal
+/// when their source patterns are.
+#[derive(Debug, Clone)]
+pub struct CompiledRegex(pub regex::Regex);
+
+impl PartialEq for CompiledRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+/// Which record of a set of duplicates `distinct` keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistinctKeep {
+    /// The first occurrence (the default).
+    First,
+    /// The last occurrence, at its position in the input.
+    Last,
+}
+
+/// What a `join` does with records that have no match in the lookup table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinKind {
+    /// Keep the record (the default).
+    Left,
+    /// Drop the record.
+    Inner,
+}
+
 /// A sort direction.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SortDirection {
@@ -162,7 +489,9 @@ pub struct SortKey {
 }
 
 /// A parsed mapping program: a list of statements.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Program {
     pub statements: Vec<Statement>,
+    /// The policy given by an `on error` line, `fail` when there is none.
+    pub on_error: OnError,
 }
@@ -0,0 +1,185 @@
+use crate::error;
+use crate::value::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// Bounded-memory settings for `distinct ... keep first` in `--stream` mode.
+///
+/// Instead of remembering every key, a Bloom filter sized for `capacity`
+/// keys is used. Memory stays fixed, but a record may be dropped as a
+/// duplicate with probability up to `false_positive_rate` once `capacity`
+/// keys have been seen (more beyond that).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ApproxDistinct {
+    pub capacity: usize,
+    pub false_positive_rate: f64,
+}
+
+impl ApproxDistinct {
+    pub fn new(capacity: usize, false_positive_rate: f64) -> error::Result<Self> {
+        if capacity == 0 {
+            return Err(error::MorphError::cli(
+                "distinct capacity must be greater than zero",
+            ));
+        }
+        if !(false_positive_rate > 0.0 && false_positive_rate < 1.0) {
+            return Err(error::MorphError::cli(format!(
+                "distinct false-positive rate must be between 0 and 1, got {false_positive_rate}"
+            )));
+        }
+        Ok(ApproxDistinct {
+            capacity,
+            false_positive_rate,
+        })
+    }
+}
+
+/// The keys a `distinct` statement has already let through.
+pub(crate) enum SeenSet {
+    Exact(HashSet<Value>),
+    Approx(BloomFilter),
+}
+
+impl SeenSet {
+    pub(crate) fn new(approx: Option<ApproxDistinct>) -> Self {
+        match approx {
+            Some(approx) => SeenSet::Approx(BloomFilter::new(approx)),
+            None => SeenSet::Exact(HashSet::new()),
+        }
+    }
+
+    /// Record `key`, returning true if it had not been seen before.
+    pub(crate) fn insert(&mut self, key: Value) -> bool {
+        match self {
+            SeenSet::Exact(seen) => seen.insert(key),
+            SeenSet::Approx(filter) => filter.insert(&key),
+        }
+    }
+}
+
+/// The last record seen for each key, for `distinct ... keep last`.
+#[derive(Default)]
+pub(crate) struct LatestByKey {
+    records: HashMap<Value, (usize, Value)>,
+    next_seq: usize,
+}
+
+impl LatestByKey {
+    pub(crate) fn insert(&mut self, key: Value, record: Value) {
+        self.records.insert(key, (self.next_seq, record));
+        self.next_seq += 1;
+    }
+
+    /// Take the kept records, ordered by where each was last seen.
+    pub(crate) fn drain(&mut self) -> Vec<Value> {
+        let mut kept: Vec<_> = self.records.drain().map(|(_, entry)| entry).collect();
+        kept.sort_by_key(|(seq, _)| *seq);
+        kept.into_iter().map(|(_, record)| record).collect()
+    }
+}
+
+/// A fixed-size Bloom filter using double hashing.
+pub(crate) struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+}
+
+impl BloomFilter {
+    fn new(approx: ApproxDistinct) -> Self {
+        let ln2 = std::f64::consts::LN_2;
+        let n = approx.capacity as f64;
+        let num_bits = (-n * approx.false_positive_rate.ln() / (ln2 * ln2))
+            .ceil()
+            .max(64.0) as u64;
+        let num_hashes = ((num_bits as f64 / n) * ln2).round().clamp(1.0, 32.0) as u32;
+        BloomFilter {
+            bits: vec![0; num_bits.div_ceil(64) as usize],
+            num_bits,
+            num_hashes,
+        }
+    }
+
+    fn insert(&mut self, key: &Value) -> bool {
+        let mut is_new = false;
+        for (word, mask) in self.positions(key) {
+            if self.bits[word] & mask == 0 {
+                self.bits[word] |= mask;
+                is_new = true;
+            }
+        }
+        is_new
+    }
+
+    #[cfg(test)]
+    fn contains(&self, key: &Value) -> bool {
+        self.positions(key)
+            .all(|(word, mask)| self.bits[word] & mask != 0)
+    }
+
+    /// The (word, bit mask) pairs for `key`.
+    fn positions(&self, key: &Value) -> impl Iterator<Item = (usize, u64)> {
+        let h1 = seeded_hash(key, 0);
+        let h2 = seeded_hash(key, 1) | 1;
+        let num_bits = self.num_bits;
+        (0..u64::from(self.num_hashes)).map(move |i| {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % num_bits;
+            ((bit / 64) as usize, 1u64 << (bit % 64))
+        })
+    }
+}
+
+fn seeded_hash(key: &Value, seed: u8) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_set_dedupes() {
+        let mut seen = SeenSet::new(None);
+        assert!(seen.insert(Value::String("a".into())));
+        assert!(seen.insert(Value::Int(1)));
+        assert!(!seen.insert(Value::String("a".into())));
+        // Int and Float are different values
+        assert!(seen.insert(Value::Float(1.0)));
+    }
+
+    #[test]
+    fn bloom_filter_has_no_false_negatives() {
+        let approx = ApproxDistinct::new(1000, 0.01).unwrap();
+        let mut seen = SeenSet::new(Some(approx));
+        for i in 0..1000 {
+            seen.insert(Value::Int(i));
+        }
+        for i in 0..1000 {
+            assert!(!seen.insert(Value::Int(i)));
+        }
+    }
+
+    #[test]
+    fn bloom_filter_false_positive_rate_is_bounded() {
+        let mut filter = BloomFilter::new(ApproxDistinct::new(10_000, 0.01).unwrap());
+        for i in 0..10_000 {
+            filter.insert(&Value::Int(i));
+        }
+        let false_positives = (10_000..20_000)
+            .filter(|i| filter.contains(&Value::Int(*i)))
+            .count();
+        // Expected ~1%; allow generous slack for hash variance.
+        assert!(false_positives < 300, "false positives: {false_positives}");
+    }
+
+    #[test]
+    fn approx_rejects_bad_settings() {
+        assert!(ApproxDistinct::new(0, 0.01).is_err());
+        assert!(ApproxDistinct::new(10, 0.0).is_err());
+        assert!(ApproxDistinct::new(10, 1.5).is_err());
+    }
+}
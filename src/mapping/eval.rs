@@ -1,37 +1,469 @@
 use crate::error;
 use crate::mapping::ast::*;
+use crate::mapping::distinct::{ApproxDistinct, LatestByKey, SeenSet};
 use crate::mapping::functions;
-use crate::value::Value;
-use indexmap::IndexMap;
+use crate::mapping::lookup::Lookups;
+use crate::mapping::random::{self, Reservoir, Rng};
+use crate::value::{decimal_from_f64, BigDecimal, DateTime, Value};
+use indexmap::{IndexMap, IndexSet};
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
+
+/// Settings for evaluating a [`Program`] that come from outside the mapping
+/// text, such as the command line.
+#[derive(Debug, Clone, Default)]
+pub struct EvalOptions {
+    /// Tables that `join` statements look records up in.
+    pub lookups: Lookups,
+    /// Bounded-memory `distinct` for streaming; exact when `None`.
+    pub approx_distinct: Option<ApproxDistinct>,
+    /// Seed for `sample` statements that do not give their own, and for the
+    /// random functions.
+    pub seed: Option<u64>,
+    /// Replaces the program's own `on error` policy when set.
+    pub on_error: Option<OnError>,
+}
 
-/// Evaluate a parsed mapping program against a Value.
+impl EvalOptions {
+    /// The `on error` policy to evaluate `program` under.
+    fn on_error(&self, program: &Program) -> OnError {
+        self.on_error.unwrap_or(program.on_error)
+    }
+}
+
+/// Evaluate a parsed mapping program against a Value with default
+/// [`EvalOptions`].
 pub fn eval(program: &Program, input: &Value) -> error::Result<Value> {
-    let mut value = input.clone();
-    for stmt in &program.statements {
-        value = eval_statement(stmt, &value)?;
+    eval_with(program, &EvalOptions::default(), input)
+}
+
+/// Evaluate a parsed mapping program against a Value.
+///
+/// With `on error skip`, a statement that fails on a root array is run again
+/// without the records it fails on by themselves, as `--stream` would drop
+/// them. Any other failing input is one record, so the result is then an
+/// empty array.
+pub fn eval_with(program: &Program, options: &EvalOptions, input: &Value) -> error::Result<Value> {
+    let on_error = options.on_error(program);
+    let apply = |stmt: &Statement, value: &Value| match stmt {
+        Statement::Join { .. } => eval_join(value, stmt, &options.lookups),
+        Statement::Sample { count, seed, .. } => {
+            Ok(eval_sample(value, *count, seed.or(options.seed)))
+        }
+        _ => eval_statement(stmt, value),
+    };
+    let run = || {
+        let mut value = input.clone();
+        for stmt in &program.statements {
+            value = match apply(stmt, &value) {
+                Err(_) if on_error == OnError::Skip => {
+                    let Value::Array(records) = &value else {
+                        return Ok(Value::Array(Vec::new()));
+                    };
+                    let kept = records
+                        .iter()
+                        .filter(|record| {
+                            apply(stmt, &Value::Array(vec![(*record).clone()])).is_ok()
+                        })
+                        .cloned()
+                        .collect();
+                    match apply(stmt, &Value::Array(kept)) {
+                        Ok(value) => value,
+                        Err(_) => return Ok(Value::Array(Vec::new())),
+                    }
+                }
+                result => result?,
+            };
+        }
+        Ok(value)
+    };
+    with_on_error(on_error, || match options.seed {
+        Some(seed) => random::with_rng(&mut Rng::new(seed), run),
+        None => run(),
+    })
+}
+
+/// Evaluate a mapping program against a single streamed record.
+///
+/// Statements run on each record in turn. An `explode` statement can turn
+/// one record into several; the statements after it then run on each of them.
+pub fn eval_record(
+    program: &Program,
+    options: &EvalOptions,
+    input: &Value,
+) -> error::Result<Vec<Value>> {
+    let mut evaluator = RecordEvaluator::new(program, options)?;
+    let mut records = evaluator.push(input)?;
+    records.extend(evaluator.finish()?);
+    Ok(records)
+}
+
+/// Evaluates a program record by record, as in `--stream` mode.
+///
+/// Statements up to the first `aggregate`, `pivot`, `distinct ... keep
+/// last`, window `set`, `tail` or `sample` run on each pushed record as it
+/// arrives. Those statements only keep their per-group state (a window
+/// `set` keeps every record), and their output records are released by
+/// [`RecordEvaluator::finish`], after which the remaining statements run on
+/// them. `distinct ... keep first`, `limit` and `skip`
+/// keep their state between records.
+pub struct RecordEvaluator<'a> {
+    head: &'a [Statement],
+    options: &'a EvalOptions,
+    on_error: OnError,
+    /// State of each `distinct`, `limit` and `skip` in `head`, by position.
+    counters: HashMap<usize, Counter>,
+    collector: Option<(Collector<'a>, Box<RecordEvaluator<'a>>)>,
+    /// The random functions' generator when the program is seeded, kept
+    /// from one record to the next. Nested evaluators never have one.
+    rng: Option<Rng>,
+}
+
+/// Per-statement state carried from one streamed record to the next.
+enum Counter {
+    Distinct(SeenSet),
+    /// Records `limit` may still let through.
+    Limit(usize),
+    /// Records `skip` must still drop.
+    Skip(usize),
+}
+
+/// A statement that needs every record before it can produce output.
+enum Collector<'a> {
+    Aggregate(Aggregator<'a>),
+    Pivot(Pivoter<'a>),
+    Distinct(&'a [Path], LatestByKey),
+    Window(Windower<'a>),
+    Tail(VecDeque<Value>, usize),
+    Sample(Reservoir),
+}
+
+impl Collector<'_> {
+    fn add(&mut self, record: &Value) -> error::Result<()> {
+        match self {
+            Collector::Aggregate(aggregator) => aggregator.add(record),
+            Collector::Pivot(pivoter) => pivoter.add(record),
+            Collector::Distinct(by, latest) => {
+                latest.insert(distinct_key(record, by)?, record.clone());
+                Ok(())
+            }
+            Collector::Window(windower) => {
+                windower.add(record);
+                Ok(())
+            }
+            Collector::Tail(last, count) => {
+                if *count > 0 {
+                    if last.len() == *count {
+                        last.pop_front();
+                    }
+                    last.push_back(record.clone());
+                }
+                Ok(())
+            }
+            Collector::Sample(reservoir) => {
+                reservoir.add(record.clone());
+                Ok(())
+            }
+        }
+    }
+
+    fn finish(&mut self) -> error::Result<Vec<Value>> {
+        match self {
+            Collector::Aggregate(aggregator) => aggregator.finish(),
+            Collector::Pivot(pivoter) => pivoter.finish(),
+            Collector::Distinct(_, latest) => Ok(latest.drain()),
+            Collector::Window(windower) => windower.finish(),
+            Collector::Tail(last, _) => Ok(last.drain(..).collect()),
+            Collector::Sample(reservoir) => Ok(reservoir.drain()),
+        }
+    }
+}
+
+impl<'a> RecordEvaluator<'a> {
+    pub fn new(program: &'a Program, options: &'a EvalOptions) -> error::Result<Self> {
+        let on_error = options.on_error(program);
+        let mut evaluator = Self::with_statements(&program.statements, options, on_error)?;
+        evaluator.rng = options.seed.map(Rng::new);
+        Ok(evaluator)
+    }
+
+    fn with_statements(
+        statements: &'a [Statement],
+        options: &'a EvalOptions,
+        on_error: OnError,
+    ) -> error::Result<Self> {
+        let mut counters = HashMap::new();
+        for (pos, stmt) in statements.iter().enumerate() {
+            let collector = match stmt {
+                Statement::Aggregate { keys, fields, .. } => {
+                    Collector::Aggregate(Aggregator::new(keys, fields)?)
+                }
+                Statement::Pivot { key, value, by, .. } => {
+                    Collector::Pivot(Pivoter::new(key, value, by))
+                }
+                Statement::Distinct {
+                    by,
+                    keep: DistinctKeep::Last,
+                    ..
+                } => Collector::Distinct(by, LatestByKey::default()),
+                Statement::Window {
+                    path,
+                    expr,
+                    partition,
+                    order,
+                    ..
+                } => Collector::Window(Windower::new(path, expr, partition, order)?),
+                Statement::Tail { count, .. } => Collector::Tail(VecDeque::new(), *count),
+                Statement::Sample { count, seed, .. } => Collector::Sample(Reservoir::new(
+                    *count,
+                    Rng::from_seed(seed.or(options.seed)),
+                )),
+                Statement::Distinct { .. } => {
+                    counters.insert(
+                        pos,
+                        Counter::Distinct(SeenSet::new(options.approx_distinct)),
+                    );
+                    continue;
+                }
+                Statement::Limit { count, .. } => {
+                    counters.insert(pos, Counter::Limit(*count));
+                    continue;
+                }
+                Statement::Skip { count, .. } => {
+                    counters.insert(pos, Counter::Skip(*count));
+                    continue;
+                }
+                _ => continue,
+            };
+            let tail = RecordEvaluator::with_statements(&statements[pos + 1..], options, on_error)?;
+            return Ok(RecordEvaluator {
+                head: &statements[..pos],
+                options,
+                on_error,
+                counters,
+                collector: Some((collector, Box::new(tail))),
+                rng: None,
+            });
+        }
+        Ok(RecordEvaluator {
+            head: statements,
+            options,
+            on_error,
+            counters,
+            collector: None,
+            rng: None,
+        })
+    }
+
+    /// True once a `limit` before the first collecting statement has let
+    /// through all its records, so further input cannot change the output
+    /// and the caller may stop reading.
+    pub fn is_done(&self) -> bool {
+        self.counters
+            .values()
+            .any(|counter| matches!(counter, Counter::Limit(0)))
+    }
+
+    /// Feed one input record and return the records ready for output.
+    ///
+    /// With `on error skip`, a record that a statement fails on is dropped
+    /// and the others carry on.
+    pub fn push(&mut self, input: &Value) -> error::Result<Vec<Value>> {
+        self.with_program_state(|this| this.push_record(input))
+    }
+
+    /// Signal the end of input and return any records still held back.
+    pub fn finish(&mut self) -> error::Result<Vec<Value>> {
+        self.with_program_state(Self::finish_records)
+    }
+
+    /// Run `f` under the program's `on error` policy and, when seeded, with
+    /// this evaluator's generator.
+    fn with_program_state<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        with_on_error(self.on_error, || match self.rng.take() {
+            Some(mut rng) => {
+                let result = random::with_rng(&mut rng, || f(self));
+                self.rng = Some(rng);
+                result
+            }
+            None => f(self),
+        })
+    }
+
+    /// `result` for one record, or `None` to drop the record when it is an
+    /// error and the program skips failing records.
+    fn skip_failed<T>(&self, result: error::Result<T>) -> error::Result<Option<T>> {
+        match result {
+            Err(_) if self.on_error == OnError::Skip => Ok(None),
+            result => result.map(Some),
+        }
+    }
+
+    fn push_record(&mut self, input: &Value) -> error::Result<Vec<Value>> {
+        let mut records = vec![input.clone()];
+        for (pos, stmt) in self.head.iter().enumerate() {
+            let mut next = Vec::with_capacity(records.len());
+            for record in &records {
+                if let Some(counter) = self.counters.get_mut(&pos) {
+                    let keep = match (counter, stmt) {
+                        (Counter::Distinct(seen), Statement::Distinct { by, .. }) => {
+                            seen.insert(distinct_key(record, by)?)
+                        }
+                        (Counter::Limit(remaining), _) => {
+                            let keep = *remaining > 0;
+                            *remaining = remaining.saturating_sub(1);
+                            keep
+                        }
+                        (Counter::Skip(remaining), _) => {
+                            let keep = *remaining == 0;
+                            *remaining = remaining.saturating_sub(1);
+                            keep
+                        }
+                        _ => unreachable!("counter does not match its statement"),
+                    };
+                    if keep {
+                        next.push(record.clone());
+                    }
+                    continue;
+                }
+                let result = match stmt {
+                    Statement::Explode { path, target, .. } => explode_record(record, path, target),
+                    Statement::Unpivot {
+                        paths, name, value, ..
+                    } => unpivot_record(record, paths, name, value),
+                    Statement::Join { .. } => join_record(record, stmt, &self.options.lookups)
+                        .map(|joined| joined.into_iter().collect()),
+                    // A record the condition rejects is dropped rather than
+                    // passed on as null, so later statements never count it.
+                    Statement::Where { condition, .. } => {
+                        eval_expr(condition, record).map(|keep| {
+                            if is_truthy(&keep) {
+                                vec![record.clone()]
+                            } else {
+                                Vec::new()
+                            }
+                        })
+                    }
+                    _ => eval_statement(stmt, record).map(|record| vec![record]),
+                };
+                if let Some(produced) = self.skip_failed(result)? {
+                    next.extend(produced);
+                }
+            }
+            records = next;
+        }
+        let on_error = self.on_error;
+        match &mut self.collector {
+            Some((collector, _)) => {
+                for record in &records {
+                    match collector.add(record) {
+                        Err(_) if on_error == OnError::Skip => {}
+                        result => result?,
+                    }
+                }
+                Ok(Vec::new())
+            }
+            None => Ok(records),
+        }
+    }
+
+    fn finish_records(&mut self) -> error::Result<Vec<Value>> {
+        let Some((collector, tail)) = &mut self.collector else {
+            return Ok(Vec::new());
+        };
+        let mut records = Vec::new();
+        for group in collector.finish()? {
+            records.extend(tail.push(&group)?);
+        }
+        records.extend(tail.finish()?);
+        Ok(records)
     }
-    Ok(value)
 }
 
 /// Evaluate a single statement against a Value.
 fn eval_statement(stmt: &Statement, value: &Value) -> error::Result<Value> {
     match stmt {
         Statement::Rename { from, to, .. } => eval_rename(value, from, to),
+        Statement::RenameKeys {
+            path,
+            recursive,
+            rename,
+            ..
+        } => eval_rename_keys(value, path.as_ref(), *recursive, rename),
         Statement::Select { paths, .. } => eval_select(value, paths),
         Statement::Drop { paths, .. } => eval_drop(value, paths),
         Statement::Set { path, expr, .. } => eval_set(value, path, expr),
+        Statement::Window {
+            path,
+            expr,
+            partition,
+            order,
+            ..
+        } => eval_window(value, path, expr, partition, order),
         Statement::Default { path, expr, .. } => eval_default(value, path, expr),
         Statement::Cast {
-            path, target_type, ..
-        } => eval_cast(value, path, target_type),
+            path,
+            target_type,
+            fallback,
+            ..
+        } => eval_cast(value, path, target_type, fallback.as_ref()),
         Statement::Flatten { path, prefix, .. } => eval_flatten(value, path, prefix.as_deref()),
+        Statement::FlattenAll {
+            separator, arrays, ..
+        } => Ok(map_records(value, |record| {
+            flatten_record(record, separator, arrays)
+        })),
+        Statement::UnflattenAll {
+            separator, arrays, ..
+        } => try_map_records(value, |record| unflatten_record(record, separator, arrays)),
         Statement::Nest { paths, target, .. } => eval_nest(value, paths, target),
+        Statement::Explode { path, target, .. } => eval_explode(value, path, target),
+        Statement::Aggregate { keys, fields, .. } => eval_aggregate(value, keys, fields),
+        Statement::Pivot {
+            key,
+            value: val,
+            by,
+            ..
+        } => eval_pivot(value, key, val, by),
+        Statement::Unpivot {
+            paths,
+            name,
+            value: val,
+            ..
+        } => eval_unpivot(value, paths, name, val),
+        Statement::Distinct { by, keep, .. } => eval_distinct(value, by, *keep),
+        Statement::Limit { count, .. } => Ok(eval_slice(value, |arr| {
+            arr.iter().take(*count).cloned().collect()
+        })),
+        Statement::Skip { count, .. } => Ok(eval_slice(value, |arr| {
+            arr.iter().skip(*count).cloned().collect()
+        })),
+        Statement::Tail { count, .. } => Ok(eval_slice(value, |arr| {
+            arr[arr.len().saturating_sub(*count)..].to_vec()
+        })),
+        // The parser keeps joins and samples out of blocks, and top-level
+        // ones are evaluated by the callers that hold the lookup tables and
+        // the program seed.
+        Statement::Join { span, .. } => Err(error::MorphError::mapping_at(
+            "join is only allowed at the top level of a mapping",
+            span.line,
+            span.column,
+        )),
+        Statement::Sample { span, .. } => Err(error::MorphError::mapping_at(
+            "sample is only allowed at the top level of a mapping",
+            span.line,
+            span.column,
+        )),
         Statement::Where { condition, .. } => eval_where(value, condition),
         Statement::Sort { keys, .. } => eval_sort(value, keys),
         Statement::Each { path, body, .. } => eval_each(value, path, body),
         Statement::When {
-            condition, body, ..
-        } => eval_when(value, condition, body),
+            condition,
+            body,
+            else_body,
+            ..
+        } => eval_when(value, condition, body, else_body.as_deref()),
+        Statement::Match { subject, arms, .. } => eval_match(value, subject, arms),
     }
 }
 
@@ -41,19 +473,83 @@ fn eval_statement(stmt: &Statement, value: &Value) -> error::Result<Value> {
 
 fn eval_rename(value: &Value, from: &Path, to: &Path) -> error::Result<Value> {
     // Get the value at the source path
-    let extracted = resolve_path(value, &from.segments);
+    let extracted = resolve_path(value, &from.segments)?;
     match extracted {
         Some(val) => {
             // Remove from source
-            let mut result = remove_path(value, &from.segments);
+            let mut result = remove_path(value, &from.segments)?;
             // Set at destination
-            result = set_path(&result, &to.segments, val);
+            result = set_path(&result, &to.segments, val)?;
             Ok(result)
         }
         None => Ok(value.clone()), // Source doesn't exist, no-op
     }
 }
 
+// ---------------------------------------------------------------------------
+// rename keys
+// ---------------------------------------------------------------------------
+
+fn eval_rename_keys(
+    value: &Value,
+    path: Option<&Path>,
+    recursive: bool,
+    rename: &KeyRename,
+) -> error::Result<Value> {
+    let Some(path) = path else {
+        return rename_keys(value, recursive, rename);
+    };
+    match resolve_path(value, &path.segments)? {
+        Some(target) => set_path(
+            value,
+            &path.segments,
+            rename_keys(&target, recursive, rename)?,
+        ),
+        None => Ok(value.clone()),
+    }
+}
+
+/// Rename the keys of `value` if it is a map, or of each map in it if it is
+/// an array. With `recursive`, nested maps are renamed too.
+fn rename_keys(value: &Value, recursive: bool, rename: &KeyRename) -> error::Result<Value> {
+    match value {
+        Value::Map(map) => {
+            let mut renamed = IndexMap::with_capacity(map.len());
+            let mut sources: HashMap<String, &str> = HashMap::new();
+            for (key, val) in map {
+                let new_key = match rename {
+                    KeyRename::Case(case) => functions::convert_case(key, *case),
+                    KeyRename::Pattern {
+                        pattern,
+                        replacement,
+                    } => pattern
+                        .0
+                        .replace_all(key, replacement.as_str())
+                        .into_owned(),
+                };
+                if let Some(other) = sources.insert(new_key.clone(), key) {
+                    return Err(error::MorphError::mapping(format!(
+                        "rename keys: '{other}' and '{key}' both become '{new_key}'"
+                    )));
+                }
+                let val = if recursive {
+                    rename_keys(val, recursive, rename)?
+                } else {
+                    val.clone()
+                };
+                renamed.insert(new_key, val);
+            }
+            Ok(Value::Map(renamed))
+        }
+        Value::Array(items) => items
+            .iter()
+            .map(|item| rename_keys(item, recursive, rename))
+            .collect::<error::Result<Vec<_>>>()
+            .map(Value::Array),
+        _ => Ok(value.clone()),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // select
 // ---------------------------------------------------------------------------
@@ -63,7 +559,7 @@ fn eval_select(value: &Value, paths: &[Path]) -> error::Result<Value> {
         Value::Map(_) => {
             let mut result = IndexMap::new();
             for path in paths {
-                if let Some(val) = resolve_path(value, &path.segments) {
+                if let Some(val) = resolve_path(value, &path.segments)? {
                     // Use the last segment name as the key
                     if let Some(key) = last_field_name(&path.segments) {
                         result.insert(key, val);
@@ -83,7 +579,7 @@ fn eval_select(value: &Value, paths: &[Path]) -> error::Result<Value> {
 fn eval_drop(value: &Value, paths: &[Path]) -> error::Result<Value> {
     let mut result = value.clone();
     for path in paths {
-        result = remove_path(&result, &path.segments);
+        result = remove_path(&result, &path.segments)?;
     }
     Ok(result)
 }
@@ -94,7 +590,7 @@ fn eval_drop(value: &Value, paths: &[Path]) -> error::Result<Value> {
 
 fn eval_set(value: &Value, path: &Path, expr: &Expr) -> error::Result<Value> {
     let val = eval_expr(expr, value)?;
-    Ok(set_path(value, &path.segments, val))
+    set_path(value, &path.segments, val)
 }
 
 // ---------------------------------------------------------------------------
@@ -103,11 +599,11 @@ fn eval_set(value: &Value, path: &Path, expr: &Expr) -> error::Result<Value> {
 
 fn eval_default(value: &Value, path: &Path, expr: &Expr) -> error::Result<Value> {
     // Only set if path doesn't exist or is null
-    let current = resolve_path(value, &path.segments);
+    let current = resolve_path(value, &path.segments)?;
     match current {
         None | Some(Value::Null) => {
             let val = eval_expr(expr, value)?;
-            Ok(set_path(value, &path.segments, val))
+            set_path(value, &path.segments, val)
         }
         Some(_) => Ok(value.clone()),
     }
@@ -117,12 +613,25 @@ fn eval_default(value: &Value, path: &Path, expr: &Expr) -> error::Result<Value>
 // cast
 // ---------------------------------------------------------------------------
 
-fn eval_cast(value: &Value, path: &Path, target_type: &CastType) -> error::Result<Value> {
-    let current = resolve_path(value, &path.segments);
+/// Cast the value at `path`. A value that does not convert takes the
+/// `fallback`, or null under `on error null`.
+fn eval_cast(
+    value: &Value,
+    path: &Path,
+    target_type: &CastType,
+    fallback: Option<&CastFallback>,
+) -> error::Result<Value> {
+    let current = resolve_path(value, &path.segments)?;
     match current {
         Some(val) => {
-            let casted = cast_value(&val, target_type, path)?;
-            Ok(set_path(value, &path.segments, casted))
+            let casted = match (cast_value(&val, target_type, path), fallback) {
+                (Ok(casted), _) => casted,
+                (Err(_), Some(CastFallback::Null)) => Value::Null,
+                (Err(_), Some(CastFallback::Default(expr))) => eval_expr(expr, value)?,
+                (Err(_), None) if ON_ERROR.get() == OnError::Null => Value::Null,
+                (Err(e), None) => return Err(e),
+            };
+            set_path(value, &path.segments, casted)
         }
         None => Ok(value.clone()),
     }
@@ -135,8 +644,10 @@ fn value_type_name(value: &Value) -> &'static str {
         Value::Bool(_) => "bool",
         Value::Int(_) => "int",
         Value::Float(_) => "float",
+        Value::Decimal(_) => "decimal",
         Value::String(_) => "string",
         Value::Bytes(_) => "bytes",
+        Value::DateTime(_) => "datetime",
         Value::Array(_) => "array",
         Value::Map(_) => "map",
     }
@@ -145,10 +656,9 @@ fn value_type_name(value: &Value) -> &'static str {
 /// Preview a value for error messages (truncated).
 fn value_preview(value: &Value) -> String {
     let s = format!("{value}");
-    if s.len() > 50 {
-        format!("{}...", &s[..47])
-    } else {
-        s
+    match s.char_indices().nth(47) {
+        Some((end, _)) if s.chars().count() > 50 => format!("{}...", &s[..end]),
+        _ => s,
     }
 }
 
@@ -158,15 +668,29 @@ fn cast_value(value: &Value, target_type: &CastType, path: &Path) -> error::Resu
         CastType::Int => match value {
             Value::Int(_) => Ok(value.clone()),
             Value::Float(f) => Ok(Value::Int(*f as i64)),
-            Value::String(s) => s.parse::<i64>().map(Value::Int).map_err(|_| {
-                error::MorphError::mapping(format!(
-                    "cannot cast string \"{s}\" to int at {path_str}"
-                ))
-            }),
+            // Truncates like the float cast; whole numbers past the i64
+            // range stay exact.
+            Value::Decimal(d) => Ok(functions::decimal_to_integer(
+                d,
+                bigdecimal::RoundingMode::Down,
+            )),
+            Value::String(s) => match s.parse::<i64>() {
+                Ok(i) => Ok(Value::Int(i)),
+                // Digits past the i64 range become an exact big integer.
+                Err(_) => Some(s)
+                    .filter(|s| !s.contains(['.', 'e', 'E']))
+                    .and_then(|s| s.parse::<BigDecimal>().ok())
+                    .map(Value::Decimal)
+                    .ok_or_else(|| {
+                        error::MorphError::mapping(format!(
+                            "cannot cast string \"{s}\" to int at {path_str}"
+                        ))
+                    }),
+            },
             Value::Bool(b) => Ok(Value::Int(if *b { 1 } else { 0 })),
             Value::Null => Ok(Value::Int(0)),
             _ => Err(error::MorphError::mapping(format!(
-                "cannot cast {} to int at {path_str}: got {}, expected int-compatible type (int, float, string, bool, null)",
+                "cannot cast {} to int at {path_str}: got {}, expected int-compatible type (int, float, decimal, string, bool, null)",
                 value_type_name(value),
                 value_preview(value),
             ))),
@@ -174,6 +698,10 @@ fn cast_value(value: &Value, target_type: &CastType, path: &Path) -> error::Resu
         CastType::Float => match value {
             Value::Float(_) => Ok(value.clone()),
             Value::Int(i) => Ok(Value::Float(*i as f64)),
+            Value::Decimal(d) => {
+                use bigdecimal::ToPrimitive;
+                Ok(d.to_f64().map_or(Value::Null, Value::Float))
+            }
             Value::String(s) => s.parse::<f64>().map(Value::Float).map_err(|_| {
                 error::MorphError::mapping(format!(
                     "cannot cast string \"{s}\" to float at {path_str}"
@@ -182,7 +710,28 @@ fn cast_value(value: &Value, target_type: &CastType, path: &Path) -> error::Resu
             Value::Bool(b) => Ok(Value::Float(if *b { 1.0 } else { 0.0 })),
             Value::Null => Ok(Value::Float(0.0)),
             _ => Err(error::MorphError::mapping(format!(
-                "cannot cast {} to float at {path_str}: got {}, expected float-compatible type (float, int, string, bool, null)",
+                "cannot cast {} to float at {path_str}: got {}, expected float-compatible type (float, int, decimal, string, bool, null)",
+                value_type_name(value),
+                value_preview(value),
+            ))),
+        },
+        CastType::Decimal => match value {
+            Value::Decimal(_) => Ok(value.clone()),
+            Value::Int(i) => Ok(Value::Decimal((*i).into())),
+            Value::Float(f) => decimal_from_f64(*f).map(Value::Decimal).ok_or_else(|| {
+                error::MorphError::mapping(format!(
+                    "cannot cast float {f} to decimal at {path_str}"
+                ))
+            }),
+            Value::String(s) => crate::value::parse_decimal(s.trim()).map(Value::Decimal).ok_or_else(|| {
+                error::MorphError::mapping(format!(
+                    "cannot cast string \"{s}\" to decimal at {path_str}"
+                ))
+            }),
+            Value::Bool(b) => Ok(Value::Decimal(if *b { 1 } else { 0 }.into())),
+            Value::Null => Ok(Value::Decimal(0.into())),
+            _ => Err(error::MorphError::mapping(format!(
+                "cannot cast {} to decimal at {path_str}: got {}, expected decimal-compatible type (decimal, int, float, string, bool, null)",
                 value_type_name(value),
                 value_preview(value),
             ))),
@@ -191,10 +740,17 @@ fn cast_value(value: &Value, target_type: &CastType, path: &Path) -> error::Resu
             Value::String(_) => Ok(value.clone()),
             Value::Int(i) => Ok(Value::String(i.to_string())),
             Value::Float(f) => Ok(Value::String(f.to_string())),
+            Value::Decimal(d) => Ok(Value::String(crate::value::decimal_to_text(d))),
             Value::Bool(b) => Ok(Value::String(b.to_string())),
             Value::Null => Ok(Value::String("null".to_string())),
+            Value::DateTime(dt) => Ok(Value::String(dt.to_string())),
+            // UTF-8 text as is; anything else as the writers' base64.
+            Value::Bytes(b) => Ok(Value::String(match std::str::from_utf8(b) {
+                Ok(text) => text.to_string(),
+                Err(_) => crate::formats::bytes_to_base64(b),
+            })),
             _ => Err(error::MorphError::mapping(format!(
-                "cannot cast {} to string at {path_str}: got {}, expected string-compatible type (string, int, float, bool, null)",
+                "cannot cast {} to string at {path_str}: got {}, expected string-compatible type (string, int, float, decimal, bool, datetime, bytes, null)",
                 value_type_name(value),
                 value_preview(value),
             ))),
@@ -203,6 +759,7 @@ fn cast_value(value: &Value, target_type: &CastType, path: &Path) -> error::Resu
             Value::Bool(_) => Ok(value.clone()),
             Value::Int(i) => Ok(Value::Bool(*i != 0)),
             Value::Float(f) => Ok(Value::Bool(*f != 0.0)),
+            Value::Decimal(d) => Ok(Value::Bool(!bigdecimal::Zero::is_zero(d))),
             Value::String(s) => match s.to_lowercase().as_str() {
                 "true" | "1" | "yes" => Ok(Value::Bool(true)),
                 "false" | "0" | "no" | "" => Ok(Value::Bool(false)),
@@ -212,20 +769,180 @@ fn cast_value(value: &Value, target_type: &CastType, path: &Path) -> error::Resu
             },
             Value::Null => Ok(Value::Bool(false)),
             _ => Err(error::MorphError::mapping(format!(
-                "cannot cast {} to bool at {path_str}: got {}, expected bool-compatible type (bool, int, float, string, null)",
+                "cannot cast {} to bool at {path_str}: got {}, expected bool-compatible type (bool, int, float, decimal, string, null)",
+                value_type_name(value),
+                value_preview(value),
+            ))),
+        },
+        CastType::Array { split } => match (value, split) {
+            (Value::Array(_), _) => Ok(value.clone()),
+            (Value::Null, _) => Ok(Value::Array(Vec::new())),
+            (Value::String(s), Some(_)) if s.trim().is_empty() => Ok(Value::Array(Vec::new())),
+            (Value::String(s), Some(delimiter)) => Ok(Value::Array(
+                s.split(delimiter.as_str())
+                    .map(|item| Value::String(item.trim().to_string()))
+                    .collect(),
+            )),
+            (Value::Map(_), _) => Err(error::MorphError::mapping(format!(
+                "cannot cast map to array at {path_str}: got {}, expected array, string, scalar or null",
+                value_preview(value),
+            ))),
+            _ => Ok(Value::Array(vec![value.clone()])),
+        },
+        CastType::DateTime { format } => match value {
+            Value::DateTime(_) => Ok(value.clone()),
+            Value::String(s) => {
+                let parsed = match format {
+                    Some(format) => parse_datetime_with_format(s.trim(), format),
+                    None => DateTime::parse(s),
+                };
+                parsed.map(Value::DateTime).ok_or_else(|| {
+                    let expected = match format {
+                        Some(format) => format!("format \"{format}\""),
+                        None => "an RFC 3339 date or date-time".to_string(),
+                    };
+                    error::MorphError::mapping(format!(
+                        "cannot cast string {} to datetime at {path_str}: expected {expected}",
+                        value_preview(value),
+                    ))
+                })
+            }
+            Value::Int(_) | Value::Float(_) | Value::Decimal(_) => unix_datetime(value)
+                .map(Value::DateTime)
+                .ok_or_else(|| {
+                    error::MorphError::mapping(format!(
+                        "cannot cast {} to datetime at {path_str}: {} is out of range for Unix seconds",
+                        value_type_name(value),
+                        value_preview(value),
+                    ))
+                }),
+            _ => Err(error::MorphError::mapping(format!(
+                "cannot cast {} to datetime at {path_str}: got {}, expected datetime-compatible type (datetime, string, int, float, decimal)",
+                value_type_name(value),
+                value_preview(value),
+            ))),
+        },
+        CastType::Bytes { encoding } => match value {
+            Value::Bytes(_) => Ok(value.clone()),
+            Value::Null => Ok(Value::Bytes(Vec::new())),
+            Value::String(s) => match encoding {
+                BytesEncoding::Utf8 => Ok(Value::Bytes(s.as_bytes().to_vec())),
+                BytesEncoding::Base64 => {
+                    functions::call_function("base64_decode", std::slice::from_ref(value)).map_err(|_| {
+                        error::MorphError::mapping(format!(
+                            "cannot cast string {} to bytes at {path_str}: invalid base64",
+                            value_preview(value),
+                        ))
+                    })
+                }
+                BytesEncoding::Hex => decode_hex(s).map(Value::Bytes).ok_or_else(|| {
+                    error::MorphError::mapping(format!(
+                        "cannot cast string {} to bytes at {path_str}: invalid hex",
+                        value_preview(value),
+                    ))
+                }),
+            },
+            _ => Err(error::MorphError::mapping(format!(
+                "cannot cast {} to bytes at {path_str}: got {}, expected bytes-compatible type (bytes, string, null)",
                 value_type_name(value),
                 value_preview(value),
             ))),
         },
+        CastType::Object { format } => {
+            let text = match value {
+                Value::Map(_) => return Ok(value.clone()),
+                Value::Null => return Ok(Value::Map(IndexMap::new())),
+                Value::String(s) => std::borrow::Cow::Borrowed(s.as_str()),
+                Value::Bytes(b) => String::from_utf8_lossy(b),
+                _ => {
+                    return Err(error::MorphError::mapping(format!(
+                        "cannot cast {} to object at {path_str}: got {}, expected object-compatible type (map, string, bytes, null)",
+                        value_type_name(value),
+                        value_preview(value),
+                    )))
+                }
+            };
+            let parsed = crate::cli::parse_input(&text, *format).map_err(|e| {
+                let detail = match e {
+                    error::MorphError::Format { message, .. } => message,
+                    other => other.to_string(),
+                };
+                error::MorphError::mapping(format!(
+                    "cannot cast {} {} to object at {path_str}: invalid {format}: {detail}",
+                    value_type_name(value),
+                    value_preview(value),
+                ))
+            })?;
+            match parsed {
+                Value::Map(_) => Ok(parsed),
+                other => Err(error::MorphError::mapping(format!(
+                    "cannot cast {} {} to object at {path_str}: {format} holds {}, not an object",
+                    value_type_name(value),
+                    value_preview(value),
+                    value_type_name(&other),
+                ))),
+            }
+        }
     }
 }
 
+/// Parse `text` with a chrono `strftime` format, keeping as much as the
+/// format gives: an offset date-time, a local date-time, a date or a time.
+fn parse_datetime_with_format(text: &str, format: &str) -> Option<DateTime> {
+    use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+    if let Ok(instant) = chrono::DateTime::parse_from_str(text, format) {
+        return Some(DateTime::Offset(instant));
+    }
+    if let Ok(local) = NaiveDateTime::parse_from_str(text, format) {
+        return Some(DateTime::Local(local));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(text, format) {
+        return Some(DateTime::Date(date));
+    }
+    NaiveTime::parse_from_str(text, format)
+        .ok()
+        .map(DateTime::Time)
+}
+
+/// A number of Unix seconds as a UTC instant.
+fn unix_datetime(value: &Value) -> Option<DateTime> {
+    let seconds = match value {
+        Value::Int(i) => *i as f64,
+        Value::Float(f) => *f,
+        Value::Decimal(d) => bigdecimal::ToPrimitive::to_f64(d)?,
+        _ => return None,
+    };
+    if !seconds.is_finite() || seconds.abs() >= i64::MAX as f64 {
+        return None;
+    }
+    let whole = seconds.floor();
+    let nanos = ((seconds - whole) * 1e9).round().min(999_999_999.0) as u32;
+    chrono::DateTime::from_timestamp(whole as i64, nanos)
+        .map(|instant| DateTime::Offset(instant.fixed_offset()))
+}
+
+/// Decode a hex string (either case, optional `0x` prefix).
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    if !digits.len().is_multiple_of(2) || !digits.is_ascii() {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+        .collect()
+}
+
 // ---------------------------------------------------------------------------
 // flatten
 // ---------------------------------------------------------------------------
 
 fn eval_flatten(value: &Value, path: &Path, prefix: Option<&str>) -> error::Result<Value> {
-    let target_val = resolve_path(value, &path.segments);
+    let target_val = resolve_path(value, &path.segments)?;
     match target_val {
         Some(Value::Map(inner_map)) => {
             // Determine the prefix for flattened keys
@@ -238,7 +955,7 @@ fn eval_flatten(value: &Value, path: &Path, prefix: Option<&str>) -> error::Resu
             };
 
             // Remove the original nested field
-            let mut result = remove_path(value, &path.segments);
+            let mut result = remove_path(value, &path.segments)?;
 
             // Insert flattened key-value pairs into the parent map
             if let Value::Map(ref mut parent_map) = result {
@@ -261,6 +978,302 @@ fn eval_flatten(value: &Value, path: &Path, prefix: Option<&str>) -> error::Resu
     }
 }
 
+// ---------------------------------------------------------------------------
+// flatten all / unflatten all
+// ---------------------------------------------------------------------------
+
+/// Apply `f` to each record of a root array, or to a single record.
+fn map_records(value: &Value, f: impl Fn(&Value) -> Value) -> Value {
+    match value {
+        Value::Array(arr) => Value::Array(arr.iter().map(f).collect()),
+        _ => f(value),
+    }
+}
+
+fn try_map_records(
+    value: &Value,
+    f: impl Fn(&Value) -> error::Result<Value>,
+) -> error::Result<Value> {
+    match value {
+        Value::Array(arr) => arr
+            .iter()
+            .map(f)
+            .collect::<error::Result<Vec<_>>>()
+            .map(Value::Array),
+        _ => f(value),
+    }
+}
+
+/// Flatten every nested map and array of a record into top-level keys.
+fn flatten_record(record: &Value, separator: &str, arrays: &ArrayFlatten) -> Value {
+    let Value::Map(map) = record else {
+        return record.clone();
+    };
+    let mut flat = IndexMap::new();
+    for (key, value) in map {
+        flatten_into(
+            escape_flat_key(key, separator),
+            value,
+            separator,
+            arrays,
+            &mut flat,
+        );
+    }
+    Value::Map(flat)
+}
+
+fn flatten_into(
+    key: String,
+    value: &Value,
+    separator: &str,
+    arrays: &ArrayFlatten,
+    flat: &mut IndexMap<String, Value>,
+) {
+    match (value, arrays) {
+        (Value::Map(map), _) if !map.is_empty() => {
+            for (k, v) in map {
+                let k = escape_flat_key(k, separator);
+                flatten_into(format!("{key}{separator}{k}"), v, separator, arrays, flat);
+            }
+        }
+        // Arrays of scalars, even empty ones, are joined into a single cell.
+        (Value::Array(items), ArrayFlatten::Join(delimiter))
+            if !items
+                .iter()
+                .any(|item| matches!(item, Value::Array(_) | Value::Map(_))) =>
+        {
+            let joined = items
+                .iter()
+                .map(crate::formats::csv::csv_field_to_string)
+                .collect::<Vec<_>>()
+                .join(delimiter);
+            flat.insert(format!("{key}[]"), Value::String(joined));
+        }
+        (Value::Array(items), _) if !items.is_empty() => {
+            for (i, item) in items.iter().enumerate() {
+                flatten_into(
+                    format!("{key}{separator}{i}"),
+                    item,
+                    separator,
+                    arrays,
+                    flat,
+                );
+            }
+        }
+        // Empty containers are written as text so the record holds only
+        // scalars; `unflatten all` turns the text back into containers.
+        (Value::Map(_), _) => {
+            flat.insert(key, Value::String("{}".into()));
+        }
+        (Value::Array(_), _) => {
+            flat.insert(key, Value::String("[]".into()));
+        }
+        // Strings that read as the markers above get an escaping backslash.
+        (Value::String(text), _) if is_container_marker(text.trim_start_matches('\\')) => {
+            flat.insert(key, Value::String(format!("\\{text}")));
+        }
+        _ => {
+            flat.insert(key, value.clone());
+        }
+    }
+}
+
+/// The text `flatten all` writes for an empty array or map.
+fn is_container_marker(text: &str) -> bool {
+    text == "[]" || text == "{}"
+}
+
+/// Escape a map key for a flattened key, so `unflatten all` reads it back
+/// as one key: a backslash goes before each `\`, each separator and a
+/// trailing `[]`.
+fn escape_flat_key(key: &str, separator: &str) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    let mut rest = key;
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with(separator) {
+            escaped.push('\\');
+            escaped.push_str(separator);
+            rest = &rest[separator.len()..];
+            continue;
+        }
+        if c == '\\' || rest == "[]" {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    escaped
+}
+
+/// Split a flattened key at its unescaped separators, unescaping each
+/// segment, and report whether it ends in an unescaped `[]`.
+fn split_flat_key(key: &str, separator: &str) -> (Vec<String>, bool) {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut rest = key;
+    while let Some(c) = rest.chars().next() {
+        if c == '\\' {
+            let after = &rest[1..];
+            let escaped = if after.starts_with(separator) {
+                Some(separator)
+            } else if after.starts_with('\\') {
+                Some("\\")
+            } else if after.starts_with('[') {
+                Some("[")
+            } else {
+                None
+            };
+            match escaped {
+                Some(text) => {
+                    current.push_str(text);
+                    rest = &after[text.len()..];
+                }
+                // A lone backslash in hand-written input stays as it is.
+                None => {
+                    current.push('\\');
+                    rest = after;
+                }
+            }
+        } else if rest.starts_with(separator) {
+            segments.push(std::mem::take(&mut current));
+            rest = &rest[separator.len()..];
+        } else if rest == "[]" {
+            segments.push(current);
+            return (segments, true);
+        } else {
+            current.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    segments.push(current);
+    (segments, false)
+}
+
+/// Rebuild nested maps and arrays from a record flattened by `flatten all`.
+/// Maps whose keys are exactly `0` to `n - 1` become arrays, the strings
+/// `"[]"` and `"{}"` become empty containers again, and escaped separators
+/// and markers lose their backslash.
+fn unflatten_record(
+    record: &Value,
+    separator: &str,
+    arrays: &ArrayFlatten,
+) -> error::Result<Value> {
+    let Value::Map(map) = record else {
+        return Ok(record.clone());
+    };
+    let mut root = IndexMap::new();
+    for (key, value) in map {
+        let (mut segments, joined) = split_flat_key(key, separator);
+        let value = match (arrays, joined) {
+            (ArrayFlatten::Join(delimiter), true) => split_joined(value, delimiter),
+            _ => {
+                if joined {
+                    // `[]` only marks joined arrays in `arrays join` mode.
+                    segments
+                        .last_mut()
+                        .expect("split yields a segment")
+                        .push_str("[]");
+                }
+                match value {
+                    Value::String(s) if s == "[]" => Value::Array(Vec::new()),
+                    Value::String(s) if s == "{}" => Value::Map(IndexMap::new()),
+                    Value::String(s) if is_container_marker(s.trim_start_matches('\\')) => {
+                        Value::String(s[1..].to_string())
+                    }
+                    _ => value.clone(),
+                }
+            }
+        };
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        insert_flat(&mut root, &segments, value, key)?;
+    }
+    // The record itself stays a map, even if its keys look like indexes.
+    Ok(Value::Map(
+        root.into_iter()
+            .map(|(k, v)| (k, indexed_maps_to_arrays(v)))
+            .collect(),
+    ))
+}
+
+/// Split a `[]`-suffixed `arrays join` value back into its elements, which
+/// are typed the way CSV cells are.
+fn split_joined(value: &Value, delimiter: &str) -> Value {
+    match value {
+        Value::String(s) if s.is_empty() => Value::Array(Vec::new()),
+        Value::String(s) => Value::Array(
+            s.split(delimiter)
+                .map(crate::formats::csv::parse_csv_field)
+                .collect(),
+        ),
+        Value::Null => Value::Array(Vec::new()),
+        // A single element that CSV already typed, e.g. `5`.
+        other => Value::Array(vec![other.clone()]),
+    }
+}
+
+fn insert_flat(
+    map: &mut IndexMap<String, Value>,
+    segments: &[&str],
+    value: Value,
+    key: &str,
+) -> error::Result<()> {
+    let (first, rest) = segments.split_first().expect("split yields a segment");
+    if rest.is_empty() {
+        if map.contains_key(*first) {
+            return Err(unflatten_conflict(key));
+        }
+        map.insert(first.to_string(), value);
+        return Ok(());
+    }
+    let child = map
+        .entry(first.to_string())
+        .or_insert_with(|| Value::Map(IndexMap::new()));
+    match child {
+        Value::Map(child) => insert_flat(child, rest, value, key),
+        _ => Err(unflatten_conflict(key)),
+    }
+}
+
+fn unflatten_conflict(key: &str) -> error::MorphError {
+    error::MorphError::mapping(format!(
+        "unflatten: key '{key}' conflicts with another key for the same field"
+    ))
+}
+
+fn indexed_maps_to_arrays(value: Value) -> Value {
+    match value {
+        Value::Map(map) => {
+            let map: IndexMap<String, Value> = map
+                .into_iter()
+                .map(|(k, v)| (k, indexed_maps_to_arrays(v)))
+                .collect();
+            if !has_index_keys(&map) {
+                return Value::Map(map);
+            }
+            let mut items: Vec<(usize, Value)> = map
+                .into_iter()
+                .map(|(k, v)| (k.parse().expect("index key"), v))
+                .collect();
+            items.sort_by_key(|(i, _)| *i);
+            Value::Array(items.into_iter().map(|(_, v)| v).collect())
+        }
+        Value::Array(items) => {
+            Value::Array(items.into_iter().map(indexed_maps_to_arrays).collect())
+        }
+        other => other,
+    }
+}
+
+/// Are the keys of `map` exactly `0` to `n - 1`, in any order? Keys such as
+/// `01` are not indexes.
+fn has_index_keys(map: &IndexMap<String, Value>) -> bool {
+    !map.is_empty()
+        && map.keys().all(|k| {
+            k.parse::<usize>()
+                .is_ok_and(|i| i < map.len() && i.to_string() == *k)
+        })
+}
+
 // ---------------------------------------------------------------------------
 // nest
 // ---------------------------------------------------------------------------
@@ -273,7 +1286,7 @@ fn eval_nest(value: &Value, paths: &[Path], target: &Path) -> error::Result<Valu
     let mut result = value.clone();
 
     for path in paths {
-        if let Some(val) = resolve_path(value, &path.segments) {
+        if let Some(val) = resolve_path(value, &path.segments)? {
             // Get the field name from the path
             let field_name = last_field_name(&path.segments).unwrap_or_default();
 
@@ -292,16 +1305,946 @@ fn eval_nest(value: &Value, paths: &[Path], target: &Path) -> error::Result<Valu
             nested_map.insert(nested_key, val);
 
             // Remove the original field
-            result = remove_path(&result, &path.segments);
+            result = remove_path(&result, &path.segments)?;
         }
     }
 
     // Set the nested map at the target path
-    result = set_path(&result, &target.segments, Value::Map(nested_map));
+    result = set_path(&result, &target.segments, Value::Map(nested_map))?;
 
     Ok(result)
 }
 
+// ---------------------------------------------------------------------------
+// explode
+// ---------------------------------------------------------------------------
+
+fn eval_explode(value: &Value, path: &Path, target: &Path) -> error::Result<Value> {
+    match value {
+        Value::Array(arr) => {
+            let mut exploded = Vec::with_capacity(arr.len());
+            for item in arr {
+                match item {
+                    Value::Map(_) => exploded.extend(explode_record(item, path, target)?),
+                    _ => exploded.push(item.clone()),
+                }
+            }
+            Ok(Value::Array(exploded))
+        }
+        Value::Map(_) => Ok(Value::Array(explode_record(value, path, target)?)),
+        _ => Ok(value.clone()),
+    }
+}
+
+/// Copy `record` once per element of the array at `path`, storing the
+/// element at `target`. When `target` differs from `path`, the source array
+/// is removed from the copies. A missing, null or empty array, or a
+/// non-array value, yields a single record holding null or that value.
+fn explode_record(record: &Value, path: &Path, target: &Path) -> error::Result<Vec<Value>> {
+    let source = resolve_path(record, &path.segments)?;
+    let base = if path.segments == target.segments {
+        record.clone()
+    } else {
+        remove_path(record, &path.segments)?
+    };
+    match source {
+        Some(Value::Array(items)) if !items.is_empty() => items
+            .into_iter()
+            .map(|item| set_path(&base, &target.segments, item))
+            .collect(),
+        Some(Value::Array(_)) | None => Ok(vec![set_path(&base, &target.segments, Value::Null)?]),
+        Some(other) => Ok(vec![set_path(&base, &target.segments, other)?]),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// aggregate
+// ---------------------------------------------------------------------------
+
+fn eval_aggregate(value: &Value, keys: &[Path], fields: &[AggregateField]) -> error::Result<Value> {
+    let mut aggregator = Aggregator::new(keys, fields)?;
+    match value {
+        Value::Array(arr) => {
+            for item in arr {
+                aggregator.add(item)?;
+            }
+        }
+        Value::Map(_) => aggregator.add(value)?,
+        _ => return Ok(value.clone()),
+    }
+    Ok(Value::Array(aggregator.finish()?))
+}
+
+/// Is `name(...)` with `argc` arguments an aggregate call inside an
+/// `aggregate` block? Two-argument `min`/`max` stay ordinary functions.
+fn is_aggregate_call(name: &str, argc: usize) -> bool {
+    match name {
+        "count" => argc <= 1,
+        "sum" | "avg" | "min" | "max" => argc == 1,
+        _ => false,
+    }
+}
+
+/// Collect the aggregate calls in `expr`, in evaluation order.
+fn collect_aggregate_calls<'e>(
+    expr: &'e Expr,
+    calls: &mut Vec<(&'e str, Option<&'e Expr>)>,
+) -> error::Result<()> {
+    match expr {
+        Expr::FunctionCall { name, args, span } if is_aggregate_call(name, args.len()) => {
+            if let Some(arg) = args.first() {
+                let mut nested = Vec::new();
+                collect_aggregate_calls(arg, &mut nested)?;
+                if !nested.is_empty() {
+                    return Err(error::MorphError::mapping_at(
+                        format!("aggregate function {name}() cannot contain another aggregate"),
+                        span.line,
+                        span.column,
+                    ));
+                }
+            }
+            calls.push((name, args.first()));
+        }
+        Expr::FunctionCall { args, .. } => {
+            for arg in args {
+                collect_aggregate_calls(arg, calls)?;
+            }
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            collect_aggregate_calls(left, calls)?;
+            collect_aggregate_calls(right, calls)?;
+        }
+        Expr::UnaryOp { expr, .. } => collect_aggregate_calls(expr, calls)?,
+        Expr::Array(items) => {
+            for item in items {
+                collect_aggregate_calls(item, calls)?;
+            }
+        }
+        Expr::Conditional {
+            condition,
+            then_expr,
+            else_expr,
+        } => {
+            collect_aggregate_calls(condition, calls)?;
+            collect_aggregate_calls(then_expr, calls)?;
+            collect_aggregate_calls(else_expr, calls)?;
+        }
+        Expr::StringInterpolation { parts } => {
+            for part in parts {
+                if let InterpolationPart::Expr(e) = part {
+                    collect_aggregate_calls(e, calls)?;
+                }
+            }
+        }
+        Expr::Lambda { body, .. } => collect_aggregate_calls(body, calls)?,
+        Expr::Literal(_) | Expr::Path(_) | Expr::Variable { .. } => {}
+    }
+    Ok(())
+}
+
+/// Replace the calls in `expr` that `is_call` picks out (aggregate or window
+/// calls) with their results, in the order they were collected.
+fn substitute_calls(
+    expr: &Expr,
+    is_call: fn(&str, usize) -> bool,
+    results: &mut impl Iterator<Item = Value>,
+) -> Expr {
+    match expr {
+        Expr::FunctionCall { name, args, .. } if is_call(name, args.len()) => {
+            Expr::Literal(results.next().unwrap_or(Value::Null))
+        }
+        Expr::FunctionCall { name, args, span } => Expr::FunctionCall {
+            name: name.clone(),
+            args: args
+                .iter()
+                .map(|a| substitute_calls(a, is_call, results))
+                .collect(),
+            span: *span,
+        },
+        Expr::BinaryOp { left, op, right } => Expr::BinaryOp {
+            left: Box::new(substitute_calls(left, is_call, results)),
+            op: *op,
+            right: Box::new(substitute_calls(right, is_call, results)),
+        },
+        Expr::UnaryOp { op, expr } => Expr::UnaryOp {
+            op: *op,
+            expr: Box::new(substitute_calls(expr, is_call, results)),
+        },
+        Expr::Array(items) => Expr::Array(
+            items
+                .iter()
+                .map(|item| substitute_calls(item, is_call, results))
+                .collect(),
+        ),
+        Expr::Conditional {
+            condition,
+            then_expr,
+            else_expr,
+        } => Expr::Conditional {
+            condition: Box::new(substitute_calls(condition, is_call, results)),
+            then_expr: Box::new(substitute_calls(then_expr, is_call, results)),
+            else_expr: Box::new(substitute_calls(else_expr, is_call, results)),
+        },
+        Expr::StringInterpolation { parts } => Expr::StringInterpolation {
+            parts: parts
+                .iter()
+                .map(|part| match part {
+                    InterpolationPart::Expr(e) => {
+                        InterpolationPart::Expr(substitute_calls(e, is_call, results))
+                    }
+                    literal => literal.clone(),
+                })
+                .collect(),
+        },
+        Expr::Lambda { params, body, span } => Expr::Lambda {
+            params: params.clone(),
+            body: Box::new(substitute_calls(body, is_call, results)),
+            span: *span,
+        },
+        other => other.clone(),
+    }
+}
+
+/// Running state of one aggregate call for one group.
+enum Accumulator {
+    Count(i64),
+    /// Totals use the same arithmetic as `+`: ints stay exact (promoting to
+    /// a decimal on overflow) until a float or decimal joins in.
+    Sum(Value),
+    Avg {
+        total: Value,
+        n: usize,
+    },
+    Min(Option<Value>),
+    Max(Option<Value>),
+}
+
+impl Accumulator {
+    fn new(name: &str) -> Self {
+        match name {
+            "count" => Accumulator::Count(0),
+            "sum" => Accumulator::Sum(Value::Int(0)),
+            "avg" => Accumulator::Avg {
+                total: Value::Int(0),
+                n: 0,
+            },
+            "min" => Accumulator::Min(None),
+            _ => Accumulator::Max(None),
+        }
+    }
+
+    /// Add one record's value. `None` means the call had no argument
+    /// (`count()`); null values are skipped by every other form.
+    fn add(&mut self, value: Option<Value>) -> error::Result<()> {
+        let value = match value {
+            None => {
+                if let Accumulator::Count(n) = self {
+                    *n += 1;
+                }
+                return Ok(());
+            }
+            Some(Value::Null) => return Ok(()),
+            Some(v) => v,
+        };
+        match self {
+            Accumulator::Count(n) => *n += 1,
+            Accumulator::Sum(total) => match value {
+                Value::Int(_) | Value::Float(_) | Value::Decimal(_) => {
+                    *total = eval_add(total, &value)?;
+                }
+                other => return Err(aggregate_type_error("sum", &other)),
+            },
+            Accumulator::Avg { total, n } => match value {
+                Value::Int(_) | Value::Float(_) | Value::Decimal(_) => {
+                    *total = eval_add(total, &value)?;
+                    *n += 1;
+                }
+                other => return Err(aggregate_type_error("avg", &other)),
+            },
+            Accumulator::Min(current) => keep_extreme(current, value, std::cmp::Ordering::Less),
+            Accumulator::Max(current) => keep_extreme(current, value, std::cmp::Ordering::Greater),
+        }
+        Ok(())
+    }
+
+    fn finish(&self) -> Value {
+        match self {
+            Accumulator::Count(n) => Value::Int(*n),
+            Accumulator::Sum(total) => total.clone(),
+            Accumulator::Avg { total, n } => match total {
+                _ if *n == 0 => Value::Null,
+                Value::Int(t) => Value::Float(*t as f64 / *n as f64),
+                Value::Float(t) => Value::Float(t / *n as f64),
+                Value::Decimal(t) => Value::Decimal(t / BigDecimal::from(*n as u64)),
+                other => other.clone(),
+            },
+            Accumulator::Min(v) | Accumulator::Max(v) => v.clone().unwrap_or(Value::Null),
+        }
+    }
+}
+
+/// Replace `current` with `value` when it orders as `want` (or when no value
+/// has been seen yet), by the same total order as `min()`, `max()` and `sort`.
+fn keep_extreme(current: &mut Option<Value>, value: Value, want: std::cmp::Ordering) {
+    match current {
+        Some(cur) if value.cmp(cur) != want => {}
+        _ => *current = Some(value),
+    }
+}
+
+fn aggregate_type_error(name: &str, value: &Value) -> error::MorphError {
+    error::MorphError::mapping(format!(
+        "{name}() in aggregate expects numbers, got {}",
+        value_type_name(value)
+    ))
+}
+
+/// Hash-based group-by state for an `aggregate` statement. Only the group
+/// keys and one accumulator per aggregate call are kept, never the records.
+struct Aggregator<'a> {
+    keys: &'a [Path],
+    fields: &'a [AggregateField],
+    /// The aggregate calls of every field, flattened in field order.
+    calls: Vec<(&'a str, Option<&'a Expr>)>,
+    groups: IndexMap<Vec<Value>, Vec<Accumulator>>,
+    records_seen: bool,
+}
+
+impl<'a> Aggregator<'a> {
+    fn new(keys: &'a [Path], fields: &'a [AggregateField]) -> error::Result<Self> {
+        let mut calls = Vec::new();
+        for field in fields {
+            collect_aggregate_calls(&field.expr, &mut calls)?;
+        }
+        Ok(Aggregator {
+            keys,
+            fields,
+            calls,
+            groups: IndexMap::new(),
+            records_seen: false,
+        })
+    }
+
+    fn new_accumulators(&self) -> Vec<Accumulator> {
+        self.calls
+            .iter()
+            .map(|(name, _)| Accumulator::new(name))
+            .collect()
+    }
+
+    /// Fold one record into its group. Records that are not maps (such as
+    /// the null left by a failed `where`) are skipped.
+    fn add(&mut self, record: &Value) -> error::Result<()> {
+        if !matches!(record, Value::Map(_)) {
+            return Ok(());
+        }
+        self.records_seen = true;
+        let mut key_values = Vec::with_capacity(self.keys.len());
+        for key in self.keys {
+            key_values.push(resolve_path(record, &key.segments)?.unwrap_or(Value::Null));
+        }
+        // Keys compare as values, so 1 and "1" form separate groups.
+        if !self.groups.contains_key(&key_values) {
+            let accs = self.new_accumulators();
+            self.groups.insert(key_values.clone(), accs);
+        }
+        let accumulators = self.groups.get_mut(&key_values).unwrap();
+        for ((_, arg), acc) in self.calls.iter().zip(accumulators.iter_mut()) {
+            let value = match arg {
+                Some(expr) => Some(eval_expr(expr, record)?),
+                None => None,
+            };
+            acc.add(value)?;
+        }
+        Ok(())
+    }
+
+    /// Build one output record per group, in first-seen order.
+    fn finish(&mut self) -> error::Result<Vec<Value>> {
+        // Without `by`, an empty input still yields one summary record.
+        if self.keys.is_empty() && !self.records_seen {
+            let accs = self.new_accumulators();
+            self.groups.insert(Vec::new(), accs);
+        }
+        let mut records = Vec::with_capacity(self.groups.len());
+        for (key_values, accumulators) in self.groups.drain(..) {
+            let mut record = Value::Map(IndexMap::new());
+            for (key, val) in self.keys.iter().zip(key_values) {
+                record = set_path(&record, &key.segments, val)?;
+            }
+            let mut results = accumulators.iter().map(Accumulator::finish);
+            for field in self.fields {
+                let expr = substitute_calls(&field.expr, is_aggregate_call, &mut results);
+                let val = eval_expr(&expr, &record)?;
+                record = set_path(&record, &field.path.segments, val)?;
+            }
+            records.push(record);
+        }
+        Ok(records)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// pivot / unpivot
+// ---------------------------------------------------------------------------
+
+fn eval_pivot(value: &Value, key: &Path, val: &Path, by: &[Path]) -> error::Result<Value> {
+    let mut pivoter = Pivoter::new(key, val, by);
+    match value {
+        Value::Array(arr) => {
+            for item in arr {
+                pivoter.add(item)?;
+            }
+        }
+        Value::Map(_) => pivoter.add(value)?,
+        _ => return Ok(value.clone()),
+    }
+    Ok(Value::Array(pivoter.finish()?))
+}
+
+/// Long-to-wide state for a `pivot` statement: one row of cells per group,
+/// plus every column name in first-seen order.
+struct Pivoter<'a> {
+    key: &'a Path,
+    value: &'a Path,
+    by: &'a [Path],
+    columns: IndexSet<String>,
+    rows: IndexMap<Vec<Value>, IndexMap<String, Value>>,
+}
+
+impl<'a> Pivoter<'a> {
+    fn new(key: &'a Path, value: &'a Path, by: &'a [Path]) -> Self {
+        Pivoter {
+            key,
+            value,
+            by,
+            columns: IndexSet::new(),
+            rows: IndexMap::new(),
+        }
+    }
+
+    /// Store one record's value in its row. Records that are not maps or
+    /// have a null or missing key are skipped; a repeated cell keeps the
+    /// last value.
+    fn add(&mut self, record: &Value) -> error::Result<()> {
+        if !matches!(record, Value::Map(_)) {
+            return Ok(());
+        }
+        let column = match resolve_path(record, &self.key.segments)? {
+            None | Some(Value::Null) => return Ok(()),
+            Some(key) => value_to_display(&key),
+        };
+        let cell = resolve_path(record, &self.value.segments)?.unwrap_or(Value::Null);
+        let mut by_values = Vec::with_capacity(self.by.len());
+        for path in self.by {
+            by_values.push(resolve_path(record, &path.segments)?.unwrap_or(Value::Null));
+        }
+        // Keys compare as values, so 1 and "1" form separate rows.
+        let cells = self.rows.entry(by_values).or_default();
+        cells.insert(column.clone(), cell);
+        self.columns.insert(column);
+        Ok(())
+    }
+
+    /// Build one record per row, in first-seen order. Every record has
+    /// every column, so the output is rectangular.
+    fn finish(&mut self) -> error::Result<Vec<Value>> {
+        let mut records = Vec::with_capacity(self.rows.len());
+        for (by_values, mut cells) in self.rows.drain(..) {
+            let mut record = Value::Map(IndexMap::new());
+            for (path, val) in self.by.iter().zip(by_values) {
+                record = set_path(&record, &path.segments, val)?;
+            }
+            if let Value::Map(fields) = &mut record {
+                // Column names are inserted as-is, even if they contain dots.
+                for column in &self.columns {
+                    let cell = cells.swap_remove(column).unwrap_or(Value::Null);
+                    fields.insert(column.clone(), cell);
+                }
+            }
+            records.push(record);
+        }
+        Ok(records)
+    }
+}
+
+fn eval_unpivot(value: &Value, paths: &[Path], name: &Path, val: &Path) -> error::Result<Value> {
+    match value {
+        Value::Array(arr) => {
+            let mut rows = Vec::with_capacity(arr.len() * paths.len());
+            for item in arr {
+                match item {
+                    Value::Map(_) => rows.extend(unpivot_record(item, paths, name, val)?),
+                    _ => rows.push(item.clone()),
+                }
+            }
+            Ok(Value::Array(rows))
+        }
+        Value::Map(_) => Ok(Value::Array(unpivot_record(value, paths, name, val)?)),
+        _ => Ok(value.clone()),
+    }
+}
+
+/// Turn the listed fields of `record` into one record each, holding the
+/// field name at `name` and its value at `value`. The other fields are
+/// copied to every row. Missing fields yield no row; null ones do.
+fn unpivot_record(
+    record: &Value,
+    paths: &[Path],
+    name: &Path,
+    value: &Path,
+) -> error::Result<Vec<Value>> {
+    let mut base = record.clone();
+    for path in paths {
+        base = remove_path(&base, &path.segments)?;
+    }
+    let mut rows = Vec::with_capacity(paths.len());
+    for path in paths {
+        let Some(cell) = resolve_path(record, &path.segments)? else {
+            continue;
+        };
+        let field = last_field_name(&path.segments).unwrap_or_else(|| path.to_string());
+        let row = set_path(&base, &name.segments, Value::String(field))?;
+        rows.push(set_path(&row, &value.segments, cell)?);
+    }
+    Ok(rows)
+}
+
+// ---------------------------------------------------------------------------
+// distinct
+// ---------------------------------------------------------------------------
+
+fn eval_distinct(value: &Value, by: &[Path], keep: DistinctKeep) -> error::Result<Value> {
+    // A single record is already distinct.
+    let Value::Array(arr) = value else {
+        return Ok(value.clone());
+    };
+    match keep {
+        DistinctKeep::First => {
+            let mut seen = SeenSet::new(None);
+            let mut kept = Vec::new();
+            for item in arr {
+                if seen.insert(distinct_key(item, by)?) {
+                    kept.push(item.clone());
+                }
+            }
+            Ok(Value::Array(kept))
+        }
+        DistinctKeep::Last => {
+            let mut latest = LatestByKey::default();
+            for item in arr {
+                latest.insert(distinct_key(item, by)?, item.clone());
+            }
+            Ok(Value::Array(latest.drain()))
+        }
+    }
+}
+
+/// The values that make a record a duplicate: the `by` paths (missing
+/// counts as null), or the whole record without `by`.
+fn distinct_key(record: &Value, by: &[Path]) -> error::Result<Value> {
+    if by.is_empty() {
+        return Ok(record.clone());
+    }
+    let mut key = Vec::with_capacity(by.len());
+    for path in by {
+        key.push(resolve_path(record, &path.segments)?.unwrap_or(Value::Null));
+    }
+    Ok(Value::Array(key))
+}
+
+// ---------------------------------------------------------------------------
+// window functions
+// ---------------------------------------------------------------------------
+
+fn eval_window(
+    value: &Value,
+    path: &Path,
+    expr: &Expr,
+    partition: &[Path],
+    order: &[SortKey],
+) -> error::Result<Value> {
+    let mut windower = Windower::new(path, expr, partition, order)?;
+    match value {
+        Value::Array(arr) => {
+            for item in arr {
+                windower.add(item);
+            }
+            Ok(Value::Array(windower.finish()?))
+        }
+        // A single record is a window of one.
+        Value::Map(_) => {
+            windower.add(value);
+            Ok(windower.finish()?.remove(0))
+        }
+        _ => Ok(value.clone()),
+    }
+}
+
+/// Is `name` a window function? Window functions are only evaluated by a
+/// top-level `set`, never as ordinary functions.
+fn is_window_function(name: &str) -> bool {
+    matches!(
+        name,
+        "row_number"
+            | "rank"
+            | "dense_rank"
+            | "running_count"
+            | "running_sum"
+            | "running_avg"
+            | "running_min"
+            | "running_max"
+            | "lag"
+            | "lead"
+    )
+}
+
+/// Does `expr` call a window function? Errors on window calls with the
+/// wrong number of arguments or nested inside each other.
+pub(crate) fn has_window_call(expr: &Expr) -> error::Result<bool> {
+    let mut calls = Vec::new();
+    collect_window_calls(expr, &mut calls)?;
+    Ok(!calls.is_empty())
+}
+
+/// Collect the window calls in `expr`, in evaluation order.
+fn collect_window_calls<'e>(
+    expr: &'e Expr,
+    calls: &mut Vec<(&'e str, &'e [Expr])>,
+) -> error::Result<()> {
+    match expr {
+        Expr::FunctionCall { name, args, span } if is_window_function(name) => {
+            let (min, max) = match name.as_str() {
+                "row_number" | "rank" | "dense_rank" => (0, 0),
+                "running_count" => (0, 1),
+                "lag" | "lead" => (1, 3),
+                _ => (1, 1),
+            };
+            if args.len() < min || args.len() > max {
+                let expected = match (min, max) {
+                    (0, 0) => "no arguments".to_string(),
+                    (min, max) if min == max => format!("{min} argument"),
+                    (min, max) => format!("{min} to {max} arguments"),
+                };
+                return Err(error::MorphError::mapping_at(
+                    format!("{name}() takes {expected}, got {}", args.len()),
+                    span.line,
+                    span.column,
+                ));
+            }
+            let mut nested = Vec::new();
+            for arg in args {
+                collect_window_calls(arg, &mut nested)?;
+            }
+            if !nested.is_empty() {
+                return Err(error::MorphError::mapping_at(
+                    format!("window function {name}() cannot contain another window function"),
+                    span.line,
+                    span.column,
+                ));
+            }
+            calls.push((name, args));
+        }
+        Expr::FunctionCall { args, .. } => {
+            for arg in args {
+                collect_window_calls(arg, calls)?;
+            }
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            collect_window_calls(left, calls)?;
+            collect_window_calls(right, calls)?;
+        }
+        Expr::UnaryOp { expr, .. } => collect_window_calls(expr, calls)?,
+        Expr::Array(items) => {
+            for item in items {
+                collect_window_calls(item, calls)?;
+            }
+        }
+        Expr::Conditional {
+            condition,
+            then_expr,
+            else_expr,
+        } => {
+            collect_window_calls(condition, calls)?;
+            collect_window_calls(then_expr, calls)?;
+            collect_window_calls(else_expr, calls)?;
+        }
+        Expr::StringInterpolation { parts } => {
+            for part in parts {
+                if let InterpolationPart::Expr(e) = part {
+                    collect_window_calls(e, calls)?;
+                }
+            }
+        }
+        Expr::Lambda { body, .. } => collect_window_calls(body, calls)?,
+        Expr::Literal(_) | Expr::Path(_) | Expr::Variable { .. } => {}
+    }
+    Ok(())
+}
+
+/// Running state of one window call within one partition.
+enum WindowState {
+    Rank(i64),
+    Running(Accumulator),
+    Stateless,
+}
+
+impl WindowState {
+    fn new(name: &str) -> Self {
+        match name {
+            "rank" | "dense_rank" => WindowState::Rank(0),
+            _ => match name.strip_prefix("running_") {
+                Some(aggregate) => WindowState::Running(Accumulator::new(aggregate)),
+                None => WindowState::Stateless,
+            },
+        }
+    }
+}
+
+/// Collects records for a window `set` and evaluates it once every record
+/// is known. Records keep their input order.
+struct Windower<'a> {
+    path: &'a Path,
+    expr: &'a Expr,
+    partition: &'a [Path],
+    order: &'a [SortKey],
+    calls: Vec<(&'a str, &'a [Expr])>,
+    records: Vec<Value>,
+}
+
+impl<'a> Windower<'a> {
+    fn new(
+        path: &'a Path,
+        expr: &'a Expr,
+        partition: &'a [Path],
+        order: &'a [SortKey],
+    ) -> error::Result<Self> {
+        let mut calls = Vec::new();
+        collect_window_calls(expr, &mut calls)?;
+        Ok(Windower {
+            path,
+            expr,
+            partition,
+            order,
+            calls,
+            records: Vec::new(),
+        })
+    }
+
+    fn add(&mut self, record: &Value) {
+        self.records.push(record.clone());
+    }
+
+    fn finish(&mut self) -> error::Result<Vec<Value>> {
+        let mut records = std::mem::take(&mut self.records);
+
+        // Without `partition by`, all records share one partition. Values
+        // that are not records take no part and pass through unchanged.
+        let mut partitions: IndexMap<Value, Vec<usize>> = IndexMap::new();
+        for (pos, record) in records.iter().enumerate() {
+            if !matches!(record, Value::Map(_)) {
+                continue;
+            }
+            let key = if self.partition.is_empty() {
+                Value::Null
+            } else {
+                distinct_key(record, self.partition)?
+            };
+            partitions.entry(key).or_default().push(pos);
+        }
+
+        // Every call is evaluated against the original records, so `lag`
+        // sees values from before this statement ran.
+        let mut results = vec![Vec::new(); records.len()];
+        for rows in partitions.values() {
+            let mut keyed = Vec::with_capacity(rows.len());
+            for &pos in rows {
+                keyed.push((sort_key_values(&records[pos], self.order)?, pos));
+            }
+            keyed.sort_by(|(a, _), (b, _)| compare_sort_keys(self.order, a, b));
+            let ordered: Vec<usize> = keyed.iter().map(|(_, pos)| *pos).collect();
+
+            let mut states: Vec<WindowState> = self
+                .calls
+                .iter()
+                .map(|(name, _)| WindowState::new(name))
+                .collect();
+            for (i, &pos) in ordered.iter().enumerate() {
+                let is_peer = i > 0
+                    && compare_sort_keys(self.order, &keyed[i - 1].0, &keyed[i].0)
+                        == std::cmp::Ordering::Equal;
+                let mut row = Vec::with_capacity(self.calls.len());
+                for ((name, args), state) in self.calls.iter().zip(&mut states) {
+                    row.push(window_value(
+                        name, args, state, i, is_peer, &ordered, &records,
+                    )?);
+                }
+                results[pos] = row;
+            }
+        }
+
+        for (record, row) in records.iter_mut().zip(results) {
+            if !matches!(record, Value::Map(_)) {
+                continue;
+            }
+            let expr = substitute_calls(
+                self.expr,
+                |name, _| is_window_function(name),
+                &mut row.into_iter(),
+            );
+            let value = eval_expr(&expr, record)?;
+            *record = set_path(record, &self.path.segments, value)?;
+        }
+        Ok(records)
+    }
+}
+
+/// The value of one window call for the `i`-th record of a partition, whose
+/// records are `ordered` (positions into `records`). `is_peer` is true when
+/// the record ties with the one before it on the `order by` keys.
+fn window_value(
+    name: &str,
+    args: &[Expr],
+    state: &mut WindowState,
+    i: usize,
+    is_peer: bool,
+    ordered: &[usize],
+    records: &[Value],
+) -> error::Result<Value> {
+    let current = &records[ordered[i]];
+    match (name, state) {
+        ("row_number", _) => Ok(Value::Int(i as i64 + 1)),
+        ("rank", WindowState::Rank(rank)) => {
+            if !is_peer {
+                *rank = i as i64 + 1;
+            }
+            Ok(Value::Int(*rank))
+        }
+        ("dense_rank", WindowState::Rank(rank)) => {
+            if !is_peer {
+                *rank += 1;
+            }
+            Ok(Value::Int(*rank))
+        }
+        ("lag" | "lead", _) => {
+            let offset = match args.get(1) {
+                None => 1,
+                Some(arg) => match eval_expr(arg, current)? {
+                    Value::Int(n) if n >= 0 => n as usize,
+                    other => {
+                        return Err(error::MorphError::mapping(format!(
+                            "{name}() offset must be a non-negative integer, got {}",
+                            value_preview(&other)
+                        )));
+                    }
+                },
+            };
+            let target = if name == "lag" {
+                i.checked_sub(offset)
+            } else {
+                i.checked_add(offset).filter(|&j| j < ordered.len())
+            };
+            match (target, args.get(2)) {
+                (Some(j), _) => eval_expr(&args[0], &records[ordered[j]]),
+                (None, Some(default)) => eval_expr(default, current),
+                (None, None) => Ok(Value::Null),
+            }
+        }
+        (_, WindowState::Running(accumulator)) => {
+            let value = args
+                .first()
+                .map(|arg| eval_expr(arg, current))
+                .transpose()?;
+            accumulator.add(value)?;
+            Ok(accumulator.finish())
+        }
+        _ => unreachable!("window state does not match {name}()"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// limit / skip / tail / sample
+// ---------------------------------------------------------------------------
+
+/// Apply `slice` to the records of an array. A single record is left as is.
+fn eval_slice(value: &Value, slice: impl FnOnce(&[Value]) -> Vec<Value>) -> Value {
+    match value {
+        Value::Array(arr) => Value::Array(slice(arr)),
+        _ => value.clone(),
+    }
+}
+
+fn eval_sample(value: &Value, count: usize, seed: Option<u64>) -> Value {
+    eval_slice(value, |arr| {
+        let mut reservoir = Reservoir::new(count, Rng::from_seed(seed));
+        for item in arr {
+            reservoir.add(item.clone());
+        }
+        reservoir.drain()
+    })
+}
+
+// ---------------------------------------------------------------------------
+// join
+// ---------------------------------------------------------------------------
+
+fn eval_join(value: &Value, join: &Statement, lookups: &Lookups) -> error::Result<Value> {
+    match value {
+        Value::Array(arr) => {
+            let mut joined = Vec::with_capacity(arr.len());
+            for item in arr {
+                joined.extend(join_record(item, join, lookups)?);
+            }
+            Ok(Value::Array(joined))
+        }
+        // Like `where`, an inner join on a single record that has no match
+        // leaves null behind.
+        _ => Ok(join_record(value, join, lookups)?.unwrap_or(Value::Null)),
+    }
+}
+
+/// Join one record against its lookup table. Returns None when an inner
+/// join finds no match and the record should be dropped.
+fn join_record(
+    record: &Value,
+    join: &Statement,
+    lookups: &Lookups,
+) -> error::Result<Option<Value>> {
+    let Statement::Join {
+        kind,
+        table,
+        key,
+        lookup_key,
+        target,
+        ..
+    } = join
+    else {
+        unreachable!("join_record called with a non-join statement")
+    };
+    let key_value = resolve_path(record, &key.segments)?.unwrap_or(Value::Null);
+    let matched = lookups.find(table, lookup_key, &key_value)?;
+    match (matched, target) {
+        (Some(found), Some(target)) => set_path(record, &target.segments, found.clone()).map(Some),
+        (Some(Value::Map(fields)), None) => match record {
+            // Fields already on the record win over the looked-up ones.
+            Value::Map(existing) => {
+                let mut merged = existing.clone();
+                for (k, v) in fields {
+                    if !merged.contains_key(k) {
+                        merged.insert(k.clone(), v.clone());
+                    }
+                }
+                Ok(Some(Value::Map(merged)))
+            }
+            _ => Ok(Some(record.clone())),
+        },
+        (Some(_), None) => Ok(Some(record.clone())),
+        (None, _) if *kind == JoinKind::Inner => Ok(None),
+        (None, Some(target)) => set_path(record, &target.segments, Value::Null).map(Some),
+        (None, None) => Ok(Some(record.clone())),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // where (filter)
 // ---------------------------------------------------------------------------
@@ -338,41 +2281,56 @@ fn eval_where(value: &Value, condition: &Expr) -> error::Result<Value> {
 fn eval_sort(value: &Value, keys: &[SortKey]) -> error::Result<Value> {
     match value {
         Value::Array(arr) => {
-            let mut sorted = arr.clone();
-            sorted.sort_by(|a, b| {
-                for key in keys {
-                    let val_a = resolve_path(a, &key.path.segments).unwrap_or(Value::Null);
-                    let val_b = resolve_path(b, &key.path.segments).unwrap_or(Value::Null);
-
-                    // Nulls always sort last regardless of direction
-                    let ordering = match (&val_a, &val_b) {
-                        (Value::Null, Value::Null) => std::cmp::Ordering::Equal,
-                        (Value::Null, _) => std::cmp::Ordering::Greater, // null last
-                        (_, Value::Null) => std::cmp::Ordering::Less,    // null last
-                        _ => compare_values(&val_a, &val_b).unwrap_or(std::cmp::Ordering::Equal),
-                    };
+            // Resolve every sort key up front so path errors can propagate.
+            let mut keyed = Vec::with_capacity(arr.len());
+            for item in arr {
+                keyed.push((sort_key_values(item, keys)?, item));
+            }
+            keyed.sort_by(|(vals_a, _), (vals_b, _)| compare_sort_keys(keys, vals_a, vals_b));
+            Ok(Value::Array(
+                keyed.into_iter().map(|(_, item)| item.clone()).collect(),
+            ))
+        }
+        _ => Ok(value.clone()), // non-array: no-op
+    }
+}
 
-                    let ordering = match key.direction {
-                        SortDirection::Asc => ordering,
-                        SortDirection::Desc => {
-                            // Reverse, but keep nulls last
-                            match (&val_a, &val_b) {
-                                (Value::Null, _) | (_, Value::Null) => ordering,
-                                _ => ordering.reverse(),
-                            }
-                        }
-                    };
+/// The values of `keys` in `item`; missing fields sort as null.
+fn sort_key_values(item: &Value, keys: &[SortKey]) -> error::Result<Vec<Value>> {
+    let mut vals = Vec::with_capacity(keys.len());
+    for key in keys {
+        vals.push(resolve_path(item, &key.path.segments)?.unwrap_or(Value::Null));
+    }
+    Ok(vals)
+}
 
-                    if ordering != std::cmp::Ordering::Equal {
-                        return ordering;
-                    }
+/// Compare two records' [`sort_key_values`].
+fn compare_sort_keys(keys: &[SortKey], vals_a: &[Value], vals_b: &[Value]) -> std::cmp::Ordering {
+    for (key, (val_a, val_b)) in keys.iter().zip(vals_a.iter().zip(vals_b)) {
+        // Nulls always sort last regardless of direction
+        let ordering = match (val_a, val_b) {
+            (Value::Null, Value::Null) => std::cmp::Ordering::Equal,
+            (Value::Null, _) => std::cmp::Ordering::Greater, // null last
+            (_, Value::Null) => std::cmp::Ordering::Less,    // null last
+            _ => val_a.cmp(val_b),
+        };
+
+        let ordering = match key.direction {
+            SortDirection::Asc => ordering,
+            SortDirection::Desc => {
+                // Reverse, but keep nulls last
+                match (val_a, val_b) {
+                    (Value::Null, _) | (_, Value::Null) => ordering,
+                    _ => ordering.reverse(),
                 }
-                std::cmp::Ordering::Equal
-            });
-            Ok(Value::Array(sorted))
+            }
+        };
+
+        if ordering != std::cmp::Ordering::Equal {
+            return ordering;
         }
-        _ => Ok(value.clone()), // non-array: no-op
     }
+    std::cmp::Ordering::Equal
 }
 
 // ---------------------------------------------------------------------------
@@ -380,7 +2338,23 @@ fn eval_sort(value: &Value, keys: &[SortKey]) -> error::Result<Value> {
 // ---------------------------------------------------------------------------
 
 fn eval_each(value: &Value, path: &Path, body: &[Statement]) -> error::Result<Value> {
-    let target = resolve_path(value, &path.segments);
+    // `each .items[1:3]` runs the body on the sliced elements in place.
+    if let Some((PathSegment::Slice { start, end, step }, parent)) = path.segments.split_last() {
+        return match resolve_path(value, parent)? {
+            Some(target @ Value::Array(_)) => {
+                let updated = update_slice(&target, *start, *end, *step, &mut |item: &Value| {
+                    eval_block(item, body).map(Some)
+                })?;
+                set_path(value, parent, updated)
+            }
+            Some(_) => Err(error::MorphError::mapping("each requires an array target")),
+            None => Err(error::MorphError::mapping(
+                "each target path does not exist",
+            )),
+        };
+    }
+
+    let target = resolve_path(value, &path.segments)?;
     match target {
         Some(Value::Array(arr)) => {
             let mut updated = Vec::with_capacity(arr.len());
@@ -391,7 +2365,7 @@ fn eval_each(value: &Value, path: &Path, body: &[Statement]) -> error::Result<Va
                 }
                 updated.push(result);
             }
-            Ok(set_path(value, &path.segments, Value::Array(updated)))
+            set_path(value, &path.segments, Value::Array(updated))
         }
         Some(_) => Err(error::MorphError::mapping("each requires an array target")),
         None => Err(error::MorphError::mapping(
@@ -404,41 +2378,173 @@ fn eval_each(value: &Value, path: &Path, body: &[Statement]) -> error::Result<Va
 // when
 // ---------------------------------------------------------------------------
 
-fn eval_when(value: &Value, condition: &Expr, body: &[Statement]) -> error::Result<Value> {
+fn eval_when(
+    value: &Value,
+    condition: &Expr,
+    body: &[Statement],
+    else_body: Option<&[Statement]>,
+) -> error::Result<Value> {
     let cond_result = eval_expr(condition, value)?;
     if is_truthy(&cond_result) {
-        let mut result = value.clone();
-        for stmt in body {
-            result = eval_statement(stmt, &result)?;
-        }
-        Ok(result)
+        eval_block(value, body)
+    } else if let Some(else_body) = else_body {
+        eval_block(value, else_body)
     } else {
         Ok(value.clone())
     }
 }
 
+fn eval_block(value: &Value, body: &[Statement]) -> error::Result<Value> {
+    let mut result = value.clone();
+    for stmt in body {
+        result = eval_statement(stmt, &result)?;
+    }
+    Ok(result)
+}
+
+// ---------------------------------------------------------------------------
+// match
+// ---------------------------------------------------------------------------
+
+fn eval_match(value: &Value, subject: &Expr, arms: &[MatchArm]) -> error::Result<Value> {
+    // The subject is evaluated once, so an arm that rewrites the matched
+    // field cannot cause a later arm to fire as well.
+    let subject_val = eval_expr(subject, value)?;
+    for arm in arms {
+        let matched = arm.patterns.iter().any(|pattern| match pattern {
+            MatchPattern::Wildcard => true,
+            MatchPattern::Literal(lit) => values_equal(&subject_val, lit),
+        });
+        if matched {
+            return eval_block(value, &arm.body);
+        }
+    }
+    Ok(value.clone())
+}
+
 // ---------------------------------------------------------------------------
 // Expression evaluation
 // ---------------------------------------------------------------------------
 
+/// Lambda parameter bindings visible while evaluating an expression.
+///
+/// Each lambda invocation pushes one frame; inner lambdas can still see the
+/// parameters of the lambdas enclosing them.
+struct Scope<'a> {
+    bindings: Vec<(&'a str, Value)>,
+    parent: Option<&'a Scope<'a>>,
+}
+
+impl Scope<'_> {
+    fn lookup(&self, name: &str) -> Option<&Value> {
+        self.bindings
+            .iter()
+            .rev()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v)
+            .or_else(|| self.parent.and_then(|p| p.lookup(name)))
+    }
+}
+
+thread_local! {
+    /// The `on error` policy of the program being evaluated.
+    static ON_ERROR: Cell<OnError> = const { Cell::new(OnError::Fail) };
+}
+
+/// Run `f` with `policy` as the `on error` policy, restoring the previous
+/// one afterwards.
+fn with_on_error<T>(policy: OnError, f: impl FnOnce() -> T) -> T {
+    let previous = ON_ERROR.replace(policy);
+    let result = f();
+    ON_ERROR.set(previous);
+    result
+}
+
+/// Evaluate a statement's expression. With `on error null`, an expression
+/// that fails yields null.
 fn eval_expr(expr: &Expr, context: &Value) -> error::Result<Value> {
+    match eval_expr_in(expr, context, None) {
+        Err(_) if ON_ERROR.get() == OnError::Null => Ok(Value::Null),
+        result => result,
+    }
+}
+
+fn eval_expr_in(expr: &Expr, context: &Value, scope: Option<&Scope>) -> error::Result<Value> {
     match expr {
         Expr::Literal(val) => Ok(val.clone()),
-        Expr::Path(path) => Ok(resolve_path(context, &path.segments).unwrap_or(Value::Null)),
-        Expr::FunctionCall { name, args, .. } => {
+        Expr::Path(path) => Ok(resolve_path_in(context, &path.segments, scope)?.unwrap_or(Value::Null)),
+        Expr::FunctionCall { name, args, span } => {
+            if is_window_function(name) {
+                return Err(error::MorphError::mapping_at(
+                    format!("window function {name}() can only be used in a top-level set"),
+                    span.line,
+                    span.column,
+                ));
+            }
+            if name == "try" {
+                return eval_try(args, *span, context, scope);
+            }
+            if let Some(Expr::Lambda { params, body, .. }) = args.get(1) {
+                if let Some(arity) = functions::lambda_arity(name) {
+                    return eval_higher_order(name, args, params, body, arity, context, scope);
+                }
+            }
+            if args.iter().any(|a| matches!(a, Expr::Lambda { .. })) {
+                return Err(error::MorphError::mapping_at(
+                    format!("{name}() does not accept a lambda argument"),
+                    span.line,
+                    span.column,
+                ));
+            }
             let evaluated_args: Vec<Value> = args
                 .iter()
-                .map(|a| eval_expr(a, context))
+                .map(|a| eval_expr_in(a, context, scope))
                 .collect::<error::Result<Vec<_>>>()?;
-            functions::call_function(name, &evaluated_args)
+            functions::call_function(name, &evaluated_args).map_err(|e| match (e, args.first()) {
+                // Point parse errors at the field holding the bad document.
+                (error::MorphError::Mapping { message, .. }, Some(Expr::Path(path)))
+                    if functions::is_document_parser(name) =>
+                {
+                    error::MorphError::mapping_at(
+                        format!("{message} (in {path})"),
+                        span.line,
+                        span.column,
+                    )
+                }
+                (e, _) => e,
+            })
         }
         Expr::BinaryOp { left, op, right } => {
-            let l = eval_expr(left, context)?;
-            let r = eval_expr(right, context)?;
+            let l = eval_expr_in(left, context, scope)?;
+            // `and`, `or` and `??` only evaluate their right side when the
+            // left side does not already decide the result.
+            match op {
+                BinOp::And if !is_truthy(&l) => return Ok(Value::Bool(false)),
+                BinOp::Or if is_truthy(&l) => return Ok(Value::Bool(true)),
+                BinOp::Coalesce if !matches!(l, Value::Null) => return Ok(l),
+                _ => {}
+            }
+            let r = eval_expr_in(right, context, scope)?;
             eval_binary_op(&l, *op, &r)
         }
+        Expr::Array(items) => items
+            .iter()
+            .map(|item| eval_expr_in(item, context, scope))
+            .collect::<error::Result<Vec<_>>>()
+            .map(Value::Array),
+        Expr::Conditional {
+            condition,
+            then_expr,
+            else_expr,
+        } => {
+            if is_truthy(&eval_expr_in(condition, context, scope)?) {
+                eval_expr_in(then_expr, context, scope)
+            } else {
+                eval_expr_in(else_expr, context, scope)
+            }
+        }
         Expr::UnaryOp { op, expr } => {
-            let val = eval_expr(expr, context)?;
+            let val = eval_expr_in(expr, context, scope)?;
             eval_unary_op(*op, &val)
         }
         Expr::StringInterpolation { parts } => {
@@ -447,22 +2553,109 @@ fn eval_expr(expr: &Expr, context: &Value) -> error::Result<Value> {
                 match part {
                     crate::mapping::ast::InterpolationPart::Literal(s) => result.push_str(s),
                     crate::mapping::ast::InterpolationPart::Expr(expr) => {
-                        let val = eval_expr(expr, context)?;
+                        let val = eval_expr_in(expr, context, scope)?;
                         result.push_str(&functions::to_str(&val));
                     }
                 }
             }
             Ok(Value::String(result))
         }
+        Expr::Lambda { span, .. } => Err(error::MorphError::mapping_at(
+            "a lambda can only be passed as the second argument of a higher-order function such as map() or filter()",
+            span.line,
+            span.column,
+        )),
+        Expr::Variable {
+            name,
+            segments,
+            span,
+        } => match scope.and_then(|s| s.lookup(name)) {
+            Some(val) => Ok(resolve_path_in(val, segments, scope)?.unwrap_or(Value::Null)),
+            None => Err(error::MorphError::mapping_at(
+                format!("unknown variable '{name}'"),
+                span.line,
+                span.column,
+            )),
+        },
+    }
+}
+
+/// `try(expr, fallback)`: the value of `expr`, or of `fallback` (default
+/// null) when evaluating `expr` fails. Errors in `fallback` still propagate.
+fn eval_try(
+    args: &[Expr],
+    span: crate::mapping::lexer::Span,
+    context: &Value,
+    scope: Option<&Scope>,
+) -> error::Result<Value> {
+    let (expr, fallback) = match args {
+        [expr] => (expr, None),
+        [expr, fallback] => (expr, Some(fallback)),
+        _ => {
+            return Err(error::MorphError::mapping_at(
+                format!("try() expects 1 or 2 argument(s), got {}", args.len()),
+                span.line,
+                span.column,
+            ));
+        }
+    };
+    match eval_expr_in(expr, context, scope) {
+        Ok(value) => Ok(value),
+        Err(_) => match fallback {
+            Some(fallback) => eval_expr_in(fallback, context, scope),
+            None => Ok(Value::Null),
+        },
     }
 }
 
+/// Evaluate a higher-order function call such as `map(.items, x => x.price)`.
+///
+/// Every argument except the lambda is evaluated up front; the lambda body is
+/// evaluated once per invocation with its parameters bound in a new scope
+/// frame. Lambdas have no name and cannot be stored, so they cannot recurse.
+fn eval_higher_order(
+    name: &str,
+    args: &[Expr],
+    params: &[String],
+    body: &Expr,
+    arity: usize,
+    context: &Value,
+    scope: Option<&Scope>,
+) -> error::Result<Value> {
+    if params.len() != arity {
+        return Err(error::MorphError::mapping(format!(
+            "{name}() expects a lambda with {arity} parameter(s), got {}",
+            params.len()
+        )));
+    }
+
+    let evaluated_args: Vec<Value> = args
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != 1)
+        .map(|(_, a)| eval_expr_in(a, context, scope))
+        .collect::<error::Result<Vec<_>>>()?;
+
+    let mut call = |values: &[Value]| {
+        let frame = Scope {
+            bindings: params
+                .iter()
+                .map(String::as_str)
+                .zip(values.iter().cloned())
+                .collect(),
+            parent: scope,
+        };
+        eval_expr_in(body, context, Some(&frame))
+    };
+    functions::call_higher_order(name, &evaluated_args, &mut call)
+}
+
 fn eval_binary_op(left: &Value, op: BinOp, right: &Value) -> error::Result<Value> {
     match op {
         // Arithmetic
         BinOp::Add => eval_add(left, right),
-        BinOp::Sub => eval_arithmetic(left, right, |a, b| a - b, |a, b| a - b),
-        BinOp::Mul => eval_arithmetic(left, right, |a, b| a * b, |a, b| a * b),
+        BinOp::Sub => eval_arithmetic(left, right, i64::checked_sub, |a, b| a - b, |a, b| a - b),
+        BinOp::Mul => eval_arithmetic(left, right, i64::checked_mul, |a, b| a * b, |a, b| a * b),
         BinOp::Div => {
             // Check for division by zero
             match right {
@@ -472,15 +2665,22 @@ fn eval_binary_op(left: &Value, op: BinOp, right: &Value) -> error::Result<Value
                 Value::Float(f) if *f == 0.0 => {
                     return Err(error::MorphError::mapping("division by zero"));
                 }
+                Value::Decimal(d) if bigdecimal::Zero::is_zero(d) => {
+                    return Err(error::MorphError::mapping("division by zero"));
+                }
                 _ => {}
             }
-            eval_arithmetic(left, right, |a, b| a / b, |a, b| a / b)
+            eval_arithmetic(left, right, i64::checked_div, |a, b| a / b, |a, b| a / b)
         }
         BinOp::Mod => {
-            if let Value::Int(0) = right {
-                return Err(error::MorphError::mapping("modulo by zero"));
+            match right {
+                Value::Int(0) => return Err(error::MorphError::mapping("modulo by zero")),
+                Value::Decimal(d) if bigdecimal::Zero::is_zero(d) => {
+                    return Err(error::MorphError::mapping("modulo by zero"));
+                }
+                _ => {}
             }
-            eval_arithmetic(left, right, |a, b| a % b, |a, b| a % b)
+            eval_arithmetic(left, right, i64::checked_rem, |a, b| a % b, |a, b| a % b)
         }
 
         // Comparison
@@ -504,6 +2704,40 @@ fn eval_binary_op(left: &Value, op: BinOp, right: &Value) -> error::Result<Value
         // Logical
         BinOp::And => Ok(Value::Bool(is_truthy(left) && is_truthy(right))),
         BinOp::Or => Ok(Value::Bool(is_truthy(left) || is_truthy(right))),
+        BinOp::Coalesce => Ok(if matches!(left, Value::Null) {
+            right.clone()
+        } else {
+            left.clone()
+        }),
+
+        // Membership and matching
+        BinOp::In => eval_in(left, right).map(Value::Bool),
+        BinOp::NotIn => eval_in(left, right).map(|found| Value::Bool(!found)),
+        BinOp::Match => match (left, right) {
+            (Value::Null, _) => Ok(Value::Bool(false)),
+            (_, Value::String(pattern)) => {
+                functions::regex_is_match(pattern, &functions::to_str(left)).map(Value::Bool)
+            }
+            _ => Err(error::MorphError::mapping(format!(
+                "'=~' expects a string pattern, got {}",
+                value_type_name(right)
+            ))),
+        },
+    }
+}
+
+/// `needle in haystack`: an element of an array, a substring of a string or
+/// a key of a map. Nothing is in `null`.
+fn eval_in(needle: &Value, haystack: &Value) -> error::Result<bool> {
+    match haystack {
+        Value::Array(items) => Ok(items.iter().any(|item| values_equal(needle, item))),
+        Value::String(s) => Ok(s.contains(functions::to_str(needle).as_str())),
+        Value::Map(map) => Ok(map.contains_key(functions::to_str(needle).as_str())),
+        Value::Null => Ok(false),
+        other => Err(error::MorphError::mapping(format!(
+            "'in' expects an array, string or map on the right, got {}",
+            value_type_name(other)
+        ))),
     }
 }
 
@@ -514,25 +2748,40 @@ fn eval_add(left: &Value, right: &Value) -> error::Result<Value> {
         (Value::String(a), other) => Ok(Value::String(format!("{a}{}", value_to_display(other)))),
         (other, Value::String(b)) => Ok(Value::String(format!("{}{b}", value_to_display(other)))),
         // Numeric addition
-        _ => eval_arithmetic(left, right, |a, b| a + b, |a, b| a + b),
+        _ => eval_arithmetic(left, right, i64::checked_add, |a, b| a + b, |a, b| a + b),
     }
 }
 
-fn eval_arithmetic<F1, F2>(
+/// Ints that overflow, and any operand that is a decimal, are computed
+/// exactly as decimals; a float operand then counts as its shortest text.
+fn eval_arithmetic<F1, F2, F3>(
     left: &Value,
     right: &Value,
     int_op: F1,
     float_op: F2,
+    decimal_op: F3,
 ) -> error::Result<Value>
 where
-    F1: Fn(i64, i64) -> i64,
+    F1: Fn(i64, i64) -> Option<i64>,
     F2: Fn(f64, f64) -> f64,
+    F3: Fn(&BigDecimal, &BigDecimal) -> BigDecimal,
 {
     match (left, right) {
-        (Value::Int(a), Value::Int(b)) => Ok(Value::Int(int_op(*a, *b))),
+        (Value::Int(a), Value::Int(b)) => Ok(int_op(*a, *b).map_or_else(
+            || Value::Decimal(decimal_op(&(*a).into(), &(*b).into())),
+            Value::Int,
+        )),
         (Value::Float(a), Value::Float(b)) => Ok(Value::Float(float_op(*a, *b))),
         (Value::Int(a), Value::Float(b)) => Ok(Value::Float(float_op(*a as f64, *b))),
         (Value::Float(a), Value::Int(b)) => Ok(Value::Float(float_op(*a, *b as f64))),
+        (Value::Decimal(_), _) | (_, Value::Decimal(_)) => {
+            match (functions::as_decimal(left), functions::as_decimal(right)) {
+                (Some(a), Some(b)) => Ok(Value::Decimal(decimal_op(&a, &b))),
+                _ => Err(error::MorphError::mapping(format!(
+                    "cannot perform arithmetic on {left:?} and {right:?}"
+                ))),
+            }
+        }
         _ => Err(error::MorphError::mapping(format!(
             "cannot perform arithmetic on {left:?} and {right:?}"
         ))),
@@ -543,8 +2792,11 @@ fn eval_unary_op(op: UnaryOp, value: &Value) -> error::Result<Value> {
     match op {
         UnaryOp::Not => Ok(Value::Bool(!is_truthy(value))),
         UnaryOp::Neg => match value {
-            Value::Int(i) => Ok(Value::Int(-i)),
+            Value::Int(i) => Ok(i
+                .checked_neg()
+                .map_or_else(|| Value::Decimal(-BigDecimal::from(*i)), Value::Int)),
             Value::Float(f) => Ok(Value::Float(-f)),
+            Value::Decimal(d) => Ok(Value::Decimal(-d)),
             _ => Err(error::MorphError::mapping(format!(
                 "cannot negate {value:?}"
             ))),
@@ -556,6 +2808,13 @@ fn values_equal(a: &Value, b: &Value) -> bool {
     match (a, b) {
         (Value::Int(a), Value::Float(b)) => (*a as f64) == *b,
         (Value::Float(a), Value::Int(b)) => *a == (*b as f64),
+        (Value::Decimal(_), Value::Int(_) | Value::Float(_))
+        | (Value::Int(_) | Value::Float(_), Value::Decimal(_)) => {
+            compare_values(a, b) == Some(std::cmp::Ordering::Equal)
+        }
+        (Value::DateTime(a), Value::String(b)) | (Value::String(b), Value::DateTime(a)) => {
+            DateTime::parse(b).is_some_and(|b| a.compare(&b) == Some(std::cmp::Ordering::Equal))
+        }
         _ => a == b,
     }
 }
@@ -566,8 +2825,18 @@ fn compare_values(a: &Value, b: &Value) -> Option<std::cmp::Ordering> {
         (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
         (Value::Int(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
         (Value::Float(a), Value::Int(b)) => a.partial_cmp(&(*b as f64)),
+        (Value::Decimal(_), _) | (_, Value::Decimal(_)) => {
+            functions::as_decimal(a)?.partial_cmp(&functions::as_decimal(b)?)
+        }
         (Value::String(a), Value::String(b)) => a.partial_cmp(b),
-        _ => None,
+        (Value::DateTime(a), Value::DateTime(b)) => a.compare(b),
+        // Lets `.created >= "2024-01-01"` compare as dates.
+        (Value::DateTime(a), Value::String(b)) => a.compare(&DateTime::parse(b)?),
+        (Value::String(a), Value::DateTime(b)) => DateTime::parse(a)?.compare(b),
+        // Nothing compares with null.
+        (Value::Null, _) | (_, Value::Null) => None,
+        // Other mixed types follow the total order: by type, then by value.
+        _ => Some(a.cmp(b)),
     }
 }
 
@@ -577,10 +2846,12 @@ fn is_truthy(value: &Value) -> bool {
         Value::Bool(b) => *b,
         Value::Int(i) => *i != 0,
         Value::Float(f) => *f != 0.0,
+        Value::Decimal(d) => !bigdecimal::Zero::is_zero(d),
         Value::String(s) => !s.is_empty(),
         Value::Array(a) => !a.is_empty(),
         Value::Map(m) => !m.is_empty(),
         Value::Bytes(b) => !b.is_empty(),
+        Value::DateTime(_) => true,
     }
 }
 
@@ -590,10 +2861,12 @@ fn value_to_display(value: &Value) -> String {
         Value::Bool(b) => b.to_string(),
         Value::Int(i) => i.to_string(),
         Value::Float(f) => f.to_string(),
+        Value::Decimal(d) => crate::value::decimal_to_text(d),
         Value::String(s) => s.clone(),
         Value::Array(_) => "[array]".into(),
         Value::Map(_) => "{map}".into(),
         Value::Bytes(_) => "[bytes]".into(),
+        Value::DateTime(dt) => dt.to_string(),
     }
 }
 
@@ -601,51 +2874,287 @@ fn value_to_display(value: &Value) -> String {
 // Path resolution helpers
 // ---------------------------------------------------------------------------
 
-fn resolve_path(value: &Value, segments: &[PathSegment]) -> Option<Value> {
+pub(crate) fn resolve_path(
+    value: &Value,
+    segments: &[PathSegment],
+) -> error::Result<Option<Value>> {
+    resolve_path_in(value, segments, None)
+}
+
+fn resolve_path_in(
+    value: &Value,
+    segments: &[PathSegment],
+    scope: Option<&Scope>,
+) -> error::Result<Option<Value>> {
     if segments.is_empty() {
-        return Some(value.clone());
+        return Ok(Some(value.clone()));
     }
 
     let (first, rest) = segments.split_first().unwrap();
     match first {
         PathSegment::Field(name) => match value {
-            Value::Map(map) => map.get(name).and_then(|v| resolve_path(v, rest)),
-            _ => None,
+            Value::Map(map) => match map.get(name) {
+                Some(v) => resolve_path_in(v, rest, scope),
+                None => Ok(None),
+            },
+            _ => Ok(None),
         },
         PathSegment::Index(idx) => match value {
-            Value::Array(arr) => {
-                let index = if *idx < 0 {
-                    (arr.len() as i64 + idx) as usize
-                } else {
-                    *idx as usize
-                };
-                arr.get(index).and_then(|v| resolve_path(v, rest))
-            }
-            _ => None,
+            Value::Array(arr) => match normalize_index(*idx, arr.len()) {
+                Some(index) => resolve_path_in(&arr[index], rest, scope),
+                None => Ok(None),
+            },
+            _ => Ok(None),
         },
-        PathSegment::Wildcard => match value {
+        PathSegment::Slice { start, end, step } => match value {
             Value::Array(arr) => {
-                let results: Vec<Value> = arr
-                    .iter()
-                    .filter_map(|item| resolve_path(item, rest))
+                let items = slice_indices(arr.len(), *start, *end, *step)
+                    .into_iter()
+                    .map(|i| &arr[i])
                     .collect();
-                if results.is_empty() {
-                    None
-                } else {
-                    Some(Value::Array(results))
-                }
+                // Like a filter, a slice always yields an array.
+                Ok(Some(Value::Array(resolve_each(items, rest, scope)?)))
             }
-            _ => None,
+            _ => Ok(None),
         },
+        PathSegment::Wildcard => {
+            let Some(items) = elements(value) else {
+                return Ok(None);
+            };
+            let results = resolve_each(items, rest, scope)?;
+            if results.is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some(Value::Array(results)))
+            }
+        }
+        PathSegment::Descendant(name) => {
+            let mut matches = Vec::new();
+            collect_descendants(value, name, &mut matches);
+            let results = resolve_each(matches, rest, scope)?;
+            if results.is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some(Value::Array(results)))
+            }
+        }
+        PathSegment::Filter(predicate) => {
+            let Some(items) = elements(value) else {
+                return Ok(None);
+            };
+            let mut kept = Vec::new();
+            for item in items {
+                if is_truthy(&eval_expr_in(predicate, item, scope)?) {
+                    kept.push(item);
+                }
+            }
+            // A filter always yields an array, even when nothing matches.
+            Ok(Some(Value::Array(resolve_each(kept, rest, scope)?)))
+        }
+    }
+}
+
+/// Resolve a possibly negative index against an array of length `len`.
+fn normalize_index(idx: i64, len: usize) -> Option<usize> {
+    let index = if idx < 0 { len as i64 + idx } else { idx };
+    if (0..len as i64).contains(&index) {
+        Some(index as usize)
+    } else {
+        None
+    }
+}
+
+fn index_out_of_range(idx: i64, len: usize) -> error::MorphError {
+    error::MorphError::mapping(format!(
+        "index {idx} is out of range for array of length {len}"
+    ))
+}
+
+/// The indexes selected by a slice, with Python semantics: negative bounds
+/// count from the end, bounds are clamped, and a negative step walks
+/// backwards from the end.
+fn slice_indices(
+    len: usize,
+    start: Option<i64>,
+    end: Option<i64>,
+    step: Option<i64>,
+) -> Vec<usize> {
+    let len = len as i64;
+    let step = step.unwrap_or(1);
+    let bound = |b: i64, lo: i64, hi: i64| if b < 0 { b + len } else { b }.clamp(lo, hi);
+    let mut indexes = Vec::new();
+    if step > 0 {
+        let mut i = start.map_or(0, |b| bound(b, 0, len));
+        let stop = end.map_or(len, |b| bound(b, 0, len));
+        while i < stop {
+            indexes.push(i as usize);
+            match i.checked_add(step) {
+                Some(next) => i = next,
+                None => break,
+            }
+        }
+    } else {
+        let mut i = start.map_or(len - 1, |b| bound(b, -1, len - 1));
+        let stop = end.map_or(-1, |b| bound(b, -1, len - 1));
+        while i > stop {
+            indexes.push(i as usize);
+            match i.checked_add(step) {
+                Some(next) => i = next,
+                None => break,
+            }
+        }
+    }
+    indexes
+}
+
+/// Rewrite the array elements selected by a slice. Non-arrays are returned
+/// unchanged.
+fn update_slice(
+    value: &Value,
+    start: Option<i64>,
+    end: Option<i64>,
+    step: Option<i64>,
+    update: &mut Update,
+) -> error::Result<Value> {
+    let Value::Array(arr) = value else {
+        return Ok(value.clone());
+    };
+    let mut selected = vec![false; arr.len()];
+    for i in slice_indices(arr.len(), start, end, step) {
+        selected[i] = true;
+    }
+    let mut updated = Vec::with_capacity(arr.len());
+    for (item, selected) in arr.iter().zip(selected) {
+        if !selected {
+            updated.push(item.clone());
+        } else if let Some(v) = update(item)? {
+            updated.push(v);
+        }
+    }
+    Ok(Value::Array(updated))
+}
+
+/// The elements of an array or the values of a map, in order.
+fn elements(value: &Value) -> Option<Vec<&Value>> {
+    match value {
+        Value::Array(arr) => Some(arr.iter().collect()),
+        Value::Map(map) => Some(map.values().collect()),
+        _ => None,
+    }
+}
+
+/// Resolve the remaining segments against each item, skipping misses.
+fn resolve_each(
+    items: Vec<&Value>,
+    segments: &[PathSegment],
+    scope: Option<&Scope>,
+) -> error::Result<Vec<Value>> {
+    let mut results = Vec::new();
+    for item in items {
+        if let Some(v) = resolve_path_in(item, segments, scope)? {
+            results.push(v);
+        }
+    }
+    Ok(results)
+}
+
+/// Collect every value stored under `name` at any depth, in document order.
+/// Matches nested inside an earlier match are included too.
+fn collect_descendants<'v>(value: &'v Value, name: &str, out: &mut Vec<&'v Value>) {
+    match value {
+        Value::Map(map) => {
+            for (key, child) in map {
+                if key == name {
+                    out.push(child);
+                }
+                collect_descendants(child, name, out);
+            }
+        }
+        Value::Array(arr) => {
+            for child in arr {
+                collect_descendants(child, name, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Callback used when rewriting the targets of a multi-match segment.
+/// Returning `None` removes the target from its parent.
+type Update<'a> = dyn FnMut(&Value) -> error::Result<Option<Value>> + 'a;
+
+/// Rewrite the array elements or map values of `value`, optionally only those
+/// matching `predicate`. Scalars are returned unchanged.
+fn update_elements(
+    value: &Value,
+    predicate: Option<&Expr>,
+    update: &mut Update,
+) -> error::Result<Value> {
+    let matches = |item: &Value| -> error::Result<bool> {
+        match predicate {
+            Some(p) => Ok(is_truthy(&eval_expr(p, item)?)),
+            None => Ok(true),
+        }
+    };
+    match value {
+        Value::Array(arr) => {
+            let mut updated = Vec::with_capacity(arr.len());
+            for item in arr {
+                if !matches(item)? {
+                    updated.push(item.clone());
+                } else if let Some(v) = update(item)? {
+                    updated.push(v);
+                }
+            }
+            Ok(Value::Array(updated))
+        }
+        Value::Map(map) => {
+            let mut updated = IndexMap::with_capacity(map.len());
+            for (key, item) in map {
+                if !matches(item)? {
+                    updated.insert(key.clone(), item.clone());
+                } else if let Some(v) = update(item)? {
+                    updated.insert(key.clone(), v);
+                }
+            }
+            Ok(Value::Map(updated))
+        }
+        _ => Ok(value.clone()),
+    }
+}
+
+/// Rewrite every value stored under `name` at any depth. Children are
+/// processed before their parents, so nested matches are all visited.
+fn update_descendants(value: &Value, name: &str, update: &mut Update) -> error::Result<Value> {
+    match value {
+        Value::Map(map) => {
+            let mut updated = IndexMap::with_capacity(map.len());
+            for (key, child) in map {
+                let child = update_descendants(child, name, update)?;
+                if key != name {
+                    updated.insert(key.clone(), child);
+                } else if let Some(v) = update(&child)? {
+                    updated.insert(key.clone(), v);
+                }
+            }
+            Ok(Value::Map(updated))
+        }
+        Value::Array(arr) => Ok(Value::Array(
+            arr.iter()
+                .map(|child| update_descendants(child, name, update))
+                .collect::<error::Result<Vec<_>>>()?,
+        )),
+        _ => Ok(value.clone()),
     }
 }
 
-fn set_path(value: &Value, segments: &[PathSegment], new_val: Value) -> Value {
+fn set_path(value: &Value, segments: &[PathSegment], new_val: Value) -> error::Result<Value> {
     if segments.is_empty() {
-        return new_val;
+        return Ok(new_val);
     }
 
     let (first, rest) = segments.split_first().unwrap();
+    let mut set_rest = |item: &Value| set_path(item, rest, new_val.clone()).map(Some);
     match first {
         PathSegment::Field(name) => {
             let mut map = match value {
@@ -653,120 +3162,86 @@ fn set_path(value: &Value, segments: &[PathSegment], new_val: Value) -> Value {
                 _ => IndexMap::new(),
             };
             let existing = map.get(name).cloned().unwrap_or(Value::Null);
-            let updated = set_path(&existing, rest, new_val);
+            let updated = set_path(&existing, rest, new_val)?;
             map.insert(name.clone(), updated);
-            Value::Map(map)
+            Ok(Value::Map(map))
         }
         PathSegment::Index(idx) => {
             let mut arr = match value {
                 Value::Array(a) => a.clone(),
                 _ => Vec::new(),
             };
-            let index = if *idx < 0 {
-                (arr.len() as i64 + idx) as usize
-            } else {
-                *idx as usize
-            };
-            // Extend array if needed
-            while arr.len() <= index {
-                arr.push(Value::Null);
-            }
-            let existing = arr[index].clone();
-            arr[index] = set_path(&existing, rest, new_val);
-            Value::Array(arr)
+            // Indexes must address an existing element; arrays never grow.
+            let index = normalize_index(*idx, arr.len())
+                .ok_or_else(|| index_out_of_range(*idx, arr.len()))?;
+            arr[index] = set_path(&arr[index], rest, new_val)?;
+            Ok(Value::Array(arr))
         }
-        PathSegment::Wildcard => {
-            // Set on all elements
-            match value {
-                Value::Array(arr) => {
-                    let updated: Vec<Value> = arr
-                        .iter()
-                        .map(|item| set_path(item, rest, new_val.clone()))
-                        .collect();
-                    Value::Array(updated)
-                }
-                _ => value.clone(),
-            }
+        PathSegment::Slice { start, end, step } => {
+            update_slice(value, *start, *end, *step, &mut set_rest)
         }
+        // Multi-match segments only update targets that already exist.
+        PathSegment::Wildcard => update_elements(value, None, &mut set_rest),
+        PathSegment::Filter(predicate) => update_elements(value, Some(predicate), &mut set_rest),
+        PathSegment::Descendant(name) => update_descendants(value, name, &mut set_rest),
     }
 }
 
-fn remove_path(value: &Value, segments: &[PathSegment]) -> Value {
+fn remove_path(value: &Value, segments: &[PathSegment]) -> error::Result<Value> {
     if segments.is_empty() {
-        return Value::Null;
+        return Ok(Value::Null);
     }
 
-    if segments.len() == 1 {
-        match &segments[0] {
-            PathSegment::Field(name) => match value {
-                Value::Map(map) => {
-                    let mut new_map = map.clone();
-                    new_map.shift_remove(name);
-                    Value::Map(new_map)
-                }
-                _ => value.clone(),
-            },
-            PathSegment::Index(idx) => match value {
-                Value::Array(arr) => {
-                    let index = if *idx < 0 {
-                        (arr.len() as i64 + idx) as usize
-                    } else {
-                        *idx as usize
-                    };
-                    let mut new_arr = arr.clone();
-                    if index < new_arr.len() {
-                        new_arr.remove(index);
-                    }
-                    Value::Array(new_arr)
-                }
-                _ => value.clone(),
-            },
-            PathSegment::Wildcard => value.clone(),
+    let (first, rest) = segments.split_first().unwrap();
+    // On the last segment the target itself is removed; otherwise removal
+    // continues inside it.
+    let mut remove_rest = |item: &Value| {
+        if rest.is_empty() {
+            Ok(None)
+        } else {
+            remove_path(item, rest).map(Some)
         }
-    } else {
-        let (first, rest) = segments.split_first().unwrap();
-        match first {
-            PathSegment::Field(name) => match value {
-                Value::Map(map) => {
-                    let mut new_map = map.clone();
-                    if let Some(child) = new_map.get(name) {
-                        let updated = remove_path(child, rest);
-                        new_map.insert(name.clone(), updated);
-                    }
-                    Value::Map(new_map)
-                }
-                _ => value.clone(),
-            },
-            PathSegment::Index(idx) => match value {
-                Value::Array(arr) => {
-                    let index = if *idx < 0 {
-                        (arr.len() as i64 + idx) as usize
-                    } else {
-                        *idx as usize
-                    };
-                    let mut new_arr = arr.clone();
-                    if index < new_arr.len() {
-                        new_arr[index] = remove_path(&new_arr[index], rest);
-                    }
-                    Value::Array(new_arr)
+    };
+    match first {
+        PathSegment::Field(name) => match value {
+            Value::Map(map) => {
+                let mut new_map = map.clone();
+                if rest.is_empty() {
+                    new_map.shift_remove(name);
+                } else if let Some(child) = new_map.get(name) {
+                    let updated = remove_path(child, rest)?;
+                    new_map.insert(name.clone(), updated);
                 }
-                _ => value.clone(),
-            },
-            PathSegment::Wildcard => match value {
-                Value::Array(arr) => {
-                    let updated: Vec<Value> =
-                        arr.iter().map(|item| remove_path(item, rest)).collect();
-                    Value::Array(updated)
+                Ok(Value::Map(new_map))
+            }
+            _ => Ok(value.clone()),
+        },
+        PathSegment::Index(idx) => match value {
+            Value::Array(arr) => {
+                let index = normalize_index(*idx, arr.len())
+                    .ok_or_else(|| index_out_of_range(*idx, arr.len()))?;
+                let mut new_arr = arr.clone();
+                if rest.is_empty() {
+                    new_arr.remove(index);
+                } else {
+                    new_arr[index] = remove_path(&new_arr[index], rest)?;
                 }
-                _ => value.clone(),
-            },
+                Ok(Value::Array(new_arr))
+            }
+            _ => Ok(value.clone()),
+        },
+        PathSegment::Slice { start, end, step } => {
+            update_slice(value, *start, *end, *step, &mut remove_rest)
         }
+        PathSegment::Wildcard => update_elements(value, None, &mut remove_rest),
+        PathSegment::Filter(predicate) => update_elements(value, Some(predicate), &mut remove_rest),
+        PathSegment::Descendant(name) => update_descendants(value, name, &mut remove_rest),
     }
 }
 
 fn last_field_name(segments: &[PathSegment]) -> Option<String> {
     for seg in segments.iter().rev() {
-        if let PathSegment::Field(name) = seg {
+        if let PathSegment::Field(name) | PathSegment::Descendant(name) = seg {
             return Some(name.clone());
         }
     }
@@ -1207,6 +3682,111 @@ mod tests {
         assert!(matches!(err, error::MorphError::Mapping { .. }));
     }
 
+    #[test]
+    fn value_preview_truncates_on_char_boundary() {
+        let long = format!("{}漢字漢字漢字", "a".repeat(45));
+        let preview = value_preview(&Value::String(long.clone()));
+        assert!(preview.ends_with("..."), "preview: {preview}");
+        assert_eq!(preview.chars().count(), 50);
+        let mut m = IndexMap::new();
+        m.insert("s".into(), Value::String(long));
+        let val = Value::Map(m);
+        for mapping in [
+            "cast .s as datetime",
+            "cast .s as bytes base64",
+            "cast .s as object json",
+        ] {
+            let err = run_err(mapping, &val);
+            assert!(err.to_string().contains("..."), "err: {err}");
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Decimals
+    // -----------------------------------------------------------------------
+
+    fn dec(text: &str) -> Value {
+        Value::Decimal(text.parse().unwrap())
+    }
+
+    #[test]
+    fn cast_to_decimal_makes_arithmetic_exact() {
+        let mut m = IndexMap::new();
+        m.insert("a".into(), Value::Float(0.1));
+        m.insert("b".into(), Value::String("0.2".into()));
+        let result = run(
+            "cast .a as decimal\ncast .b as decimal\nset .sum = .a + .b\nset .eq = .sum == 0.3",
+            &Value::Map(m),
+        );
+        assert_eq!(result.get_path(".sum"), Some(&dec("0.3")));
+        assert_eq!(result.get_path(".eq"), Some(&Value::Bool(true)));
+    }
+
+    #[test]
+    fn int_overflow_promotes_to_decimal() {
+        let result = run(
+            "set .a = 9223372036854775807 + 1\nset .b = -(0 - 9223372036854775807 - 1)",
+            &Value::Map(IndexMap::new()),
+        );
+        assert_eq!(result.get_path(".a"), Some(&dec("9223372036854775808")));
+        assert_eq!(result.get_path(".b"), Some(&dec("9223372036854775808")));
+    }
+
+    #[test]
+    fn decimal_mixed_with_int_and_float() {
+        let mut m = IndexMap::new();
+        m.insert("d".into(), dec("10.50"));
+        let result = run(
+            "set .a = .d * 2\nset .b = .d - 0.5\nset .c = .d / 4\nset .gt = .d > 10",
+            &Value::Map(m),
+        );
+        assert_eq!(result.get_path(".a"), Some(&dec("21.00")));
+        assert_eq!(result.get_path(".b"), Some(&dec("10.00")));
+        assert_eq!(result.get_path(".c"), Some(&dec("2.625")));
+        assert_eq!(result.get_path(".gt"), Some(&Value::Bool(true)));
+    }
+
+    #[test]
+    fn decimal_division_by_zero_error() {
+        let mut m = IndexMap::new();
+        m.insert("d".into(), dec("0.0"));
+        let err = run_err("set .x = 1 / .d", &Value::Map(m));
+        assert!(err.to_string().contains("division by zero"), "err: {err}");
+    }
+
+    #[test]
+    fn cast_decimal_to_other_types() {
+        let mut m = IndexMap::new();
+        m.insert("i".into(), dec("-2.75"));
+        m.insert("big".into(), Value::String("12345678901234567890".into()));
+        m.insert("s".into(), dec("1.50"));
+        let result = run(
+            "cast .i as int\ncast .big as int\ncast .s as string",
+            &Value::Map(m),
+        );
+        assert_eq!(result.get_path(".i"), Some(&Value::Int(-2)));
+        assert_eq!(result.get_path(".big"), Some(&dec("12345678901234567890")));
+        assert_eq!(result.get_path(".s"), Some(&Value::String("1.50".into())));
+    }
+
+    #[test]
+    fn aggregate_sum_of_decimals_is_exact() {
+        let records: Vec<Value> = ["0.1", "0.2", "0.3"]
+            .iter()
+            .map(|d| {
+                let mut m = IndexMap::new();
+                m.insert("amount".into(), dec(d));
+                Value::Map(m)
+            })
+            .collect();
+        let result = run(
+            "aggregate {\n  set .total = sum(.amount)\n  set .mean = avg(.amount)\n}",
+            &Value::Array(records),
+        );
+        assert_eq!(result.get_path("[0].total"), Some(&dec("0.6")));
+        assert_eq!(result.get_path("[0].mean"), Some(&dec("0.2")));
+    }
+
     // -----------------------------------------------------------------------
     // Multi-statement
     // -----------------------------------------------------------------------
@@ -1383,4 +3963,35 @@ select .full_name, .age, .age_str, .country",
             Some(&Value::String("Springfield".into()))
         );
     }
+
+    // -----------------------------------------------------------------------
+    // slices
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn slice_indices_python_semantics() {
+        assert_eq!(slice_indices(6, Some(1), Some(3), None), vec![1, 2]);
+        assert_eq!(slice_indices(6, Some(-2), None, None), vec![4, 5]);
+        assert_eq!(slice_indices(6, None, None, Some(2)), vec![0, 2, 4]);
+        assert_eq!(slice_indices(4, None, None, Some(-1)), vec![3, 2, 1, 0]);
+        assert_eq!(slice_indices(4, Some(-1), Some(0), Some(-2)), vec![3, 1]);
+        assert_eq!(slice_indices(3, Some(-10), Some(10), None), vec![0, 1, 2]);
+        assert_eq!(
+            slice_indices(3, Some(2), Some(1), None),
+            Vec::<usize>::new()
+        );
+        assert_eq!(slice_indices(0, None, None, Some(-1)), Vec::<usize>::new());
+        assert_eq!(slice_indices(3, Some(1), None, Some(i64::MAX)), vec![1]);
+        assert_eq!(slice_indices(3, None, None, Some(i64::MIN)), vec![2]);
+    }
+
+    #[test]
+    fn normalize_index_bounds() {
+        assert_eq!(normalize_index(0, 3), Some(0));
+        assert_eq!(normalize_index(-1, 3), Some(2));
+        assert_eq!(normalize_index(-3, 3), Some(0));
+        assert_eq!(normalize_index(-4, 3), None);
+        assert_eq!(normalize_index(3, 3), None);
+        assert_eq!(normalize_index(0, 0), None);
+    }
 }
@@ -0,0 +1,237 @@
+//! Synthetic test data for `fake(kind)`, drawn from small bundled word
+//! lists so no network access or data files are needed.
+//!
+//! Generated emails use the reserved `example.*` domains and phone numbers
+//! the fictional `555-01xx` range, so fake records never reach real people.
+
+use crate::mapping::random::Rng;
+
+/// The kinds `fake()` accepts, in the order error messages list them.
+pub const KINDS: &[&str] = &[
+    "name",
+    "first_name",
+    "last_name",
+    "email",
+    "username",
+    "phone",
+    "company",
+    "street",
+    "city",
+    "postcode",
+    "country",
+    "address",
+    "word",
+    "sentence",
+];
+
+const FIRST_NAMES: &[&str] = &[
+    "Ada", "Alan", "Alice", "Amara", "Anna", "Ben", "Carla", "Chen", "Clara", "Daniel", "Diego",
+    "Elena", "Emma", "Farah", "Felix", "Grace", "Hana", "Hugo", "Ines", "Ivan", "Jack", "James",
+    "Julia", "Kai", "Karin", "Leo", "Lina", "Lucas", "Maria", "Mateo", "Maya", "Mia", "Nadia",
+    "Noah", "Nora", "Oliver", "Omar", "Paula", "Priya", "Rafael", "Rosa", "Sam", "Sara", "Sofia",
+    "Tom", "Uma", "Victor", "Yara", "Yusuf", "Zoe",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "Adams", "Alvarez", "Bauer", "Becker", "Brown", "Campbell", "Chen", "Costa", "Dubois", "Evans",
+    "Fischer", "Garcia", "Gomez", "Green", "Hansen", "Hoffmann", "Ito", "Jensen", "Johnson", "Kim",
+    "Kowalski", "Larsen", "Lopez", "Martin", "Meyer", "Miller", "Moreau", "Nakamura", "Nguyen",
+    "Novak", "Okafor", "Patel", "Petrov", "Rossi", "Santos", "Schmidt", "Silva", "Singh", "Smith",
+    "Suzuki", "Taylor", "Tanaka", "Walker", "Weber", "Wilson", "Wong", "Young", "Zhang",
+];
+
+const COMPANY_WORDS: &[&str] = &[
+    "Acme", "Apex", "Blue", "Bright", "Cedar", "Cobalt", "Delta", "Echo", "Granite", "Harbor",
+    "Iron", "Juniper", "Lumen", "Maple", "Nimbus", "North", "Orbit", "Pine", "Quartz", "River",
+    "Silver", "Summit", "Vertex", "Willow",
+];
+
+const COMPANY_SUFFIXES: &[&str] = &[
+    "Labs",
+    "Systems",
+    "Group",
+    "Partners",
+    "Works",
+    "Industries",
+    "Logistics",
+    "Analytics",
+    "Foods",
+    "Studio",
+];
+
+const STREET_NAMES: &[&str] = &[
+    "Oak", "Maple", "Cedar", "Elm", "Pine", "Birch", "Willow", "Park", "Lake", "Hill", "River",
+    "Church", "Mill", "Station", "Market", "High", "Spring", "Garden", "Meadow", "Forest",
+];
+
+const STREET_SUFFIXES: &[&str] = &[
+    "Street", "Avenue", "Road", "Lane", "Drive", "Way", "Court", "Place",
+];
+
+const CITIES: &[&str] = &[
+    "Springfield",
+    "Riverton",
+    "Fairview",
+    "Lakeside",
+    "Greenville",
+    "Franklin",
+    "Clinton",
+    "Georgetown",
+    "Salem",
+    "Madison",
+    "Ashford",
+    "Brookfield",
+    "Kingsport",
+    "Millbrook",
+    "Oakdale",
+    "Westfield",
+    "Newport",
+    "Hillcrest",
+    "Bridgewater",
+    "Northgate",
+];
+
+const COUNTRIES: &[&str] = &[
+    "Argentina",
+    "Australia",
+    "Brazil",
+    "Canada",
+    "Chile",
+    "Denmark",
+    "Egypt",
+    "Finland",
+    "France",
+    "Germany",
+    "Ghana",
+    "India",
+    "Ireland",
+    "Italy",
+    "Japan",
+    "Kenya",
+    "Mexico",
+    "Netherlands",
+    "New Zealand",
+    "Nigeria",
+    "Norway",
+    "Poland",
+    "Portugal",
+    "South Korea",
+    "Spain",
+    "Sweden",
+    "Switzerland",
+    "United Kingdom",
+    "United States",
+    "Vietnam",
+];
+
+const WORDS: &[&str] = &[
+    "alpha", "amber", "anchor", "arrow", "autumn", "basket", "beacon", "bridge", "canvas",
+    "candle", "castle", "cloud", "copper", "coral", "crystal", "desert", "ember", "falcon",
+    "feather", "garden", "glacier", "harbor", "island", "jungle", "lantern", "meadow", "mirror",
+    "mountain", "nectar", "ocean", "orchard", "pebble", "planet", "prairie", "quiet", "rocket",
+    "saddle", "shadow", "signal", "silver", "spark", "stone", "thunder", "timber", "valley",
+    "velvet", "voyage", "willow", "winter", "zephyr",
+];
+
+const EMAIL_DOMAINS: &[&str] = &["example.com", "example.org", "example.net"];
+
+fn pick<'a>(rng: &mut Rng, items: &[&'a str]) -> &'a str {
+    items[rng.below(items.len() as u64) as usize]
+}
+
+/// A fake value of `kind`, or `None` if `kind` is not one of [`KINDS`].
+pub fn generate(kind: &str, rng: &mut Rng) -> Option<String> {
+    let value = match kind {
+        "first_name" => pick(rng, FIRST_NAMES).to_string(),
+        "last_name" => pick(rng, LAST_NAMES).to_string(),
+        "name" => format!("{} {}", pick(rng, FIRST_NAMES), pick(rng, LAST_NAMES)),
+        "email" => format!(
+            "{}.{}{}@{}",
+            pick(rng, FIRST_NAMES).to_lowercase(),
+            pick(rng, LAST_NAMES).to_lowercase(),
+            rng.below(100),
+            pick(rng, EMAIL_DOMAINS)
+        ),
+        "username" => format!(
+            "{}_{}{}",
+            pick(rng, FIRST_NAMES).to_lowercase(),
+            pick(rng, WORDS),
+            rng.below(1000)
+        ),
+        "phone" => format!("+1-{}-555-01{:02}", 200 + rng.below(800), rng.below(100)),
+        "company" => format!(
+            "{} {}",
+            pick(rng, COMPANY_WORDS),
+            pick(rng, COMPANY_SUFFIXES)
+        ),
+        "street" => format!(
+            "{} {} {}",
+            1 + rng.below(9999),
+            pick(rng, STREET_NAMES),
+            pick(rng, STREET_SUFFIXES)
+        ),
+        "city" => pick(rng, CITIES).to_string(),
+        "postcode" => format!("{:05}", rng.below(100_000)),
+        "country" => pick(rng, COUNTRIES).to_string(),
+        "address" => {
+            let street = generate("street", rng)?;
+            format!(
+                "{street}, {} {}",
+                pick(rng, CITIES),
+                generate("postcode", rng)?
+            )
+        }
+        "word" => pick(rng, WORDS).to_string(),
+        "sentence" => {
+            let count = 4 + rng.below(6) as usize;
+            let words: Vec<&str> = (0..count).map(|_| pick(rng, WORDS)).collect();
+            let sentence = words.join(" ");
+            let mut chars = sentence.chars();
+            match chars.next() {
+                Some(first) => format!("{}{}.", first.to_uppercase(), chars.as_str()),
+                None => String::new(),
+            }
+        }
+        _ => return None,
+    };
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_kind_generates() {
+        let mut rng = Rng::new(3);
+        for kind in KINDS {
+            let value = generate(kind, &mut rng).unwrap();
+            assert!(!value.is_empty(), "{kind} generated nothing");
+        }
+        assert_eq!(generate("ssn", &mut rng), None);
+    }
+
+    #[test]
+    fn same_seed_same_values() {
+        let draw = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..5)
+                .map(|_| generate("address", &mut rng).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(draw(9), draw(9));
+        assert_ne!(draw(9), draw(10));
+    }
+
+    #[test]
+    fn emails_and_phones_use_reserved_ranges() {
+        let mut rng = Rng::new(5);
+        for _ in 0..50 {
+            let email = generate("email", &mut rng).unwrap();
+            let domain = email.split_once('@').unwrap().1;
+            assert!(EMAIL_DOMAINS.contains(&domain), "email: {email}");
+            let phone = generate("phone", &mut rng).unwrap();
+            assert!(phone.contains("-555-01"), "phone: {phone}");
+        }
+    }
+}
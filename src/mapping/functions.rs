@@ -1,5 +1,18 @@
+use crate::cli::Format;
 use crate::error;
-use crate::value::Value;
+use crate::mapping::ast::KeyCase;
+use crate::mapping::{fake, random};
+use crate::value::{decimal_from_f64, decimal_to_i64, BigDecimal, Value};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use indexmap::IndexMap;
+use percent_encoding::{percent_decode_str, percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use sha2::Digest;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
 
 /// Call a built-in function by name.
 pub fn call_function(name: &str, args: &[Value]) -> error::Result<Value> {
@@ -20,10 +33,42 @@ pub fn call_function(name: &str, args: &[Value]) -> error::Result<Value> {
         "split" => fn_split(args),
         "join" => fn_join(args),
         "reverse" => fn_reverse(args),
+        "snake_case" | "camel_case" | "kebab_case" | "pascal_case" | "screaming_snake_case" => {
+            fn_case(name, args)
+        }
+        "title_case" => fn_title_case(args),
+        "slugify" => fn_slugify(args),
+
+        // Text formatting
+        "pad_left" | "lpad" => fn_pad(name, args, true),
+        "pad_right" | "rpad" => fn_pad(name, args, false),
+        "truncate" => fn_truncate(args),
+        "wrap" => fn_wrap(args),
+        "nfc" | "nfd" | "nfkc" | "nfkd" => fn_normalize(name, args),
+        "format_number" => fn_format_number(args),
+        "parse_number" => fn_parse_number(args),
+
+        // Hashing and encoding
+        "sha256" | "sha1" | "md5" => fn_digest(name, args),
+        "hmac_sha256" => fn_hmac_sha256(args),
+        "base64_encode" => fn_base64_encode(args),
+        "base64_decode" => fn_base64_decode(args),
+        "hex" => fn_hex(args),
+        "url_encode" => fn_url_encode(args),
+        "url_decode" => fn_url_decode(args),
+        "uuid_v4" => fn_uuid_v4(args),
+        "uuid_v5" => fn_uuid_v5(args),
+
+        // Embedded documents
+        "parse_json" | "parse_jsonl" | "parse_yaml" | "parse_toml" | "parse_csv" | "parse_xml"
+        | "parse_msgpack" | "parse_format" => fn_parse_document(name, args),
+        "to_json" | "to_jsonl" | "to_yaml" | "to_toml" | "to_csv" | "to_xml" | "to_msgpack"
+        | "to_format" => fn_serialize_document(name, args),
 
         // Type functions
         "to_int" | "int" => fn_to_int(args),
         "to_float" | "float" => fn_to_float(args),
+        "to_decimal" | "decimal" => fn_to_decimal(args),
         "to_string" | "string" | "str" => fn_to_string(args),
         "to_bool" | "bool" => fn_to_bool(args),
         "type_of" | "typeof" => fn_type_of(args),
@@ -50,10 +95,38 @@ pub fn call_function(name: &str, args: &[Value]) -> error::Result<Value> {
         "last" => fn_last(args),
         "sum" => fn_sum(args),
         "group_by" | "groupby" => fn_group_by(args),
+        "zip" => fn_zip(args),
+        "chunk" => fn_chunk(args),
+        "range" => fn_range(args),
+        "slice" => fn_slice(args),
+        "index_of" => fn_index_of(args),
+        "entries" => fn_entries(args),
+        "from_entries" => fn_from_entries(args),
+        "merge" => fn_merge(args),
+        "pick" => fn_pick(args),
+        "omit" => fn_omit(args),
+        "flatten" => fn_flatten(args),
+        "count" => fn_count(args),
+        "avg" | "mean" => fn_avg(args),
+        "median" => fn_median(args),
+        "percentile" => fn_percentile(args),
+
+        // Higher-order functions reached without a lambda argument
+        "map" | "filter" | "reduce" | "any" | "all" | "find" | "sort_by" | "flat_map" => {
+            Err(error::MorphError::mapping(format!(
+                "{name}() expects a lambda as its second argument, e.g. {name}(.items, x => x)"
+            )))
+        }
 
         // Conditional
         "if" => fn_if(args),
 
+        // Random values and synthetic data
+        "random_int" => fn_random_int(args),
+        "random_choice" => fn_random_choice(args),
+        "shuffle" => fn_shuffle(args),
+        "fake" => fn_fake(args),
+
         _ => {
             let mut msg = format!("unknown function: {name}");
             if let Some(suggestion) = crate::error::suggest_function(name) {
@@ -64,6 +137,43 @@ pub fn call_function(name: &str, args: &[Value]) -> error::Result<Value> {
     }
 }
 
+/// The number of lambda parameters a higher-order function expects, or `None`
+/// if `name` does not take a lambda.
+pub fn lambda_arity(name: &str) -> Option<usize> {
+    match name {
+        "map" | "filter" | "any" | "all" | "find" | "sort_by" | "flat_map" | "group_by"
+        | "groupby" => Some(1),
+        "reduce" => Some(2),
+        _ => None,
+    }
+}
+
+/// Call a higher-order function.
+///
+/// `args` holds the evaluated non-lambda arguments (the array first, then
+/// any trailing arguments such as the initial value for `reduce`), and
+/// `lambda` invokes the lambda body with the given parameter values.
+pub fn call_higher_order(
+    name: &str,
+    args: &[Value],
+    lambda: &mut dyn FnMut(&[Value]) -> error::Result<Value>,
+) -> error::Result<Value> {
+    match name {
+        "map" => hof_map(args, lambda),
+        "filter" => hof_filter(args, lambda),
+        "reduce" => hof_reduce(args, lambda),
+        "any" => hof_any(args, lambda),
+        "all" => hof_all(args, lambda),
+        "find" => hof_find(args, lambda),
+        "sort_by" => hof_sort_by(args, lambda),
+        "flat_map" => hof_flat_map(args, lambda),
+        "group_by" | "groupby" => hof_group_by(args, lambda),
+        _ => Err(error::MorphError::mapping(format!(
+            "{name}() does not accept a lambda argument"
+        ))),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // String functions
 // ---------------------------------------------------------------------------
@@ -93,8 +203,11 @@ pub fn to_str(value: &Value) -> String {
         Value::String(s) => s.clone(),
         Value::Int(i) => i.to_string(),
         Value::Float(f) => f.to_string(),
+        Value::Decimal(d) => crate::value::decimal_to_text(d),
         Value::Bool(b) => b.to_string(),
         Value::Null => "null".into(),
+        Value::Bytes(b) => String::from_utf8_lossy(b).into_owned(),
+        Value::DateTime(dt) => dt.to_string(),
         _ => format!("{value:?}"),
     }
 }
@@ -127,7 +240,7 @@ fn fn_trim_end(args: &[Value]) -> error::Result<Value> {
 fn fn_len(args: &[Value]) -> error::Result<Value> {
     expect_args("len", args, 1)?;
     match &args[0] {
-        Value::String(s) => Ok(Value::Int(s.len() as i64)),
+        Value::String(s) => Ok(Value::Int(grapheme_len(s) as i64)),
         Value::Array(a) => Ok(Value::Int(a.len() as i64)),
         Value::Map(m) => Ok(Value::Int(m.len() as i64)),
         Value::Bytes(b) => Ok(Value::Int(b.len() as i64)),
@@ -143,6 +256,34 @@ fn fn_replace(args: &[Value]) -> error::Result<Value> {
     Ok(Value::String(s.replace(&from, &to)))
 }
 
+/// Most compiled patterns kept per thread by [`regex_is_match`]. Patterns
+/// built from record data can differ on every record, so the cache is
+/// emptied once it reaches this size instead of growing without bound.
+const REGEX_CACHE_LIMIT: usize = 64;
+
+thread_local! {
+    static REGEX_CACHE: RefCell<HashMap<String, regex::Regex>> = RefCell::new(HashMap::new());
+}
+
+/// Whether `text` matches the regex `pattern`. Compiled patterns are cached
+/// per thread, since the same pattern is usually matched once per record.
+pub(crate) fn regex_is_match(pattern: &str, text: &str) -> error::Result<bool> {
+    REGEX_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if let Some(re) = cache.get(pattern) {
+            return Ok(re.is_match(text));
+        }
+        let re = regex::Regex::new(pattern)
+            .map_err(|e| error::MorphError::mapping(format!("invalid regex \"{pattern}\": {e}")))?;
+        let matched = re.is_match(text);
+        if cache.len() >= REGEX_CACHE_LIMIT {
+            cache.clear();
+        }
+        cache.insert(pattern.to_string(), re);
+        Ok(matched)
+    })
+}
+
 fn fn_contains(args: &[Value]) -> error::Result<Value> {
     expect_args("contains", args, 2)?;
     let s = to_str(&args[0]);
@@ -188,11 +329,11 @@ fn fn_substr(args: &[Value]) -> error::Result<Value> {
         None
     };
 
-    let chars: Vec<char> = s.chars().collect();
-    let start = start.min(chars.len());
+    let graphemes: Vec<&str> = s.graphemes(true).collect();
+    let start = start.min(graphemes.len());
     let result: String = match len {
-        Some(l) => chars[start..].iter().take(l).collect(),
-        None => chars[start..].iter().collect(),
+        Some(l) => graphemes[start..].iter().take(l).copied().collect(),
+        None => graphemes[start..].concat(),
     };
     Ok(Value::String(result))
 }
@@ -232,7 +373,7 @@ fn fn_join(args: &[Value]) -> error::Result<Value> {
 fn fn_reverse(args: &[Value]) -> error::Result<Value> {
     expect_args("reverse", args, 1)?;
     match &args[0] {
-        Value::String(s) => Ok(Value::String(s.chars().rev().collect())),
+        Value::String(s) => Ok(Value::String(s.graphemes(true).rev().collect())),
         Value::Array(a) => {
             let mut reversed = a.clone();
             reversed.reverse();
@@ -244,6 +385,689 @@ fn fn_reverse(args: &[Value]) -> error::Result<Value> {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Case conversion
+// ---------------------------------------------------------------------------
+
+fn fn_case(name: &str, args: &[Value]) -> error::Result<Value> {
+    expect_args(name, args, 1)?;
+    let case = KeyCase::from_name(name).expect("case function name");
+    Ok(Value::String(convert_case(&to_str(&args[0]), case)))
+}
+
+/// Convert `s` to a naming convention. Words are split at non-alphanumeric
+/// characters and at case changes (`userID` → `user`, `ID`;
+/// `HTTPServer` → `HTTP`, `Server`). Leading underscores, as in `_id`, are
+/// kept.
+pub(crate) fn convert_case(s: &str, case: KeyCase) -> String {
+    let body = s.trim_start_matches('_');
+    let prefix = &s[..s.len() - body.len()];
+    let words = split_words(body);
+    let converted = match case {
+        KeyCase::Snake => join_words(&words, "_", str::to_lowercase),
+        KeyCase::ScreamingSnake => join_words(&words, "_", str::to_uppercase),
+        KeyCase::Kebab => join_words(&words, "-", str::to_lowercase),
+        KeyCase::Pascal => join_words(&words, "", capitalize),
+        KeyCase::Camel => match words.split_first() {
+            Some((first, rest)) => first.to_lowercase() + &join_words(rest, "", capitalize),
+            None => String::new(),
+        },
+    };
+    format!("{prefix}{converted}")
+}
+
+fn split_words(s: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = s.char_indices().collect();
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, &(pos, c)) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if let Some(begin) = start.take() {
+                words.push(&s[begin..pos]);
+            }
+            continue;
+        }
+        if let Some(begin) = start {
+            let prev = chars[i - 1].1;
+            let next_is_lower = chars.get(i + 1).is_some_and(|(_, n)| n.is_lowercase());
+            let boundary = c.is_uppercase()
+                && (prev.is_lowercase()
+                    || prev.is_numeric()
+                    || (prev.is_uppercase() && next_is_lower));
+            if boundary {
+                words.push(&s[begin..pos]);
+                start = Some(pos);
+            }
+        } else {
+            start = Some(pos);
+        }
+    }
+    if let Some(begin) = start {
+        words.push(&s[begin..]);
+    }
+    words
+}
+
+fn join_words(words: &[&str], separator: &str, convert: fn(&str) -> String) -> String {
+    words
+        .iter()
+        .map(|w| convert(w))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + &chars.as_str().to_lowercase(),
+        None => String::new(),
+    }
+}
+
+fn fn_title_case(args: &[Value]) -> error::Result<Value> {
+    expect_args("title_case", args, 1)?;
+    let s = to_str(&args[0]);
+    let mut out = String::with_capacity(s.len());
+    let mut word = String::new();
+    for c in s.chars() {
+        if c.is_whitespace() {
+            out.push_str(&capitalize(&word));
+            word.clear();
+            out.push(c);
+        } else {
+            word.push(c);
+        }
+    }
+    out.push_str(&capitalize(&word));
+    Ok(Value::String(out))
+}
+
+/// Lowercase, strip accents and join the remaining words with `-`:
+/// `"Crème Brûlée!"` → `"creme-brulee"`.
+fn fn_slugify(args: &[Value]) -> error::Result<Value> {
+    expect_args("slugify", args, 1)?;
+    let s = to_str(&args[0]);
+    let mut slug = String::with_capacity(s.len());
+    let mut pending_dash = false;
+    for c in s.nfkd() {
+        if unicode_normalization::char::is_combining_mark(c) {
+            continue;
+        }
+        if c.is_alphanumeric() {
+            if pending_dash && !slug.is_empty() {
+                slug.push('-');
+            }
+            pending_dash = false;
+            slug.extend(c.to_lowercase());
+        } else {
+            pending_dash = true;
+        }
+    }
+    Ok(Value::String(slug))
+}
+
+// ---------------------------------------------------------------------------
+// Text formatting functions
+// ---------------------------------------------------------------------------
+
+/// Length in user-perceived characters, so `"é"` counts once whether or not
+/// it is precomposed.
+fn grapheme_len(s: &str) -> usize {
+    s.graphemes(true).count()
+}
+
+/// A width or length argument, which must not be negative.
+fn expect_width_arg(name: &str, what: &str, value: &Value) -> error::Result<usize> {
+    let n = expect_int_arg(name, what, value)?;
+    usize::try_from(n).map_err(|_| {
+        error::MorphError::mapping(format!("{name}() {what} must not be negative, got {n}"))
+    })
+}
+
+/// `pad_left(s, width, fill)` / `pad_right(...)`: pad to `width` graphemes
+/// by repeating `fill` (default a space). Longer strings are unchanged.
+fn fn_pad(name: &str, args: &[Value], left: bool) -> error::Result<Value> {
+    expect_min_args(name, args, 2)?;
+    if args.len() > 3 {
+        return Err(error::MorphError::mapping(format!(
+            "{name}() expects 2 or 3 argument(s), got {}",
+            args.len()
+        )));
+    }
+    let s = to_str(&args[0]);
+    let width = expect_width_arg(name, "width", &args[1])?;
+    let fill = args.get(2).map_or_else(|| " ".to_string(), to_str);
+    if fill.is_empty() {
+        return Err(error::MorphError::mapping(format!(
+            "{name}() fill must not be empty"
+        )));
+    }
+    let padding: String = fill
+        .graphemes(true)
+        .cycle()
+        .take(width.saturating_sub(grapheme_len(&s)))
+        .collect();
+    Ok(Value::String(if left {
+        padding + &s
+    } else {
+        s + &padding
+    }))
+}
+
+/// `truncate(s, n, ellipsis)`: cut to at most `n` graphemes, ending with
+/// `ellipsis` (default none) when anything was removed.
+fn fn_truncate(args: &[Value]) -> error::Result<Value> {
+    expect_min_args("truncate", args, 2)?;
+    if args.len() > 3 {
+        return Err(error::MorphError::mapping(format!(
+            "truncate() expects 2 or 3 argument(s), got {}",
+            args.len()
+        )));
+    }
+    let s = to_str(&args[0]);
+    let max = expect_width_arg("truncate", "length", &args[1])?;
+    if grapheme_len(&s) <= max {
+        return Ok(Value::String(s));
+    }
+    let ellipsis = args.get(2).map(to_str).unwrap_or_default();
+    let ellipsis: String = ellipsis.graphemes(true).take(max).collect();
+    let keep = max - grapheme_len(&ellipsis);
+    let mut out: String = s.graphemes(true).take(keep).collect();
+    out.push_str(&ellipsis);
+    Ok(Value::String(out))
+}
+
+/// `wrap(s, width)`: break lines between words so each holds at most
+/// `width` graphemes. Existing line breaks are kept, and a word longer than
+/// `width` gets a line of its own.
+fn fn_wrap(args: &[Value]) -> error::Result<Value> {
+    expect_args("wrap", args, 2)?;
+    let s = to_str(&args[0]);
+    let width = expect_width_arg("wrap", "width", &args[1])?.max(1);
+    let mut lines = Vec::new();
+    for paragraph in s.lines() {
+        let mut line = String::new();
+        let mut line_len = 0;
+        for word in paragraph.split_whitespace() {
+            let word_len = grapheme_len(word);
+            if line_len > 0 && line_len + 1 + word_len > width {
+                lines.push(std::mem::take(&mut line));
+                line_len = 0;
+            }
+            if line_len > 0 {
+                line.push(' ');
+                line_len += 1;
+            }
+            line.push_str(word);
+            line_len += word_len;
+        }
+        lines.push(line);
+    }
+    Ok(Value::String(lines.join("\n")))
+}
+
+fn fn_normalize(name: &str, args: &[Value]) -> error::Result<Value> {
+    expect_args(name, args, 1)?;
+    let s = to_str(&args[0]);
+    let normalized = match name {
+        "nfc" => s.nfc().collect(),
+        "nfd" => s.nfd().collect(),
+        "nfkc" => s.nfkc().collect(),
+        _ => s.nfkd().collect(),
+    };
+    Ok(Value::String(normalized))
+}
+
+/// Digit grouping and decimal separators for a locale such as `"de"` or
+/// `"fr-CA"`. Only the language matters, except for Swiss locales.
+struct NumberLocale {
+    group: char,
+    decimal: char,
+}
+
+fn number_locale(name: &str, locale: &Value) -> error::Result<NumberLocale> {
+    let tag = to_str(locale).to_ascii_lowercase().replace('_', "-");
+    let language = tag.split('-').next().unwrap_or_default();
+    let (group, decimal) = match language {
+        _ if tag.ends_with("-ch") && matches!(language, "de" | "fr" | "it") => ('\u{2019}', '.'),
+        "en" | "ja" | "zh" | "ko" | "he" | "th" | "hi" => (',', '.'),
+        "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" => ('.', ','),
+        "fr" | "ru" | "pl" | "sv" | "cs" | "sk" | "fi" | "nb" | "no" | "uk" | "hu" => {
+            ('\u{a0}', ',')
+        }
+        _ => {
+            return Err(error::MorphError::mapping(format!(
+                "{name}() does not know locale \"{}\"",
+                to_str(locale)
+            )));
+        }
+    };
+    Ok(NumberLocale { group, decimal })
+}
+
+/// A parsed `format_number` pattern such as `"#,##0.00"` or `"$0.00"`.
+struct NumberPattern<'a> {
+    prefix: &'a str,
+    suffix: &'a str,
+    min_int: usize,
+    group_size: Option<usize>,
+    min_frac: usize,
+    max_frac: usize,
+}
+
+impl<'a> NumberPattern<'a> {
+    /// `0` is a required digit, `#` an optional one and `,` marks the digit
+    /// grouping. Text around the run of `0#,.` characters is copied as is.
+    fn parse(pattern: &'a str) -> error::Result<Self> {
+        let invalid = || {
+            error::MorphError::mapping(format!(
+                "format_number() pattern \"{pattern}\" needs digits such as \"#,##0.00\""
+            ))
+        };
+        let start = pattern.find(['0', '#']).ok_or_else(invalid)?;
+        let end = pattern[start..]
+            .find(|c| !matches!(c, '0' | '#' | '.' | ','))
+            .map_or(pattern.len(), |i| start + i);
+        let body = &pattern[start..end];
+        let (int_part, frac_part) = body.split_once('.').unwrap_or((body, ""));
+        if !int_part.chars().all(|c| matches!(c, '0' | '#' | ','))
+            || !frac_part.chars().all(|c| matches!(c, '0' | '#'))
+        {
+            return Err(invalid());
+        }
+        Ok(NumberPattern {
+            prefix: &pattern[..start],
+            suffix: &pattern[end..],
+            min_int: int_part.matches('0').count(),
+            group_size: int_part
+                .rsplit_once(',')
+                .map(|(_, tail)| tail.len())
+                .filter(|&n| n > 0),
+            min_frac: frac_part.matches('0').count(),
+            max_frac: frac_part.len(),
+        })
+    }
+}
+
+/// `format_number(n, pattern, locale)`: round half up to the pattern's
+/// decimals and lay the digits out in the locale's separators.
+fn fn_format_number(args: &[Value]) -> error::Result<Value> {
+    expect_min_args("format_number", args, 2)?;
+    if args.len() > 3 {
+        return Err(error::MorphError::mapping(format!(
+            "format_number() expects 2 or 3 argument(s), got {}",
+            args.len()
+        )));
+    }
+    let pattern = to_str(&args[1]);
+    let pattern = NumberPattern::parse(&pattern)?;
+    let locale = number_locale(
+        "format_number",
+        args.get(2).unwrap_or(&Value::String("en".into())),
+    )?;
+    let number = match &args[0] {
+        Value::Float(f) if !f.is_finite() => return Ok(Value::String(to_str(&args[0]))),
+        other => as_decimal(other).ok_or_else(|| {
+            error::MorphError::mapping(format!(
+                "format_number() expects a number, got {}",
+                type_name(other)
+            ))
+        })?,
+    };
+
+    let rounded =
+        number.with_scale_round(pattern.max_frac as i64, bigdecimal::RoundingMode::HalfUp);
+    let text = rounded.abs().to_plain_string();
+    let (int_digits, frac_digits) = text.split_once('.').unwrap_or((&text, ""));
+    let frac_digits = frac_digits.trim_end_matches('0');
+    let frac_digits = format!("{frac_digits:0<width$}", width = pattern.min_frac);
+    let int_digits = int_digits.trim_start_matches('0');
+    let int_digits = format!("{int_digits:0>width$}", width = pattern.min_int);
+
+    let mut out = String::new();
+    if rounded.sign() == bigdecimal::num_bigint::Sign::Minus {
+        out.push('-');
+    }
+    out.push_str(pattern.prefix);
+    let digits: Vec<char> = int_digits.chars().collect();
+    for (i, digit) in digits.iter().enumerate() {
+        let remaining = digits.len() - i;
+        if i > 0
+            && pattern
+                .group_size
+                .is_some_and(|size| remaining.is_multiple_of(size))
+        {
+            out.push(locale.group);
+        }
+        out.push(*digit);
+    }
+    if !frac_digits.is_empty() {
+        out.push(locale.decimal);
+        out.push_str(&frac_digits);
+    } else if digits.is_empty() {
+        out.push('0');
+    }
+    out.push_str(pattern.suffix);
+    Ok(Value::String(out))
+}
+
+/// `parse_number(s, locale)`: read a number written with the locale's
+/// separators, e.g. `parse_number("1.234,56", "de")` → `1234.56`. Any
+/// whitespace counts as a group separator where the locale groups with
+/// spaces, and `'` where it groups with `’`.
+fn fn_parse_number(args: &[Value]) -> error::Result<Value> {
+    expect_min_args("parse_number", args, 1)?;
+    if args.len() > 2 {
+        return Err(error::MorphError::mapping(format!(
+            "parse_number() expects 1 or 2 argument(s), got {}",
+            args.len()
+        )));
+    }
+    let locale = number_locale(
+        "parse_number",
+        args.get(1).unwrap_or(&Value::String("en".into())),
+    )?;
+    let text = to_str(&args[0]);
+    let is_group = |c: char| {
+        c == locale.group
+            || (locale.group.is_whitespace() && c.is_whitespace())
+            || (locale.group == '\u{2019}' && c == '\'')
+    };
+    let normalized: String = text
+        .trim()
+        .chars()
+        .filter(|c| !is_group(*c))
+        .map(|c| if c == locale.decimal { '.' } else { c })
+        .collect();
+    let digits = normalized.strip_prefix(['-', '+']).unwrap_or(&normalized);
+    let well_formed = digits.chars().any(|c| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && digits.matches('.').count() <= 1;
+    if !well_formed {
+        return Err(error::MorphError::mapping(format!(
+            "parse_number() cannot read \"{text}\" as a number"
+        )));
+    }
+    if let Ok(i) = normalized.parse::<i64>() {
+        return Ok(Value::Int(i));
+    }
+    if let Some(d) = crate::formats::exact_number(&normalized) {
+        return Ok(Value::Decimal(d));
+    }
+    normalized.parse::<f64>().map(Value::Float).map_err(|_| {
+        error::MorphError::mapping(format!("parse_number() cannot read \"{text}\" as a number"))
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Hashing and encoding functions
+// ---------------------------------------------------------------------------
+
+/// The bytes a hashing or encoding function works on: bytes as they are,
+/// anything else as the UTF-8 of its string form. `None` for null, which
+/// these functions pass through.
+fn input_bytes(value: &Value) -> Option<Cow<'_, [u8]>> {
+    match value {
+        Value::Null => None,
+        Value::Bytes(b) => Some(Cow::Borrowed(b)),
+        Value::String(s) => Some(Cow::Borrowed(s.as_bytes())),
+        other => Some(Cow::Owned(to_str(other).into_bytes())),
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn fn_digest(name: &str, args: &[Value]) -> error::Result<Value> {
+    expect_args(name, args, 1)?;
+    let Some(data) = input_bytes(&args[0]) else {
+        return Ok(Value::Null);
+    };
+    let digest = match name {
+        "sha256" => sha2::Sha256::digest(&data).to_vec(),
+        "sha1" => sha1::Sha1::digest(&data).to_vec(),
+        _ => md5::Md5::digest(&data).to_vec(),
+    };
+    Ok(Value::String(to_hex(&digest)))
+}
+
+fn fn_hmac_sha256(args: &[Value]) -> error::Result<Value> {
+    expect_args("hmac_sha256", args, 2)?;
+    let (Some(key), Some(data)) = (input_bytes(&args[0]), input_bytes(&args[1])) else {
+        return Ok(Value::Null);
+    };
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(&key)
+        .map_err(|e| error::MorphError::mapping(format!("hmac_sha256(): {e}")))?;
+    mac.update(&data);
+    Ok(Value::String(to_hex(&mac.finalize().into_bytes())))
+}
+
+fn fn_base64_encode(args: &[Value]) -> error::Result<Value> {
+    expect_args("base64_encode", args, 1)?;
+    Ok(input_bytes(&args[0]).map_or(Value::Null, |data| {
+        Value::String(crate::formats::bytes_to_base64(&data))
+    }))
+}
+
+/// Decode standard or URL-safe base64, padded or not, into bytes.
+fn fn_base64_decode(args: &[Value]) -> error::Result<Value> {
+    use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+    use base64::engine::DecodePaddingMode;
+
+    expect_args("base64_decode", args, 1)?;
+    let text = match &args[0] {
+        Value::Null => return Ok(Value::Null),
+        Value::String(s) => s.trim(),
+        other => {
+            return Err(error::MorphError::mapping(format!(
+                "base64_decode() expects a string, got {}",
+                type_name(other)
+            )))
+        }
+    };
+    let config =
+        GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+    let engine = if text.contains(['-', '_']) {
+        GeneralPurpose::new(&base64::alphabet::URL_SAFE, config)
+    } else {
+        GeneralPurpose::new(&base64::alphabet::STANDARD, config)
+    };
+    engine
+        .decode(text)
+        .map(Value::Bytes)
+        .map_err(|e| error::MorphError::mapping(format!("base64_decode(): invalid base64: {e}")))
+}
+
+fn fn_hex(args: &[Value]) -> error::Result<Value> {
+    expect_args("hex", args, 1)?;
+    Ok(input_bytes(&args[0]).map_or(Value::Null, |data| Value::String(to_hex(&data))))
+}
+
+/// Everything but unreserved characters (RFC 3986) is percent-encoded.
+const URL_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+fn fn_url_encode(args: &[Value]) -> error::Result<Value> {
+    expect_args("url_encode", args, 1)?;
+    Ok(input_bytes(&args[0]).map_or(Value::Null, |data| {
+        Value::String(percent_encode(&data, URL_COMPONENT).to_string())
+    }))
+}
+
+fn fn_url_decode(args: &[Value]) -> error::Result<Value> {
+    expect_args("url_decode", args, 1)?;
+    if matches!(args[0], Value::Null) {
+        return Ok(Value::Null);
+    }
+    let text = to_str(&args[0]);
+    percent_decode_str(&text)
+        .decode_utf8()
+        .map(|decoded| Value::String(decoded.into_owned()))
+        .map_err(|_| error::MorphError::mapping("url_decode(): result is not valid UTF-8"))
+}
+
+/// Random UUID (RFC 4122 version 4) from the operating system's generator,
+/// so IDs stay unique and unpredictable even under `--seed`.
+fn fn_uuid_v4(args: &[Value]) -> error::Result<Value> {
+    expect_args("uuid_v4", args, 0)?;
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|e| {
+        error::MorphError::mapping(format!("uuid_v4(): system randomness unavailable: {e}"))
+    })?;
+    Ok(Value::String(format_uuid(bytes, 4)))
+}
+
+/// Name-based UUID (RFC 4122 version 5): the same namespace and name always
+/// give the same UUID. The namespace is a UUID or one of `dns`, `url`, `oid`
+/// and `x500`.
+fn fn_uuid_v5(args: &[Value]) -> error::Result<Value> {
+    expect_args("uuid_v5", args, 2)?;
+    let namespace = match &args[0] {
+        Value::String(s) => parse_uuid_namespace(s)?,
+        other => {
+            return Err(error::MorphError::mapping(format!(
+                "uuid_v5() expects a namespace string, got {}",
+                type_name(other)
+            )))
+        }
+    };
+    let Some(name) = input_bytes(&args[1]) else {
+        return Ok(Value::Null);
+    };
+    let mut hasher = sha1::Sha1::new();
+    hasher.update(namespace);
+    hasher.update(&name);
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hasher.finalize()[..16]);
+    Ok(Value::String(format_uuid(bytes, 5)))
+}
+
+fn parse_uuid_namespace(namespace: &str) -> error::Result<[u8; 16]> {
+    let text = match namespace.to_lowercase().as_str() {
+        "dns" => "6ba7b810-9dad-11d1-80b4-00c04fd430c8".to_string(),
+        "url" => "6ba7b811-9dad-11d1-80b4-00c04fd430c8".to_string(),
+        "oid" => "6ba7b812-9dad-11d1-80b4-00c04fd430c8".to_string(),
+        "x500" => "6ba7b814-9dad-11d1-80b4-00c04fd430c8".to_string(),
+        other => other.to_string(),
+    };
+    let digits: String = text.chars().filter(|c| *c != '-').collect();
+    let invalid = || {
+        error::MorphError::mapping(format!(
+            "uuid_v5(): invalid namespace \"{namespace}\", expected a UUID or one of dns, url, oid, x500"
+        ))
+    };
+    if digits.len() != 32 || !digits.is_ascii() {
+        return Err(invalid());
+    }
+    let mut bytes = [0u8; 16];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(bytes)
+}
+
+/// Set the version and RFC 4122 variant bits and format as
+/// `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`.
+fn format_uuid(mut bytes: [u8; 16], version: u8) -> String {
+    bytes[6] = (bytes[6] & 0x0f) | (version << 4);
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = to_hex(&bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Embedded document functions
+// ---------------------------------------------------------------------------
+
+/// Whether `name` parses a string into a document, so that its errors can be
+/// reported against the field the string came from.
+pub(crate) fn is_document_parser(name: &str) -> bool {
+    name.starts_with("parse_")
+}
+
+/// The format named by a `parse_<format>` / `to_<format>` function, or by the
+/// format argument of `parse_format` / `to_format`.
+fn document_format(name: &str, fixed: Option<&str>, arg: Option<&Value>) -> error::Result<Format> {
+    let format_name = match (fixed, arg) {
+        (Some(fixed), _) => fixed.to_string(),
+        (None, Some(Value::String(s))) => s.clone(),
+        (None, other) => {
+            return Err(error::MorphError::mapping(format!(
+                "{name}() expects a format name, got {}",
+                other.map_or("nothing", type_name)
+            )))
+        }
+    };
+    Format::from_name(&format_name).ok_or_else(|| {
+        let mut msg = format!("{name}(): unknown format '{format_name}'");
+        if let Some(suggestion) = crate::error::suggest_format(&format_name) {
+            msg.push_str(&format!(". Did you mean '{suggestion}'?"));
+        }
+        error::MorphError::mapping(msg)
+    })
+}
+
+/// `parse_json(s)`, `parse_yaml(s)`, ... and `parse_format(s, "csv")`: read a
+/// string with any input format. Null passes through.
+fn fn_parse_document(name: &str, args: &[Value]) -> error::Result<Value> {
+    let fixed = name.strip_prefix("parse_").filter(|f| *f != "format");
+    expect_args(name, args, if fixed.is_some() { 1 } else { 2 })?;
+    let format = document_format(name, fixed, args.get(1))?;
+    let text = match &args[0] {
+        Value::Null => return Ok(Value::Null),
+        Value::String(s) => Cow::Borrowed(s.as_str()),
+        Value::Bytes(b) => String::from_utf8_lossy(b),
+        other => {
+            return Err(error::MorphError::mapping(format!(
+                "{name}() expects a string, got {}",
+                type_name(other)
+            )))
+        }
+    };
+    crate::cli::parse_input(&text, format).map_err(|e| {
+        let detail = match e {
+            error::MorphError::Format { message, .. } => message,
+            other => other.to_string(),
+        };
+        error::MorphError::mapping(format!("{name}(): invalid {format}: {detail}"))
+    })
+}
+
+/// `to_json(v)`, `to_yaml(v)`, ... and `to_format(v, "yaml")`: write a value
+/// with any output format. `to_json` and `to_format` take an optional
+/// `pretty` flag.
+fn fn_serialize_document(name: &str, args: &[Value]) -> error::Result<Value> {
+    let fixed = name.strip_prefix("to_").filter(|f| *f != "format");
+    let format_args = usize::from(fixed.is_none());
+    let max = format_args + 1 + usize::from(matches!(name, "to_json" | "to_format"));
+    if args.len() <= format_args || args.len() > max {
+        let expected = if max == format_args + 1 {
+            max.to_string()
+        } else {
+            format!("{} or {max}", max - 1)
+        };
+        return Err(error::MorphError::mapping(format!(
+            "{name}() expects {expected} argument(s), got {}",
+            args.len()
+        )));
+    }
+    let format = document_format(name, fixed, args.get(1))?;
+    let pretty = args.get(1 + format_args).is_some_and(is_truthy);
+    let text = crate::cli::serialize_output(&args[0], format, pretty)?;
+    Ok(Value::String(text.trim_end_matches('\n').to_string()))
+}
+
 // ---------------------------------------------------------------------------
 // Type functions
 // ---------------------------------------------------------------------------
@@ -253,6 +1077,7 @@ fn fn_to_int(args: &[Value]) -> error::Result<Value> {
     match &args[0] {
         Value::Int(_) => Ok(args[0].clone()),
         Value::Float(f) => Ok(Value::Int(*f as i64)),
+        Value::Decimal(d) => Ok(decimal_to_integer(d, bigdecimal::RoundingMode::Down)),
         Value::String(s) => s
             .parse::<i64>()
             .map(Value::Int)
@@ -270,6 +1095,10 @@ fn fn_to_float(args: &[Value]) -> error::Result<Value> {
     match &args[0] {
         Value::Float(_) => Ok(args[0].clone()),
         Value::Int(i) => Ok(Value::Float(*i as f64)),
+        Value::Decimal(d) => {
+            use bigdecimal::ToPrimitive;
+            Ok(d.to_f64().map_or(Value::Null, Value::Float))
+        }
         Value::String(s) => s
             .parse::<f64>()
             .map(Value::Float)
@@ -282,6 +1111,21 @@ fn fn_to_float(args: &[Value]) -> error::Result<Value> {
     }
 }
 
+fn fn_to_decimal(args: &[Value]) -> error::Result<Value> {
+    expect_args("to_decimal", args, 1)?;
+    match &args[0] {
+        Value::String(s) => crate::value::parse_decimal(s.trim())
+            .map(Value::Decimal)
+            .ok_or_else(|| {
+                error::MorphError::mapping(format!("cannot convert \"{s}\" to decimal"))
+            }),
+        Value::Bool(b) => Ok(Value::Decimal(if *b { 1 } else { 0 }.into())),
+        other => as_decimal(other).map(Value::Decimal).ok_or_else(|| {
+            error::MorphError::mapping(format!("cannot convert {other:?} to decimal"))
+        }),
+    }
+}
+
 fn fn_to_string(args: &[Value]) -> error::Result<Value> {
     expect_args("to_string", args, 1)?;
     Ok(Value::String(to_str(&args[0])))
@@ -293,6 +1137,7 @@ fn fn_to_bool(args: &[Value]) -> error::Result<Value> {
         Value::Bool(_) => Ok(args[0].clone()),
         Value::Int(i) => Ok(Value::Bool(*i != 0)),
         Value::Float(f) => Ok(Value::Bool(*f != 0.0)),
+        Value::Decimal(d) => Ok(Value::Bool(!bigdecimal::Zero::is_zero(d))),
         Value::String(s) => match s.to_lowercase().as_str() {
             "true" | "1" | "yes" => Ok(Value::Bool(true)),
             "false" | "0" | "no" | "" => Ok(Value::Bool(false)),
@@ -310,17 +1155,22 @@ fn fn_to_bool(args: &[Value]) -> error::Result<Value> {
 
 fn fn_type_of(args: &[Value]) -> error::Result<Value> {
     expect_args("type_of", args, 1)?;
-    let type_name = match &args[0] {
+    Ok(Value::String(type_name(&args[0]).to_string()))
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
         Value::Null => "null",
         Value::Bool(_) => "bool",
         Value::Int(_) => "int",
         Value::Float(_) => "float",
+        Value::Decimal(_) => "decimal",
         Value::String(_) => "string",
         Value::Array(_) => "array",
         Value::Map(_) => "map",
         Value::Bytes(_) => "bytes",
-    };
-    Ok(Value::String(type_name.to_string()))
+        Value::DateTime(_) => "datetime",
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -332,44 +1182,51 @@ fn fn_abs(args: &[Value]) -> error::Result<Value> {
     match &args[0] {
         Value::Int(i) => Ok(Value::Int(i.abs())),
         Value::Float(f) => Ok(Value::Float(f.abs())),
+        Value::Decimal(d) => Ok(Value::Decimal(d.abs())),
         _ => Err(error::MorphError::mapping("abs() expects a number")),
     }
 }
 
+/// A number as an exact decimal, a float counting as its shortest text.
+/// `None` for non-numbers, NaN and the infinities.
+pub(crate) fn as_decimal(value: &Value) -> Option<BigDecimal> {
+    match value {
+        Value::Int(i) => Some((*i).into()),
+        Value::Float(f) => decimal_from_f64(*f),
+        Value::Decimal(d) => Some(d.clone()),
+        _ => None,
+    }
+}
+
+/// Round `d` to a whole number: an `Int` when it fits, else a `Decimal`.
+pub(crate) fn decimal_to_integer(d: &BigDecimal, mode: bigdecimal::RoundingMode) -> Value {
+    let whole = d.with_scale_round(0, mode);
+    decimal_to_i64(&whole).map_or(Value::Decimal(whole), Value::Int)
+}
+
 fn fn_min(args: &[Value]) -> error::Result<Value> {
     expect_min_args("min", args, 2)?;
-    let mut result = &args[0];
-    for arg in &args[1..] {
-        if compare_for_minmax(arg, result) == std::cmp::Ordering::Less {
-            result = arg;
-        }
-    }
-    Ok(result.clone())
+    Ok(extreme(args, std::cmp::Ordering::Less))
 }
 
 fn fn_max(args: &[Value]) -> error::Result<Value> {
     expect_min_args("max", args, 2)?;
-    let mut result = &args[0];
-    for arg in &args[1..] {
-        if compare_for_minmax(arg, result) == std::cmp::Ordering::Greater {
-            result = arg;
-        }
-    }
-    Ok(result.clone())
+    Ok(extreme(args, std::cmp::Ordering::Greater))
 }
 
-fn compare_for_minmax(a: &Value, b: &Value) -> std::cmp::Ordering {
-    match (a, b) {
-        (Value::Int(a), Value::Int(b)) => a.cmp(b),
-        (Value::Float(a), Value::Float(b)) => a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal),
-        (Value::Int(a), Value::Float(b)) => (*a as f64)
-            .partial_cmp(b)
-            .unwrap_or(std::cmp::Ordering::Equal),
-        (Value::Float(a), Value::Int(b)) => a
-            .partial_cmp(&(*b as f64))
-            .unwrap_or(std::cmp::Ordering::Equal),
-        _ => std::cmp::Ordering::Equal,
+/// The first argument that orders as `want` against all others. Nulls are
+/// skipped, as in the aggregate `min` and `max`; all nulls give null.
+fn extreme(args: &[Value], want: std::cmp::Ordering) -> Value {
+    let mut values = args.iter().filter(|arg| !matches!(arg, Value::Null));
+    let Some(mut result) = values.next() else {
+        return Value::Null;
+    };
+    for arg in values {
+        if arg.cmp(result) == want {
+            result = arg;
+        }
     }
+    result.clone()
 }
 
 fn fn_floor(args: &[Value]) -> error::Result<Value> {
@@ -377,6 +1234,7 @@ fn fn_floor(args: &[Value]) -> error::Result<Value> {
     match &args[0] {
         Value::Float(f) => Ok(Value::Int(f.floor() as i64)),
         Value::Int(_) => Ok(args[0].clone()),
+        Value::Decimal(d) => Ok(decimal_to_integer(d, bigdecimal::RoundingMode::Floor)),
         _ => Err(error::MorphError::mapping("floor() expects a number")),
     }
 }
@@ -386,6 +1244,7 @@ fn fn_ceil(args: &[Value]) -> error::Result<Value> {
     match &args[0] {
         Value::Float(f) => Ok(Value::Int(f.ceil() as i64)),
         Value::Int(_) => Ok(args[0].clone()),
+        Value::Decimal(d) => Ok(decimal_to_integer(d, bigdecimal::RoundingMode::Ceiling)),
         _ => Err(error::MorphError::mapping("ceil() expects a number")),
     }
 }
@@ -395,6 +1254,7 @@ fn fn_round(args: &[Value]) -> error::Result<Value> {
     match &args[0] {
         Value::Float(f) => Ok(Value::Int(f.round() as i64)),
         Value::Int(_) => Ok(args[0].clone()),
+        Value::Decimal(d) => Ok(decimal_to_integer(d, bigdecimal::RoundingMode::HalfUp)),
         _ => Err(error::MorphError::mapping("round() expects a number")),
     }
 }
@@ -453,13 +1313,8 @@ fn fn_unique(args: &[Value]) -> error::Result<Value> {
     expect_args("unique", args, 1)?;
     match &args[0] {
         Value::Array(arr) => {
-            let mut seen = Vec::new();
-            for item in arr {
-                if !seen.contains(item) {
-                    seen.push(item.clone());
-                }
-            }
-            Ok(Value::Array(seen))
+            let seen: indexmap::IndexSet<&Value> = arr.iter().collect();
+            Ok(Value::Array(seen.into_iter().cloned().collect()))
         }
         _ => Err(error::MorphError::mapping("unique() expects an array")),
     }
@@ -485,19 +1340,25 @@ fn fn_sum(args: &[Value]) -> error::Result<Value> {
     expect_args("sum", args, 1)?;
     match &args[0] {
         Value::Array(arr) => {
-            let mut int_sum: i64 = 0;
+            let mut int_sum: Option<i64> = Some(0);
             let mut is_float = false;
+            let mut is_decimal = false;
             let mut float_sum: f64 = 0.0;
             for item in arr {
                 match item {
                     Value::Int(i) => {
-                        int_sum += i;
+                        int_sum = int_sum.and_then(|sum| sum.checked_add(*i));
                         float_sum += *i as f64;
                     }
                     Value::Float(f) => {
                         is_float = true;
                         float_sum += f;
                     }
+                    Value::Decimal(d) => {
+                        use bigdecimal::ToPrimitive;
+                        is_decimal = true;
+                        float_sum += d.to_f64().unwrap_or(f64::NAN);
+                    }
                     _ => {
                         return Err(error::MorphError::mapping(
                             "sum() array must contain only numbers",
@@ -505,10 +1366,16 @@ fn fn_sum(args: &[Value]) -> error::Result<Value> {
                     }
                 }
             }
-            if is_float {
-                Ok(Value::Float(float_sum))
-            } else {
-                Ok(Value::Int(int_sum))
+            match int_sum {
+                Some(sum) if !is_float && !is_decimal => Ok(Value::Int(sum)),
+                _ if is_float && !is_decimal => Ok(Value::Float(float_sum)),
+                // Decimals, and ints that overflow, are summed exactly. A
+                // NaN or infinite float leaves the float total.
+                _ => Ok(arr
+                    .iter()
+                    .map(as_decimal)
+                    .sum::<Option<BigDecimal>>()
+                    .map_or(Value::Float(float_sum), Value::Decimal)),
             }
         }
         _ => Err(error::MorphError::mapping("sum() expects an array")),
@@ -551,32 +1418,687 @@ fn fn_group_by(args: &[Value]) -> error::Result<Value> {
     Ok(Value::Map(result))
 }
 
-// ---------------------------------------------------------------------------
-// Conditional functions
-// ---------------------------------------------------------------------------
+/// An integer argument of `name()`; `what` names it in the error.
+fn expect_int_arg(name: &str, what: &str, value: &Value) -> error::Result<i64> {
+    match value {
+        Value::Int(i) => Ok(*i),
+        other => Err(error::MorphError::mapping(format!(
+            "{name}() {what} must be an integer, got {}",
+            type_name(other)
+        ))),
+    }
+}
 
-fn fn_if(args: &[Value]) -> error::Result<Value> {
-    expect_args("if", args, 3)?;
-    let condition = &args[0];
-    let is_truthy = match condition {
-        Value::Null => false,
-        Value::Bool(b) => *b,
-        Value::Int(i) => *i != 0,
-        Value::Float(f) => *f != 0.0,
-        Value::String(s) => !s.is_empty(),
-        Value::Array(a) => !a.is_empty(),
-        Value::Map(m) => !m.is_empty(),
-        Value::Bytes(b) => !b.is_empty(),
-    };
-    if is_truthy {
-        Ok(args[1].clone())
+fn expect_map_arg<'a>(name: &str, value: &'a Value) -> error::Result<&'a IndexMap<String, Value>> {
+    match value {
+        Value::Map(m) => Ok(m),
+        other => Err(error::MorphError::mapping(format!(
+            "{name}() expects a map as its first argument, got {}",
+            type_name(other)
+        ))),
+    }
+}
+
+/// Resolve a possibly negative index against a sequence of `len` items,
+/// clamping to `0..=len`. Negative indexes count from the end.
+fn clamp_index(index: i64, len: usize) -> usize {
+    if index < 0 {
+        len.saturating_sub(index.unsigned_abs() as usize)
     } else {
-        Ok(args[2].clone())
+        (index as usize).min(len)
     }
 }
 
-// ---------------------------------------------------------------------------
-// Null / existence functions
+fn fn_zip(args: &[Value]) -> error::Result<Value> {
+    expect_min_args("zip", args, 1)?;
+    let arrays = args
+        .iter()
+        .map(|arg| match arg {
+            Value::Array(arr) => Ok(arr),
+            other => Err(error::MorphError::mapping(format!(
+                "zip() expects arrays, got {}",
+                type_name(other)
+            ))),
+        })
+        .collect::<error::Result<Vec<_>>>()?;
+    let len = arrays.iter().map(|arr| arr.len()).min().unwrap_or(0);
+    Ok(Value::Array(
+        (0..len)
+            .map(|i| Value::Array(arrays.iter().map(|arr| arr[i].clone()).collect()))
+            .collect(),
+    ))
+}
+
+fn fn_chunk(args: &[Value]) -> error::Result<Value> {
+    expect_args("chunk", args, 2)?;
+    let arr = expect_array_arg("chunk", args)?;
+    let size = expect_int_arg("chunk", "size", &args[1])?;
+    if size < 1 {
+        return Err(error::MorphError::mapping(format!(
+            "chunk() size must be at least 1, got {size}"
+        )));
+    }
+    Ok(Value::Array(
+        arr.chunks(size as usize)
+            .map(|chunk| Value::Array(chunk.to_vec()))
+            .collect(),
+    ))
+}
+
+/// Most items `range()` may produce.
+const MAX_RANGE_LEN: u64 = 10_000_000;
+
+/// `range(end)`, `range(start, end)` or `range(start, end, step)`; `end` is
+/// exclusive.
+fn fn_range(args: &[Value]) -> error::Result<Value> {
+    if args.is_empty() || args.len() > 3 {
+        return Err(error::MorphError::mapping(format!(
+            "range() expects 1 to 3 argument(s), got {}",
+            args.len()
+        )));
+    }
+    let bounds = args
+        .iter()
+        .zip(
+            ["start", "end", "step"]
+                .iter()
+                .skip(usize::from(args.len() == 1)),
+        )
+        .map(|(arg, what)| expect_int_arg("range", what, arg))
+        .collect::<error::Result<Vec<_>>>()?;
+    let (start, end, step) = match bounds[..] {
+        [end] => (0, end, 1),
+        [start, end] => (start, end, 1),
+        [start, end, step] => (start, end, step),
+        _ => unreachable!(),
+    };
+    if step == 0 {
+        return Err(error::MorphError::mapping("range() step must not be 0"));
+    }
+    let span = if step > 0 {
+        i128::from(end) - i128::from(start)
+    } else {
+        i128::from(start) - i128::from(end)
+    };
+    let len = if span > 0 {
+        ((span - 1) / i128::from(step).abs() + 1) as u64
+    } else {
+        0
+    };
+    if len > MAX_RANGE_LEN {
+        return Err(error::MorphError::mapping(format!(
+            "range() would produce {len} items, more than the limit of {MAX_RANGE_LEN}"
+        )));
+    }
+    let items = (0..len as i64)
+        .map(|n| Value::Int(start + n * step))
+        .collect();
+    Ok(Value::Array(items))
+}
+
+/// `slice(value, start, end?)` over an array or the graphemes of a string.
+/// Negative indexes count from the end.
+fn fn_slice(args: &[Value]) -> error::Result<Value> {
+    expect_min_args("slice", args, 2)?;
+    if args.len() > 3 {
+        return Err(error::MorphError::mapping(format!(
+            "slice() expects 2 or 3 argument(s), got {}",
+            args.len()
+        )));
+    }
+    let start = expect_int_arg("slice", "start", &args[1])?;
+    let end = args
+        .get(2)
+        .map(|end| expect_int_arg("slice", "end", end))
+        .transpose()?;
+    let range = |len: usize| {
+        let start = clamp_index(start, len);
+        let end = end.map_or(len, |end| clamp_index(end, len));
+        start..end.max(start)
+    };
+    match &args[0] {
+        Value::Array(arr) => Ok(Value::Array(arr[range(arr.len())].to_vec())),
+        Value::String(s) => {
+            let graphemes: Vec<&str> = s.graphemes(true).collect();
+            Ok(Value::String(graphemes[range(graphemes.len())].concat()))
+        }
+        other => Err(error::MorphError::mapping(format!(
+            "slice() expects an array or string, got {}",
+            type_name(other)
+        ))),
+    }
+}
+
+/// Position of the first element equal to the needle, or of the first
+/// occurrence of a substring that starts a grapheme (counted in graphemes);
+/// null when absent.
+fn fn_index_of(args: &[Value]) -> error::Result<Value> {
+    expect_args("index_of", args, 2)?;
+    let index = match (&args[0], &args[1]) {
+        (Value::Array(arr), needle) => arr.iter().position(|item| item == needle),
+        (Value::String(_), Value::String(needle)) if needle.is_empty() => Some(0),
+        (Value::String(s), Value::String(needle)) => s
+            .grapheme_indices(true)
+            .position(|(byte, _)| s[byte..].starts_with(needle.as_str())),
+        (Value::String(_), other) => {
+            return Err(error::MorphError::mapping(format!(
+                "index_of() on a string expects a string to find, got {}",
+                type_name(other)
+            )));
+        }
+        (other, _) => {
+            return Err(error::MorphError::mapping(format!(
+                "index_of() expects an array or string, got {}",
+                type_name(other)
+            )));
+        }
+    };
+    Ok(index.map_or(Value::Null, |i| Value::Int(i as i64)))
+}
+
+fn fn_entries(args: &[Value]) -> error::Result<Value> {
+    expect_args("entries", args, 1)?;
+    let map = expect_map_arg("entries", &args[0])?;
+    Ok(Value::Array(
+        map.iter()
+            .map(|(k, v)| {
+                let mut entry = IndexMap::new();
+                entry.insert("key".to_string(), Value::String(k.clone()));
+                entry.insert("value".to_string(), v.clone());
+                Value::Map(entry)
+            })
+            .collect(),
+    ))
+}
+
+/// Build a map from `{"key": k, "value": v}` maps or `[k, v]` pairs. Later
+/// entries win when a key repeats.
+fn fn_from_entries(args: &[Value]) -> error::Result<Value> {
+    expect_args("from_entries", args, 1)?;
+    let arr = expect_array_arg("from_entries", args)?;
+    let mut map = IndexMap::new();
+    for entry in arr {
+        let (key, value) = match entry {
+            Value::Map(m) => (
+                m.get("key").unwrap_or(&Value::Null),
+                m.get("value").cloned().unwrap_or(Value::Null),
+            ),
+            Value::Array(pair) if pair.len() == 2 => (&pair[0], pair[1].clone()),
+            other => {
+                return Err(error::MorphError::mapping(format!(
+                    "from_entries() expects {{key, value}} maps or [key, value] pairs, got {}",
+                    type_name(other)
+                )));
+            }
+        };
+        let key = match key {
+            Value::String(s) => s.clone(),
+            Value::Int(_) | Value::Float(_) | Value::Decimal(_) | Value::Bool(_) => to_str(key),
+            other => {
+                return Err(error::MorphError::mapping(format!(
+                    "from_entries() keys must be strings, got {}",
+                    type_name(other)
+                )));
+            }
+        };
+        map.insert(key, value);
+    }
+    Ok(Value::Map(map))
+}
+
+/// Deep-merge maps left to right, as `Value::merge` does.
+fn fn_merge(args: &[Value]) -> error::Result<Value> {
+    expect_min_args("merge", args, 2)?;
+    let mut merged = Value::Map(IndexMap::new());
+    for arg in args {
+        match arg {
+            Value::Map(_) => merged.merge(arg.clone()),
+            Value::Null => {}
+            other => {
+                return Err(error::MorphError::mapping(format!(
+                    "merge() expects maps, got {}",
+                    type_name(other)
+                )));
+            }
+        }
+    }
+    Ok(merged)
+}
+
+/// Key names for `pick`/`omit`: either trailing string arguments or a single
+/// array of strings.
+fn key_list<'a>(name: &str, args: &'a [Value]) -> error::Result<Vec<&'a str>> {
+    let keys = match args {
+        [Value::Array(keys)] => keys.as_slice(),
+        keys => keys,
+    };
+    keys.iter()
+        .map(|key| match key {
+            Value::String(s) => Ok(s.as_str()),
+            other => Err(error::MorphError::mapping(format!(
+                "{name}() keys must be strings, got {}",
+                type_name(other)
+            ))),
+        })
+        .collect()
+}
+
+/// Keep only the named keys, in the order they are named.
+fn fn_pick(args: &[Value]) -> error::Result<Value> {
+    expect_min_args("pick", args, 1)?;
+    let map = expect_map_arg("pick", &args[0])?;
+    let keys = key_list("pick", &args[1..])?;
+    Ok(Value::Map(
+        keys.into_iter()
+            .filter_map(|key| map.get_key_value(key))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect(),
+    ))
+}
+
+fn fn_omit(args: &[Value]) -> error::Result<Value> {
+    expect_min_args("omit", args, 1)?;
+    let map = expect_map_arg("omit", &args[0])?;
+    let keys = key_list("omit", &args[1..])?;
+    Ok(Value::Map(
+        map.iter()
+            .filter(|(k, _)| !keys.contains(&k.as_str()))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect(),
+    ))
+}
+
+/// `flatten(arr, depth)` splices nested arrays `depth` levels deep (default 1).
+fn fn_flatten(args: &[Value]) -> error::Result<Value> {
+    expect_min_args("flatten", args, 1)?;
+    if args.len() > 2 {
+        return Err(error::MorphError::mapping(format!(
+            "flatten() expects 1 or 2 argument(s), got {}",
+            args.len()
+        )));
+    }
+    let arr = expect_array_arg("flatten", args)?;
+    let depth = match args.get(1) {
+        Some(depth) => expect_int_arg("flatten", "depth", depth)?,
+        None => 1,
+    };
+    if depth < 0 {
+        return Err(error::MorphError::mapping(format!(
+            "flatten() depth must not be negative, got {depth}"
+        )));
+    }
+
+    fn splice(items: &[Value], depth: i64, out: &mut Vec<Value>) {
+        for item in items {
+            match item {
+                Value::Array(inner) if depth > 0 => splice(inner, depth - 1, out),
+                other => out.push(other.clone()),
+            }
+        }
+    }
+    let mut out = Vec::new();
+    splice(arr, depth, &mut out);
+    Ok(Value::Array(out))
+}
+
+/// Number of non-null elements, matching `count(.field)` in `aggregate`.
+fn fn_count(args: &[Value]) -> error::Result<Value> {
+    expect_args("count", args, 1)?;
+    let arr = expect_array_arg("count", args)?;
+    let n = arr.iter().filter(|item| **item != Value::Null).count();
+    Ok(Value::Int(n as i64))
+}
+
+/// The non-null elements of `name()`'s array argument, which must all be
+/// numbers.
+fn numeric_items(name: &str, args: &[Value]) -> error::Result<Vec<Value>> {
+    let arr = expect_array_arg(name, args)?;
+    arr.iter()
+        .filter(|item| **item != Value::Null)
+        .map(|item| match item {
+            Value::Int(_) | Value::Float(_) | Value::Decimal(_) => Ok(item.clone()),
+            other => Err(error::MorphError::mapping(format!(
+                "{name}() array must contain only numbers, got {}",
+                type_name(other)
+            ))),
+        })
+        .collect()
+}
+
+/// Mean of the non-null elements; null for an empty array. Like `avg` in
+/// `aggregate`, the result is a float unless decimals are involved.
+fn fn_avg(args: &[Value]) -> error::Result<Value> {
+    expect_args("avg", args, 1)?;
+    let items = numeric_items("avg", args)?;
+    if items.is_empty() {
+        return Ok(Value::Null);
+    }
+    let n = items.len();
+    Ok(match fn_sum(&[Value::Array(items)])? {
+        Value::Int(total) => Value::Float(total as f64 / n as f64),
+        Value::Float(total) => Value::Float(total / n as f64),
+        Value::Decimal(total) => Value::Decimal(total / BigDecimal::from(n as u64)),
+        other => other,
+    })
+}
+
+fn fn_median(args: &[Value]) -> error::Result<Value> {
+    expect_args("median", args, 1)?;
+    let items = numeric_items("median", args)?;
+    Ok(percentile_of(items, 50.0))
+}
+
+/// `percentile(arr, p)` for `p` in 0..=100, interpolating linearly between
+/// the two nearest elements.
+fn fn_percentile(args: &[Value]) -> error::Result<Value> {
+    expect_args("percentile", args, 2)?;
+    let items = numeric_items("percentile", args)?;
+    let p = match &args[1] {
+        Value::Int(i) => *i as f64,
+        Value::Float(f) => *f,
+        Value::Decimal(d) => bigdecimal::ToPrimitive::to_f64(d).unwrap_or(f64::NAN),
+        other => {
+            return Err(error::MorphError::mapping(format!(
+                "percentile() p must be a number, got {}",
+                type_name(other)
+            )));
+        }
+    };
+    if !(0.0..=100.0).contains(&p) {
+        return Err(error::MorphError::mapping(format!(
+            "percentile() p must be between 0 and 100, got {p}"
+        )));
+    }
+    Ok(percentile_of(items, p))
+}
+
+/// An element that lands exactly on the rank is returned as is; otherwise the
+/// neighbours are interpolated, exactly when either is a decimal.
+fn percentile_of(mut items: Vec<Value>, p: f64) -> Value {
+    if items.is_empty() {
+        return Value::Null;
+    }
+    items.sort();
+    let rank = p * (items.len() - 1) as f64 / 100.0;
+    let (lo, hi) = (&items[rank.floor() as usize], &items[rank.ceil() as usize]);
+    let frac = rank.fract();
+    if frac == 0.0 || lo == hi {
+        return lo.clone();
+    }
+    if matches!(lo, Value::Decimal(_)) || matches!(hi, Value::Decimal(_)) {
+        if let (Some(a), Some(b), Some(t)) =
+            (as_decimal(lo), as_decimal(hi), decimal_from_f64(frac))
+        {
+            return Value::Decimal(&a + (&b - &a) * t);
+        }
+    }
+    let as_f64 = |v: &Value| match v {
+        Value::Int(i) => *i as f64,
+        Value::Float(f) => *f,
+        Value::Decimal(d) => bigdecimal::ToPrimitive::to_f64(d).unwrap_or(f64::NAN),
+        _ => f64::NAN,
+    };
+    let (a, b) = (as_f64(lo), as_f64(hi));
+    Value::Float(a + (b - a) * frac)
+}
+
+// ---------------------------------------------------------------------------
+// Conditional functions
+// ---------------------------------------------------------------------------
+
+fn fn_if(args: &[Value]) -> error::Result<Value> {
+    expect_args("if", args, 3)?;
+    if is_truthy(&args[0]) {
+        Ok(args[1].clone())
+    } else {
+        Ok(args[2].clone())
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Int(i) => *i != 0,
+        Value::Float(f) => *f != 0.0,
+        Value::Decimal(d) => !bigdecimal::Zero::is_zero(d),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Map(m) => !m.is_empty(),
+        Value::Bytes(b) => !b.is_empty(),
+        Value::DateTime(_) => true,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Random functions
+// ---------------------------------------------------------------------------
+
+/// A random integer between `lo` and `hi`, both inclusive.
+fn fn_random_int(args: &[Value]) -> error::Result<Value> {
+    expect_args("random_int", args, 2)?;
+    let lo = expect_int_arg("random_int", "lower bound", &args[0])?;
+    let hi = expect_int_arg("random_int", "upper bound", &args[1])?;
+    if lo > hi {
+        return Err(error::MorphError::mapping(format!(
+            "random_int() lower bound {lo} is greater than upper bound {hi}"
+        )));
+    }
+    let span = hi.abs_diff(lo).checked_add(1);
+    let offset = random::with_current(|rng| match span {
+        Some(span) => rng.below(span),
+        None => rng.next_u64(),
+    });
+    Ok(Value::Int(lo.wrapping_add_unsigned(offset)))
+}
+
+/// A random element of an array, or null for an empty one.
+fn fn_random_choice(args: &[Value]) -> error::Result<Value> {
+    expect_args("random_choice", args, 1)?;
+    let arr = expect_array_arg("random_choice", args)?;
+    if arr.is_empty() {
+        return Ok(Value::Null);
+    }
+    let i = random::with_current(|rng| rng.below(arr.len() as u64));
+    Ok(arr[i as usize].clone())
+}
+
+fn fn_shuffle(args: &[Value]) -> error::Result<Value> {
+    expect_args("shuffle", args, 1)?;
+    let mut items = expect_array_arg("shuffle", args)?.to_vec();
+    random::with_current(|rng| {
+        // Fisher-Yates
+        for i in (1..items.len()).rev() {
+            let j = rng.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    });
+    Ok(Value::Array(items))
+}
+
+fn fn_fake(args: &[Value]) -> error::Result<Value> {
+    expect_args("fake", args, 1)?;
+    let kind = match &args[0] {
+        Value::String(s) => s,
+        other => {
+            return Err(error::MorphError::mapping(format!(
+                "fake() expects a kind such as \"email\", got {}",
+                type_name(other)
+            )));
+        }
+    };
+    random::with_current(|rng| fake::generate(kind, rng))
+        .map(Value::String)
+        .ok_or_else(|| {
+            error::MorphError::mapping(format!(
+                "fake() does not know kind \"{kind}\"; expected one of: {}",
+                fake::KINDS.join(", ")
+            ))
+        })
+}
+
+// ---------------------------------------------------------------------------
+// Higher-order functions
+// ---------------------------------------------------------------------------
+
+type Lambda<'a> = dyn FnMut(&[Value]) -> error::Result<Value> + 'a;
+
+/// Like [`expect_args`], but for higher-order functions whose lambda argument
+/// is not part of `args`; counts in the message include the lambda.
+fn expect_lambda_args(name: &str, args: &[Value], expected: usize) -> error::Result<()> {
+    if args.len() != expected {
+        return Err(error::MorphError::mapping(format!(
+            "{name}() expects {} argument(s), got {}",
+            expected + 1,
+            args.len() + 1
+        )));
+    }
+    Ok(())
+}
+
+fn expect_array_arg<'a>(name: &str, args: &'a [Value]) -> error::Result<&'a [Value]> {
+    match args.first() {
+        Some(Value::Array(arr)) => Ok(arr),
+        Some(other) => Err(error::MorphError::mapping(format!(
+            "{name}() expects an array as its first argument, got {}",
+            type_name(other)
+        ))),
+        None => Err(error::MorphError::mapping(format!(
+            "{name}() expects an array as its first argument"
+        ))),
+    }
+}
+
+fn hof_map(args: &[Value], f: &mut Lambda) -> error::Result<Value> {
+    expect_lambda_args("map", args, 1)?;
+    let arr = expect_array_arg("map", args)?;
+    let mapped = arr
+        .iter()
+        .map(|item| f(std::slice::from_ref(item)))
+        .collect::<error::Result<Vec<_>>>()?;
+    Ok(Value::Array(mapped))
+}
+
+fn hof_filter(args: &[Value], f: &mut Lambda) -> error::Result<Value> {
+    expect_lambda_args("filter", args, 1)?;
+    let arr = expect_array_arg("filter", args)?;
+    let mut kept = Vec::new();
+    for item in arr {
+        if is_truthy(&f(std::slice::from_ref(item))?) {
+            kept.push(item.clone());
+        }
+    }
+    Ok(Value::Array(kept))
+}
+
+fn hof_reduce(args: &[Value], f: &mut Lambda) -> error::Result<Value> {
+    if args.len() > 2 {
+        return Err(error::MorphError::mapping(format!(
+            "reduce() expects 2 or 3 argument(s), got {}",
+            args.len() + 1
+        )));
+    }
+    let arr = expect_array_arg("reduce", args)?;
+    // Without an initial value the first element seeds the accumulator.
+    let (mut acc, rest) = match args.get(1) {
+        Some(init) => (init.clone(), arr),
+        None => match arr.split_first() {
+            Some((first, rest)) => (first.clone(), rest),
+            None => return Ok(Value::Null),
+        },
+    };
+    for item in rest {
+        acc = f(&[acc, item.clone()])?;
+    }
+    Ok(acc)
+}
+
+fn hof_any(args: &[Value], f: &mut Lambda) -> error::Result<Value> {
+    expect_lambda_args("any", args, 1)?;
+    let arr = expect_array_arg("any", args)?;
+    for item in arr {
+        if is_truthy(&f(std::slice::from_ref(item))?) {
+            return Ok(Value::Bool(true));
+        }
+    }
+    Ok(Value::Bool(false))
+}
+
+fn hof_all(args: &[Value], f: &mut Lambda) -> error::Result<Value> {
+    expect_lambda_args("all", args, 1)?;
+    let arr = expect_array_arg("all", args)?;
+    for item in arr {
+        if !is_truthy(&f(std::slice::from_ref(item))?) {
+            return Ok(Value::Bool(false));
+        }
+    }
+    Ok(Value::Bool(true))
+}
+
+fn hof_find(args: &[Value], f: &mut Lambda) -> error::Result<Value> {
+    expect_lambda_args("find", args, 1)?;
+    let arr = expect_array_arg("find", args)?;
+    for item in arr {
+        if is_truthy(&f(std::slice::from_ref(item))?) {
+            return Ok(item.clone());
+        }
+    }
+    Ok(Value::Null)
+}
+
+fn hof_sort_by(args: &[Value], f: &mut Lambda) -> error::Result<Value> {
+    expect_lambda_args("sort_by", args, 1)?;
+    let arr = expect_array_arg("sort_by", args)?;
+    let mut keyed = arr
+        .iter()
+        .map(|item| Ok((f(std::slice::from_ref(item))?, item.clone())))
+        .collect::<error::Result<Vec<_>>>()?;
+    keyed.sort_by(|(a, _), (b, _)| compare_sort_keys(a, b));
+    Ok(Value::Array(keyed.into_iter().map(|(_, v)| v).collect()))
+}
+
+/// Order sort keys by `Value`'s canonical order, except that nulls go last.
+fn compare_sort_keys(a: &Value, b: &Value) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        _ => a.cmp(b),
+    }
+}
+
+fn hof_flat_map(args: &[Value], f: &mut Lambda) -> error::Result<Value> {
+    expect_lambda_args("flat_map", args, 1)?;
+    let arr = expect_array_arg("flat_map", args)?;
+    let mut flattened = Vec::new();
+    for item in arr {
+        match f(std::slice::from_ref(item))? {
+            Value::Array(inner) => flattened.extend(inner),
+            other => flattened.push(other),
+        }
+    }
+    Ok(Value::Array(flattened))
+}
+
+fn hof_group_by(args: &[Value], f: &mut Lambda) -> error::Result<Value> {
+    expect_lambda_args("group_by", args, 1)?;
+    let arr = expect_array_arg("group_by", args)?;
+    let mut groups: indexmap::IndexMap<String, Vec<Value>> = indexmap::IndexMap::new();
+    for item in arr {
+        let key = to_str(&f(std::slice::from_ref(item))?);
+        groups.entry(key).or_default().push(item.clone());
+    }
+
+    let mut result = indexmap::IndexMap::new();
+    for (key, values) in groups {
+        result.insert(key, Value::Array(values));
+    }
+    Ok(Value::Map(result))
+}
+
+// ---------------------------------------------------------------------------
+// Null / existence functions
 // ---------------------------------------------------------------------------
 
 fn fn_is_array(args: &[Value]) -> error::Result<Value> {
@@ -880,6 +2402,51 @@ mod tests {
         );
     }
 
+    // -----------------------------------------------------------------------
+    // Case conversion
+    // -----------------------------------------------------------------------
+
+    fn case(name: &str, s: &str) -> String {
+        match call_function(name, &[Value::String(s.into())]).unwrap() {
+            Value::String(out) => out,
+            other => panic!("expected string, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(split_words("userID"), vec!["user", "ID"]);
+        assert_eq!(split_words("HTTPServer"), vec!["HTTP", "Server"]);
+        assert_eq!(split_words("version2Name"), vec!["version2", "Name"]);
+        assert_eq!(split_words("kebab-case key"), vec!["kebab", "case", "key"]);
+        assert_eq!(split_words("__"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_case_functions() {
+        assert_eq!(case("snake_case", "firstName"), "first_name");
+        assert_eq!(case("camel_case", "first_name"), "firstName");
+        assert_eq!(case("kebab_case", "FirstName"), "first-name");
+        assert_eq!(case("pascal_case", "first-name"), "FirstName");
+        assert_eq!(case("screaming_snake_case", "apiKey"), "API_KEY");
+        assert_eq!(
+            case("snake_case", "parseHTTPResponse"),
+            "parse_http_response"
+        );
+    }
+
+    #[test]
+    fn test_case_keeps_leading_underscores() {
+        assert_eq!(case("camel_case", "_id"), "_id");
+        assert_eq!(case("snake_case", "__typeName"), "__type_name");
+    }
+
+    #[test]
+    fn test_case_non_ascii() {
+        assert_eq!(case("snake_case", "straßeName"), "straße_name");
+        assert_eq!(case("pascal_case", "élan vital"), "ÉlanVital");
+    }
+
     // -----------------------------------------------------------------------
     // Type functions
     // -----------------------------------------------------------------------
@@ -984,6 +2551,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn min_and_max_skip_nulls() {
+        assert_eq!(
+            call_function("min", &[Value::Int(3), Value::Null]).unwrap(),
+            Value::Int(3)
+        );
+        assert_eq!(
+            call_function("max", &[Value::Null, Value::Int(1), Value::Null]).unwrap(),
+            Value::Int(1)
+        );
+        assert_eq!(
+            call_function("min", &[Value::Null, Value::Null]).unwrap(),
+            Value::Null
+        );
+    }
+
     #[test]
     fn test_floor() {
         assert_eq!(
@@ -1012,22 +2595,65 @@ mod tests {
         );
     }
 
-    // -----------------------------------------------------------------------
-    // Null functions
-    // -----------------------------------------------------------------------
-
     #[test]
-    fn test_is_null() {
+    fn test_decimal_math() {
+        let dec = |s: &str| Value::Decimal(s.parse().unwrap());
         assert_eq!(
-            call_function("is_null", &[Value::Null]).unwrap(),
-            Value::Bool(true)
+            call_function("round", &[dec("2.5")]).unwrap(),
+            Value::Int(3)
         );
         assert_eq!(
-            call_function("is_null", &[Value::Int(0)]).unwrap(),
-            Value::Bool(false)
+            call_function("floor", &[dec("-2.5")]).unwrap(),
+            Value::Int(-3)
         );
-    }
-
+        assert_eq!(
+            call_function("ceil", &[dec("12345678901234567890.1")]).unwrap(),
+            dec("12345678901234567891")
+        );
+        assert_eq!(call_function("abs", &[dec("-0.10")]).unwrap(), dec("0.10"));
+        assert_eq!(
+            call_function("to_decimal", &[Value::Float(0.1)]).unwrap(),
+            dec("0.1")
+        );
+        assert_eq!(
+            call_function("type_of", &[dec("1")]).unwrap(),
+            Value::String("decimal".into())
+        );
+    }
+
+    #[test]
+    fn test_sum_exact() {
+        let dec = |s: &str| Value::Decimal(s.parse().unwrap());
+        assert_eq!(
+            call_function("sum", &[Value::Array(vec![dec("0.1"), Value::Float(0.2)])]).unwrap(),
+            dec("0.3")
+        );
+        assert_eq!(
+            call_function(
+                "sum",
+                &[Value::Array(vec![Value::Int(i64::MAX), Value::Int(1)])]
+            )
+            .unwrap(),
+            dec("9223372036854775808")
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // Null functions
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn test_is_null() {
+        assert_eq!(
+            call_function("is_null", &[Value::Null]).unwrap(),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            call_function("is_null", &[Value::Int(0)]).unwrap(),
+            Value::Bool(false)
+        );
+    }
+
     #[test]
     fn test_coalesce() {
         assert_eq!(
@@ -1102,6 +2728,79 @@ mod tests {
         }
     }
 
+    // -----------------------------------------------------------------------
+    // Higher-order functions
+    // -----------------------------------------------------------------------
+
+    fn ints(values: &[i64]) -> Value {
+        Value::Array(values.iter().map(|i| Value::Int(*i)).collect())
+    }
+
+    fn double(args: &[Value]) -> error::Result<Value> {
+        match &args[0] {
+            Value::Int(i) => Ok(Value::Int(i * 2)),
+            _ => Ok(Value::Null),
+        }
+    }
+
+    #[test]
+    fn test_lambda_arity() {
+        assert_eq!(lambda_arity("map"), Some(1));
+        assert_eq!(lambda_arity("reduce"), Some(2));
+        assert_eq!(lambda_arity("lower"), None);
+    }
+
+    #[test]
+    fn test_hof_map() {
+        let r = call_higher_order("map", &[ints(&[1, 2, 3])], &mut double).unwrap();
+        assert_eq!(r, ints(&[2, 4, 6]));
+    }
+
+    #[test]
+    fn test_hof_reduce_without_init() {
+        let mut add = |args: &[Value]| match (&args[0], &args[1]) {
+            (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a + b)),
+            _ => Ok(Value::Null),
+        };
+        let r = call_higher_order("reduce", &[ints(&[1, 2, 3])], &mut add).unwrap();
+        assert_eq!(r, Value::Int(6));
+        let r = call_higher_order("reduce", &[ints(&[])], &mut add).unwrap();
+        assert_eq!(r, Value::Null);
+    }
+
+    #[test]
+    fn test_hof_sort_by_nulls_last() {
+        let arr = Value::Array(vec![Value::Null, Value::Int(3), Value::Int(1)]);
+        let r = call_higher_order("sort_by", &[arr], &mut |args: &[Value]| Ok(args[0].clone()))
+            .unwrap();
+        assert_eq!(
+            r,
+            Value::Array(vec![Value::Int(1), Value::Int(3), Value::Null])
+        );
+    }
+
+    #[test]
+    fn test_hof_requires_array() {
+        let err = call_higher_order("map", &[Value::Int(1)], &mut double).unwrap_err();
+        match err {
+            crate::error::MorphError::Mapping { message, .. } => {
+                assert!(message.contains("expects an array"), "msg: {message}");
+            }
+            other => panic!("expected Mapping error, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_hof_without_lambda() {
+        let err = call_function("map", &[ints(&[1]), Value::String("x".into())]).unwrap_err();
+        match err {
+            crate::error::MorphError::Mapping { message, .. } => {
+                assert!(message.contains("expects a lambda"), "msg: {message}");
+            }
+            other => panic!("expected Mapping error, got: {other:?}"),
+        }
+    }
+
     // -----------------------------------------------------------------------
     // Aliases
     // -----------------------------------------------------------------------
@@ -1125,4 +2824,577 @@ mod tests {
         assert!(call_function("bool", &[Value::Int(1)]).is_ok());
         assert!(call_function("typeof", &[Value::Int(1)]).is_ok());
     }
+
+    // -----------------------------------------------------------------------
+    // Random functions
+    // -----------------------------------------------------------------------
+
+    /// Call `name` with a generator seeded from `seed`.
+    fn call_seeded(seed: u64, name: &str, args: &[Value]) -> Value {
+        random::with_rng(&mut random::Rng::new(seed), || {
+            call_function(name, args).unwrap()
+        })
+    }
+
+    #[test]
+    fn test_random_int_bounds_and_seed() {
+        let args = [Value::Int(-2), Value::Int(2)];
+        for seed in 0..50 {
+            match call_seeded(seed, "random_int", &args) {
+                Value::Int(i) => assert!((-2..=2).contains(&i), "got {i}"),
+                other => panic!("expected int, got: {other:?}"),
+            }
+        }
+        assert_eq!(
+            call_seeded(7, "random_int", &args),
+            call_seeded(7, "random_int", &args)
+        );
+        let full = [Value::Int(i64::MIN), Value::Int(i64::MAX)];
+        assert!(matches!(call_seeded(1, "random_int", &full), Value::Int(_)));
+        let err = call_function("random_int", &[Value::Int(3), Value::Int(1)]).unwrap_err();
+        assert!(err.to_string().contains("greater than"), "err: {err}");
+    }
+
+    #[test]
+    fn test_random_choice() {
+        let arr = ints(&[10, 20, 30]);
+        let picked = call_seeded(3, "random_choice", std::slice::from_ref(&arr));
+        assert!(matches!(picked, Value::Int(10 | 20 | 30)), "got {picked:?}");
+        assert_eq!(call_seeded(3, "random_choice", &[ints(&[])]), Value::Null);
+    }
+
+    #[test]
+    fn test_shuffle_is_a_permutation() {
+        let arr = ints(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        let shuffled = call_seeded(11, "shuffle", std::slice::from_ref(&arr));
+        let mut sorted = match shuffled.clone() {
+            Value::Array(items) => items,
+            other => panic!("expected array, got: {other:?}"),
+        };
+        sorted.sort();
+        assert_eq!(Value::Array(sorted), arr);
+        assert_eq!(
+            call_seeded(11, "shuffle", std::slice::from_ref(&arr)),
+            shuffled
+        );
+    }
+
+    #[test]
+    fn test_fake_kinds() {
+        let email = call_seeded(1, "fake", &[Value::String("email".into())]);
+        assert!(matches!(&email, Value::String(s) if s.contains("@example.")));
+        let err = call_function("fake", &[Value::String("ssn".into())]).unwrap_err();
+        assert!(
+            err.to_string().contains("expected one of: name,"),
+            "err: {err}"
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // Text formatting functions
+    // -----------------------------------------------------------------------
+
+    fn text(s: &str) -> Value {
+        Value::String(s.into())
+    }
+
+    #[test]
+    fn test_len_counts_graphemes() {
+        // "e" + combining acute, and a thumbs-up with a skin tone modifier.
+        let r = call_function("len", &[text("e\u{301}👍🏽!")]).unwrap();
+        assert_eq!(r, Value::Int(3));
+    }
+
+    #[test]
+    fn test_pad_left_and_right() {
+        let r = call_function("pad_left", &[Value::Int(42), Value::Int(5), text("0")]).unwrap();
+        assert_eq!(r, text("00042"));
+        let r = call_function("pad_right", &[text("né"), Value::Int(4)]).unwrap();
+        assert_eq!(r, text("né  "));
+        let r = call_function("pad_left", &[text("x"), Value::Int(4), text("ab")]).unwrap();
+        assert_eq!(r, text("abax"));
+        let r = call_function("pad_left", &[text("toolong"), Value::Int(3)]).unwrap();
+        assert_eq!(r, text("toolong"));
+        let err = call_function("pad_left", &[text("x"), Value::Int(-1)]).unwrap_err();
+        assert!(
+            err.to_string().contains("must not be negative"),
+            "err: {err}"
+        );
+    }
+
+    #[test]
+    fn test_truncate() {
+        let r =
+            call_function("truncate", &[text("Hello world"), Value::Int(8), text("…")]).unwrap();
+        assert_eq!(r, text("Hello w…"));
+        let r = call_function("truncate", &[text("Hello"), Value::Int(5), text("…")]).unwrap();
+        assert_eq!(r, text("Hello"));
+        let r = call_function("truncate", &[text("Hello"), Value::Int(3)]).unwrap();
+        assert_eq!(r, text("Hel"));
+    }
+
+    #[test]
+    fn test_wrap() {
+        let r = call_function(
+            "wrap",
+            &[text("the quick brown fox jumps\nover"), Value::Int(10)],
+        )
+        .unwrap();
+        assert_eq!(r, text("the quick\nbrown fox\njumps\nover"));
+    }
+
+    #[test]
+    fn test_title_case_and_slugify() {
+        let r = call_function("title_case", &[text("the old  MAN")]).unwrap();
+        assert_eq!(r, text("The Old  Man"));
+        let r = call_function("slugify", &[text("  Crème Brûlée: 2 ways! ")]).unwrap();
+        assert_eq!(r, text("creme-brulee-2-ways"));
+    }
+
+    #[test]
+    fn test_normalization_forms() {
+        let decomposed = text("e\u{301}");
+        assert_eq!(
+            call_function("nfc", std::slice::from_ref(&decomposed)).unwrap(),
+            text("\u{e9}")
+        );
+        assert_eq!(call_function("nfd", &[text("\u{e9}")]).unwrap(), decomposed);
+        assert_eq!(call_function("nfkd", &[text("ﬁ")]).unwrap(), text("fi"));
+    }
+
+    #[test]
+    fn test_format_number_patterns() {
+        let fmt =
+            |n: Value, pattern: &str| call_function("format_number", &[n, text(pattern)]).unwrap();
+        assert_eq!(fmt(Value::Float(1234.5), "#,##0.00"), text("1,234.50"));
+        assert_eq!(fmt(Value::Int(5), "0,000.00"), text("0,005.00"));
+        assert_eq!(fmt(Value::Float(2.675), "0.##"), text("2.68"));
+        assert_eq!(fmt(Value::Float(2.5), "0.##"), text("2.5"));
+        assert_eq!(fmt(Value::Int(1234567), "#,##0"), text("1,234,567"));
+        assert_eq!(fmt(Value::Int(-5), "$#,##0.00"), text("-$5.00"));
+        assert_eq!(fmt(Value::Float(-0.001), "0.00"), text("0.00"));
+        assert_eq!(fmt(Value::Int(0), "#"), text("0"));
+        let err = call_function("format_number", &[Value::Int(1), text("abc")]).unwrap_err();
+        assert!(err.to_string().contains("needs digits"), "err: {err}");
+    }
+
+    #[test]
+    fn test_format_number_locales() {
+        let fmt = |locale: &str| {
+            call_function(
+                "format_number",
+                &[Value::Float(1234567.891), text("#,##0.00"), text(locale)],
+            )
+            .unwrap()
+        };
+        assert_eq!(fmt("de"), text("1.234.567,89"));
+        assert_eq!(fmt("fr_FR"), text("1\u{a0}234\u{a0}567,89"));
+        assert_eq!(fmt("de-CH"), text("1\u{2019}234\u{2019}567.89"));
+        let err =
+            call_function("format_number", &[Value::Int(1), text("0"), text("xx")]).unwrap_err();
+        assert!(err.to_string().contains("locale"), "err: {err}");
+    }
+
+    #[test]
+    fn test_parse_number() {
+        let parse = |s: &str, locale: &str| call_function("parse_number", &[text(s), text(locale)]);
+        assert_eq!(parse("1.234,56", "de").unwrap(), Value::Float(1234.56));
+        assert_eq!(parse("1,234", "en").unwrap(), Value::Int(1234));
+        assert_eq!(parse("-1 234,5", "fr").unwrap(), Value::Float(-1234.5));
+        assert_eq!(parse("1'234.50", "de-CH").unwrap(), Value::Float(1234.5));
+        assert_eq!(
+            parse("12,345,678,901,234,567,890", "en").unwrap(),
+            Value::Decimal("12345678901234567890".parse().unwrap())
+        );
+        assert!(parse("1.2.3", "en").is_err());
+        assert!(parse("abc", "en").is_err());
+        assert_eq!(
+            call_function("parse_number", &[text("3.5")]).unwrap(),
+            Value::Float(3.5)
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // Hashing and encoding functions
+    // -----------------------------------------------------------------------
+
+    fn call_str(name: &str, args: &[Value]) -> String {
+        match call_function(name, args).unwrap() {
+            Value::String(s) => s,
+            other => panic!("expected string, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_digests() {
+        let abc = [Value::String("abc".into())];
+        assert_eq!(
+            call_str("sha256", &abc),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            call_str("sha1", &abc),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(call_str("md5", &abc), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(
+            call_function("sha256", &[Value::Null]).unwrap(),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn test_digest_of_bytes_matches_string() {
+        let bytes = call_function("sha1", &[Value::Bytes(b"abc".to_vec())]).unwrap();
+        assert_eq!(
+            bytes,
+            call_function("sha1", &[Value::String("abc".into())]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hmac_sha256() {
+        let args = [
+            Value::String("key".into()),
+            Value::String("The quick brown fox jumps over the lazy dog".into()),
+        ];
+        assert_eq!(
+            call_str("hmac_sha256", &args),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn test_base64_round_trip() {
+        let encoded = call_function("base64_encode", &[Value::String("hi?>".into())]).unwrap();
+        assert_eq!(encoded, Value::String("aGk/Pg==".into()));
+        let decoded = call_function("base64_decode", &[encoded]).unwrap();
+        assert_eq!(decoded, Value::Bytes(b"hi?>".to_vec()));
+        // URL-safe and unpadded input is accepted too.
+        let decoded = call_function("base64_decode", &[Value::String("aGk_Pg".into())]).unwrap();
+        assert_eq!(decoded, Value::Bytes(b"hi?>".to_vec()));
+    }
+
+    #[test]
+    fn test_base64_decode_invalid() {
+        let err = call_function("base64_decode", &[Value::String("%%%".into())]).unwrap_err();
+        assert!(err.to_string().contains("invalid base64"), "err: {err}");
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(call_str("hex", &[Value::Bytes(vec![0xca, 0xfe])]), "cafe");
+        assert_eq!(call_str("hex", &[Value::String("A".into())]), "41");
+    }
+
+    #[test]
+    fn test_url_encode_decode() {
+        let encoded = call_str("url_encode", &[Value::String("a b&c=d/é~".into())]);
+        assert_eq!(encoded, "a%20b%26c%3Dd%2F%C3%A9~");
+        assert_eq!(
+            call_str("url_decode", &[Value::String(encoded)]),
+            "a b&c=d/é~"
+        );
+    }
+
+    #[test]
+    fn test_uuid_v4_format() {
+        let a = call_str("uuid_v4", &[]);
+        assert_eq!(a.len(), 36);
+        assert_eq!(&a[14..15], "4");
+        assert!(matches!(&a[19..20], "8" | "9" | "a" | "b"), "uuid: {a}");
+        assert_ne!(a, call_str("uuid_v4", &[]));
+    }
+
+    #[test]
+    fn test_uuid_v5() {
+        let args = [
+            Value::String("dns".into()),
+            Value::String("python.org".into()),
+        ];
+        assert_eq!(
+            call_str("uuid_v5", &args),
+            "886313e1-3b8a-5372-9b90-0c9aee199e5d"
+        );
+        let explicit = [
+            Value::String("6ba7b810-9dad-11d1-80b4-00c04fd430c8".into()),
+            Value::String("python.org".into()),
+        ];
+        assert_eq!(call_str("uuid_v5", &explicit), call_str("uuid_v5", &args));
+    }
+
+    #[test]
+    fn test_uuid_v5_bad_namespace() {
+        let args = [Value::String("nope".into()), Value::String("x".into())];
+        let err = call_function("uuid_v5", &args).unwrap_err();
+        assert!(err.to_string().contains("invalid namespace"), "err: {err}");
+    }
+
+    // -----------------------------------------------------------------------
+    // Embedded document functions
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn test_parse_json_and_back() {
+        let parsed =
+            call_function("parse_json", &[Value::String(r#"{"a":[1,true]}"#.into())]).unwrap();
+        assert_eq!(
+            parsed.get_path(".a"),
+            Some(&Value::Array(vec![Value::Int(1), Value::Bool(true)]))
+        );
+        assert_eq!(
+            call_str("to_json", std::slice::from_ref(&parsed)),
+            r#"{"a":[1,true]}"#
+        );
+        assert!(call_str("to_json", &[parsed, Value::Bool(true)]).contains("\n  "));
+    }
+
+    #[test]
+    fn test_parse_format_by_name() {
+        let args = [
+            Value::String("a,b\n1,2\n".into()),
+            Value::String("csv".into()),
+        ];
+        let parsed = call_function("parse_format", &args).unwrap();
+        assert_eq!(parsed.get_path(".[0].b"), Some(&Value::Int(2)));
+        let yaml = call_str("to_format", &[parsed, Value::String("yml".into())]);
+        assert_eq!(yaml, "- a: 1\n  b: 2");
+    }
+
+    #[test]
+    fn test_parse_null_passes_through() {
+        assert_eq!(
+            call_function("parse_yaml", &[Value::Null]).unwrap(),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_document() {
+        let err = call_function("parse_json", &[Value::String("{".into())]).unwrap_err();
+        assert!(
+            err.to_string().contains("parse_json(): invalid json"),
+            "err: {err}"
+        );
+    }
+
+    #[test]
+    fn test_unknown_document_format() {
+        let args = [Value::String("".into()), Value::String("jsn".into())];
+        let err = call_function("parse_format", &args).unwrap_err();
+        assert!(
+            err.to_string().contains("Did you mean 'json'"),
+            "err: {err}"
+        );
+    }
+
+    #[test]
+    fn test_to_yaml_rejects_pretty_flag() {
+        let err = call_function("to_yaml", &[Value::Int(1), Value::Bool(true)]).unwrap_err();
+        assert!(
+            err.to_string().contains("expects 1 argument(s)"),
+            "err: {err}"
+        );
+    }
+
+    // -----------------------------------------------------------------------
+    // Collection functions
+    // -----------------------------------------------------------------------
+
+    fn map_of(pairs: &[(&str, Value)]) -> Value {
+        Value::Map(
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_zip_stops_at_shortest() {
+        let r = call_function("zip", &[ints(&[1, 2, 3]), ints(&[4, 5])]).unwrap();
+        assert_eq!(r, Value::Array(vec![ints(&[1, 4]), ints(&[2, 5])]));
+    }
+
+    #[test]
+    fn test_chunk() {
+        let r = call_function("chunk", &[ints(&[1, 2, 3, 4, 5]), Value::Int(2)]).unwrap();
+        assert_eq!(
+            r,
+            Value::Array(vec![ints(&[1, 2]), ints(&[3, 4]), ints(&[5])])
+        );
+        let err = call_function("chunk", &[ints(&[1]), Value::Int(0)]).unwrap_err();
+        assert!(err.to_string().contains("at least 1"), "err: {err}");
+    }
+
+    #[test]
+    fn test_range_forms() {
+        assert_eq!(
+            call_function("range", &[Value::Int(3)]).unwrap(),
+            ints(&[0, 1, 2])
+        );
+        assert_eq!(
+            call_function("range", &[Value::Int(2), Value::Int(5)]).unwrap(),
+            ints(&[2, 3, 4])
+        );
+        assert_eq!(
+            call_function("range", &[Value::Int(5), Value::Int(0), Value::Int(-2)]).unwrap(),
+            ints(&[5, 3, 1])
+        );
+        let err =
+            call_function("range", &[Value::Int(0), Value::Int(5), Value::Int(0)]).unwrap_err();
+        assert!(err.to_string().contains("step must not be 0"), "err: {err}");
+    }
+
+    #[test]
+    fn test_slice_negative_indexes() {
+        let r = call_function(
+            "slice",
+            &[ints(&[1, 2, 3, 4]), Value::Int(-3), Value::Int(-1)],
+        )
+        .unwrap();
+        assert_eq!(r, ints(&[2, 3]));
+        let r = call_function("slice", &[Value::String("héllo".into()), Value::Int(1)]).unwrap();
+        assert_eq!(r, Value::String("éllo".into()));
+        let r = call_function("slice", &[ints(&[1, 2]), Value::Int(5)]).unwrap();
+        assert_eq!(r, ints(&[]));
+    }
+
+    #[test]
+    fn test_index_of() {
+        let r = call_function("index_of", &[ints(&[5, 6, 7]), Value::Int(7)]).unwrap();
+        assert_eq!(r, Value::Int(2));
+        let r = call_function("index_of", &[ints(&[5]), Value::Int(9)]).unwrap();
+        assert_eq!(r, Value::Null);
+        let r = call_function(
+            "index_of",
+            &[Value::String("añb".into()), Value::String("b".into())],
+        )
+        .unwrap();
+        assert_eq!(r, Value::Int(2));
+    }
+
+    #[test]
+    fn test_entries_round_trip() {
+        let m = map_of(&[("a", Value::Int(1)), ("b", Value::Int(2))]);
+        let entries = call_function("entries", std::slice::from_ref(&m)).unwrap();
+        assert_eq!(
+            entries,
+            Value::Array(vec![
+                map_of(&[("key", Value::String("a".into())), ("value", Value::Int(1))]),
+                map_of(&[("key", Value::String("b".into())), ("value", Value::Int(2))]),
+            ])
+        );
+        assert_eq!(call_function("from_entries", &[entries]).unwrap(), m);
+    }
+
+    #[test]
+    fn test_from_entries_pairs() {
+        let pairs = Value::Array(vec![Value::Array(vec![
+            Value::String("x".into()),
+            Value::Int(1),
+        ])]);
+        let r = call_function("from_entries", &[pairs]).unwrap();
+        assert_eq!(r, map_of(&[("x", Value::Int(1))]));
+        let err = call_function("from_entries", &[ints(&[1])]).unwrap_err();
+        assert!(err.to_string().contains("got int"), "err: {err}");
+    }
+
+    #[test]
+    fn test_merge_is_deep() {
+        let a = map_of(&[("db", map_of(&[("host", Value::String("a".into()))]))]);
+        let b = map_of(&[("db", map_of(&[("port", Value::Int(5432))]))]);
+        let r = call_function("merge", &[a, b]).unwrap();
+        assert_eq!(
+            r,
+            map_of(&[(
+                "db",
+                map_of(&[
+                    ("host", Value::String("a".into())),
+                    ("port", Value::Int(5432))
+                ])
+            )])
+        );
+        let err = call_function("merge", &[map_of(&[]), Value::Int(1)]).unwrap_err();
+        assert!(err.to_string().contains("expects maps"), "err: {err}");
+    }
+
+    #[test]
+    fn test_pick_and_omit() {
+        let m = map_of(&[
+            ("a", Value::Int(1)),
+            ("b", Value::Int(2)),
+            ("c", Value::Int(3)),
+        ]);
+        let r = call_function(
+            "pick",
+            &[
+                m.clone(),
+                Value::String("c".into()),
+                Value::String("a".into()),
+            ],
+        )
+        .unwrap();
+        assert_eq!(r, map_of(&[("c", Value::Int(3)), ("a", Value::Int(1))]));
+        let keys = Value::Array(vec![Value::String("a".into())]);
+        let r = call_function("omit", &[m, keys]).unwrap();
+        assert_eq!(r, map_of(&[("b", Value::Int(2)), ("c", Value::Int(3))]));
+    }
+
+    #[test]
+    fn test_flatten_depth() {
+        let nested = Value::Array(vec![
+            Value::Int(1),
+            Value::Array(vec![Value::Int(2), Value::Array(vec![Value::Int(3)])]),
+        ]);
+        let r = call_function("flatten", std::slice::from_ref(&nested)).unwrap();
+        assert_eq!(
+            r,
+            Value::Array(vec![Value::Int(1), Value::Int(2), ints(&[3])])
+        );
+        let r = call_function("flatten", &[nested, Value::Int(5)]).unwrap();
+        assert_eq!(r, ints(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn test_count_skips_nulls() {
+        let arr = Value::Array(vec![Value::Int(1), Value::Null, Value::String("x".into())]);
+        assert_eq!(call_function("count", &[arr]).unwrap(), Value::Int(2));
+    }
+
+    #[test]
+    fn test_avg_median_percentile() {
+        assert_eq!(
+            call_function("avg", &[ints(&[1, 2, 3, 4])]).unwrap(),
+            Value::Float(2.5)
+        );
+        assert_eq!(call_function("avg", &[ints(&[])]).unwrap(), Value::Null);
+        assert_eq!(
+            call_function("median", &[ints(&[3, 1, 2])]).unwrap(),
+            Value::Int(2)
+        );
+        assert_eq!(
+            call_function("median", &[ints(&[4, 1, 3, 2])]).unwrap(),
+            Value::Float(2.5)
+        );
+        assert_eq!(
+            call_function("percentile", &[ints(&[10, 20, 30, 40, 50]), Value::Int(90)]).unwrap(),
+            Value::Float(46.0)
+        );
+        let err = call_function("percentile", &[ints(&[1]), Value::Int(101)]).unwrap_err();
+        assert!(err.to_string().contains("between 0 and 100"), "err: {err}");
+        let err =
+            call_function("median", &[Value::Array(vec![Value::String("a".into())])]).unwrap_err();
+        assert!(err.to_string().contains("only numbers"), "err: {err}");
+    }
+
+    #[test]
+    fn regex_cache_stays_bounded() {
+        for i in 0..REGEX_CACHE_LIMIT * 3 {
+            assert!(regex_is_match(&format!("^x{i}$"), &format!("x{i}")).unwrap());
+        }
+        let cached = REGEX_CACHE.with(|cache| cache.borrow().len());
+        assert!(cached <= REGEX_CACHE_LIMIT, "cached: {cached}");
+        assert!(regex_is_match("^x0$", "x0").unwrap());
+    }
 }
@@ -30,28 +30,47 @@ pub enum TokenKind {
     Sort,
     Each,
     When,
+    Else,
+    Match,
     Not,
     And,
     Or,
     Flatten,
+    Unflatten,
     Nest,
+    Explode,
+    Unnest,
+    Aggregate,
+    Pivot,
+    Unpivot,
+    Distinct,
+    Limit,
+    Head,
+    Skip,
+    Tail,
+    Sample,
     Asc,
     Desc,
 
     // Operators
-    Arrow,   // ->
-    Eq,      // =
-    EqEq,    // ==
-    NotEq,   // !=
-    Gt,      // >
-    GtEq,    // >=
-    Lt,      // <
-    LtEq,    // <=
-    Plus,    // +
-    Minus,   // -
-    Star,    // *
-    Slash,   // /
-    Percent, // %
+    Arrow,            // ->
+    FatArrow,         // =>
+    Eq,               // =
+    EqEq,             // ==
+    NotEq,            // !=
+    Gt,               // >
+    GtEq,             // >=
+    Lt,               // <
+    LtEq,             // <=
+    Plus,             // +
+    Minus,            // -
+    Star,             // *
+    Slash,            // /
+    Percent,          // %
+    Pipe,             // |
+    Question,         // ?
+    QuestionQuestion, // ??
+    RegexMatch,       // =~
 
     // Delimiters
     LBrace,   // {
@@ -61,6 +80,7 @@ pub enum TokenKind {
     LBracket, // [
     RBracket, // ]
     Comma,    // ,
+    Colon,    // :
     Dot,      // .
 
     // Literals
@@ -205,6 +225,7 @@ impl<'a> Lexer<'a> {
                                     | Some(TokenKind::LParen)
                                     | Some(TokenKind::LBracket)
                                     | Some(TokenKind::Comma)
+                                    | Some(TokenKind::Colon)
                                     | Some(TokenKind::Eq)
                                     | Some(TokenKind::EqEq)
                                     | Some(TokenKind::NotEq)
@@ -213,6 +234,11 @@ impl<'a> Lexer<'a> {
                                     | Some(TokenKind::Lt)
                                     | Some(TokenKind::LtEq)
                                     | Some(TokenKind::Arrow)
+                                    | Some(TokenKind::FatArrow)
+                                    | Some(TokenKind::RegexMatch)
+                                    | Some(TokenKind::Question)
+                                    | Some(TokenKind::QuestionQuestion)
+                                    | Some(TokenKind::Pipe)
                                     | Some(TokenKind::Plus)
                                     | Some(TokenKind::Minus)
                                     | Some(TokenKind::Star)
@@ -238,6 +264,12 @@ impl<'a> Lexer<'a> {
                     if self.peek() == Some(b'=') {
                         self.advance();
                         tokens.push(Token::new(TokenKind::EqEq, span));
+                    } else if self.peek() == Some(b'>') {
+                        self.advance();
+                        tokens.push(Token::new(TokenKind::FatArrow, span));
+                    } else if self.peek() == Some(b'~') {
+                        self.advance();
+                        tokens.push(Token::new(TokenKind::RegexMatch, span));
                     } else {
                         tokens.push(Token::new(TokenKind::Eq, span));
                     }
@@ -304,6 +336,12 @@ impl<'a> Lexer<'a> {
                     tokens.push(Token::new(TokenKind::Percent, span));
                 }
 
+                b'|' => {
+                    let span = self.span();
+                    self.advance();
+                    tokens.push(Token::new(TokenKind::Pipe, span));
+                }
+
                 b'{' => {
                     let span = self.span();
                     self.advance();
@@ -346,6 +384,23 @@ impl<'a> Lexer<'a> {
                     tokens.push(Token::new(TokenKind::Comma, span));
                 }
 
+                b':' => {
+                    let span = self.span();
+                    self.advance();
+                    tokens.push(Token::new(TokenKind::Colon, span));
+                }
+
+                b'?' => {
+                    let span = self.span();
+                    self.advance();
+                    if self.peek() == Some(b'?') {
+                        self.advance();
+                        tokens.push(Token::new(TokenKind::QuestionQuestion, span));
+                    } else {
+                        tokens.push(Token::new(TokenKind::Question, span));
+                    }
+                }
+
                 b'.' => {
                     let span = self.span();
                     self.advance();
@@ -654,11 +709,25 @@ impl<'a> Lexer<'a> {
             "sort" => TokenKind::Sort,
             "each" => TokenKind::Each,
             "when" => TokenKind::When,
+            "else" => TokenKind::Else,
+            "match" => TokenKind::Match,
             "not" => TokenKind::Not,
             "and" => TokenKind::And,
             "or" => TokenKind::Or,
             "flatten" => TokenKind::Flatten,
+            "unflatten" => TokenKind::Unflatten,
             "nest" => TokenKind::Nest,
+            "explode" => TokenKind::Explode,
+            "unnest" => TokenKind::Unnest,
+            "aggregate" => TokenKind::Aggregate,
+            "pivot" => TokenKind::Pivot,
+            "unpivot" => TokenKind::Unpivot,
+            "distinct" => TokenKind::Distinct,
+            "limit" => TokenKind::Limit,
+            "head" => TokenKind::Head,
+            "skip" => TokenKind::Skip,
+            "tail" => TokenKind::Tail,
+            "sample" => TokenKind::Sample,
             "asc" => TokenKind::Asc,
             "desc" => TokenKind::Desc,
             "true" => TokenKind::True,
@@ -761,6 +830,7 @@ mod tests {
     #[test]
     fn keyword_flatten() {
         assert_eq!(kinds("flatten"), vec![TokenKind::Flatten]);
+        assert_eq!(kinds("unflatten"), vec![TokenKind::Unflatten]);
     }
 
     #[test]
@@ -768,6 +838,37 @@ mod tests {
         assert_eq!(kinds("nest"), vec![TokenKind::Nest]);
     }
 
+    #[test]
+    fn keyword_explode() {
+        assert_eq!(kinds("explode"), vec![TokenKind::Explode]);
+        assert_eq!(kinds("unnest"), vec![TokenKind::Unnest]);
+    }
+
+    #[test]
+    fn keyword_aggregate() {
+        assert_eq!(kinds("aggregate"), vec![TokenKind::Aggregate]);
+    }
+
+    #[test]
+    fn keyword_pivot() {
+        assert_eq!(kinds("pivot"), vec![TokenKind::Pivot]);
+        assert_eq!(kinds("unpivot"), vec![TokenKind::Unpivot]);
+    }
+
+    #[test]
+    fn keyword_distinct() {
+        assert_eq!(kinds("distinct"), vec![TokenKind::Distinct]);
+    }
+
+    #[test]
+    fn keyword_limit_skip_sample() {
+        assert_eq!(kinds("limit"), vec![TokenKind::Limit]);
+        assert_eq!(kinds("head"), vec![TokenKind::Head]);
+        assert_eq!(kinds("skip"), vec![TokenKind::Skip]);
+        assert_eq!(kinds("tail"), vec![TokenKind::Tail]);
+        assert_eq!(kinds("sample"), vec![TokenKind::Sample]);
+    }
+
     #[test]
     fn keyword_asc() {
         assert_eq!(kinds("asc"), vec![TokenKind::Asc]);
@@ -778,6 +879,16 @@ mod tests {
         assert_eq!(kinds("desc"), vec![TokenKind::Desc]);
     }
 
+    #[test]
+    fn keyword_else() {
+        assert_eq!(kinds("else"), vec![TokenKind::Else]);
+    }
+
+    #[test]
+    fn keyword_match() {
+        assert_eq!(kinds("match"), vec![TokenKind::Match]);
+    }
+
     #[test]
     fn all_keywords() {
         let input = "rename select drop set default cast as where sort each when not and or flatten nest asc desc";
@@ -821,6 +932,46 @@ mod tests {
         assert_eq!(kinds("="), vec![TokenKind::Eq]);
     }
 
+    #[test]
+    fn operator_fat_arrow() {
+        assert_eq!(kinds("=>"), vec![TokenKind::FatArrow]);
+    }
+
+    #[test]
+    fn operator_pipe() {
+        assert_eq!(kinds("|"), vec![TokenKind::Pipe]);
+    }
+
+    #[test]
+    fn operator_question() {
+        assert_eq!(kinds("?"), vec![TokenKind::Question]);
+    }
+
+    #[test]
+    fn operator_question_question() {
+        assert_eq!(kinds("??"), vec![TokenKind::QuestionQuestion]);
+        assert_eq!(kinds("? ?"), vec![TokenKind::Question, TokenKind::Question]);
+    }
+
+    #[test]
+    fn operator_regex_match() {
+        assert_eq!(kinds("=~"), vec![TokenKind::RegexMatch]);
+    }
+
+    #[test]
+    fn slice_with_negative_bounds() {
+        assert_eq!(
+            kinds("[-2:-1]"),
+            vec![
+                TokenKind::LBracket,
+                TokenKind::IntLit(-2),
+                TokenKind::Colon,
+                TokenKind::IntLit(-1),
+                TokenKind::RBracket,
+            ]
+        );
+    }
+
     #[test]
     fn operator_eqeq() {
         assert_eq!(kinds("=="), vec![TokenKind::EqEq]);
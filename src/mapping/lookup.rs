@@ -0,0 +1,223 @@
+use crate::error;
+use crate::mapping::ast::{Path, Statement};
+use crate::mapping::eval::resolve_path;
+use crate::value::Value;
+use std::collections::HashMap;
+
+/// Named record tables that `join` statements look records up in, such as
+/// the files loaded with `--lookup users=users.csv`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lookups {
+    tables: HashMap<String, LookupTable>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct LookupTable {
+    records: Vec<Value>,
+    /// Per lookup-key path, the position of the first record for each key.
+    indexes: HashMap<String, HashMap<String, usize>>,
+}
+
+impl Lookups {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a table named `name`. `data` must be an array of records.
+    pub fn insert(&mut self, name: impl Into<String>, data: Value) -> error::Result<()> {
+        let name = name.into();
+        let records = match data {
+            Value::Array(items) if items.iter().all(|item| matches!(item, Value::Map(_))) => items,
+            _ => {
+                return Err(error::MorphError::mapping(format!(
+                    "lookup table '{name}' must be an array of records"
+                )));
+            }
+        };
+        self.tables.insert(
+            name,
+            LookupTable {
+                records,
+                indexes: HashMap::new(),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.tables.contains_key(name)
+    }
+
+    /// Check that every table joined by `statements` exists, and build the
+    /// key index each join needs so lookups do not scan the table.
+    pub fn prepare(&mut self, statements: &[Statement]) -> error::Result<()> {
+        for stmt in statements {
+            let Statement::Join {
+                table,
+                lookup_key,
+                span,
+                ..
+            } = stmt
+            else {
+                continue;
+            };
+            let Some(lookup) = self.tables.get_mut(table) else {
+                return Err(unknown_table(table, Some(span.line), Some(span.column)));
+            };
+            let index_name = lookup_key.to_string();
+            if lookup.indexes.contains_key(&index_name) {
+                continue;
+            }
+            let mut index = HashMap::new();
+            for (pos, record) in lookup.records.iter().enumerate() {
+                if let Some(key) = resolve_path(record, &lookup_key.segments)?
+                    .as_ref()
+                    .and_then(join_key)
+                {
+                    index.entry(key).or_insert(pos);
+                }
+            }
+            lookup.indexes.insert(index_name, index);
+        }
+        Ok(())
+    }
+
+    /// Find the first record in `table` whose `lookup_key` equals `key`.
+    pub fn find(
+        &self,
+        table: &str,
+        lookup_key: &Path,
+        key: &Value,
+    ) -> error::Result<Option<&Value>> {
+        let lookup = self
+            .tables
+            .get(table)
+            .ok_or_else(|| unknown_table(table, None, None))?;
+        let Some(key) = join_key(key) else {
+            return Ok(None);
+        };
+        if let Some(index) = lookup.indexes.get(&lookup_key.to_string()) {
+            return Ok(index.get(&key).map(|&pos| &lookup.records[pos]));
+        }
+        // Tables that were not prepared for this key fall back to a scan.
+        for record in &lookup.records {
+            let found = resolve_path(record, &lookup_key.segments)?;
+            if found.as_ref().and_then(join_key).as_deref() == Some(key.as_str()) {
+                return Ok(Some(record));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The comparable form of a join key. Keys match by their text, so the
+/// number `42` matches the CSV string `"42"`. Null and container values
+/// never match.
+fn join_key(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Int(i) => Some(i.to_string()),
+        Value::Float(f) if f.fract() == 0.0 && f.abs() < 1e15 => Some((*f as i64).to_string()),
+        Value::Float(f) => Some(f.to_string()),
+        Value::Decimal(d) => Some(d.normalized().to_plain_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::DateTime(dt) => Some(dt.to_string()),
+        Value::Null | Value::Bytes(_) | Value::Array(_) | Value::Map(_) => None,
+    }
+}
+
+fn unknown_table(name: &str, line: Option<usize>, column: Option<usize>) -> error::MorphError {
+    error::MorphError::Mapping {
+        message: format!("unknown lookup table '{name}'; load it with --lookup {name}=<file>"),
+        line,
+        column,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mapping::parser;
+    use indexmap::IndexMap;
+
+    fn record(id: Value, name: &str) -> Value {
+        let mut m = IndexMap::new();
+        m.insert("id".to_string(), id);
+        m.insert("name".to_string(), Value::String(name.into()));
+        Value::Map(m)
+    }
+
+    fn users() -> Lookups {
+        let mut lookups = Lookups::new();
+        lookups
+            .insert(
+                "users",
+                Value::Array(vec![
+                    record(Value::Int(1), "ada"),
+                    record(Value::String("2".into()), "bob"),
+                    record(Value::Int(1), "duplicate"),
+                ]),
+            )
+            .unwrap();
+        lookups
+    }
+
+    fn id_path() -> Path {
+        match parser::parse_str("join users on .id = .id")
+            .unwrap()
+            .statements
+            .remove(0)
+        {
+            Statement::Join { lookup_key, .. } => lookup_key,
+            other => panic!("expected Join, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn join_key_normalizes_numbers() {
+        assert_eq!(join_key(&Value::Int(42)), Some("42".into()));
+        assert_eq!(join_key(&Value::Float(42.0)), Some("42".into()));
+        assert_eq!(join_key(&Value::String("42".into())), Some("42".into()));
+        assert_eq!(join_key(&Value::Float(1.5)), Some("1.5".into()));
+        assert_eq!(join_key(&Value::Null), None);
+        assert_eq!(join_key(&Value::Array(vec![])), None);
+    }
+
+    #[test]
+    fn find_with_and_without_index() {
+        let mut lookups = users();
+        let path = id_path();
+        let unprepared = lookups.find("users", &path, &Value::Int(2)).unwrap();
+        assert_eq!(unprepared, Some(&record(Value::String("2".into()), "bob")));
+
+        let program = parser::parse_str("join users on .user_id = .id").unwrap();
+        lookups.prepare(&program.statements).unwrap();
+        assert_eq!(lookups.tables["users"].indexes[".id"].len(), 2);
+        let found = lookups.find("users", &path, &Value::Float(1.0)).unwrap();
+        assert_eq!(found, Some(&record(Value::Int(1), "ada")));
+        assert_eq!(lookups.find("users", &path, &Value::Null).unwrap(), None);
+    }
+
+    #[test]
+    fn prepare_rejects_unknown_table() {
+        let program = parser::parse_str("\n join accounts on .id = .id").unwrap();
+        let err = users().prepare(&program.statements).unwrap_err();
+        match err {
+            error::MorphError::Mapping { message, line, .. } => {
+                assert!(message.contains("unknown lookup table 'accounts'"));
+                assert_eq!(line, Some(2));
+            }
+            other => panic!("expected Mapping error, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn insert_requires_records() {
+        let mut lookups = Lookups::new();
+        let err = lookups
+            .insert("codes", Value::Array(vec![Value::Int(1)]))
+            .unwrap_err();
+        assert!(err.to_string().contains("must be an array of records"));
+        assert!(!lookups.contains("codes"));
+    }
+}
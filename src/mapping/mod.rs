@@ -1,5 +1,9 @@
 pub mod ast;
+pub mod distinct;
 pub mod eval;
+pub mod fake;
 pub mod functions;
 pub mod lexer;
+pub mod lookup;
 pub mod parser;
+pub mod random;
@@ -1,8 +1,14 @@
+use crate::cli::Format;
 use crate::error;
 use crate::mapping::ast::*;
+use crate::mapping::eval;
 use crate::mapping::lexer::{Span, Token, TokenKind};
 use crate::value::Value;
 
+/// The cast target names listed in parse errors.
+const CAST_TYPE_NAMES: &str =
+    "int, float, decimal, string, bool, array, datetime, bytes, or object";
+
 /// Parse a token stream into a Program (list of statements).
 pub fn parse(tokens: Vec<Token>) -> error::Result<Program> {
     let mut parser = Parser::new(tokens);
@@ -18,11 +24,17 @@ pub fn parse_str(input: &str) -> error::Result<Program> {
 struct Parser {
     tokens: Vec<Token>,
     pos: usize,
+    /// Names of the lambda parameters in scope at the current position.
+    lambda_params: Vec<String>,
 }
 
 impl Parser {
     fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, pos: 0 }
+        Self {
+            tokens,
+            pos: 0,
+            lambda_params: Vec::new(),
+        }
     }
 
     fn peek(&self) -> Option<&Token> {
@@ -68,16 +80,51 @@ impl Parser {
 
     fn parse_program(&mut self) -> error::Result<Program> {
         let mut statements = Vec::new();
+        let mut on_error = None;
         self.skip_newlines();
 
         while self.peek().is_some() {
-            let stmt = self.parse_statement()?;
-            statements.push(stmt);
+            if matches!(self.peek_kind(), Some(TokenKind::Ident(word)) if word == "on") {
+                let span = self.current_span();
+                let policy = self.parse_on_error()?;
+                if on_error.replace(policy).is_some() {
+                    return Err(error::MorphError::mapping_at(
+                        "'on error' can only be given once",
+                        span.line,
+                        span.column,
+                    ));
+                }
+            } else {
+                let stmt = self.parse_statement()?;
+                statements.push(stmt);
+            }
             // Consume newlines between statements
             self.skip_newlines();
         }
 
-        Ok(Program { statements })
+        Ok(Program {
+            statements,
+            on_error: on_error.unwrap_or_default(),
+        })
+    }
+
+    /// `on error skip|null|fail`
+    fn parse_on_error(&mut self) -> error::Result<OnError> {
+        self.advance(); // consume 'on'
+        self.expect_word("error", "after 'on'")?;
+        let token = self.advance().ok_or_else(|| {
+            error::MorphError::mapping("unexpected end of input, expected skip, null or fail")
+        })?;
+        match &token.kind {
+            TokenKind::Skip => Ok(OnError::Skip),
+            TokenKind::Ident(word) if word == "fail" => Ok(OnError::Fail),
+            TokenKind::Null => Ok(OnError::Null),
+            other => Err(error::MorphError::mapping_at(
+                format!("expected skip, null or fail after 'on error', found {other:?}"),
+                token.span.line,
+                token.span.column,
+            )),
+        }
     }
 
     fn parse_statement(&mut self) -> error::Result<Statement> {
@@ -98,11 +145,75 @@ impl Parser {
             TokenKind::Default => self.parse_default(),
             TokenKind::Cast => self.parse_cast(),
             TokenKind::Flatten => self.parse_flatten(),
+            TokenKind::Unflatten => {
+                let start = self.advance().unwrap(); // consume 'unflatten'
+                self.expect_word("all", "after 'unflatten'")?;
+                let (separator, arrays) = self.parse_flatten_options()?;
+                Ok(Statement::UnflattenAll {
+                    separator,
+                    arrays,
+                    span: start.span,
+                })
+            }
             TokenKind::Nest => self.parse_nest(),
+            TokenKind::Explode | TokenKind::Unnest => self.parse_explode(),
+            TokenKind::Aggregate => self.parse_aggregate(),
+            TokenKind::Pivot => self.parse_pivot(),
+            TokenKind::Unpivot => self.parse_unpivot(),
+            TokenKind::Distinct => self.parse_distinct(),
+            TokenKind::Limit | TokenKind::Head => {
+                let start = self.advance().unwrap(); // consume 'limit' / 'head'
+                let count = self.parse_count()?;
+                Ok(Statement::Limit {
+                    count,
+                    span: start.span,
+                })
+            }
+            TokenKind::Skip => {
+                let start = self.advance().unwrap(); // consume 'skip'
+                let count = self.parse_count()?;
+                Ok(Statement::Skip {
+                    count,
+                    span: start.span,
+                })
+            }
+            TokenKind::Tail => {
+                let start = self.advance().unwrap(); // consume 'tail'
+                let count = self.parse_count()?;
+                Ok(Statement::Tail {
+                    count,
+                    span: start.span,
+                })
+            }
+            TokenKind::Sample => self.parse_sample(),
+            // `join` stays an identifier so the join() function still works;
+            // it only starts a statement at the beginning of a line.
+            TokenKind::Ident(word) if word == "join" => self.parse_join(JoinKind::Left),
+            TokenKind::Ident(word)
+                if (word == "left" || word == "inner")
+                    && matches!(
+                        self.tokens.get(self.pos + 1).map(|t| &t.kind),
+                        Some(TokenKind::Ident(next)) if next == "join"
+                    ) =>
+            {
+                let kind = if word == "inner" {
+                    JoinKind::Inner
+                } else {
+                    JoinKind::Left
+                };
+                self.advance(); // consume 'left' / 'inner'
+                self.parse_join(kind)
+            }
             TokenKind::Where => self.parse_where(),
             TokenKind::Sort => self.parse_sort(),
             TokenKind::Each => self.parse_each(),
             TokenKind::When => self.parse_when(),
+            TokenKind::Match => self.parse_match(),
+            TokenKind::Else => Err(error::MorphError::mapping_at(
+                "'else' without a preceding 'when' block",
+                token.span.line,
+                token.span.column,
+            )),
             _ => {
                 let suggestion = suggest_keyword(&token.kind);
                 let msg = if let Some(s) = suggestion {
@@ -127,6 +238,9 @@ impl Parser {
 
     fn parse_rename(&mut self) -> error::Result<Statement> {
         let start = self.advance().unwrap(); // consume 'rename'
+        if matches!(self.peek_kind(), Some(TokenKind::Ident(word)) if word == "keys") {
+            return self.parse_rename_keys(start.span);
+        }
         let from = self.parse_path()?;
         self.expect_exact(&TokenKind::Arrow)?;
         let to = self.parse_path()?;
@@ -137,6 +251,140 @@ impl Parser {
         })
     }
 
+    /// Parse the `[separator "SEP"] [arrays index | arrays join "DELIM"]`
+    /// options of `flatten all` and `unflatten all`.
+    fn parse_flatten_options(&mut self) -> error::Result<(String, ArrayFlatten)> {
+        let mut separator = ".".to_string();
+        if matches!(self.peek_kind(), Some(TokenKind::Ident(word)) if word == "separator") {
+            let span = self.advance().unwrap().span; // consume 'separator'
+            separator = self.expect_string("after 'separator'")?;
+            if separator.is_empty() {
+                return Err(error::MorphError::mapping_at(
+                    "separator must not be empty",
+                    span.line,
+                    span.column,
+                ));
+            }
+        }
+        let mut arrays = ArrayFlatten::Index;
+        if matches!(self.peek_kind(), Some(TokenKind::Ident(word)) if word == "arrays") {
+            self.advance(); // consume 'arrays'
+            let span = self.current_span();
+            arrays = match self.advance() {
+                Some(Token {
+                    kind: TokenKind::Ident(word),
+                    ..
+                }) if word == "index" => ArrayFlatten::Index,
+                Some(Token {
+                    kind: TokenKind::Ident(word),
+                    ..
+                }) if word == "join" => ArrayFlatten::Join(self.expect_string("after 'join'")?),
+                _ => {
+                    return Err(error::MorphError::mapping_at(
+                        "expected 'index' or 'join' after 'arrays'",
+                        span.line,
+                        span.column,
+                    ));
+                }
+            };
+        }
+        Ok((separator, arrays))
+    }
+
+    /// Parse `keys [PATH] [recursive] using CASE` or
+    /// `keys [PATH] [recursive] matching "PATTERN" -> "REPLACEMENT"`, after
+    /// `rename`.
+    fn parse_rename_keys(&mut self, span: Span) -> error::Result<Statement> {
+        self.advance(); // consume 'keys'
+        let path = if self.peek_kind() == Some(&TokenKind::Dot) {
+            Some(self.parse_path()?)
+        } else {
+            None
+        };
+        let recursive =
+            matches!(self.peek_kind(), Some(TokenKind::Ident(word)) if word == "recursive");
+        if recursive {
+            self.advance(); // consume 'recursive'
+        }
+
+        let rename = match self.advance() {
+            Some(Token {
+                kind: TokenKind::Ident(word),
+                ..
+            }) if word == "using" => {
+                let case_span = self.current_span();
+                let case = match self.advance() {
+                    Some(Token {
+                        kind: TokenKind::Ident(name),
+                        ..
+                    }) => KeyCase::from_name(&name),
+                    _ => None,
+                };
+                let case = case.ok_or_else(|| {
+                    error::MorphError::mapping_at(
+                        "expected snake_case, camel_case, kebab_case, pascal_case or screaming_snake_case after 'using'",
+                        case_span.line,
+                        case_span.column,
+                    )
+                })?;
+                KeyRename::Case(case)
+            }
+            Some(Token {
+                kind: TokenKind::Ident(word),
+                ..
+            }) if word == "matching" => {
+                let pattern_span = self.current_span();
+                let pattern = self.expect_string("after 'matching'")?;
+                let pattern = regex::Regex::new(&pattern).map_err(|e| {
+                    error::MorphError::mapping_at(
+                        format!("invalid regex \"{pattern}\": {e}"),
+                        pattern_span.line,
+                        pattern_span.column,
+                    )
+                })?;
+                self.expect_exact(&TokenKind::Arrow)?;
+                let replacement = self.expect_string("after '->'")?;
+                KeyRename::Pattern {
+                    pattern: CompiledRegex(pattern),
+                    replacement,
+                }
+            }
+            other => {
+                let span = other.map_or_else(|| self.current_span(), |t| t.span);
+                return Err(error::MorphError::mapping_at(
+                    "expected 'using' or 'matching' in 'rename keys'",
+                    span.line,
+                    span.column,
+                ));
+            }
+        };
+
+        Ok(Statement::RenameKeys {
+            path,
+            recursive,
+            rename,
+            span,
+        })
+    }
+
+    /// Consume a string literal.
+    fn expect_string(&mut self, context: &str) -> error::Result<String> {
+        match self.advance() {
+            Some(Token {
+                kind: TokenKind::StringLit(s),
+                ..
+            }) => Ok(s),
+            Some(token) => Err(error::MorphError::mapping_at(
+                format!("expected a string {context}"),
+                token.span.line,
+                token.span.column,
+            )),
+            None => Err(error::MorphError::mapping(format!(
+                "unexpected end of input, expected a string {context}"
+            ))),
+        }
+    }
+
     fn parse_select(&mut self) -> error::Result<Statement> {
         let start = self.advance().unwrap(); // consume 'select'
         let paths = self.parse_path_list()?;
@@ -160,9 +408,39 @@ impl Parser {
         let path = self.parse_path()?;
         self.expect_exact(&TokenKind::Eq)?;
         let expr = self.parse_expr()?;
-        Ok(Statement::Set {
+
+        let mut partition = Vec::new();
+        if matches!(self.peek_kind(), Some(TokenKind::Ident(word)) if word == "partition") {
+            self.advance(); // consume 'partition'
+            self.expect_word("by", "after 'partition'")?;
+            partition = self.parse_path_list()?;
+        }
+        let mut order = Vec::new();
+        if matches!(self.peek_kind(), Some(TokenKind::Ident(word)) if word == "order") {
+            self.advance(); // consume 'order'
+            self.expect_word("by", "after 'order'")?;
+            order = self.parse_sort_keys()?;
+        }
+
+        if !eval::has_window_call(&expr)? {
+            if partition.is_empty() && order.is_empty() {
+                return Ok(Statement::Set {
+                    path,
+                    expr,
+                    span: start.span,
+                });
+            }
+            return Err(error::MorphError::mapping_at(
+                "'partition by' and 'order by' require a window function such as row_number() or lag()",
+                start.span.line,
+                start.span.column,
+            ));
+        }
+        Ok(Statement::Window {
             path,
             expr,
+            partition,
+            order,
             span: start.span,
         })
     }
@@ -184,9 +462,32 @@ impl Parser {
         let path = self.parse_path()?;
         self.expect_exact(&TokenKind::As)?;
         let target_type = self.parse_cast_type()?;
+        let fallback = if self.peek_kind() == Some(&TokenKind::Or) {
+            let or = self.advance().unwrap(); // consume 'or'
+            match self.peek_kind() {
+                Some(TokenKind::Null) => {
+                    self.advance();
+                    Some(CastFallback::Null)
+                }
+                Some(TokenKind::Default) => {
+                    self.advance();
+                    Some(CastFallback::Default(self.parse_expr()?))
+                }
+                _ => {
+                    return Err(error::MorphError::mapping_at(
+                        "expected 'null' or 'default <value>' after 'or'",
+                        or.span.line,
+                        or.span.column,
+                    ));
+                }
+            }
+        } else {
+            None
+        };
         Ok(Statement::Cast {
             path,
             target_type,
+            fallback,
             span: start.span,
         })
     }
@@ -200,17 +501,72 @@ impl Parser {
             TokenKind::Ident(name) | TokenKind::StringLit(name) => match name.as_str() {
                 "int" | "integer" => Ok(CastType::Int),
                 "float" | "number" => Ok(CastType::Float),
+                "decimal" => Ok(CastType::Decimal),
                 "string" | "str" => Ok(CastType::String),
                 "bool" | "boolean" => Ok(CastType::Bool),
+                "array" | "list" => {
+                    let split = if matches!(self.peek_kind(), Some(TokenKind::Ident(word)) if word == "split")
+                    {
+                        self.advance(); // consume 'split'
+                        Some(self.expect_string("after 'split'")?)
+                    } else {
+                        None
+                    };
+                    Ok(CastType::Array { split })
+                }
+                "datetime" | "date" | "timestamp" => {
+                    let format = match self.peek_kind() {
+                        Some(TokenKind::StringLit(format)) => {
+                            let format = format.clone();
+                            self.advance();
+                            Some(format)
+                        }
+                        _ => None,
+                    };
+                    Ok(CastType::DateTime { format })
+                }
+                "bytes" | "binary" => {
+                    let encoding = match self.take_cast_option() {
+                        None => BytesEncoding::default(),
+                        Some((word, span)) => match word.to_lowercase().as_str() {
+                            "utf8" | "utf-8" => BytesEncoding::Utf8,
+                            "base64" => BytesEncoding::Base64,
+                            "hex" => BytesEncoding::Hex,
+                            other => {
+                                return Err(error::MorphError::mapping_at(
+                                    format!(
+                                        "unknown bytes encoding '{other}'; expected utf8, base64, or hex"
+                                    ),
+                                    span.line,
+                                    span.column,
+                                ))
+                            }
+                        },
+                    };
+                    Ok(CastType::Bytes { encoding })
+                }
+                "object" | "map" => {
+                    let format = match self.take_cast_option() {
+                        None => Format::Json,
+                        Some((word, span)) => Format::from_name(&word).ok_or_else(|| {
+                            let mut msg = format!("unknown format '{word}' for an object cast");
+                            if let Some(suggestion) = error::suggest_format(&word) {
+                                msg.push_str(&format!(". Did you mean '{suggestion}'?"));
+                            }
+                            error::MorphError::mapping_at(msg, span.line, span.column)
+                        })?,
+                    };
+                    Ok(CastType::Object { format })
+                }
                 other => Err(error::MorphError::mapping_at(
-                    format!("unknown type '{other}'; expected int, float, string, or bool"),
+                    format!("unknown type '{other}'; expected {CAST_TYPE_NAMES}"),
                     token.span.line,
                     token.span.column,
                 )),
             },
             _ => Err(error::MorphError::mapping_at(
                 format!(
-                    "expected type name (int, float, string, bool), found {:?}",
+                    "expected type name ({CAST_TYPE_NAMES}), found {:?}",
                     token.kind
                 ),
                 token.span.line,
@@ -219,8 +575,32 @@ impl Parser {
         }
     }
 
+    /// Consume the word after a cast type that takes an option, as in
+    /// `bytes base64` or `object "yaml"`.
+    fn take_cast_option(&mut self) -> Option<(String, Span)> {
+        match self.peek_kind() {
+            Some(TokenKind::Ident(_) | TokenKind::StringLit(_)) => match self.advance() {
+                Some(Token {
+                    kind: TokenKind::Ident(word) | TokenKind::StringLit(word),
+                    span,
+                }) => Some((word, span)),
+                _ => unreachable!(),
+            },
+            _ => None,
+        }
+    }
+
     fn parse_flatten(&mut self) -> error::Result<Statement> {
         let start = self.advance().unwrap(); // consume 'flatten'
+        if matches!(self.peek_kind(), Some(TokenKind::Ident(word)) if word == "all") {
+            self.advance(); // consume 'all'
+            let (separator, arrays) = self.parse_flatten_options()?;
+            return Ok(Statement::FlattenAll {
+                separator,
+                arrays,
+                span: start.span,
+            });
+        }
         let path = self.parse_path()?;
 
         // Check for optional -> prefix "..."
@@ -278,6 +658,252 @@ impl Parser {
         })
     }
 
+    fn parse_explode(&mut self) -> error::Result<Statement> {
+        let start = self.advance().unwrap(); // consume 'explode' / 'unnest'
+        let path = self.parse_path()?;
+        let target = if self.peek_kind() == Some(&TokenKind::As) {
+            self.advance(); // consume 'as'
+            self.parse_path()?
+        } else {
+            path.clone()
+        };
+        Ok(Statement::Explode {
+            path,
+            target,
+            span: start.span,
+        })
+    }
+
+    fn parse_aggregate(&mut self) -> error::Result<Statement> {
+        let start = self.advance().unwrap(); // consume 'aggregate'
+        let keys = match self.peek_kind() {
+            Some(TokenKind::Ident(word)) if word == "by" => {
+                self.advance(); // consume 'by'
+                self.parse_path_list()?
+            }
+            _ => Vec::new(),
+        };
+
+        self.expect_exact(&TokenKind::LBrace)?;
+        self.skip_newlines();
+        let mut fields = Vec::new();
+        while self.peek_kind() != Some(&TokenKind::RBrace) {
+            match self.peek() {
+                None => {
+                    return Err(error::MorphError::mapping(
+                        "unexpected end of input, expected '}'",
+                    ));
+                }
+                Some(Token {
+                    kind: TokenKind::Set,
+                    ..
+                }) => {
+                    self.advance(); // consume 'set'
+                    let path = self.parse_path()?;
+                    self.expect_exact(&TokenKind::Eq)?;
+                    let expr = self.parse_expr()?;
+                    fields.push(AggregateField { path, expr });
+                }
+                Some(token) => {
+                    return Err(error::MorphError::mapping_at(
+                        "aggregate blocks may only contain 'set' statements",
+                        token.span.line,
+                        token.span.column,
+                    ));
+                }
+            }
+            // Entries are separated by commas and/or newlines
+            if self.peek_kind() == Some(&TokenKind::Comma) {
+                self.advance();
+            }
+            self.skip_newlines();
+        }
+        self.expect_exact(&TokenKind::RBrace)?;
+
+        Ok(Statement::Aggregate {
+            keys,
+            fields,
+            span: start.span,
+        })
+    }
+
+    fn parse_pivot(&mut self) -> error::Result<Statement> {
+        let start = self.advance().unwrap(); // consume 'pivot'
+        self.expect_word("key", "after 'pivot'")?;
+        let key = self.parse_path()?;
+        self.expect_word("value", "after the pivot key")?;
+        let value = self.parse_path()?;
+        let by = match self.peek_kind() {
+            Some(TokenKind::Ident(word)) if word == "by" => {
+                self.advance(); // consume 'by'
+                self.parse_path_list()?
+            }
+            _ => Vec::new(),
+        };
+        Ok(Statement::Pivot {
+            key,
+            value,
+            by,
+            span: start.span,
+        })
+    }
+
+    fn parse_unpivot(&mut self) -> error::Result<Statement> {
+        let start = self.advance().unwrap(); // consume 'unpivot'
+        let paths = self.parse_path_list()?;
+        self.expect_word("into", "after the unpivot fields")?;
+        let name = self.parse_path()?;
+        self.expect_exact(&TokenKind::Comma)?;
+        let value = self.parse_path()?;
+        Ok(Statement::Unpivot {
+            paths,
+            name,
+            value,
+            span: start.span,
+        })
+    }
+
+    fn parse_distinct(&mut self) -> error::Result<Statement> {
+        let start = self.advance().unwrap(); // consume 'distinct'
+        let by = match self.peek_kind() {
+            Some(TokenKind::Ident(word)) if word == "by" => {
+                self.advance(); // consume 'by'
+                self.parse_path_list()?
+            }
+            _ => Vec::new(),
+        };
+        let keep = match self.peek_kind() {
+            Some(TokenKind::Ident(word)) if word == "keep" => {
+                self.advance(); // consume 'keep'
+                match self.advance() {
+                    Some(Token {
+                        kind: TokenKind::Ident(which),
+                        ..
+                    }) if which == "first" => DistinctKeep::First,
+                    Some(Token {
+                        kind: TokenKind::Ident(which),
+                        ..
+                    }) if which == "last" => DistinctKeep::Last,
+                    Some(token) => {
+                        return Err(error::MorphError::mapping_at(
+                            "expected 'first' or 'last' after 'keep'",
+                            token.span.line,
+                            token.span.column,
+                        ));
+                    }
+                    None => {
+                        return Err(error::MorphError::mapping(
+                            "unexpected end of input, expected 'first' or 'last'",
+                        ));
+                    }
+                }
+            }
+            _ => DistinctKeep::First,
+        };
+        Ok(Statement::Distinct {
+            by,
+            keep,
+            span: start.span,
+        })
+    }
+
+    fn parse_sample(&mut self) -> error::Result<Statement> {
+        let start = self.advance().unwrap(); // consume 'sample'
+        let count = self.parse_count()?;
+        let seed = match self.peek_kind() {
+            Some(TokenKind::Ident(word)) if word == "seed" => {
+                self.advance(); // consume 'seed'
+                Some(self.parse_count()? as u64)
+            }
+            _ => None,
+        };
+        Ok(Statement::Sample {
+            count,
+            seed,
+            span: start.span,
+        })
+    }
+
+    /// Parse a non-negative integer literal, such as a record count.
+    fn parse_count(&mut self) -> error::Result<usize> {
+        match self.advance() {
+            Some(Token {
+                kind: TokenKind::IntLit(n),
+                ..
+            }) if n >= 0 => Ok(n as usize),
+            Some(token) => Err(error::MorphError::mapping_at(
+                format!("expected a non-negative integer, found {:?}", token.kind),
+                token.span.line,
+                token.span.column,
+            )),
+            None => Err(error::MorphError::mapping(
+                "unexpected end of input, expected a non-negative integer",
+            )),
+        }
+    }
+
+    /// Consume a contextual keyword such as `on` or `into`, which the lexer
+    /// leaves as a plain identifier.
+    fn expect_word(&mut self, word: &str, context: &str) -> error::Result<()> {
+        match self.peek_kind() {
+            Some(TokenKind::Ident(found)) if found == word => {
+                self.advance();
+                Ok(())
+            }
+            _ => {
+                let span = self.current_span();
+                Err(error::MorphError::mapping_at(
+                    format!("expected '{word}' {context}"),
+                    span.line,
+                    span.column,
+                ))
+            }
+        }
+    }
+
+    fn parse_join(&mut self, kind: JoinKind) -> error::Result<Statement> {
+        let start = self.advance().unwrap(); // consume 'join'
+        let table = match self.advance() {
+            Some(Token {
+                kind: TokenKind::Ident(name),
+                ..
+            }) => name,
+            Some(token) => {
+                return Err(error::MorphError::mapping_at(
+                    format!(
+                        "expected lookup table name after 'join', found {:?}",
+                        token.kind
+                    ),
+                    token.span.line,
+                    token.span.column,
+                ));
+            }
+            None => {
+                return Err(error::MorphError::mapping(
+                    "unexpected end of input, expected lookup table name",
+                ));
+            }
+        };
+        self.expect_word("on", &format!("after 'join {table}'"))?;
+        let key = self.parse_path()?;
+        self.expect_exact(&TokenKind::Eq)?;
+        let lookup_key = self.parse_path()?;
+        let target = if self.peek_kind() == Some(&TokenKind::As) {
+            self.advance(); // consume 'as'
+            Some(self.parse_path()?)
+        } else {
+            None
+        };
+        Ok(Statement::Join {
+            kind,
+            table,
+            key,
+            lookup_key,
+            target,
+            span: start.span,
+        })
+    }
+
     fn parse_where(&mut self) -> error::Result<Statement> {
         let start = self.advance().unwrap(); // consume 'where'
         let condition = self.parse_expr()?;
@@ -289,6 +915,14 @@ impl Parser {
 
     fn parse_sort(&mut self) -> error::Result<Statement> {
         let start = self.advance().unwrap(); // consume 'sort'
+        let keys = self.parse_sort_keys()?;
+        Ok(Statement::Sort {
+            keys,
+            span: start.span,
+        })
+    }
+
+    fn parse_sort_keys(&mut self) -> error::Result<Vec<SortKey>> {
         let mut keys = Vec::new();
 
         // Parse first sort key
@@ -303,11 +937,7 @@ impl Parser {
             let direction = self.parse_sort_direction();
             keys.push(SortKey { path, direction });
         }
-
-        Ok(Statement::Sort {
-            keys,
-            span: start.span,
-        })
+        Ok(keys)
     }
 
     fn parse_sort_direction(&mut self) -> SortDirection {
@@ -339,31 +969,149 @@ impl Parser {
         let start = self.advance().unwrap(); // consume 'when'
         let condition = self.parse_expr()?;
         let body = self.parse_block()?;
+        let else_body = self.parse_else()?;
         Ok(Statement::When {
             condition,
             body,
+            else_body,
             span: start.span,
         })
     }
 
-    fn parse_block(&mut self) -> error::Result<Vec<Statement>> {
+    /// Parse an optional `else { ... }` or `else when ... { ... }` following a
+    /// `when` block. The `else` may sit on the same line as the closing brace
+    /// or on the next line.
+    fn parse_else(&mut self) -> error::Result<Option<Vec<Statement>>> {
+        let saved = self.pos;
+        self.skip_newlines();
+        if self.peek_kind() != Some(&TokenKind::Else) {
+            self.pos = saved;
+            return Ok(None);
+        }
+        self.advance(); // consume 'else'
+
+        if self.peek_kind() == Some(&TokenKind::When) {
+            let nested = self.parse_when()?;
+            Ok(Some(vec![nested]))
+        } else {
+            Ok(Some(self.parse_block()?))
+        }
+    }
+
+    fn parse_match(&mut self) -> error::Result<Statement> {
+        let start = self.advance().unwrap(); // consume 'match'
+        let subject = self.parse_expr()?;
         self.skip_newlines();
         self.expect_exact(&TokenKind::LBrace)?;
         self.skip_newlines();
 
-        let mut statements = Vec::new();
+        let mut arms = Vec::new();
         while self.peek_kind() != Some(&TokenKind::RBrace) {
             if self.peek().is_none() {
                 return Err(error::MorphError::mapping(
                     "unexpected end of input, expected '}'",
                 ));
             }
-            let stmt = self.parse_statement()?;
-            statements.push(stmt);
-            self.skip_newlines();
-        }
 
-        self.expect_exact(&TokenKind::RBrace)?;
+            let mut patterns = vec![self.parse_match_pattern()?];
+            while let Some(TokenKind::Pipe) = self.peek_kind() {
+                self.advance(); // consume '|'
+                patterns.push(self.parse_match_pattern()?);
+            }
+            self.expect_exact(&TokenKind::FatArrow)?;
+            let body = self.parse_block()?;
+            arms.push(MatchArm { patterns, body });
+
+            // Arms may be separated by commas, newlines, or both
+            self.skip_newlines();
+            if let Some(TokenKind::Comma) = self.peek_kind() {
+                self.advance();
+                self.skip_newlines();
+            }
+        }
+
+        self.expect_exact(&TokenKind::RBrace)?;
+
+        if arms.is_empty() {
+            return Err(error::MorphError::mapping_at(
+                "match requires at least one arm",
+                start.span.line,
+                start.span.column,
+            ));
+        }
+
+        Ok(Statement::Match {
+            subject,
+            arms,
+            span: start.span,
+        })
+    }
+
+    fn parse_match_pattern(&mut self) -> error::Result<MatchPattern> {
+        let token = self.advance().ok_or_else(|| {
+            error::MorphError::mapping("unexpected end of input, expected a match pattern")
+        })?;
+
+        match token.kind {
+            TokenKind::Ident(ref name) if name == "_" => Ok(MatchPattern::Wildcard),
+            TokenKind::StringLit(s) => Ok(MatchPattern::Literal(Value::String(s))),
+            TokenKind::IntLit(n) => Ok(MatchPattern::Literal(Value::Int(n))),
+            TokenKind::FloatLit(f) => Ok(MatchPattern::Literal(Value::Float(f))),
+            TokenKind::True => Ok(MatchPattern::Literal(Value::Bool(true))),
+            TokenKind::False => Ok(MatchPattern::Literal(Value::Bool(false))),
+            TokenKind::Null => Ok(MatchPattern::Literal(Value::Null)),
+            TokenKind::Minus => match self.advance() {
+                Some(Token {
+                    kind: TokenKind::IntLit(n),
+                    ..
+                }) => Ok(MatchPattern::Literal(Value::Int(-n))),
+                Some(Token {
+                    kind: TokenKind::FloatLit(f),
+                    ..
+                }) => Ok(MatchPattern::Literal(Value::Float(-f))),
+                _ => Err(error::MorphError::mapping_at(
+                    "expected a number after '-' in match pattern",
+                    token.span.line,
+                    token.span.column,
+                )),
+            },
+            other => Err(error::MorphError::mapping_at(
+                format!("expected a literal or '_' as match pattern, found {other:?}"),
+                token.span.line,
+                token.span.column,
+            )),
+        }
+    }
+
+    fn parse_block(&mut self) -> error::Result<Vec<Statement>> {
+        self.skip_newlines();
+        self.expect_exact(&TokenKind::LBrace)?;
+        self.skip_newlines();
+
+        let mut statements = Vec::new();
+        while self.peek_kind() != Some(&TokenKind::RBrace) {
+            if self.peek().is_none() {
+                return Err(error::MorphError::mapping(
+                    "unexpected end of input, expected '}'",
+                ));
+            }
+            let span = self.current_span();
+            let stmt = self.parse_statement()?;
+            // Blocks rewrite a single value in place, so they cannot turn
+            // one record into several, merge records together, or drop
+            // records.
+            if let Some(name) = top_level_only(&stmt) {
+                return Err(error::MorphError::mapping_at(
+                    format!("{name} is only allowed at the top level of a mapping"),
+                    span.line,
+                    span.column,
+                ));
+            }
+            statements.push(stmt);
+            self.skip_newlines();
+        }
+
+        self.expect_exact(&TokenKind::RBrace)?;
         Ok(statements)
     }
 
@@ -382,20 +1130,98 @@ impl Parser {
 
         let mut segments = Vec::new();
 
-        // After initial dot, parse first segment
-        self.parse_path_segment(&mut segments)?;
-
-        // Parse additional `.field` or `.[index]` segments
-        while let Some(TokenKind::Dot) = self.peek_kind() {
-            self.advance(); // consume '.'
+        // After initial dot, parse first segment (`..name` descends)
+        if self.peek_kind() == Some(&TokenKind::Dot) {
+            self.advance(); // consume second '.'
+            self.parse_descendant_segment(&mut segments)?;
+        } else {
             self.parse_path_segment(&mut segments)?;
         }
 
+        self.parse_path_tail(&mut segments)?;
         Ok(Path { segments, span })
     }
 
+    /// Parse any further `.field`, `..field`, `.[index]` or `[index]` segments.
+    fn parse_path_tail(&mut self, segments: &mut Vec<PathSegment>) -> error::Result<()> {
+        loop {
+            match self.peek_kind() {
+                Some(TokenKind::Dot) => {
+                    self.advance(); // consume '.'
+                    if self.peek_kind() == Some(&TokenKind::Dot) {
+                        self.advance(); // consume second '.'
+                        self.parse_descendant_segment(segments)?;
+                    } else {
+                        self.parse_path_segment(segments)?;
+                    }
+                }
+                // Brackets may follow a segment directly: `.items[0]`
+                Some(TokenKind::LBracket) => self.parse_path_segment(segments)?,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn parse_descendant_segment(&mut self, segments: &mut Vec<PathSegment>) -> error::Result<()> {
+        let name = match self.peek_kind() {
+            Some(TokenKind::Ident(name)) => name.clone(),
+            Some(kind) if is_keyword(kind) => keyword_to_string(kind),
+            _ => {
+                let span = self.current_span();
+                return Err(error::MorphError::mapping_at(
+                    "expected field name after '..'",
+                    span.line,
+                    span.column,
+                ));
+            }
+        };
+        self.advance();
+        segments.push(PathSegment::Descendant(name));
+        Ok(())
+    }
+
+    /// Parse the inside of `[n]` or `[start:end:step]`, up to the `]`.
+    fn parse_index_or_slice(&mut self) -> error::Result<PathSegment> {
+        let start = self.parse_optional_int();
+        if self.peek_kind() != Some(&TokenKind::Colon) {
+            // `start` is always present here: the caller saw an int or ':'
+            return Ok(PathSegment::Index(start.unwrap_or_default()));
+        }
+        self.advance(); // consume ':'
+        let end = self.parse_optional_int();
+        let mut step = None;
+        if self.peek_kind() == Some(&TokenKind::Colon) {
+            let span = self.current_span();
+            self.advance(); // consume ':'
+            step = self.parse_optional_int();
+            if step == Some(0) {
+                return Err(error::MorphError::mapping_at(
+                    "slice step cannot be zero",
+                    span.line,
+                    span.column,
+                ));
+            }
+        }
+        Ok(PathSegment::Slice { start, end, step })
+    }
+
+    fn parse_optional_int(&mut self) -> Option<i64> {
+        match self.peek_kind() {
+            Some(TokenKind::IntLit(i)) => {
+                let i = *i;
+                self.advance();
+                Some(i)
+            }
+            _ => None,
+        }
+    }
+
     fn parse_path_segment(&mut self, segments: &mut Vec<PathSegment>) -> error::Result<()> {
         match self.peek_kind() {
+            Some(TokenKind::Star) => {
+                self.advance();
+                segments.push(PathSegment::Wildcard);
+            }
             Some(TokenKind::Ident(_)) => {
                 if let Some(Token {
                     kind: TokenKind::Ident(name),
@@ -408,14 +1234,8 @@ impl Parser {
             Some(TokenKind::LBracket) => {
                 self.advance(); // consume '['
                 match self.peek_kind() {
-                    Some(TokenKind::IntLit(_)) => {
-                        if let Some(Token {
-                            kind: TokenKind::IntLit(idx),
-                            ..
-                        }) = self.advance()
-                        {
-                            segments.push(PathSegment::Index(idx));
-                        }
+                    Some(TokenKind::IntLit(_)) | Some(TokenKind::Colon) => {
+                        segments.push(self.parse_index_or_slice()?);
                     }
                     Some(TokenKind::Star) => {
                         self.advance();
@@ -430,10 +1250,17 @@ impl Parser {
                             segments.push(PathSegment::Field(key));
                         }
                     }
+                    Some(TokenKind::Question) => {
+                        self.advance(); // consume '?'
+                        self.expect_exact(&TokenKind::LParen)?;
+                        let predicate = self.parse_expr()?;
+                        self.expect_exact(&TokenKind::RParen)?;
+                        segments.push(PathSegment::Filter(Box::new(predicate)));
+                    }
                     _ => {
                         let span = self.current_span();
                         return Err(error::MorphError::mapping_at(
-                            "expected index, '*', or string key in brackets",
+                            "expected index, slice, '*', '?(...)', or string key in brackets",
                             span.line,
                             span.column,
                         ));
@@ -463,7 +1290,39 @@ impl Parser {
     // -----------------------------------------------------------------------
 
     fn parse_expr(&mut self) -> error::Result<Expr> {
-        self.parse_or_expr()
+        self.parse_conditional()
+    }
+
+    /// `cond ? a : b`, right-associative and binding loosest of all, so
+    /// `.a ?? .b ? x : y ? z : w` reads as `(.a ?? .b) ? x : (y ? z : w)`.
+    fn parse_conditional(&mut self) -> error::Result<Expr> {
+        let condition = self.parse_coalesce()?;
+        if self.peek_kind() != Some(&TokenKind::Question) {
+            return Ok(condition);
+        }
+        self.advance();
+        let then_expr = self.parse_conditional()?;
+        self.expect_exact(&TokenKind::Colon)?;
+        let else_expr = self.parse_conditional()?;
+        Ok(Expr::Conditional {
+            condition: Box::new(condition),
+            then_expr: Box::new(then_expr),
+            else_expr: Box::new(else_expr),
+        })
+    }
+
+    fn parse_coalesce(&mut self) -> error::Result<Expr> {
+        let mut left = self.parse_or_expr()?;
+        while let Some(TokenKind::QuestionQuestion) = self.peek_kind() {
+            self.advance();
+            let right = self.parse_or_expr()?;
+            left = Expr::BinaryOp {
+                left: Box::new(left),
+                op: BinOp::Coalesce,
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
     }
 
     fn parse_or_expr(&mut self) -> error::Result<Expr> {
@@ -503,11 +1362,32 @@ impl Parser {
             Some(TokenKind::GtEq) => Some(BinOp::GtEq),
             Some(TokenKind::Lt) => Some(BinOp::Lt),
             Some(TokenKind::LtEq) => Some(BinOp::LtEq),
+            Some(TokenKind::RegexMatch) => Some(BinOp::Match),
+            Some(TokenKind::Ident(word)) if word == "in" => Some(BinOp::In),
+            Some(TokenKind::Not)
+                if matches!(
+                    self.tokens.get(self.pos + 1).map(|t| &t.kind),
+                    Some(TokenKind::Ident(word)) if word == "in"
+                ) =>
+            {
+                self.advance(); // consume 'not'
+                Some(BinOp::NotIn)
+            }
             _ => None,
         };
         if let Some(op) = op {
             self.advance();
+            let right_span = self.current_span();
             let right = self.parse_additive()?;
+            if let (BinOp::Match, Expr::Literal(Value::String(pattern))) = (op, &right) {
+                if let Err(e) = regex::Regex::new(pattern) {
+                    return Err(error::MorphError::mapping_at(
+                        format!("invalid regex \"{pattern}\": {e}"),
+                        right_span.line,
+                        right_span.column,
+                    ));
+                }
+            }
             Ok(Expr::BinaryOp {
                 left: Box::new(left),
                 op,
@@ -626,7 +1506,19 @@ impl Parser {
                     TokenKind::Ident(n) => n,
                     _ => unreachable!(),
                 };
-                if self.peek_kind() == Some(&TokenKind::LParen) {
+                if self.peek_kind() == Some(&TokenKind::FatArrow) {
+                    // Single-parameter lambda: x => <expr>
+                    self.parse_lambda_body(vec![name], token.span)
+                } else if self.lambda_params.contains(&name) {
+                    // Lambda parameter, optionally followed by a path: x.price
+                    let mut segments = Vec::new();
+                    self.parse_path_tail(&mut segments)?;
+                    Ok(Expr::Variable {
+                        name,
+                        segments,
+                        span: token.span,
+                    })
+                } else if self.peek_kind() == Some(&TokenKind::LParen) {
                     self.advance(); // consume '('
                     let args = self.parse_arg_list()?;
                     self.expect_exact(&TokenKind::RParen)?;
@@ -644,6 +1536,45 @@ impl Parser {
                 }
             }
 
+            // Keywords that double as function names: flatten(.xs, 1)
+            Some(kind @ (TokenKind::Flatten | TokenKind::Default))
+                if self.tokens.get(self.pos + 1).map(|t| &t.kind) == Some(&TokenKind::LParen) =>
+            {
+                let token = self.advance().unwrap();
+                self.advance(); // consume '('
+                let args = self.parse_arg_list()?;
+                self.expect_exact(&TokenKind::RParen)?;
+                Ok(Expr::FunctionCall {
+                    name: keyword_to_string(&kind),
+                    args,
+                    span: token.span,
+                })
+            }
+
+            // Multi-parameter lambda: (acc, x) => <expr>
+            Some(TokenKind::LParen) if self.lambda_params_ahead().is_some() => {
+                let span = self.current_span();
+                let params = self.lambda_params_ahead().unwrap();
+                // Skip '(' + params + separating commas + ')'
+                self.pos += params.len() * 2 + 1;
+                self.parse_lambda_body(params, span)
+            }
+
+            // Array literal: [1, .a, "x"]
+            Some(TokenKind::LBracket) => {
+                self.advance(); // consume '['
+                let mut items = Vec::new();
+                if self.peek_kind() != Some(&TokenKind::RBracket) {
+                    items.push(self.parse_expr()?);
+                    while let Some(TokenKind::Comma) = self.peek_kind() {
+                        self.advance();
+                        items.push(self.parse_expr()?);
+                    }
+                }
+                self.expect_exact(&TokenKind::RBracket)?;
+                Ok(Expr::Array(items))
+            }
+
             // Parenthesized expression
             Some(TokenKind::LParen) => {
                 self.advance(); // consume '('
@@ -667,6 +1598,55 @@ impl Parser {
         }
     }
 
+    /// Look ahead for a parenthesized lambda parameter list `(a, b) =>`
+    /// starting at the current `(` and return the parameter names.
+    fn lambda_params_ahead(&self) -> Option<Vec<String>> {
+        let mut params = Vec::new();
+        let mut i = self.pos + 1;
+        loop {
+            match self.tokens.get(i).map(|t| &t.kind) {
+                Some(TokenKind::Ident(name)) => params.push(name.clone()),
+                _ => return None,
+            }
+            i += 1;
+            match self.tokens.get(i).map(|t| &t.kind) {
+                Some(TokenKind::Comma) => i += 1,
+                Some(TokenKind::RParen) => break,
+                _ => return None,
+            }
+        }
+        match self.tokens.get(i + 1).map(|t| &t.kind) {
+            Some(TokenKind::FatArrow) => Some(params),
+            _ => None,
+        }
+    }
+
+    /// Parse the `=> <expr>` part of a lambda whose parameters have already
+    /// been consumed.
+    fn parse_lambda_body(&mut self, params: Vec<String>, span: Span) -> error::Result<Expr> {
+        self.expect_exact(&TokenKind::FatArrow)?;
+        for (i, param) in params.iter().enumerate() {
+            if params[..i].contains(param) {
+                return Err(error::MorphError::mapping_at(
+                    format!("duplicate lambda parameter '{param}'"),
+                    span.line,
+                    span.column,
+                ));
+            }
+        }
+
+        let scope_len = self.lambda_params.len();
+        self.lambda_params.extend(params.iter().cloned());
+        let body = self.parse_expr();
+        self.lambda_params.truncate(scope_len);
+
+        Ok(Expr::Lambda {
+            params,
+            body: Box::new(body?),
+            span,
+        })
+    }
+
     fn parse_interpolated_string(
         &mut self,
         parts: &[crate::mapping::lexer::InterpolatedPart],
@@ -681,15 +1661,17 @@ impl Parser {
                     ast_parts.push(InterpolationPart::Literal(s.clone()));
                 }
                 LexPart::Expression(expr_str) => {
-                    let sub_program =
-                        crate::mapping::parser::parse_str(&format!("set .x = {expr_str}"))?;
-                    if let Some(Statement::Set { expr, .. }) = sub_program.statements.first() {
-                        ast_parts.push(InterpolationPart::Expr(expr.clone()));
-                    } else {
+                    // Parse with the enclosing lambda scope so parameters can
+                    // be interpolated: "{x.name}"
+                    let mut sub_parser = Parser::new(crate::mapping::lexer::tokenize(expr_str)?);
+                    sub_parser.lambda_params = self.lambda_params.clone();
+                    let expr = sub_parser.parse_expr()?;
+                    if sub_parser.peek().is_some() {
                         return Err(error::MorphError::mapping(
                             "invalid expression in string interpolation",
                         ));
                     }
+                    ast_parts.push(InterpolationPart::Expr(expr));
                 }
             }
         }
@@ -717,6 +1699,12 @@ impl Parser {
 fn kind_name(kind: &TokenKind) -> &'static str {
     match kind {
         TokenKind::Arrow => "'->'",
+        TokenKind::FatArrow => "'=>'",
+        TokenKind::Pipe => "'|'",
+        TokenKind::Question => "'?'",
+        TokenKind::QuestionQuestion => "'??'",
+        TokenKind::RegexMatch => "'=~'",
+        TokenKind::Colon => "':'",
         TokenKind::Eq => "'='",
         TokenKind::EqEq => "'=='",
         TokenKind::NotEq => "'!='",
@@ -762,16 +1750,49 @@ fn is_keyword(kind: &TokenKind) -> bool {
             | TokenKind::Sort
             | TokenKind::Each
             | TokenKind::When
+            | TokenKind::Else
+            | TokenKind::Match
             | TokenKind::Not
             | TokenKind::And
             | TokenKind::Or
             | TokenKind::Flatten
+            | TokenKind::Unflatten
             | TokenKind::Nest
+            | TokenKind::Explode
+            | TokenKind::Unnest
+            | TokenKind::Aggregate
+            | TokenKind::Pivot
+            | TokenKind::Unpivot
+            | TokenKind::Distinct
+            | TokenKind::Limit
+            | TokenKind::Head
+            | TokenKind::Skip
+            | TokenKind::Tail
+            | TokenKind::Sample
             | TokenKind::Asc
             | TokenKind::Desc
     )
 }
 
+/// The name of a statement that may only appear at the top level of a
+/// mapping, or None if it may also appear inside blocks.
+fn top_level_only(stmt: &Statement) -> Option<&'static str> {
+    match stmt {
+        Statement::Explode { .. } => Some("explode"),
+        Statement::Aggregate { .. } => Some("aggregate"),
+        Statement::Pivot { .. } => Some("pivot"),
+        Statement::Unpivot { .. } => Some("unpivot"),
+        Statement::Distinct { .. } => Some("distinct"),
+        Statement::Limit { .. } => Some("limit"),
+        Statement::Skip { .. } => Some("skip"),
+        Statement::Tail { .. } => Some("tail"),
+        Statement::Sample { .. } => Some("sample"),
+        Statement::Join { .. } => Some("join"),
+        Statement::Window { .. } => Some("a window function"),
+        _ => None,
+    }
+}
+
 fn keyword_to_string(kind: &TokenKind) -> String {
     match kind {
         TokenKind::Rename => "rename".into(),
@@ -785,11 +1806,25 @@ fn keyword_to_string(kind: &TokenKind) -> String {
         TokenKind::Sort => "sort".into(),
         TokenKind::Each => "each".into(),
         TokenKind::When => "when".into(),
+        TokenKind::Else => "else".into(),
+        TokenKind::Match => "match".into(),
         TokenKind::Not => "not".into(),
         TokenKind::And => "and".into(),
         TokenKind::Or => "or".into(),
         TokenKind::Flatten => "flatten".into(),
+        TokenKind::Unflatten => "unflatten".into(),
         TokenKind::Nest => "nest".into(),
+        TokenKind::Explode => "explode".into(),
+        TokenKind::Unnest => "unnest".into(),
+        TokenKind::Aggregate => "aggregate".into(),
+        TokenKind::Pivot => "pivot".into(),
+        TokenKind::Unpivot => "unpivot".into(),
+        TokenKind::Distinct => "distinct".into(),
+        TokenKind::Limit => "limit".into(),
+        TokenKind::Head => "head".into(),
+        TokenKind::Skip => "skip".into(),
+        TokenKind::Tail => "tail".into(),
+        TokenKind::Sample => "sample".into(),
         TokenKind::Asc => "asc".into(),
         TokenKind::Desc => "desc".into(),
         _ => String::new(),
@@ -1401,6 +2436,112 @@ mod tests {
         }
     }
 
+    // -----------------------------------------------------------------------
+    // ??, in, =~ and ?:
+    // -----------------------------------------------------------------------
+
+    fn set_expr(input: &str) -> Expr {
+        match first_stmt(input) {
+            Statement::Set { expr, .. } => expr,
+            other => panic!("expected Set, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn coalesce_binds_looser_than_or() {
+        match set_expr("set .x = .a or .b ?? .c") {
+            Expr::BinaryOp { op, left, .. } => {
+                assert_eq!(op, BinOp::Coalesce);
+                assert!(matches!(*left, Expr::BinaryOp { op: BinOp::Or, .. }));
+            }
+            other => panic!("expected BinaryOp, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn in_and_not_in() {
+        match set_expr("set .x = .a in [1, 2]") {
+            Expr::BinaryOp { op, .. } => assert_eq!(op, BinOp::In),
+            other => panic!("expected BinaryOp, got: {other:?}"),
+        }
+        match set_expr("set .x = .a not in .list and .b") {
+            Expr::BinaryOp { op, left, .. } => {
+                assert_eq!(op, BinOp::And);
+                assert!(matches!(
+                    *left,
+                    Expr::BinaryOp {
+                        op: BinOp::NotIn,
+                        ..
+                    }
+                ));
+            }
+            other => panic!("expected BinaryOp, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn array_literal() {
+        match set_expr(r#"set .x = [1, .a, "b"]"#) {
+            Expr::Array(items) => {
+                assert_eq!(items.len(), 3);
+                assert!(matches!(items[1], Expr::Path(_)));
+            }
+            other => panic!("expected Array, got: {other:?}"),
+        }
+        assert_eq!(set_expr("set .x = []"), Expr::Array(vec![]));
+    }
+
+    #[test]
+    fn regex_match() {
+        match set_expr(r#"set .x = .email =~ "@example\\.com$""#) {
+            Expr::BinaryOp { op, .. } => assert_eq!(op, BinOp::Match),
+            other => panic!("expected BinaryOp, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_regex_match_bad_pattern() {
+        let err = parse_err(r#"set .x = .a =~ "(""#);
+        assert!(err.to_string().contains("invalid regex"), "err: {err}");
+    }
+
+    #[test]
+    fn conditional_is_right_associative() {
+        match set_expr(r#"set .x = .a ? "a" : .b ? "b" : "c""#) {
+            Expr::Conditional {
+                condition,
+                else_expr,
+                ..
+            } => {
+                assert!(matches!(*condition, Expr::Path(_)));
+                assert!(matches!(*else_expr, Expr::Conditional { .. }));
+            }
+            other => panic!("expected Conditional, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn conditional_condition_includes_coalesce() {
+        match set_expr("set .x = .a ?? .b ? 1 : 2") {
+            Expr::Conditional { condition, .. } => {
+                assert!(matches!(
+                    *condition,
+                    Expr::BinaryOp {
+                        op: BinOp::Coalesce,
+                        ..
+                    }
+                ));
+            }
+            other => panic!("expected Conditional, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_conditional_missing_colon() {
+        let err = parse_err("set .x = .a ? 1 2");
+        assert!(err.to_string().contains("':'"), "err: {err}");
+    }
+
     // -----------------------------------------------------------------------
     // default
     // -----------------------------------------------------------------------
@@ -1457,6 +2598,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cast_decimal() {
+        let stmt = first_stmt("cast .price as decimal");
+        match stmt {
+            Statement::Cast { target_type, .. } => {
+                assert_eq!(target_type, CastType::Decimal);
+            }
+            other => panic!("expected Cast, got: {other:?}"),
+        }
+    }
+
     #[test]
     fn cast_string() {
         let stmt = first_stmt("cast .id as string");
@@ -1523,30 +2675,167 @@ mod tests {
         ));
     }
 
-    // -----------------------------------------------------------------------
-    // multi-statement programs
-    // -----------------------------------------------------------------------
-
     #[test]
-    fn multi_statement() {
-        let prog = parse_ok("rename .old -> .new\nset .x = 42\nselect .new, .x");
-        assert_eq!(prog.statements.len(), 3);
-        assert!(matches!(prog.statements[0], Statement::Rename { .. }));
-        assert!(matches!(prog.statements[1], Statement::Set { .. }));
-        assert!(matches!(prog.statements[2], Statement::Select { .. }));
+    fn cast_targets_with_options() {
+        let target = |src: &str| match first_stmt(src) {
+            Statement::Cast { target_type, .. } => target_type,
+            other => panic!("expected Cast, got {other:?}"),
+        };
+        assert_eq!(
+            target("cast .tags as array split \",\""),
+            CastType::Array {
+                split: Some(",".into())
+            }
+        );
+        assert_eq!(
+            target("cast .tags as list"),
+            CastType::Array { split: None }
+        );
+        assert_eq!(
+            target("cast .ts as datetime \"%d/%m/%Y\""),
+            CastType::DateTime {
+                format: Some("%d/%m/%Y".into())
+            }
+        );
+        assert_eq!(
+            target("cast .ts as date"),
+            CastType::DateTime { format: None }
+        );
+        assert_eq!(
+            target("cast .blob as bytes base64"),
+            CastType::Bytes {
+                encoding: BytesEncoding::Base64
+            }
+        );
+        assert_eq!(
+            target("cast .blob as bytes"),
+            CastType::Bytes {
+                encoding: BytesEncoding::Utf8
+            }
+        );
+        assert_eq!(
+            target("cast .meta as object yaml"),
+            CastType::Object {
+                format: Format::Yaml
+            }
+        );
+        assert_eq!(
+            target("cast .meta as map"),
+            CastType::Object {
+                format: Format::Json
+            }
+        );
+        assert!(matches!(
+            first_stmt("cast .tags as array split \";\" or null"),
+            Statement::Cast {
+                fallback: Some(CastFallback::Null),
+                ..
+            }
+        ));
     }
 
     #[test]
-    fn multi_statement_with_blank_lines() {
-        let prog = parse_ok("rename .a -> .b\n\n\nselect .b");
-        assert_eq!(prog.statements.len(), 2);
+    fn cast_target_option_errors() {
+        let err = parse_err("cast .b as bytes base32");
+        assert!(
+            err.to_string().contains("utf8, base64, or hex"),
+            "err: {err}"
+        );
+        let err = parse_err("cast .m as object jsn");
+        assert!(
+            err.to_string().contains("Did you mean 'json'"),
+            "err: {err}"
+        );
+        let err = parse_err("cast .t as array split 1");
+        assert!(err.to_string().contains("after 'split'"), "err: {err}");
+        let err = parse_err("cast .t as tuple");
+        assert!(err.to_string().contains("bytes, or object"), "err: {err}");
     }
 
     #[test]
-    fn multi_statement_with_comments() {
-        let prog = parse_ok(
-            "# Rename the field\nrename .old -> .new\n# Now set a default\ndefault .x = 0",
-        );
+    fn cast_or_null_and_or_default() {
+        assert!(matches!(
+            first_stmt("cast .age as int or null"),
+            Statement::Cast {
+                fallback: Some(CastFallback::Null),
+                ..
+            }
+        ));
+        match first_stmt("cast .age as int or default -1") {
+            Statement::Cast {
+                fallback: Some(CastFallback::Default(expr)),
+                ..
+            } => assert_eq!(
+                expr,
+                Expr::UnaryOp {
+                    op: UnaryOp::Neg,
+                    expr: Box::new(Expr::Literal(Value::Int(1))),
+                }
+            ),
+            other => panic!("expected Cast with default, got: {other:?}"),
+        }
+        assert!(matches!(
+            first_stmt("cast .age as int"),
+            Statement::Cast { fallback: None, .. }
+        ));
+        let err = parse_err("cast .age as int or zero");
+        assert!(err.to_string().contains("after 'or'"), "err: {err}");
+    }
+
+    // -----------------------------------------------------------------------
+    // on error
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn on_error_policies() {
+        assert_eq!(parse_ok("set .a = 1").on_error, OnError::Fail);
+        assert_eq!(
+            parse_ok("on error skip\nset .a = 1").on_error,
+            OnError::Skip
+        );
+        assert_eq!(
+            parse_ok("set .a = 1\non error null").on_error,
+            OnError::Null
+        );
+        let prog = parse_ok("on error fail\nset .a = 1");
+        assert_eq!(prog.on_error, OnError::Fail);
+        assert_eq!(prog.statements.len(), 1);
+    }
+
+    #[test]
+    fn on_error_rejects_unknown_and_repeated_policies() {
+        let err = parse_err("on error ignore");
+        assert!(err.to_string().contains("skip, null or fail"), "err: {err}");
+        let err = parse_err("on error skip\non error null");
+        assert!(err.to_string().contains("only be given once"), "err: {err}");
+        let err = parse_err("on skip");
+        assert!(err.to_string().contains("expected 'error'"), "err: {err}");
+    }
+
+    // -----------------------------------------------------------------------
+    // multi-statement programs
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn multi_statement() {
+        let prog = parse_ok("rename .old -> .new\nset .x = 42\nselect .new, .x");
+        assert_eq!(prog.statements.len(), 3);
+        assert!(matches!(prog.statements[0], Statement::Rename { .. }));
+        assert!(matches!(prog.statements[1], Statement::Set { .. }));
+        assert!(matches!(prog.statements[2], Statement::Select { .. }));
+    }
+
+    #[test]
+    fn multi_statement_with_blank_lines() {
+        let prog = parse_ok("rename .a -> .b\n\n\nselect .b");
+        assert_eq!(prog.statements.len(), 2);
+    }
+
+    #[test]
+    fn multi_statement_with_comments() {
+        let prog = parse_ok(
+            "# Rename the field\nrename .old -> .new\n# Now set a default\ndefault .x = 0",
+        );
         assert_eq!(prog.statements.len(), 2);
         assert!(matches!(prog.statements[0], Statement::Rename { .. }));
         assert!(matches!(prog.statements[1], Statement::Default { .. }));
@@ -1811,4 +3100,827 @@ select .full_name, .age, .email
             other => panic!("expected Set, got: {other:?}"),
         }
     }
+
+    // -----------------------------------------------------------------------
+    // when / else
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn when_without_else() {
+        let stmt = first_stmt("when .x > 1 { set .y = 1 }");
+        match stmt {
+            Statement::When { else_body, .. } => assert!(else_body.is_none()),
+            other => panic!("expected When, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn when_with_else() {
+        let stmt = first_stmt("when .x > 1 { set .y = 1 } else { set .y = 2 }");
+        match stmt {
+            Statement::When {
+                body, else_body, ..
+            } => {
+                assert_eq!(body.len(), 1);
+                assert_eq!(else_body.map(|b| b.len()), Some(1));
+            }
+            other => panic!("expected When, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn when_else_on_next_line() {
+        let prog = parse_ok("when .x > 1 {\n  set .y = 1\n}\nelse {\n  set .y = 2\n}\nset .z = 3");
+        assert_eq!(prog.statements.len(), 2);
+        match &prog.statements[0] {
+            Statement::When { else_body, .. } => assert!(else_body.is_some()),
+            other => panic!("expected When, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn when_else_when_chain() {
+        let stmt = first_stmt(
+            "when .x == 1 { set .y = \"a\" } else when .x == 2 { set .y = \"b\" } else { set .y = \"c\" }",
+        );
+        match stmt {
+            Statement::When { else_body, .. } => {
+                let else_body = else_body.expect("expected else body");
+                assert_eq!(else_body.len(), 1);
+                match &else_body[0] {
+                    Statement::When { else_body, .. } => assert!(else_body.is_some()),
+                    other => panic!("expected nested When, got: {other:?}"),
+                }
+            }
+            other => panic!("expected When, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_else_without_when() {
+        let err = parse_err("else { set .y = 1 }");
+        match err {
+            error::MorphError::Mapping { message, .. } => {
+                assert!(
+                    message.contains("without a preceding 'when'"),
+                    "msg: {message}"
+                );
+            }
+            other => panic!("expected Mapping error, got: {other:?}"),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // match
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn match_arms_and_patterns() {
+        let stmt = first_stmt(
+            "match .kind {\n  \"a\" => { set .n = 1 },\n  \"b\" | \"c\" => { set .n = 2 }\n  _ => { set .n = 0 }\n}",
+        );
+        match stmt {
+            Statement::Match { subject, arms, .. } => {
+                assert!(matches!(subject, Expr::Path(_)));
+                assert_eq!(arms.len(), 3);
+                assert_eq!(
+                    arms[0].patterns,
+                    vec![MatchPattern::Literal(Value::String("a".into()))]
+                );
+                assert_eq!(
+                    arms[1].patterns,
+                    vec![
+                        MatchPattern::Literal(Value::String("b".into())),
+                        MatchPattern::Literal(Value::String("c".into())),
+                    ]
+                );
+                assert_eq!(arms[2].patterns, vec![MatchPattern::Wildcard]);
+            }
+            other => panic!("expected Match, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn match_literal_patterns() {
+        let stmt = first_stmt(
+            "match .x { 1 => { drop .a } -2 => { drop .b } 1.5 | true | null => { drop .c } }",
+        );
+        match stmt {
+            Statement::Match { arms, .. } => {
+                assert_eq!(arms[0].patterns, vec![MatchPattern::Literal(Value::Int(1))]);
+                assert_eq!(
+                    arms[1].patterns,
+                    vec![MatchPattern::Literal(Value::Int(-2))]
+                );
+                assert_eq!(
+                    arms[2].patterns,
+                    vec![
+                        MatchPattern::Literal(Value::Float(1.5)),
+                        MatchPattern::Literal(Value::Bool(true)),
+                        MatchPattern::Literal(Value::Null),
+                    ]
+                );
+            }
+            other => panic!("expected Match, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_match_without_arms() {
+        let err = parse_err("match .x { }");
+        match err {
+            error::MorphError::Mapping { message, .. } => {
+                assert!(message.contains("at least one arm"), "msg: {message}");
+            }
+            other => panic!("expected Mapping error, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_match_non_literal_pattern() {
+        let err = parse_err("match .x { .y => { drop .z } }");
+        assert!(matches!(err, error::MorphError::Mapping { .. }));
+    }
+
+    #[test]
+    fn match_as_field_name() {
+        let stmt = first_stmt("set .match = .else");
+        match stmt {
+            Statement::Set { path, expr, .. } => {
+                assert_eq!(path.segments, vec![PathSegment::Field("match".into())]);
+                assert_eq!(
+                    expr,
+                    Expr::Path(Path {
+                        segments: vec![PathSegment::Field("else".into())],
+                        span: Span::new(1, 14),
+                    })
+                );
+            }
+            other => panic!("expected Set, got: {other:?}"),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // lambdas
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn lambda_single_param() {
+        let stmt = first_stmt("set .t = map(.items, i => i.price * i.qty)");
+        match stmt {
+            Statement::Set {
+                expr: Expr::FunctionCall { name, args, .. },
+                ..
+            } => {
+                assert_eq!(name, "map");
+                match &args[1] {
+                    Expr::Lambda { params, body, .. } => {
+                        assert_eq!(params, &vec!["i".to_string()]);
+                        match body.as_ref() {
+                            Expr::BinaryOp { left, op, .. } => {
+                                assert_eq!(*op, BinOp::Mul);
+                                match left.as_ref() {
+                                    Expr::Variable { name, segments, .. } => {
+                                        assert_eq!(name, "i");
+                                        assert_eq!(
+                                            segments,
+                                            &vec![PathSegment::Field("price".into())]
+                                        );
+                                    }
+                                    other => panic!("expected Variable, got: {other:?}"),
+                                }
+                            }
+                            other => panic!("expected BinaryOp, got: {other:?}"),
+                        }
+                    }
+                    other => panic!("expected Lambda, got: {other:?}"),
+                }
+            }
+            other => panic!("expected Set with FunctionCall, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn lambda_multiple_params() {
+        let stmt = first_stmt("set .t = reduce(.xs, (acc, x) => acc + x, 0)");
+        match stmt {
+            Statement::Set {
+                expr: Expr::FunctionCall { args, .. },
+                ..
+            } => {
+                assert_eq!(args.len(), 3);
+                match &args[1] {
+                    Expr::Lambda { params, .. } => {
+                        assert_eq!(params, &vec!["acc".to_string(), "x".to_string()]);
+                    }
+                    other => panic!("expected Lambda, got: {other:?}"),
+                }
+                assert_eq!(args[2], Expr::Literal(Value::Int(0)));
+            }
+            other => panic!("expected Set with FunctionCall, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn lambda_param_out_of_scope_is_field() {
+        // Outside the lambda body `x` is a plain field reference again.
+        let stmt = first_stmt("set .t = concat(map(.xs, x => x), x)");
+        match stmt {
+            Statement::Set {
+                expr: Expr::FunctionCall { args, .. },
+                ..
+            } => assert!(matches!(&args[1], Expr::Path(_))),
+            other => panic!("expected Set with FunctionCall, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parenthesized_expr_is_not_lambda() {
+        let stmt = first_stmt("set .t = (x)");
+        match stmt {
+            Statement::Set { expr, .. } => assert!(matches!(expr, Expr::Path(_))),
+            other => panic!("expected Set, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_duplicate_lambda_param() {
+        let err = parse_err("set .t = reduce(.xs, (a, a) => a)");
+        match err {
+            error::MorphError::Mapping { message, .. } => {
+                assert!(
+                    message.contains("duplicate lambda parameter"),
+                    "msg: {message}"
+                );
+            }
+            other => panic!("expected Mapping error, got: {other:?}"),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // path queries
+    // -----------------------------------------------------------------------
+
+    fn set_path_segments(input: &str) -> Vec<PathSegment> {
+        match first_stmt(input) {
+            Statement::Set { path, .. } => path.segments,
+            other => panic!("expected Set, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn path_recursive_descent() {
+        assert_eq!(
+            set_path_segments("set ..password = null"),
+            vec![PathSegment::Descendant("password".into())]
+        );
+        assert_eq!(
+            set_path_segments("set .user..id = 1"),
+            vec![
+                PathSegment::Field("user".into()),
+                PathSegment::Descendant("id".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn path_dot_star_wildcard() {
+        assert_eq!(
+            set_path_segments("set .meta.*.seen = true"),
+            vec![
+                PathSegment::Field("meta".into()),
+                PathSegment::Wildcard,
+                PathSegment::Field("seen".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn path_bracket_without_dot() {
+        assert_eq!(
+            set_path_segments("set .items[0].name = 1"),
+            vec![
+                PathSegment::Field("items".into()),
+                PathSegment::Index(0),
+                PathSegment::Field("name".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn path_filter_predicate() {
+        let segments = set_path_segments("set .items[?(.qty > 0)].ok = true");
+        assert_eq!(segments.len(), 3);
+        match &segments[1] {
+            PathSegment::Filter(pred) => match pred.as_ref() {
+                Expr::BinaryOp { op, .. } => assert_eq!(*op, BinOp::Gt),
+                other => panic!("expected BinaryOp, got: {other:?}"),
+            },
+            other => panic!("expected Filter, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_descent_without_name() {
+        let err = parse_err("drop ..");
+        match err {
+            error::MorphError::Mapping { message, .. } => {
+                assert!(message.contains("after '..'"), "msg: {message}");
+            }
+            other => panic!("expected Mapping error, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn path_slices() {
+        let slice = |input: &str| set_path_segments(input).pop().unwrap();
+        assert_eq!(
+            slice("set .a[1:3] = 0"),
+            PathSegment::Slice {
+                start: Some(1),
+                end: Some(3),
+                step: None,
+            }
+        );
+        assert_eq!(
+            slice("set .a[-2:] = 0"),
+            PathSegment::Slice {
+                start: Some(-2),
+                end: None,
+                step: None,
+            }
+        );
+        assert_eq!(
+            slice("set .a[::2] = 0"),
+            PathSegment::Slice {
+                start: None,
+                end: None,
+                step: Some(2),
+            }
+        );
+        assert_eq!(slice("set .a[-1] = 0"), PathSegment::Index(-1));
+    }
+
+    #[test]
+    fn error_zero_slice_step() {
+        let err = parse_err("set .a[::0] = 1");
+        match err {
+            error::MorphError::Mapping { message, .. } => {
+                assert!(message.contains("step cannot be zero"), "msg: {message}");
+            }
+            other => panic!("expected Mapping error, got: {other:?}"),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // explode
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn explode_with_target() {
+        match first_stmt("explode .items as .item") {
+            Statement::Explode { path, target, .. } => {
+                assert_eq!(path.segments, vec![PathSegment::Field("items".into())]);
+                assert_eq!(target.segments, vec![PathSegment::Field("item".into())]);
+            }
+            other => panic!("expected Explode, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn unnest_defaults_target_to_source() {
+        match first_stmt("unnest .items") {
+            Statement::Explode { path, target, .. } => assert_eq!(path, target),
+            other => panic!("expected Explode, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_explode_inside_block() {
+        let err = parse_err("each .orders { explode .items }");
+        match err {
+            error::MorphError::Mapping { message, .. } => {
+                assert!(message.contains("top level"), "msg: {message}");
+            }
+            other => panic!("expected Mapping error, got: {other:?}"),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // pivot / unpivot
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn pivot_with_by() {
+        match first_stmt("pivot key .metric value .value by .date, .site") {
+            Statement::Pivot { key, value, by, .. } => {
+                assert_eq!(key.to_string(), ".metric");
+                assert_eq!(value.to_string(), ".value");
+                assert_eq!(by.len(), 2);
+            }
+            other => panic!("expected Pivot, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn pivot_without_by() {
+        match first_stmt("pivot key .k value .v") {
+            Statement::Pivot { by, .. } => assert!(by.is_empty()),
+            other => panic!("expected Pivot, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn unpivot_fields_into_name_and_value() {
+        match first_stmt("unpivot .jan, .feb, .mar into .month, .amount") {
+            Statement::Unpivot {
+                paths, name, value, ..
+            } => {
+                assert_eq!(paths.len(), 3);
+                assert_eq!(name.to_string(), ".month");
+                assert_eq!(value.to_string(), ".amount");
+            }
+            other => panic!("expected Unpivot, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_pivot_missing_value() {
+        let err = parse_err("pivot key .k by .d");
+        assert!(err.to_string().contains("expected 'value'"), "err: {err}");
+    }
+
+    #[test]
+    fn error_unpivot_missing_into() {
+        let err = parse_err("unpivot .jan, .feb as .month, .amount");
+        assert!(err.to_string().contains("expected 'into'"), "err: {err}");
+    }
+
+    #[test]
+    fn error_unpivot_inside_block() {
+        let err = parse_err("each .rows { unpivot .a into .k, .v }");
+        assert!(err.to_string().contains("top level"), "err: {err}");
+    }
+
+    // -----------------------------------------------------------------------
+    // distinct
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn distinct_defaults() {
+        match first_stmt("distinct") {
+            Statement::Distinct { by, keep, .. } => {
+                assert!(by.is_empty());
+                assert_eq!(keep, DistinctKeep::First);
+            }
+            other => panic!("expected Distinct, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn distinct_by_keep_last() {
+        match first_stmt("distinct by .email, .tenant keep last") {
+            Statement::Distinct { by, keep, .. } => {
+                assert_eq!(by.len(), 2);
+                assert_eq!(keep, DistinctKeep::Last);
+            }
+            other => panic!("expected Distinct, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_distinct_bad_keep() {
+        let err = parse_err("distinct by .id keep newest");
+        assert!(err.to_string().contains("'first' or 'last'"), "err: {err}");
+    }
+
+    // -----------------------------------------------------------------------
+    // flatten all / unflatten all
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn flatten_all_defaults() {
+        match first_stmt("flatten all") {
+            Statement::FlattenAll {
+                separator, arrays, ..
+            } => {
+                assert_eq!(separator, ".");
+                assert_eq!(arrays, ArrayFlatten::Index);
+            }
+            other => panic!("expected FlattenAll, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn unflatten_all_with_options() {
+        match first_stmt(r#"unflatten all separator "__" arrays join ",""#) {
+            Statement::UnflattenAll {
+                separator, arrays, ..
+            } => {
+                assert_eq!(separator, "__");
+                assert_eq!(arrays, ArrayFlatten::Join(",".into()));
+            }
+            other => panic!("expected UnflattenAll, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_flatten_all_empty_separator() {
+        let err = parse_err(r#"flatten all separator """#);
+        assert!(err.to_string().contains("must not be empty"), "err: {err}");
+    }
+
+    #[test]
+    fn error_unflatten_without_all() {
+        let err = parse_err("unflatten .a");
+        assert!(err.to_string().contains("expected 'all'"), "err: {err}");
+        let err = parse_err("flatten all arrays split");
+        assert!(err.to_string().contains("'index' or 'join'"), "err: {err}");
+    }
+
+    // -----------------------------------------------------------------------
+    // rename keys
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn rename_keys_using_case() {
+        match first_stmt("rename keys recursive using snake_case") {
+            Statement::RenameKeys {
+                path,
+                recursive,
+                rename,
+                ..
+            } => {
+                assert!(path.is_none());
+                assert!(recursive);
+                assert_eq!(rename, KeyRename::Case(KeyCase::Snake));
+            }
+            other => panic!("expected RenameKeys, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rename_keys_matching_at_path() {
+        match first_stmt(r#"rename keys .payload matching "^x_(.*)$" -> "$1""#) {
+            Statement::RenameKeys {
+                path,
+                recursive,
+                rename:
+                    KeyRename::Pattern {
+                        pattern,
+                        replacement,
+                    },
+                ..
+            } => {
+                assert_eq!(path.unwrap().to_string(), ".payload");
+                assert!(!recursive);
+                assert_eq!(pattern.0.as_str(), "^x_(.*)$");
+                assert_eq!(replacement, "$1");
+            }
+            other => panic!("expected RenameKeys, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_rename_keys_unknown_case() {
+        let err = parse_err("rename keys using title_case");
+        assert!(err.to_string().contains("after 'using'"), "err: {err}");
+    }
+
+    #[test]
+    fn error_rename_keys_bad_regex() {
+        let err = parse_err(r#"rename keys matching "(" -> "x""#);
+        assert!(err.to_string().contains("invalid regex"), "err: {err}");
+    }
+
+    // -----------------------------------------------------------------------
+    // window functions
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn window_set_with_partition_and_order() {
+        match first_stmt(
+            "set .cum = running_sum(.amount) partition by .account order by .date desc",
+        ) {
+            Statement::Window {
+                path,
+                partition,
+                order,
+                ..
+            } => {
+                assert_eq!(path.to_string(), ".cum");
+                assert_eq!(partition.len(), 1);
+                assert_eq!(order.len(), 1);
+                assert_eq!(order[0].direction, SortDirection::Desc);
+            }
+            other => panic!("expected Window, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn window_set_without_clauses() {
+        match first_stmt("set .delta = .value - lag(.value, 1, .value)") {
+            Statement::Window {
+                partition, order, ..
+            } => {
+                assert!(partition.is_empty());
+                assert!(order.is_empty());
+            }
+            other => panic!("expected Window, got: {other:?}"),
+        }
+        assert!(matches!(
+            first_stmt("set .n = count(.items)"),
+            Statement::Set { .. }
+        ));
+    }
+
+    #[test]
+    fn error_order_by_without_window_function() {
+        let err = parse_err("set .x = .a order by .date");
+        assert!(
+            err.to_string().contains("require a window function"),
+            "err: {err}"
+        );
+    }
+
+    #[test]
+    fn error_window_function_arity() {
+        let err = parse_err("set .n = row_number(.id)");
+        assert!(err.to_string().contains("takes no arguments"), "err: {err}");
+        let err = parse_err("set .p = lag()");
+        assert!(err.to_string().contains("1 to 3 arguments"), "err: {err}");
+    }
+
+    #[test]
+    fn error_nested_window_function() {
+        let err = parse_err("set .x = lag(row_number())");
+        assert!(
+            err.to_string().contains("cannot contain another"),
+            "err: {err}"
+        );
+    }
+
+    #[test]
+    fn error_window_inside_block() {
+        let err = parse_err("when .a { set .n = row_number() }");
+        assert!(err.to_string().contains("top level"), "err: {err}");
+    }
+
+    // -----------------------------------------------------------------------
+    // limit / skip / tail / sample
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn head_is_limit() {
+        assert!(matches!(
+            first_stmt("head 5"),
+            Statement::Limit { count: 5, .. }
+        ));
+        assert!(matches!(
+            first_stmt("limit 0"),
+            Statement::Limit { count: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn skip_and_tail() {
+        assert!(matches!(
+            first_stmt("skip 10"),
+            Statement::Skip { count: 10, .. }
+        ));
+        assert!(matches!(
+            first_stmt("tail 3"),
+            Statement::Tail { count: 3, .. }
+        ));
+    }
+
+    #[test]
+    fn sample_with_and_without_seed() {
+        assert!(matches!(
+            first_stmt("sample 100"),
+            Statement::Sample {
+                count: 100,
+                seed: None,
+                ..
+            }
+        ));
+        assert!(matches!(
+            first_stmt("sample 100 seed 42"),
+            Statement::Sample {
+                count: 100,
+                seed: Some(42),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn error_limit_needs_count() {
+        let err = parse_err("limit .n");
+        assert!(
+            err.to_string().contains("non-negative integer"),
+            "err: {err}"
+        );
+        let err = parse_err("limit -1");
+        assert!(
+            err.to_string().contains("non-negative integer"),
+            "err: {err}"
+        );
+    }
+
+    #[test]
+    fn error_limit_inside_block() {
+        let err = parse_err("each .rows { limit 1 }");
+        assert!(err.to_string().contains("top level"), "err: {err}");
+    }
+
+    // -----------------------------------------------------------------------
+    // join
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn join_defaults_to_left() {
+        match first_stmt("join users on .user_id = .id") {
+            Statement::Join {
+                kind,
+                table,
+                key,
+                lookup_key,
+                target,
+                ..
+            } => {
+                assert_eq!(kind, JoinKind::Left);
+                assert_eq!(table, "users");
+                assert_eq!(key.to_string(), ".user_id");
+                assert_eq!(lookup_key.to_string(), ".id");
+                assert!(target.is_none());
+            }
+            other => panic!("expected Join, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn inner_join_with_target() {
+        match first_stmt("inner join users on .user.id = .id as .user") {
+            Statement::Join { kind, target, .. } => {
+                assert_eq!(kind, JoinKind::Inner);
+                assert_eq!(target.unwrap().to_string(), ".user");
+            }
+            other => panic!("expected Join, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_join_without_on() {
+        let err = parse_err("join users .user_id = .id");
+        assert!(err.to_string().contains("expected 'on'"), "err: {err}");
+    }
+
+    #[test]
+    fn error_join_inside_block() {
+        let err = parse_err("when .a { join users on .id = .id }");
+        assert!(err.to_string().contains("top level"), "err: {err}");
+    }
+
+    // -----------------------------------------------------------------------
+    // aggregate
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn aggregate_by_with_comma_separated_fields() {
+        match first_stmt("aggregate by .country, .city { set .n = count(), set .t = sum(.total) }")
+        {
+            Statement::Aggregate { keys, fields, .. } => {
+                assert_eq!(keys.len(), 2);
+                assert_eq!(fields.len(), 2);
+                assert_eq!(
+                    fields[1].path.segments,
+                    vec![PathSegment::Field("t".into())]
+                );
+            }
+            other => panic!("expected Aggregate, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn aggregate_without_keys_multiline() {
+        match first_stmt("aggregate {\n  set .n = count()\n  set .m = max(.x)\n}") {
+            Statement::Aggregate { keys, fields, .. } => {
+                assert!(keys.is_empty());
+                assert_eq!(fields.len(), 2);
+            }
+            other => panic!("expected Aggregate, got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_aggregate_non_set_entry() {
+        let err = parse_err("aggregate by .a { drop .b }");
+        match err {
+            error::MorphError::Mapping { message, .. } => {
+                assert!(message.contains("only contain 'set'"), "msg: {message}");
+            }
+            other => panic!("expected Mapping error, got: {other:?}"),
+        }
+    }
 }
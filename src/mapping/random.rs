@@ -0,0 +1,179 @@
+use crate::value::Value;
+use std::cell::RefCell;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// A small, fast pseudo-random generator (SplitMix64).
+///
+/// The same seed always yields the same sequence, which keeps `--seed` runs
+/// reproducible. It is not suitable for cryptography.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    /// A generator seeded differently on every run.
+    pub fn from_entropy() -> Self {
+        Rng::new(RandomState::new().build_hasher().finish())
+    }
+
+    /// A generator from `seed`, or from entropy when there is none.
+    pub fn from_seed(seed: Option<u64>) -> Self {
+        seed.map_or_else(Rng::from_entropy, Rng::new)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`. `n` must be greater than zero.
+    pub fn below(&mut self, n: u64) -> u64 {
+        ((u128::from(self.next_u64()) * u128::from(n)) >> 64) as u64
+    }
+}
+
+thread_local! {
+    /// The generator the random mapping functions draw from.
+    static CURRENT: RefCell<Option<Rng>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with `rng` as the source for the random mapping functions
+/// (`random_int`, `random_choice`, `shuffle` and `fake`), then
+/// store the advanced state back in `rng`.
+///
+/// Evaluating a program with a seed (`--seed`) installs a generator seeded
+/// from it. Otherwise the functions use whatever generator the caller
+/// installed, so embedders and tests can wrap evaluation to get fixed output.
+pub fn with_rng<T>(rng: &mut Rng, f: impl FnOnce() -> T) -> T {
+    let previous = CURRENT.with(|current| current.replace(Some(rng.clone())));
+    let result = f();
+    if let Some(advanced) = CURRENT.with(|current| current.replace(previous)) {
+        *rng = advanced;
+    }
+    result
+}
+
+/// Call `f` with the installed generator, or with one seeded from entropy
+/// when nothing is installed.
+pub(crate) fn with_current<T>(f: impl FnOnce(&mut Rng) -> T) -> T {
+    CURRENT.with(|current| f(current.borrow_mut().get_or_insert_with(Rng::from_entropy)))
+}
+
+/// A uniform random sample of up to `count` records (reservoir sampling),
+/// for `sample`. Memory is bounded by `count`, and the kept records are
+/// returned in input order.
+#[derive(Debug, Clone)]
+pub(crate) struct Reservoir {
+    count: usize,
+    seen: usize,
+    records: Vec<(usize, Value)>,
+    rng: Rng,
+}
+
+impl Reservoir {
+    pub(crate) fn new(count: usize, rng: Rng) -> Self {
+        Reservoir {
+            count,
+            seen: 0,
+            records: Vec::with_capacity(count.min(1024)),
+            rng,
+        }
+    }
+
+    pub(crate) fn add(&mut self, record: Value) {
+        if self.records.len() < self.count {
+            self.records.push((self.seen, record));
+        } else {
+            let slot = self.rng.below(self.seen as u64 + 1) as usize;
+            if slot < self.count {
+                self.records[slot] = (self.seen, record);
+            }
+        }
+        self.seen += 1;
+    }
+
+    pub(crate) fn drain(&mut self) -> Vec<Value> {
+        let mut kept = std::mem::take(&mut self.records);
+        kept.sort_by_key(|(seq, _)| *seq);
+        kept.into_iter().map(|(_, record)| record).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+    }
+
+    #[test]
+    fn below_stays_in_range() {
+        let mut rng = Rng::new(7);
+        let mut hits = [0; 5];
+        for _ in 0..5000 {
+            hits[rng.below(5) as usize] += 1;
+        }
+        assert!(hits.iter().all(|&h| h > 800), "hits: {hits:?}");
+    }
+
+    #[test]
+    fn with_rng_advances_and_restores() {
+        let mut outer = Rng::new(1);
+        let mut inner = Rng::new(2);
+        let first = with_rng(&mut outer, || {
+            let nested = with_rng(&mut inner, || with_current(|rng| rng.next_u64()));
+            assert_eq!(nested, Rng::new(2).next_u64());
+            with_current(|rng| rng.next_u64())
+        });
+        assert_eq!(first, Rng::new(1).next_u64());
+        // Both generators moved on by one draw.
+        let mut expected = Rng::new(1);
+        expected.next_u64();
+        assert_eq!(outer.next_u64(), expected.next_u64());
+        let mut expected = Rng::new(2);
+        expected.next_u64();
+        assert_eq!(inner.next_u64(), expected.next_u64());
+    }
+
+    #[test]
+    fn reservoir_keeps_input_order() {
+        let mut reservoir = Reservoir::new(3, Rng::new(1));
+        for i in 0..100 {
+            reservoir.add(Value::Int(i));
+        }
+        let kept: Vec<i64> = reservoir
+            .drain()
+            .into_iter()
+            .map(|v| match v {
+                Value::Int(i) => i,
+                other => panic!("expected int, got: {other:?}"),
+            })
+            .collect();
+        assert_eq!(kept.len(), 3);
+        assert!(kept.windows(2).all(|w| w[0] < w[1]), "kept: {kept:?}");
+    }
+
+    #[test]
+    fn reservoir_smaller_input_keeps_everything() {
+        let mut reservoir = Reservoir::new(10, Rng::new(1));
+        reservoir.add(Value::Int(1));
+        reservoir.add(Value::Int(2));
+        assert_eq!(reservoir.drain(), vec![Value::Int(1), Value::Int(2)]);
+    }
+}
@@ -6,6 +6,7 @@
 use crate::cli::{Cli, Format};
 use crate::error;
 use crate::mapping;
+use crate::mapping::eval::{EvalOptions, RecordEvaluator};
 use crate::value::Value;
 use std::io::{self, BufRead, BufReader, Read, Write};
 
@@ -139,8 +140,10 @@ fn csv_field_to_string(value: &Value) -> String {
         Value::Bool(b) => b.to_string(),
         Value::Int(i) => i.to_string(),
         Value::Float(f) => f.to_string(),
+        Value::Decimal(d) => crate::value::decimal_to_text(d),
         Value::String(s) => s.clone(),
-        Value::Bytes(b) => b.iter().map(|byte| format!("{byte:02x}")).collect(),
+        Value::Bytes(b) => crate::formats::bytes_to_base64(b),
+        Value::DateTime(dt) => dt.to_string(),
         Value::Array(_) | Value::Map(_) => {
             let json = crate::formats::json::value_to_json(value);
             serde_json::to_string(&json).unwrap_or_default()
@@ -164,16 +167,79 @@ fn csv_escape_record(fields: &[String], delimiter: u8) -> String {
         .join(&delim.to_string())
 }
 
+/// Build the per-record evaluator for an optional mapping program.
+fn record_evaluator<'a>(
+    mapping_program: Option<(&'a mapping::ast::Program, &'a EvalOptions)>,
+) -> error::Result<Option<RecordEvaluator<'a>>> {
+    mapping_program
+        .map(|(program, options)| RecordEvaluator::new(program, options))
+        .transpose()
+}
+
+/// Has the mapping (if any) seen all the input it needs? True once a
+/// `limit` is exhausted, so the caller can stop reading early.
+fn input_done(evaluator: &Option<RecordEvaluator>) -> bool {
+    evaluator.as_ref().is_some_and(RecordEvaluator::is_done)
+}
+
+/// Apply the mapping (if any) to one input record and write every resulting
+/// record. Returns how many records were written.
+fn write_mapped<W: Write>(
+    writer: &mut StreamWriter<W>,
+    evaluator: &mut Option<RecordEvaluator>,
+    value: Value,
+) -> error::Result<usize> {
+    let records = match evaluator {
+        Some(evaluator) => evaluator.push(&value)?,
+        None => vec![value],
+    };
+    for record in &records {
+        writer.write_element(record)?;
+    }
+    Ok(records.len())
+}
+
+/// Write the records a mapping held back until the end of input, such as
+/// `aggregate` groups. Returns how many records were written.
+fn finish_mapped<W: Write>(
+    writer: &mut StreamWriter<W>,
+    evaluator: &mut Option<RecordEvaluator>,
+) -> error::Result<usize> {
+    let records = match evaluator {
+        Some(evaluator) => evaluator.finish()?,
+        None => Vec::new(),
+    };
+    for record in &records {
+        writer.write_element(record)?;
+    }
+    Ok(records.len())
+}
+
 /// Stream JSONL input: read line by line, apply mapping, write to output.
 pub fn stream_jsonl<R: Read, W: Write>(
     reader: R,
     writer: &mut StreamWriter<W>,
-    mapping_program: Option<&mapping::ast::Program>,
+    mapping_program: Option<(&mapping::ast::Program, &EvalOptions)>,
+) -> error::Result<usize> {
+    let mut evaluator = record_evaluator(mapping_program)?;
+    let count = feed_jsonl(reader, writer, &mut evaluator, InputOptions::default())?;
+    Ok(count + finish_mapped(writer, &mut evaluator)?)
+}
+
+/// Read the records of one JSONL input into `evaluator`.
+fn feed_jsonl<R: Read, W: Write>(
+    reader: R,
+    writer: &mut StreamWriter<W>,
+    evaluator: &mut Option<RecordEvaluator>,
+    input: InputOptions,
 ) -> error::Result<usize> {
     let buf_reader = BufReader::new(reader);
     let mut count = 0;
 
     for (line_num, line) in buf_reader.lines().enumerate() {
+        if input_done(evaluator) {
+            break;
+        }
         let line = line?;
         let trimmed = line.trim();
         if trimmed.is_empty() {
@@ -186,16 +252,9 @@ pub fn stream_jsonl<R: Read, W: Write>(
                 e.column(),
             )
         })?;
-        let mut value = crate::formats::json::json_to_value(json_val);
-
-        if let Some(program) = mapping_program {
-            value = mapping::eval::eval(program, &value)?;
-        }
-
-        writer.write_element(&value)?;
-        count += 1;
+        let value = crate::formats::json::json_to_value(json_val);
+        count += write_mapped(writer, evaluator, input.tag(value))?;
     }
-
     Ok(count)
 }
 
@@ -203,7 +262,26 @@ pub fn stream_jsonl<R: Read, W: Write>(
 pub fn stream_csv<R: Read, W: Write>(
     reader: R,
     writer: &mut StreamWriter<W>,
-    mapping_program: Option<&mapping::ast::Program>,
+    mapping_program: Option<(&mapping::ast::Program, &EvalOptions)>,
+    csv_config: &crate::formats::csv::CsvConfig,
+) -> error::Result<usize> {
+    let mut evaluator = record_evaluator(mapping_program)?;
+    let count = feed_csv(
+        reader,
+        writer,
+        &mut evaluator,
+        InputOptions::default(),
+        csv_config,
+    )?;
+    Ok(count + finish_mapped(writer, &mut evaluator)?)
+}
+
+/// Read the rows of one CSV input into `evaluator`.
+fn feed_csv<R: Read, W: Write>(
+    reader: R,
+    writer: &mut StreamWriter<W>,
+    evaluator: &mut Option<RecordEvaluator>,
+    input: InputOptions,
     csv_config: &crate::formats::csv::CsvConfig,
 ) -> error::Result<usize> {
     let mut rdr = csv::ReaderBuilder::new()
@@ -226,6 +304,9 @@ pub fn stream_csv<R: Read, W: Write>(
 
     let mut count = 0;
     for result in rdr.records() {
+        if input_done(evaluator) {
+            break;
+        }
         let record = result?;
         let value = if let Some(ref headers) = headers {
             let mut map = indexmap::IndexMap::new();
@@ -242,16 +323,8 @@ pub fn stream_csv<R: Read, W: Write>(
             Value::Array(row)
         };
 
-        let value = if let Some(program) = mapping_program {
-            mapping::eval::eval(program, &value)?
-        } else {
-            value
-        };
-
-        writer.write_element(&value)?;
-        count += 1;
+        count += write_mapped(writer, evaluator, input.tag(value))?;
     }
-
     Ok(count)
 }
 
@@ -268,6 +341,9 @@ fn parse_csv_field(field: &str) -> Value {
     if let Ok(i) = field.parse::<i64>() {
         return Value::Int(i);
     }
+    if let Some(d) = crate::formats::exact_number(field) {
+        return Value::Decimal(d);
+    }
     if let Ok(f) = field.parse::<f64>() {
         return Value::Float(f);
     }
@@ -283,7 +359,19 @@ fn parse_csv_field(field: &str) -> Value {
 pub fn stream_json_array<R: Read, W: Write>(
     reader: R,
     writer: &mut StreamWriter<W>,
-    mapping_program: Option<&mapping::ast::Program>,
+    mapping_program: Option<(&mapping::ast::Program, &EvalOptions)>,
+) -> error::Result<usize> {
+    let mut evaluator = record_evaluator(mapping_program)?;
+    let count = feed_json_array(reader, writer, &mut evaluator, InputOptions::default())?;
+    Ok(count + finish_mapped(writer, &mut evaluator)?)
+}
+
+/// Read the elements of one JSON array input into `evaluator`.
+fn feed_json_array<R: Read, W: Write>(
+    reader: R,
+    writer: &mut StreamWriter<W>,
+    evaluator: &mut Option<RecordEvaluator>,
+    input: InputOptions,
 ) -> error::Result<usize> {
     let mut buf = String::new();
     let mut buf_reader = BufReader::new(reader);
@@ -300,6 +388,7 @@ pub fn stream_json_array<R: Read, W: Write>(
 
     let arr = match json_val {
         serde_json::Value::Array(a) => a,
+        other if input.wrap_single => vec![other],
         _ => {
             return Err(error::MorphError::format(
                 "JSON streaming requires an array at the top level",
@@ -309,35 +398,31 @@ pub fn stream_json_array<R: Read, W: Write>(
 
     let mut count = 0;
     for json_elem in arr {
-        let mut value = crate::formats::json::json_to_value(json_elem);
-
-        if let Some(program) = mapping_program {
-            value = mapping::eval::eval(program, &value)?;
+        if input_done(evaluator) {
+            break;
         }
-
-        writer.write_element(&value)?;
-        count += 1;
+        let value = crate::formats::json::json_to_value(json_elem);
+        count += write_mapped(writer, evaluator, input.tag(value))?;
     }
-
     Ok(count)
 }
 
 /// Run the streaming pipeline.
+///
+/// With `--concat`, the inputs are read one after another into the same
+/// mapping and output, so `limit`, `aggregate` and the like see a single
+/// stream of records.
 pub fn run_streaming(
     cli: &Cli,
     in_fmt: Format,
     out_fmt: Format,
-    mapping_program: Option<&mapping::ast::Program>,
+    mapping_program: Option<(&mapping::ast::Program, &EvalOptions)>,
 ) -> error::Result<()> {
-    let input: Box<dyn Read> = match &cli.input {
-        Some(path) => {
-            let file = std::fs::File::open(path).map_err(|e| {
-                error::MorphError::Io(io::Error::new(e.kind(), format!("{}: {e}", path.display())))
-            })?;
-            Box::new(file)
-        }
-        None => Box::new(io::stdin()),
-    };
+    if cli.input.len() > 1 && !cli.concat {
+        return Err(error::MorphError::cli(
+            "streaming several inputs requires --concat",
+        ));
+    }
 
     let output: Box<dyn Write> = match &cli.output {
         Some(path) => {
@@ -351,32 +436,92 @@ pub fn run_streaming(
 
     let csv_config = cli.csv_config();
     let mut writer = StreamWriter::new(output, out_fmt, csv_config.clone());
+    let mut evaluator = record_evaluator(mapping_program)?;
 
     writer.begin()?;
 
-    match in_fmt {
-        Format::Jsonl => {
-            stream_jsonl(input, &mut writer, mapping_program)?;
-        }
-        Format::Csv => {
-            stream_csv(input, &mut writer, mapping_program, &csv_config)?;
-        }
-        Format::Json => {
-            stream_json_array(input, &mut writer, mapping_program)?;
-        }
-        _ => {
-            return Err(error::MorphError::format(format!(
-                "streaming input not supported for {}",
-                in_fmt
-            )));
-        }
+    if cli.input.is_empty() {
+        let input = InputOptions {
+            source: cli.with_source.then_some("-"),
+            wrap_single: cli.concat,
+        };
+        feed_input(
+            io::stdin(),
+            in_fmt,
+            &mut writer,
+            &mut evaluator,
+            input,
+            &csv_config,
+        )?;
+    }
+    for path in &cli.input {
+        if input_done(&evaluator) {
+            break;
+        }
+        let file = std::fs::File::open(path).map_err(|e| {
+            error::MorphError::Io(io::Error::new(e.kind(), format!("{}: {e}", path.display())))
+        })?;
+        let source = path.display().to_string();
+        let input = InputOptions {
+            source: cli.with_source.then_some(source.as_str()),
+            wrap_single: cli.concat,
+        };
+        let format = cli.input_format_for(path)?;
+        feed_input(
+            file,
+            format,
+            &mut writer,
+            &mut evaluator,
+            input,
+            &csv_config,
+        )?;
     }
 
+    finish_mapped(&mut writer, &mut evaluator)?;
     writer.end()?;
 
     Ok(())
 }
 
+/// How the records of one streamed input are read.
+#[derive(Debug, Clone, Copy, Default)]
+struct InputOptions<'a> {
+    /// Name stored in each map record's `$source` field (`--with-source`).
+    source: Option<&'a str>,
+    /// Read a JSON document that is not an array as a single record, as
+    /// `--concat` does.
+    wrap_single: bool,
+}
+
+impl InputOptions<'_> {
+    /// `value` with its `$source` field added when one is wanted.
+    fn tag(&self, mut value: Value) -> Value {
+        if let (Some(source), Value::Map(map)) = (self.source, &mut value) {
+            map.insert("$source".to_string(), Value::String(source.to_string()));
+        }
+        value
+    }
+}
+
+/// Read one input in `format` into `evaluator`.
+fn feed_input<R: Read, W: Write>(
+    reader: R,
+    format: Format,
+    writer: &mut StreamWriter<W>,
+    evaluator: &mut Option<RecordEvaluator>,
+    input: InputOptions,
+    csv_config: &crate::formats::csv::CsvConfig,
+) -> error::Result<usize> {
+    match format {
+        Format::Jsonl => feed_jsonl(reader, writer, evaluator, input),
+        Format::Csv => feed_csv(reader, writer, evaluator, input, csv_config),
+        Format::Json => feed_json_array(reader, writer, evaluator, input),
+        _ => Err(error::MorphError::format(format!(
+            "streaming input not supported for {format}"
+        ))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -599,7 +744,12 @@ mod tests {
         {
             let mut writer = StreamWriter::new(&mut output, Format::Jsonl, csv_config);
             writer.begin().unwrap();
-            stream_jsonl(&input[..], &mut writer, Some(&program)).unwrap();
+            stream_jsonl(
+                &input[..],
+                &mut writer,
+                Some((&program, &EvalOptions::default())),
+            )
+            .unwrap();
             writer.end().unwrap();
         }
         let result = String::from_utf8(output).unwrap();
@@ -620,7 +770,13 @@ mod tests {
         {
             let mut writer = StreamWriter::new(&mut output, Format::Jsonl, csv_config.clone());
             writer.begin().unwrap();
-            stream_csv(&input[..], &mut writer, Some(&program), &csv_config).unwrap();
+            stream_csv(
+                &input[..],
+                &mut writer,
+                Some((&program, &EvalOptions::default())),
+                &csv_config,
+            )
+            .unwrap();
             writer.end().unwrap();
         }
         let result = String::from_utf8(output).unwrap();
@@ -631,6 +787,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn stream_jsonl_with_explode() {
+        let input = b"{\"id\":1,\"items\":[\"a\",\"b\"]}\n{\"id\":2,\"items\":[\"c\"]}\n";
+        let program =
+            crate::mapping::parser::parse_str("explode .items as .item\nrename .item -> .sku")
+                .unwrap();
+
+        let mut output = Vec::new();
+        let csv_config = crate::formats::csv::CsvConfig::default();
+        let count;
+        {
+            let mut writer = StreamWriter::new(&mut output, Format::Csv, csv_config);
+            writer.begin().unwrap();
+            count = stream_jsonl(
+                &input[..],
+                &mut writer,
+                Some((&program, &EvalOptions::default())),
+            )
+            .unwrap();
+            writer.end().unwrap();
+        }
+        assert_eq!(count, 3);
+        let result = String::from_utf8(output).unwrap();
+        assert_eq!(result, "id,sku\n1,a\n1,b\n2,c\n");
+    }
+
     // -----------------------------------------------------------------------
     // StreamWriter unit tests
     // -----------------------------------------------------------------------
@@ -1,15 +1,29 @@
+pub use bigdecimal::BigDecimal;
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
 use indexmap::IndexMap;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
 /// Universal Value type — the internal representation all formats normalize to.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// `Value` is `Eq`, `Ord` and `Hash`, so it can key a `HashMap` or `BTreeMap`
+/// and any mix of values sorts deterministically. Equality is structural
+/// (`Int(1) != Float(1.0)`), except that NaN equals itself, `0.0` equals
+/// `-0.0`, decimals ignore trailing zeros and maps ignore key order. See the
+/// [`Ord`] impl for the ordering.
+#[derive(Debug, Clone)]
 pub enum Value {
     Null,
     Bool(bool),
     Int(i64),
     Float(f64),
+    /// An exact decimal number, also used for integers outside the `i64` range.
+    Decimal(BigDecimal),
     String(String),
     Bytes(Vec<u8>),
+    DateTime(DateTime),
     Array(Vec<Value>),
     Map(IndexMap<String, Value>),
 }
@@ -32,6 +46,7 @@ impl fmt::Display for Value {
                     write!(f, "{n}")
                 }
             }
+            Value::Decimal(d) => f.write_str(&decimal_to_text(d)),
             Value::String(s) => write!(f, "\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
             Value::Bytes(b) => {
                 write!(f, "b\"")?;
@@ -40,6 +55,7 @@ impl fmt::Display for Value {
                 }
                 write!(f, "\"")
             }
+            Value::DateTime(dt) => write!(f, "{dt}"),
             Value::Array(arr) => {
                 write!(f, "[")?;
                 for (i, v) in arr.iter().enumerate() {
@@ -69,6 +85,396 @@ impl fmt::Display for Value {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Hash
+// ---------------------------------------------------------------------------
+
+/// Hashing agrees with `PartialEq`: values that compare equal hash alike.
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Value::Null => {}
+            Value::Bool(b) => b.hash(state),
+            Value::Int(n) => n.hash(state),
+            // 0.0 and -0.0 compare equal, as do all NaNs, so they must hash
+            // alike.
+            Value::Float(n) => {
+                let n = if *n == 0.0 {
+                    0.0
+                } else if n.is_nan() {
+                    f64::NAN
+                } else {
+                    *n
+                };
+                n.to_bits().hash(state);
+            }
+            Value::Decimal(d) => d.hash(state),
+            Value::String(s) => s.hash(state),
+            Value::Bytes(b) => b.hash(state),
+            Value::DateTime(dt) => dt.hash(state),
+            Value::Array(arr) => arr.hash(state),
+            Value::Map(map) => {
+                // Map equality ignores key order, so the entries are combined
+                // with an order-independent sum.
+                let mut sum = 0u64;
+                for (k, v) in map {
+                    let mut entry = DefaultHasher::new();
+                    k.hash(&mut entry);
+                    v.hash(&mut entry);
+                    sum = sum.wrapping_add(entry.finish());
+                }
+                map.len().hash(state);
+                sum.hash(state);
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Equality and ordering
+// ---------------------------------------------------------------------------
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            // Unlike `f64`, NaN equals itself so that `Value` can be `Eq`.
+            (Value::Float(a), Value::Float(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (Value::Decimal(a), Value::Decimal(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::DateTime(a), Value::DateTime(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+/// A total order over all values. Values of different types order by type:
+/// null, bool, number, string, bytes, datetime, array, map. Within a type:
+///
+/// - Numbers compare by value across `Int`, `Float` and `Decimal`, a float
+///   counting as its shortest decimal text. Negative infinity sorts first and
+///   NaN last. Equal numbers of different types order int, float, decimal.
+/// - Strings and bytes compare lexicographically, arrays element by element.
+/// - Datetimes use [`DateTime`]'s order.
+/// - Maps compare as their entries sorted by key, since equality ignores key
+///   order.
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Null, Value::Null) => Ordering::Equal,
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (Value::Int(a), Value::Int(b)) => a.cmp(b),
+            (Value::Float(a), Value::Float(b)) => compare_floats(*a, *b),
+            (Value::Decimal(a), Value::Decimal(b)) => a.cmp(b),
+            (
+                Value::Int(_) | Value::Float(_) | Value::Decimal(_),
+                Value::Int(_) | Value::Float(_) | Value::Decimal(_),
+            ) => NumberKey::of(self)
+                .cmp(&NumberKey::of(other))
+                .then_with(|| self.type_rank().cmp(&other.type_rank())),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::Bytes(a), Value::Bytes(b)) => a.cmp(b),
+            (Value::DateTime(a), Value::DateTime(b)) => a.cmp(b),
+            (Value::Array(a), Value::Array(b)) => a.cmp(b),
+            (Value::Map(a), Value::Map(b)) => sorted_entries(a).cmp(&sorted_entries(b)),
+            _ => self.type_rank().cmp(&other.type_rank()),
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Value {
+    /// Position of the variant in the canonical order. Numbers share a rank
+    /// in the tens so that they sort together.
+    fn type_rank(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Bool(_) => 10,
+            Value::Int(_) => 20,
+            Value::Float(_) => 21,
+            Value::Decimal(_) => 22,
+            Value::String(_) => 30,
+            Value::Bytes(_) => 40,
+            Value::DateTime(_) => 50,
+            Value::Array(_) => 60,
+            Value::Map(_) => 70,
+        }
+    }
+}
+
+/// Floats in the canonical order: `0.0 == -0.0`, and NaN sorts above every
+/// other float and equal to itself.
+fn compare_floats(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+    }
+}
+
+/// A number placed on one scale for comparing across numeric types.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum NumberKey {
+    NegInfinity,
+    Finite(BigDecimal),
+    Infinity,
+    NaN,
+}
+
+impl NumberKey {
+    fn of(value: &Value) -> NumberKey {
+        match value {
+            Value::Int(i) => NumberKey::Finite((*i).into()),
+            Value::Decimal(d) => NumberKey::Finite(d.clone()),
+            Value::Float(f) if f.is_nan() => NumberKey::NaN,
+            Value::Float(f) => match decimal_from_f64(*f) {
+                Some(d) => NumberKey::Finite(d),
+                None if *f < 0.0 => NumberKey::NegInfinity,
+                None => NumberKey::Infinity,
+            },
+            _ => unreachable!("NumberKey of a non-number"),
+        }
+    }
+}
+
+fn sorted_entries(map: &IndexMap<String, Value>) -> Vec<(&String, &Value)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+// ---------------------------------------------------------------------------
+// DateTime
+// ---------------------------------------------------------------------------
+
+/// A date and/or time read from a format with a native temporal type (TOML
+/// datetimes, YAML timestamps, the MessagePack timestamp extension).
+///
+/// Two `Offset` values are equal when they denote the same instant, even if
+/// their offsets differ. See the [`Ord`] impl for how kinds are ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DateTime {
+    /// An instant with a UTC offset: `1979-05-27T07:32:00-08:00`.
+    Offset(chrono::DateTime<FixedOffset>),
+    /// A date and wall-clock time with no offset: `1979-05-27T07:32:00`.
+    Local(NaiveDateTime),
+    /// A calendar date: `1979-05-27`.
+    Date(NaiveDate),
+    /// A wall-clock time of day: `07:32:00`.
+    Time(NaiveTime),
+}
+
+impl DateTime {
+    /// Parse an RFC 3339 date-time, date or time. The date and time may also
+    /// be separated by a space, and the offset may be written `+HH`, `+HHMM`
+    /// or `+HH:MM` (with or without a space before it), as YAML allows.
+    pub fn parse(text: &str) -> Option<DateTime> {
+        let text = text.trim();
+        let bytes = text.as_bytes();
+        // Cheap rejection before trying the chrono parsers.
+        if bytes.len() < 8 || !bytes[0].is_ascii_digit() {
+            return None;
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+            return (bytes.len() == 10).then_some(DateTime::Date(date));
+        }
+        if let Ok(time) = NaiveTime::parse_from_str(text, "%H:%M:%S%.f") {
+            return Some(DateTime::Time(time));
+        }
+        if bytes.len() < 19 || !matches!(bytes[10], b'T' | b't' | b' ') {
+            return None;
+        }
+        let (date_time, offset) = split_offset(&text[11..]);
+        let naive = NaiveDateTime::parse_from_str(
+            &format!("{}T{}", &text[..10], date_time.trim_end()),
+            "%Y-%m-%dT%H:%M:%S%.f",
+        )
+        .ok()?;
+        match offset {
+            None => Some(DateTime::Local(naive)),
+            Some(offset) => {
+                let offset = parse_offset(offset)?;
+                naive
+                    .and_local_timezone(offset)
+                    .single()
+                    .map(DateTime::Offset)
+            }
+        }
+    }
+
+    /// Order two date-times. Offset instants compare by instant; a local
+    /// date-time, or a date (at midnight), compares by its wall-clock
+    /// reading, as if it were UTC. Times of day only compare with each other.
+    pub fn compare(&self, other: &DateTime) -> Option<Ordering> {
+        match (self, other) {
+            (DateTime::Time(a), DateTime::Time(b)) => Some(a.cmp(b)),
+            (DateTime::Time(_), _) | (_, DateTime::Time(_)) => None,
+            (DateTime::Offset(a), DateTime::Offset(b)) => Some(a.cmp(b)),
+            _ => Some(self.naive_utc()?.cmp(&other.naive_utc()?)),
+        }
+    }
+
+    fn kind_rank(&self) -> u8 {
+        match self {
+            DateTime::Offset(_) => 0,
+            DateTime::Local(_) => 1,
+            DateTime::Date(_) => 2,
+            DateTime::Time(_) => 3,
+        }
+    }
+
+    fn naive_utc(&self) -> Option<NaiveDateTime> {
+        match self {
+            DateTime::Offset(dt) => Some(dt.naive_utc()),
+            DateTime::Local(dt) => Some(*dt),
+            DateTime::Date(d) => Some(d.and_time(NaiveTime::MIN)),
+            DateTime::Time(_) => None,
+        }
+    }
+}
+
+/// A total order extending [`DateTime::compare`]: times of day sort after
+/// every other kind, and values that `compare` finds equal but are not `==`
+/// (such as a date and a local midnight) order offset, local, date.
+impl Ord for DateTime {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.compare(other)
+            .filter(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| self.kind_rank().cmp(&other.kind_rank()))
+    }
+}
+
+impl PartialOrd for DateTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Split `HH:MM:SS[.f][offset]` into the time and the offset, if any.
+fn split_offset(time: &str) -> (&str, Option<&str>) {
+    if let Some(rest) = time.strip_suffix(['Z', 'z']) {
+        return (rest, Some("Z"));
+    }
+    match time.rfind(['+', '-']) {
+        Some(i) => (&time[..i], Some(time[i..].trim())),
+        None => (time, None),
+    }
+}
+
+fn parse_offset(offset: &str) -> Option<FixedOffset> {
+    if offset == "Z" {
+        return FixedOffset::east_opt(0);
+    }
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let digits: String = offset[1..].chars().filter(|c| *c != ':').collect();
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (
+            digits[..2].parse::<i32>().ok()?,
+            digits[2..].parse::<i32>().ok()?,
+        ),
+        _ => return None,
+    };
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// RFC 3339 text: `Z` for a zero offset, and fractional seconds only when
+/// present.
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DateTime::Offset(dt) => write!(
+                f,
+                "{}",
+                dt.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)
+            ),
+            DateTime::Local(dt) => write!(f, "{}", dt.format("%Y-%m-%dT%H:%M:%S%.f")),
+            DateTime::Date(d) => write!(f, "{}", d.format("%Y-%m-%d")),
+            DateTime::Time(t) => write!(f, "{}", t.format("%H:%M:%S%.f")),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Decimal
+// ---------------------------------------------------------------------------
+
+/// The decimal equal to the shortest text that round-trips `f`, so `0.1_f64`
+/// becomes exactly `0.1`. `None` for NaN and the infinities.
+pub(crate) fn decimal_from_f64(f: f64) -> Option<BigDecimal> {
+    if !f.is_finite() {
+        return None;
+    }
+    f.to_string().parse().ok()
+}
+
+/// `d` as an `i64`, if it is a whole number in range.
+pub(crate) fn decimal_to_i64(d: &BigDecimal) -> Option<i64> {
+    use bigdecimal::ToPrimitive;
+    if d.is_integer() {
+        d.to_i64()
+    } else {
+        None
+    }
+}
+
+/// The most zeros a decimal read from numeric text may imply beyond its
+/// digits. Past this, `1e30000000` would expand into millions of digits, so
+/// such text is read as a float instead.
+pub(crate) const MAX_DECIMAL_PADDING: u64 = 1000;
+
+/// Zeros the plain form of `d` writes beyond its own digits: trailing zeros
+/// for `1.5e300`, leading ones for `1e-300`.
+pub(crate) fn decimal_padding(d: &BigDecimal) -> u64 {
+    let (_, scale) = d.as_bigint_and_scale();
+    if scale < 0 {
+        scale.unsigned_abs()
+    } else {
+        (scale as u64).saturating_sub(d.digits())
+    }
+}
+
+/// Parse decimal text, rejecting exponents that imply more than
+/// [`MAX_DECIMAL_PADDING`] zeros.
+pub(crate) fn parse_decimal(text: &str) -> Option<BigDecimal> {
+    let decimal: BigDecimal = text.parse().ok()?;
+    (decimal_padding(&decimal) <= MAX_DECIMAL_PADDING).then_some(decimal)
+}
+
+/// The text of `d` for writers and strings: plain digits, or scientific
+/// notation (`1.5e300`) when the plain form would pad out many zeros.
+pub(crate) fn decimal_to_text(d: &BigDecimal) -> String {
+    if decimal_padding(d) > 20 {
+        d.to_scientific_notation()
+    } else {
+        d.to_plain_string()
+    }
+}
+
+/// `d` as an `f64`, if the float reads back as the same decimal.
+pub(crate) fn decimal_to_f64_exact(d: &BigDecimal) -> Option<f64> {
+    use bigdecimal::ToPrimitive;
+    let f = d.to_f64()?;
+    (decimal_from_f64(f).as_ref() == Some(d)).then_some(f)
+}
+
 // ---------------------------------------------------------------------------
 // From impls
 // ---------------------------------------------------------------------------
@@ -85,6 +491,12 @@ impl From<f64> for Value {
     }
 }
 
+impl From<BigDecimal> for Value {
+    fn from(v: BigDecimal) -> Self {
+        Value::Decimal(v)
+    }
+}
+
 impl From<bool> for Value {
     fn from(v: bool) -> Self {
         Value::Bool(v)
@@ -109,6 +521,12 @@ impl From<Vec<Value>> for Value {
     }
 }
 
+impl From<DateTime> for Value {
+    fn from(v: DateTime) -> Self {
+        Value::DateTime(v)
+    }
+}
+
 impl From<IndexMap<String, Value>> for Value {
     fn from(v: IndexMap<String, Value>) -> Self {
         Value::Map(v)
@@ -182,6 +600,19 @@ fn parse_path(path: &str) -> Result<Vec<Segment>, String> {
 // Methods
 // ---------------------------------------------------------------------------
 
+/// How [`Value::merge_with`] combines two arrays found at the same place.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ArrayMerge {
+    /// The incoming array replaces the existing one.
+    #[default]
+    Replace,
+    /// The incoming elements are appended to the existing ones.
+    Append,
+    /// Map elements with the same value for this key are deep-merged; other
+    /// incoming elements are appended.
+    ByKey(String),
+}
+
 impl Value {
     /// Deep-merge `other` into `self`.
     ///
@@ -189,12 +620,17 @@ impl Value {
     /// into `self` recursively. For all other variant combinations `other`
     /// simply overwrites `self`.
     pub fn merge(&mut self, other: Value) {
+        self.merge_with(other, &ArrayMerge::Replace);
+    }
+
+    /// Deep-merge `other` into `self`, combining arrays according to `arrays`.
+    pub fn merge_with(&mut self, other: Value, arrays: &ArrayMerge) {
         match (self, other) {
             (Value::Map(ref mut lhs), Value::Map(rhs)) => {
                 for (k, v) in rhs {
                     match lhs.get_mut(&k) {
-                        Some(existing @ Value::Map(_)) if matches!(v, Value::Map(_)) => {
-                            existing.merge(v);
+                        Some(existing @ (Value::Map(_) | Value::Array(_))) => {
+                            existing.merge_with(v, arrays);
                         }
                         _ => {
                             lhs.insert(k, v);
@@ -202,6 +638,28 @@ impl Value {
                     }
                 }
             }
+            (Value::Array(ref mut lhs), Value::Array(rhs)) => match arrays {
+                ArrayMerge::Replace => *lhs = rhs,
+                ArrayMerge::Append => lhs.extend(rhs),
+                ArrayMerge::ByKey(key) => {
+                    for item in rhs {
+                        let id = match &item {
+                            Value::Map(m) => m.get(key).filter(|id| !matches!(id, Value::Null)),
+                            _ => None,
+                        };
+                        let existing = id.and_then(|id| {
+                            lhs.iter_mut().find(|e| match e {
+                                Value::Map(m) => m.get(key) == Some(id),
+                                _ => false,
+                            })
+                        });
+                        match existing {
+                            Some(existing) => existing.merge_with(item, arrays),
+                            None => lhs.push(item),
+                        }
+                    }
+                }
+            },
             (this, other) => {
                 *this = other;
             }
@@ -572,6 +1030,159 @@ mod tests {
         assert_eq!(nested.get("c"), Some(&Value::Int(3)));
     }
 
+    fn server(name: &str, port: i64) -> Value {
+        let mut m = IndexMap::new();
+        m.insert("name".to_string(), Value::String(name.into()));
+        m.insert("port".to_string(), Value::Int(port));
+        Value::Map(m)
+    }
+
+    #[test]
+    fn merge_with_array_strategies() {
+        let base = Value::Array(vec![server("a", 1), server("b", 2)]);
+        let patch = Value::Array(vec![server("b", 20), server("c", 3)]);
+
+        let mut replaced = base.clone();
+        replaced.merge_with(patch.clone(), &ArrayMerge::Replace);
+        assert_eq!(replaced, patch);
+
+        let mut appended = base.clone();
+        appended.merge_with(patch.clone(), &ArrayMerge::Append);
+        assert_eq!(
+            appended,
+            Value::Array(vec![
+                server("a", 1),
+                server("b", 2),
+                server("b", 20),
+                server("c", 3)
+            ])
+        );
+
+        let mut keyed = base;
+        keyed.merge_with(patch, &ArrayMerge::ByKey("name".into()));
+        assert_eq!(
+            keyed,
+            Value::Array(vec![server("a", 1), server("b", 20), server("c", 3)])
+        );
+    }
+
+    #[test]
+    fn merge_with_applies_strategy_to_nested_arrays() {
+        let mut m1 = IndexMap::new();
+        m1.insert("tags".to_string(), Value::Array(vec![Value::Int(1)]));
+        let mut m2 = IndexMap::new();
+        m2.insert("tags".to_string(), Value::Array(vec![Value::Int(2)]));
+
+        let mut v = Value::Map(m1);
+        v.merge_with(Value::Map(m2), &ArrayMerge::Append);
+        assert_eq!(
+            v.get_path(".tags"),
+            Some(&Value::Array(vec![Value::Int(1), Value::Int(2)]))
+        );
+    }
+
+    // -- Hash ---------------------------------------------------------------
+
+    fn hash_of(value: &Value) -> u64 {
+        let mut h = DefaultHasher::new();
+        value.hash(&mut h);
+        h.finish()
+    }
+
+    #[test]
+    fn hash_agrees_with_eq() {
+        assert_eq!(hash_of(&Value::Float(0.0)), hash_of(&Value::Float(-0.0)));
+        assert_ne!(hash_of(&Value::Int(1)), hash_of(&Value::String("1".into())));
+
+        let mut m1 = IndexMap::new();
+        m1.insert("a".to_string(), Value::Int(1));
+        m1.insert("b".to_string(), Value::Int(2));
+        let mut m2 = IndexMap::new();
+        m2.insert("b".to_string(), Value::Int(2));
+        m2.insert("a".to_string(), Value::Int(1));
+        let (v1, v2) = (Value::Map(m1), Value::Map(m2));
+        assert_eq!(v1, v2);
+        assert_eq!(hash_of(&v1), hash_of(&v2));
+    }
+
+    #[test]
+    fn hash_distinguishes_nesting() {
+        let flat = Value::Array(vec![Value::Int(1), Value::Int(2)]);
+        let nested = Value::Array(vec![Value::Array(vec![Value::Int(1)]), Value::Int(2)]);
+        assert_ne!(hash_of(&flat), hash_of(&nested));
+    }
+
+    // -- DateTime -----------------------------------------------------------
+
+    fn dt(text: &str) -> DateTime {
+        DateTime::parse(text).unwrap_or_else(|| panic!("failed to parse {text}"))
+    }
+
+    #[test]
+    fn datetime_parse_variants() {
+        assert!(matches!(
+            dt("2024-01-15T10:30:00+02:00"),
+            DateTime::Offset(_)
+        ));
+        assert!(matches!(dt("2024-01-15T10:30:00"), DateTime::Local(_)));
+        assert!(matches!(dt("2024-01-15"), DateTime::Date(_)));
+        assert!(matches!(dt("10:30:00.5"), DateTime::Time(_)));
+        assert_eq!(DateTime::parse("2024-13-01"), None);
+        assert_eq!(DateTime::parse("hello world"), None);
+        assert_eq!(DateTime::parse("2024-01-15 and more"), None);
+    }
+
+    #[test]
+    fn datetime_parse_rejects_non_digit_offsets() {
+        assert_eq!(DateTime::parse("2024-01-15T10:30:00+a漢"), None);
+        assert_eq!(DateTime::parse("2024-01-15T10:30:00-漢"), None);
+        assert_eq!(DateTime::parse("2024-01-15T10:30:00+1a"), None);
+    }
+
+    #[test]
+    fn datetime_parse_yaml_forms() {
+        assert_eq!(
+            dt("2001-12-14 21:59:43.10 -5").to_string(),
+            "2001-12-14T21:59:43.100-05:00"
+        );
+        assert_eq!(
+            dt("2001-12-14t21:59:43.10-05:00"),
+            dt("2001-12-15T02:59:43.1Z")
+        );
+    }
+
+    #[test]
+    fn datetime_display_is_rfc3339() {
+        assert_eq!(
+            dt("2024-01-15T10:30:00+00:00").to_string(),
+            "2024-01-15T10:30:00Z"
+        );
+        assert_eq!(dt("2024-01-15T10:30:00").to_string(), "2024-01-15T10:30:00");
+        assert_eq!(Value::DateTime(dt("2024-01-15")).to_string(), "2024-01-15");
+    }
+
+    #[test]
+    fn datetime_compare() {
+        use std::cmp::Ordering;
+        let earlier = dt("2024-01-15T10:00:00+02:00");
+        let later = dt("2024-01-15T09:00:00Z");
+        assert_eq!(earlier.compare(&later), Some(Ordering::Less));
+        assert_eq!(dt("2024-01-15").compare(&later), Some(Ordering::Less));
+        assert_eq!(
+            dt("10:00:00").compare(&dt("09:00:00")),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(dt("10:00:00").compare(&dt("2024-01-15")), None);
+    }
+
+    #[test]
+    fn datetime_same_instant_is_equal_and_hashes_alike() {
+        let a = Value::DateTime(dt("2024-01-15T12:00:00+02:00"));
+        let b = Value::DateTime(dt("2024-01-15T10:00:00Z"));
+        assert_eq!(a, b);
+        assert_eq!(hash_of(&a), hash_of(&b));
+    }
+
     // -- get_path -----------------------------------------------------------
 
     #[test]
@@ -722,4 +1333,150 @@ mod tests {
         let v = Value::Map(m);
         assert_eq!(v.to_string(), r#"{"z": 1, "a": 2, "m": 3}"#);
     }
+
+    // -- Decimal ------------------------------------------------------------
+
+    fn dec(text: &str) -> BigDecimal {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn decimal_from_float_uses_shortest_text() {
+        assert_eq!(decimal_from_f64(0.1), Some(dec("0.1")));
+        assert_eq!(decimal_from_f64(-2.5), Some(dec("-2.5")));
+        assert_eq!(decimal_from_f64(f64::NAN), None);
+        assert_eq!(decimal_from_f64(f64::INFINITY), None);
+    }
+
+    #[test]
+    fn decimal_narrowing_is_lossless() {
+        assert_eq!(decimal_to_i64(&dec("42")), Some(42));
+        assert_eq!(decimal_to_i64(&dec("42.5")), None);
+        assert_eq!(decimal_to_i64(&dec("12345678901234567890")), None);
+        assert_eq!(decimal_to_f64_exact(&dec("0.10")), Some(0.1));
+        assert_eq!(decimal_to_f64_exact(&dec("0.1000000000000000000001")), None);
+    }
+
+    #[test]
+    fn display_decimal_is_plain() {
+        assert_eq!(Value::Decimal(dec("1e3")).to_string(), "1000");
+        assert_eq!(Value::Decimal(dec("0.30")).to_string(), "0.30");
+        assert_eq!(
+            Value::Decimal(dec("12345678901234567890")).to_string(),
+            "12345678901234567890"
+        );
+    }
+
+    #[test]
+    fn display_decimal_with_large_exponent_is_scientific() {
+        assert_eq!(Value::Decimal(dec("1.5e300")).to_string(), "1.5e300");
+        assert_eq!(Value::Decimal(dec("1e-300")).to_string(), "1e-300");
+        assert_eq!(decimal_padding(&dec("1.5e300")), 299);
+        assert_eq!(decimal_padding(&dec("0.001")), 2);
+    }
+
+    #[test]
+    fn parse_decimal_caps_the_exponent() {
+        assert_eq!(parse_decimal("1.5e300"), Some(dec("1.5e300")));
+        assert_eq!(parse_decimal("1e30000000"), None);
+        assert_eq!(parse_decimal("1e-30000000"), None);
+    }
+
+    #[test]
+    fn decimal_equality_ignores_scale() {
+        assert_eq!(Value::Decimal(dec("1.0")), Value::Decimal(dec("1.00")));
+        assert_eq!(
+            hash_of(&Value::Decimal(dec("1.0"))),
+            hash_of(&Value::Decimal(dec("1.00")))
+        );
+        assert_ne!(Value::Decimal(dec("1")), Value::Int(1));
+    }
+
+    // -- Ordering -----------------------------------------------------------
+
+    #[test]
+    fn types_order_by_rank() {
+        let mut values = vec![
+            Value::Map(IndexMap::new()),
+            Value::String("a".into()),
+            Value::Int(1),
+            Value::Array(vec![]),
+            Value::Bool(true),
+            Value::Null,
+        ];
+        values.sort();
+        assert_eq!(
+            values,
+            vec![
+                Value::Null,
+                Value::Bool(true),
+                Value::Int(1),
+                Value::String("a".into()),
+                Value::Array(vec![]),
+                Value::Map(IndexMap::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn numbers_order_by_value_across_types() {
+        assert!(Value::Int(2) > Value::Float(1.5));
+        assert!(Value::Float(2.5) < Value::Decimal(dec("12345678901234567890")));
+        assert!(Value::Int(9007199254740993) > Value::Float(9007199254740992.0));
+        // Equal numbers of different types still get a fixed order.
+        assert_ne!(Value::Int(1).cmp(&Value::Float(1.0)), Ordering::Equal);
+        assert_eq!(
+            Value::Int(1).cmp(&Value::Float(1.0)),
+            Value::Float(1.0).cmp(&Value::Int(1)).reverse()
+        );
+    }
+
+    #[test]
+    fn nan_equals_itself_and_sorts_last() {
+        assert_eq!(Value::Float(f64::NAN), Value::Float(f64::NAN));
+        assert_eq!(
+            hash_of(&Value::Float(f64::NAN)),
+            hash_of(&Value::Float(-f64::NAN))
+        );
+        assert!(Value::Float(f64::NAN) > Value::Float(f64::INFINITY));
+        assert!(Value::Float(f64::NEG_INFINITY) < Value::Int(i64::MIN));
+    }
+
+    #[test]
+    fn signed_zeros_are_equal() {
+        assert_eq!(Value::Float(0.0), Value::Float(-0.0));
+        assert_eq!(Value::Float(0.0).cmp(&Value::Float(-0.0)), Ordering::Equal);
+        assert_eq!(hash_of(&Value::Float(0.0)), hash_of(&Value::Float(-0.0)));
+    }
+
+    #[test]
+    fn maps_compare_regardless_of_key_order() {
+        let mut a = IndexMap::new();
+        a.insert("x".to_string(), Value::Int(1));
+        a.insert("y".to_string(), Value::Int(2));
+        let mut b = IndexMap::new();
+        b.insert("y".to_string(), Value::Int(2));
+        b.insert("x".to_string(), Value::Int(1));
+        let (a, b) = (Value::Map(a), Value::Map(b));
+        assert_eq!(a, b);
+        assert_eq!(a.cmp(&b), Ordering::Equal);
+        assert_eq!(hash_of(&a), hash_of(&b));
+    }
+
+    #[test]
+    fn values_work_as_set_keys() {
+        use std::collections::{BTreeSet, HashSet};
+        let values = [
+            Value::Int(1),
+            Value::String("1".into()),
+            Value::Float(f64::NAN),
+            Value::Int(1),
+            Value::Float(f64::NAN),
+        ];
+        let hashed: HashSet<&Value> = values.iter().collect();
+        assert_eq!(hashed.len(), 3);
+        let ordered: BTreeSet<&Value> = values.iter().collect();
+        assert_eq!(ordered.len(), 3);
+        assert_eq!(ordered.first(), Some(&&Value::Int(1)));
+    }
 }
@@ -0,0 +1,258 @@
+//! Integration tests for the `aggregate` statement in batch and streaming
+//! mode.
+
+use indexmap::IndexMap;
+use morph::cli::Format;
+use morph::formats::csv::CsvConfig;
+use morph::mapping::{eval, parser};
+use morph::streaming::{self, StreamWriter};
+use morph::value::Value;
+
+fn run(mapping: &str, input: &Value) -> Value {
+    let program = parser::parse_str(mapping).unwrap();
+    eval::eval(&program, input).unwrap()
+}
+
+fn run_err(mapping: &str, input: &Value) -> String {
+    let program = parser::parse_str(mapping).unwrap();
+    eval::eval(&program, input).unwrap_err().to_string()
+}
+
+fn make_map(pairs: &[(&str, Value)]) -> Value {
+    let mut m = IndexMap::new();
+    for (k, v) in pairs {
+        m.insert((*k).to_string(), v.clone());
+    }
+    Value::Map(m)
+}
+
+fn s(v: &str) -> Value {
+    Value::String(v.into())
+}
+
+fn orders() -> Value {
+    let order =
+        |country: &str, total: Value| make_map(&[("country", s(country)), ("total", total)]);
+    Value::Array(vec![
+        order("US", Value::Int(10)),
+        order("DE", Value::Float(5.5)),
+        order("US", Value::Int(20)),
+        order("FR", Value::Null),
+    ])
+}
+
+fn stream(mapping: &str, input: &str) -> String {
+    let program = parser::parse_str(mapping).unwrap();
+    let mut output = Vec::new();
+    {
+        let mut writer = StreamWriter::new(&mut output, Format::Jsonl, CsvConfig::default());
+        writer.begin().unwrap();
+        streaming::stream_jsonl(
+            input.as_bytes(),
+            &mut writer,
+            Some((&program, &eval::EvalOptions::default())),
+        )
+        .unwrap();
+        writer.end().unwrap();
+    }
+    String::from_utf8(output).unwrap()
+}
+
+// ---------------------------------------------------------------------------
+// Batch
+// ---------------------------------------------------------------------------
+
+#[test]
+fn count_and_sum_per_group_in_first_seen_order() {
+    let result = run(
+        "aggregate by .country { set .orders = count(), set .revenue = sum(.total) }",
+        &orders(),
+    );
+    assert_eq!(
+        result,
+        Value::Array(vec![
+            make_map(&[
+                ("country", s("US")),
+                ("orders", Value::Int(2)),
+                ("revenue", Value::Int(30)),
+            ]),
+            make_map(&[
+                ("country", s("DE")),
+                ("orders", Value::Int(1)),
+                ("revenue", Value::Float(5.5)),
+            ]),
+            make_map(&[
+                ("country", s("FR")),
+                ("orders", Value::Int(1)),
+                ("revenue", Value::Int(0)),
+            ]),
+        ])
+    );
+}
+
+#[test]
+fn avg_min_max_skip_nulls() {
+    let result = run(
+        "aggregate by .country {\n  set .avg = avg(.total)\n  set .lo = min(.total)\n  set .hi = max(.total)\n}",
+        &orders(),
+    );
+    assert_eq!(result.get_path("[0].avg"), Some(&Value::Float(15.0)));
+    assert_eq!(result.get_path("[0].lo"), Some(&Value::Int(10)));
+    assert_eq!(result.get_path("[0].hi"), Some(&Value::Int(20)));
+    assert_eq!(result.get_path("[2].avg"), Some(&Value::Null));
+    assert_eq!(result.get_path("[2].lo"), Some(&Value::Null));
+}
+
+#[test]
+fn count_with_argument_counts_non_null() {
+    let result = run(
+        "aggregate { set .rows = count(), set .priced = count(.total) }",
+        &orders(),
+    );
+    assert_eq!(
+        result,
+        Value::Array(vec![make_map(&[
+            ("rows", Value::Int(4)),
+            ("priced", Value::Int(3)),
+        ])])
+    );
+}
+
+#[test]
+fn aggregates_combine_in_expressions() {
+    let result = run(
+        "aggregate by .country { set .mean = sum(.total) / count(), set .label = \"{.country}={count()}\" }",
+        &orders(),
+    );
+    assert_eq!(result.get_path("[0].mean"), Some(&Value::Int(15)));
+    assert_eq!(result.get_path("[1].label"), Some(&s("DE=1")));
+}
+
+#[test]
+fn later_fields_can_read_earlier_ones() {
+    let result = run(
+        "aggregate by .country { set .n = count(), set .many = .n > 1 }",
+        &orders(),
+    );
+    assert_eq!(result.get_path("[0].many"), Some(&Value::Bool(true)));
+    assert_eq!(result.get_path("[1].many"), Some(&Value::Bool(false)));
+}
+
+#[test]
+fn multiple_keys_and_nested_key_paths() {
+    let row =
+        |a: &str, b: i64| make_map(&[("loc", make_map(&[("a", s(a))])), ("b", Value::Int(b))]);
+    let input = Value::Array(vec![row("x", 1), row("x", 1), row("x", 2), row("y", 1)]);
+    let result = run("aggregate by .loc.a, .b { set .n = count() }", &input);
+    match &result {
+        Value::Array(groups) => assert_eq!(groups.len(), 3),
+        other => panic!("expected array, got: {other:?}"),
+    }
+    assert_eq!(result.get_path("[0].loc.a"), Some(&s("x")));
+    assert_eq!(result.get_path("[0].n"), Some(&Value::Int(2)));
+}
+
+#[test]
+fn keys_of_different_types_form_separate_groups() {
+    let input = Value::Array(vec![
+        make_map(&[("k", Value::Int(1))]),
+        make_map(&[("k", s("1"))]),
+    ]);
+    let result = run("aggregate by .k { set .n = count() }", &input);
+    match result {
+        Value::Array(groups) => assert_eq!(groups.len(), 2),
+        other => panic!("expected array, got: {other:?}"),
+    }
+}
+
+#[test]
+fn empty_input_without_keys_yields_one_summary() {
+    let result = run(
+        "aggregate { set .n = count(), set .total = sum(.x), set .avg = avg(.x) }",
+        &Value::Array(vec![]),
+    );
+    assert_eq!(
+        result,
+        Value::Array(vec![make_map(&[
+            ("n", Value::Int(0)),
+            ("total", Value::Int(0)),
+            ("avg", Value::Null),
+        ])])
+    );
+}
+
+#[test]
+fn statements_after_aggregate_see_groups() {
+    let result = run(
+        "aggregate by .country { set .n = count() }\nwhere .n > 1",
+        &orders(),
+    );
+    assert_eq!(
+        result,
+        Value::Array(vec![make_map(&[
+            ("country", s("US")),
+            ("n", Value::Int(2))
+        ])])
+    );
+}
+
+#[test]
+fn error_sum_of_strings() {
+    let msg = run_err(
+        "aggregate by .country { set .x = sum(.country) }",
+        &orders(),
+    );
+    assert!(
+        msg.contains("sum() in aggregate expects numbers"),
+        "msg: {msg}"
+    );
+}
+
+#[test]
+fn error_nested_aggregate() {
+    let msg = run_err("aggregate { set .x = max(count()) }", &orders());
+    assert!(
+        msg.contains("cannot contain another aggregate"),
+        "msg: {msg}"
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Streaming
+// ---------------------------------------------------------------------------
+
+#[test]
+fn streaming_emits_groups_at_end_of_input() {
+    let input = "{\"c\":\"a\",\"v\":1}\n{\"c\":\"b\",\"v\":2}\n{\"c\":\"a\",\"v\":3}\n";
+    let out = stream(
+        "aggregate by .c { set .n = count(), set .sum = sum(.v) }",
+        input,
+    );
+    assert_eq!(
+        out,
+        "{\"c\":\"a\",\"n\":2,\"sum\":4}\n{\"c\":\"b\",\"n\":1,\"sum\":2}\n"
+    );
+}
+
+#[test]
+fn streaming_statements_around_aggregate() {
+    let input = "{\"c\":\"a\",\"v\":1}\n{\"c\":\"b\",\"v\":2}\n{\"c\":\"a\",\"v\":3}\n";
+    let out = stream(
+        "set .v = .v * 10\naggregate by .c { set .sum = sum(.v) }\nrename .sum -> .total",
+        input,
+    );
+    assert_eq!(
+        out,
+        "{\"c\":\"a\",\"total\":40}\n{\"c\":\"b\",\"total\":20}\n"
+    );
+}
+
+#[test]
+fn streaming_explode_then_aggregate() {
+    let input = "{\"id\":1,\"tags\":[\"x\",\"y\"]}\n{\"id\":2,\"tags\":[\"x\"]}\n";
+    let out = stream(
+        "explode .tags as .tag\naggregate by .tag { set .n = count() }",
+        input,
+    );
+    assert_eq!(out, "{\"tag\":\"x\",\"n\":2}\n{\"tag\":\"y\",\"n\":1}\n");
+}
@@ -0,0 +1,128 @@
+//! Integration tests for the `array`, `datetime`, `bytes` and `object` cast
+//! targets and their options.
+
+mod common;
+
+use common::{cli, json, run, run_err};
+use morph::value::{DateTime, Value};
+
+#[test]
+fn array_split_trims_items() {
+    let result = run(
+        "cast .tags as array split \",\"\ncast .empty as array split \",\"\ncast .one as array",
+        r#"{"tags": "red, green ,blue", "empty": "", "one": 7}"#,
+    );
+    assert_eq!(
+        result.get_path(".tags"),
+        Some(&json(r#"["red","green","blue"]"#))
+    );
+    assert_eq!(result.get_path(".empty"), Some(&json("[]")));
+    assert_eq!(result.get_path(".one"), Some(&json("[7]")));
+}
+
+#[test]
+fn datetime_with_format_and_unix_seconds() {
+    let result = run(
+        "cast .day as datetime \"%d/%m/%Y\"\ncast .at as datetime\ncast .epoch as datetime",
+        r#"{"day": "15/01/2024", "at": "2024-01-15T10:30:00Z", "epoch": 1700000000}"#,
+    );
+    assert_eq!(
+        result.get_path(".day"),
+        Some(&Value::DateTime(DateTime::parse("2024-01-15").unwrap()))
+    );
+    assert_eq!(
+        result.get_path(".at"),
+        Some(&Value::DateTime(
+            DateTime::parse("2024-01-15T10:30:00Z").unwrap()
+        ))
+    );
+    assert_eq!(
+        result.get_path(".epoch"),
+        Some(&Value::DateTime(
+            DateTime::parse("2023-11-14T22:13:20Z").unwrap()
+        ))
+    );
+}
+
+#[test]
+fn bytes_decodings() {
+    let result = run(
+        "cast .b64 as bytes base64\ncast .hex as bytes hex\ncast .text as bytes",
+        r#"{"b64": "aGk=", "hex": "0x6869", "text": "hi"}"#,
+    );
+    for path in [".b64", ".hex", ".text"] {
+        assert_eq!(result.get_path(path), Some(&Value::Bytes(b"hi".to_vec())));
+    }
+}
+
+#[test]
+fn object_from_embedded_documents() {
+    let result = run(
+        "cast .meta as object json\ncast .conf as object yaml",
+        r#"{"meta": "{\"a\": 1}", "conf": "b: true\n"}"#,
+    );
+    assert_eq!(result.get_path(".meta"), Some(&json(r#"{"a":1}"#)));
+    assert_eq!(result.get_path(".conf"), Some(&json(r#"{"b":true}"#)));
+}
+
+#[test]
+fn errors_name_the_value_and_path() {
+    let err = run_err("cast .d as datetime \"%d/%m/%Y\"", r#"{"d": "2024-01-15"}"#);
+    assert!(
+        err.contains("\"2024-01-15\"") && err.contains(".d") && err.contains("%d/%m/%Y"),
+        "err: {err}"
+    );
+    let err = run_err("cast .b as bytes base64", r#"{"b": "not base64!"}"#);
+    assert!(
+        err.contains("\"not base64!\"") && err.contains("invalid base64"),
+        "err: {err}"
+    );
+    let err = run_err("cast .m as object", r#"{"m": "[1, 2]"}"#);
+    assert!(err.contains("holds array, not an object"), "err: {err}");
+    let err = run_err("cast .m as array split \",\"", r#"{"m": {"a": 1}}"#);
+    assert!(err.contains("cannot cast map to array at .m"), "err: {err}");
+}
+
+#[test]
+fn or_null_covers_new_targets() {
+    let result = run(
+        "cast .d as datetime or null\ncast .m as object or default .fallback",
+        r#"{"d": "soon", "m": "{", "fallback": "none"}"#,
+    );
+    assert_eq!(
+        result,
+        json(r#"{"d": null, "m": "none", "fallback": "none"}"#)
+    );
+}
+
+#[test]
+fn cli_splits_csv_tags() {
+    let out = cli(
+        &[
+            "-f",
+            "csv",
+            "-t",
+            "jsonl",
+            "--stream",
+            "-e",
+            "cast .tags as array split \";\"",
+        ],
+        "id,tags\n1,a;b\n2,\n",
+    );
+    assert_eq!(
+        out,
+        "{\"id\":1,\"tags\":[\"a\",\"b\"]}\n{\"id\":2,\"tags\":[]}\n"
+    );
+}
+
+#[test]
+fn datetime_and_bytes_cast_back_to_string() {
+    let result = run(
+        "cast .d as datetime \"%d/%m/%Y\"\ncast .d as string\ncast .b as bytes base64\ncast .b as string\ncast .raw as bytes hex\ncast .raw as string",
+        r#"{"d": "15/01/2024", "b": "aGk=", "raw": "ff00"}"#,
+    );
+    assert_eq!(
+        result,
+        json(r#"{"d": "2024-01-15", "b": "hi", "raw": "/wA="}"#)
+    );
+}
@@ -0,0 +1,99 @@
+//! Integration tests for the collection functions: reshaping arrays and maps
+//! and summarising numbers inside expressions.
+
+use morph::mapping::{eval, parser};
+use morph::value::Value;
+
+fn json(text: &str) -> Value {
+    morph::formats::json::from_str(text).unwrap()
+}
+
+fn run(mapping: &str, input: &str) -> String {
+    let program = parser::parse_str(mapping).unwrap();
+    let result = eval::eval(&program, &json(input)).unwrap();
+    morph::formats::json::to_string(&result).unwrap()
+}
+
+fn run_err(mapping: &str, input: &str) -> String {
+    let program = parser::parse_str(mapping).unwrap();
+    eval::eval(&program, &json(input)).unwrap_err().to_string()
+}
+
+#[test]
+fn zip_names_and_scores_into_entries() {
+    let out = run(
+        "set .scores = from_entries(zip(.names, .points))\ndrop .names, .points",
+        r#"{"names": ["ann", "bob"], "points": [3, 5]}"#,
+    );
+    assert_eq!(out, r#"{"scores":{"ann":3,"bob":5}}"#);
+}
+
+#[test]
+fn flatten_function_despite_keyword() {
+    let out = run(
+        "set .all = flatten(.groups)\nset .deep = flatten(.groups, 2)",
+        r#"{"groups": [[1, [2]], [3]]}"#,
+    );
+    assert_eq!(
+        out,
+        r#"{"groups":[[1,[2]],[3]],"all":[1,[2],3],"deep":[1,2,3]}"#
+    );
+}
+
+#[test]
+fn merge_pick_and_omit() {
+    let out = run(
+        "set .config = merge(.defaults, .overrides)\nset .public = omit(pick(.user, \"id\", \"name\", \"token\"), \"token\")\nselect .config, .public",
+        r#"{"defaults": {"db": {"host": "localhost", "port": 5432}}, "overrides": {"db": {"host": "prod"}},
+            "user": {"token": "t", "name": "ann", "id": 7, "email": "a@x"}}"#,
+    );
+    assert_eq!(
+        out,
+        r#"{"config":{"db":{"host":"prod","port":5432}},"public":{"id":7,"name":"ann"}}"#
+    );
+}
+
+#[test]
+fn slice_chunk_and_range() {
+    let out = run(
+        "set .last2 = slice(.xs, -2)\nset .pages = chunk(range(1, 6), 2)\nset .at = index_of(.xs, \"c\")",
+        r#"{"xs": ["a", "b", "c"]}"#,
+    );
+    assert_eq!(
+        out,
+        r#"{"xs":["a","b","c"],"last2":["b","c"],"pages":[[1,2],[3,4],[5]],"at":2}"#
+    );
+}
+
+#[test]
+fn statistics_in_expressions() {
+    let out = run(
+        "set .n = count(.ms)\nset .mean = avg(.ms)\nset .p50 = median(.ms)\nset .p95 = percentile(.ms, 95)\ndrop .ms",
+        r#"{"ms": [120, null, 80, 100, 300]}"#,
+    );
+    assert_eq!(out, r#"{"n":4,"mean":150.0,"p50":110.0,"p95":273.0}"#);
+}
+
+#[test]
+fn count_in_aggregate_stays_an_aggregate() {
+    let out = run(
+        "aggregate by .k {\n  set .n = count()\n  set .tags = count(.tags)\n}",
+        r#"[{"k": "a", "tags": 1}, {"k": "a"}, {"k": "b", "tags": 2}]"#,
+    );
+    assert_eq!(
+        out,
+        r#"[{"k":"a","n":2,"tags":1},{"k":"b","n":1,"tags":1}]"#
+    );
+}
+
+#[test]
+fn type_errors_name_the_function() {
+    assert!(run_err("set .x = chunk(.s, 2)", r#"{"s": "abc"}"#)
+        .contains("chunk() expects an array as its first argument, got string"));
+    assert!(run_err("set .x = pick(.s, \"a\")", r#"{"s": [1]}"#)
+        .contains("pick() expects a map as its first argument, got array"));
+    assert!(run_err("set .x = range(\"5\")", "{}").contains("range() end must be an integer"));
+    assert!(run_err("set .x = range(9223372036854775807)", "{}")
+        .contains("range() would produce 9223372036854775807 items, more than the limit"));
+    assert!(run_err("set .x = zip(.a)", r#"{"a": 1}"#).contains("zip() expects arrays, got int"));
+}
@@ -0,0 +1,47 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code, deprecated)]
+
+use assert_cmd::Command;
+use morph::mapping::{eval, parser};
+use morph::value::Value;
+
+pub fn json(text: &str) -> Value {
+    morph::formats::json::from_str(text).unwrap()
+}
+
+/// Evaluate `mapping` against the JSON document `input`.
+pub fn run(mapping: &str, input: &str) -> Value {
+    run_value(mapping, &json(input))
+}
+
+/// Evaluate `mapping` against `input`.
+pub fn run_value(mapping: &str, input: &Value) -> Value {
+    let program = parser::parse_str(mapping).unwrap();
+    eval::eval(&program, input).unwrap()
+}
+
+/// The error from evaluating `mapping` against the JSON document `input`.
+pub fn run_err(mapping: &str, input: &str) -> String {
+    let program = parser::parse_str(mapping).unwrap();
+    eval::eval(&program, &json(input)).unwrap_err().to_string()
+}
+
+pub fn morph() -> Command {
+    Command::cargo_bin("morph").unwrap()
+}
+
+/// Run the CLI with `input` on stdin and return its output, failing the test
+/// if it exits with an error.
+pub fn cli(args: &[&str], input: &str) -> String {
+    let output = morph()
+        .args(args)
+        .write_stdin(input.to_string())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
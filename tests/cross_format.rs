@@ -583,19 +583,19 @@ fn csv_type_inference_on_roundtrip() {
     assert_eq!(original, csv_val);
 }
 
-/// TOML datetime gets converted to string in Universal Value.
+/// A TOML datetime becomes a `DateTime` and is written to JSON as RFC 3339
+/// text and back to TOML as a native datetime.
 #[test]
-fn toml_datetime_to_string() {
+fn toml_datetime_cross_format() {
     let toml_input = "dt = 2024-06-15T10:30:00Z\n";
     let val = toml::from_str(toml_input).unwrap();
-    match val.get_path(".dt") {
-        Some(Value::String(s)) => {
-            assert!(s.contains("2024"));
-            assert!(s.contains("06"));
-            assert!(s.contains("15"));
-        }
-        other => panic!("expected datetime as string, got: {other:?}"),
-    }
+    assert!(matches!(val.get_path(".dt"), Some(Value::DateTime(_))));
+    assert_eq!(
+        json::to_string(&val).unwrap(),
+        r#"{"dt":"2024-06-15T10:30:00Z"}"#
+    );
+    let yaml_val = yaml::from_str(&yaml::to_string(&val).unwrap()).unwrap();
+    assert_eq!(toml::to_string(&yaml_val).unwrap(), toml_input);
 }
 
 // ===========================================================================
@@ -1191,6 +1191,62 @@ mod cli_integration {
         assert!(stdout.contains("to_int("), "expected to_int: {stdout}");
         assert!(stdout.contains("keys("), "expected keys: {stdout}");
         assert!(stdout.contains("if("), "expected if: {stdout}");
+        assert!(
+            stdout.contains("Error handling:"),
+            "expected Error handling: {stdout}"
+        );
+        assert!(stdout.contains("try("), "expected try: {stdout}");
+        assert!(stdout.contains("Random:"), "expected Random: {stdout}");
+        assert!(
+            stdout.contains("random_int("),
+            "expected random_int: {stdout}"
+        );
+        assert!(stdout.contains("fake("), "expected fake: {stdout}");
+        assert!(
+            stdout.contains("Formatting:"),
+            "expected Formatting: {stdout}"
+        );
+        assert!(stdout.contains("pad_left("), "expected pad_left: {stdout}");
+        assert!(
+            stdout.contains("format_number("),
+            "expected format_number: {stdout}"
+        );
+        assert!(stdout.contains("zip("), "expected zip: {stdout}");
+        assert!(stdout.contains("pick("), "expected pick: {stdout}");
+        assert!(
+            stdout.contains("percentile("),
+            "expected percentile: {stdout}"
+        );
+        assert!(
+            stdout.contains("to_decimal("),
+            "expected to_decimal: {stdout}"
+        );
+        assert!(
+            stdout.contains("Embedded documents:"),
+            "expected Embedded documents: {stdout}"
+        );
+        assert!(
+            stdout.contains("parse_json("),
+            "expected parse_json: {stdout}"
+        );
+        assert!(
+            stdout.contains("to_format("),
+            "expected to_format: {stdout}"
+        );
+        assert!(
+            stdout.contains("Hashing and encoding:"),
+            "expected Hashing and encoding: {stdout}"
+        );
+        assert!(stdout.contains("sha256("), "expected sha256: {stdout}");
+        assert!(stdout.contains("uuid_v4()"), "expected uuid_v4(): {stdout}");
+        assert!(
+            stdout.contains("Case conversion:"),
+            "expected Case conversion: {stdout}"
+        );
+        assert!(
+            stdout.contains("snake_case("),
+            "expected snake_case: {stdout}"
+        );
         assert!(
             stdout.contains("String:"),
             "expected String category: {stdout}"
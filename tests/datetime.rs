@@ -0,0 +1,142 @@
+#![allow(deprecated)]
+//! Integration tests for `Value::DateTime`: reading native datetimes,
+//! comparing and sorting them, and writing them back out.
+
+use assert_cmd::Command;
+use morph::formats::{json, msgpack, toml, yaml};
+use morph::mapping::{eval, parser};
+use morph::value::{DateTime, Value};
+
+fn run(mapping: &str, input: &Value) -> Value {
+    let program = parser::parse_str(mapping).unwrap();
+    eval::eval(&program, input).unwrap()
+}
+
+fn morph() -> Command {
+    Command::cargo_bin("morph").unwrap()
+}
+
+const EVENTS_TOML: &str = r#"
+[[events]]
+name = "b"
+at = 2024-03-01T09:00:00+02:00
+
+[[events]]
+name = "a"
+at = 2024-03-01T08:00:00Z
+
+[[events]]
+name = "c"
+at = 2024-02-28T23:00:00-05:00
+"#;
+
+fn names(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .map(|item| match item.get_path(".name") {
+                Some(Value::String(s)) => s.clone(),
+                other => panic!("expected name, got: {other:?}"),
+            })
+            .collect(),
+        other => panic!("expected array, got: {other:?}"),
+    }
+}
+
+#[test]
+fn sort_by_instant_across_offsets() {
+    let events = toml::from_str(EVENTS_TOML).unwrap();
+    let events = events.get_path(".events").unwrap().clone();
+    // b is 07:00Z and a is 08:00Z on March 1st; c is 04:00Z on February 29th.
+    assert_eq!(names(&run("sort .at", &events)), vec!["c", "b", "a"]);
+    assert_eq!(names(&run("sort .at desc", &events)), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn compare_with_string_literal() {
+    let events = toml::from_str(EVENTS_TOML).unwrap();
+    let events = events.get_path(".events").unwrap().clone();
+    let result = run("where .at >= \"2024-03-01T07:30:00Z\"", &events);
+    assert_eq!(names(&result), vec!["a"]);
+    let result = run("where .at < \"2024-03-01\"", &events);
+    assert_eq!(names(&result), vec!["c"]);
+}
+
+#[test]
+fn aggregate_min_and_max_datetimes() {
+    let events = toml::from_str(EVENTS_TOML).unwrap();
+    let events = events.get_path(".events").unwrap().clone();
+    let result = run(
+        "aggregate {\n  set .first = min(.at)\n  set .last = max(.at)\n}",
+        &events,
+    );
+    assert_eq!(
+        json::to_string(&result).unwrap(),
+        r#"[{"first":"2024-02-28T23:00:00-05:00","last":"2024-03-01T08:00:00Z"}]"#
+    );
+}
+
+#[test]
+fn type_of_datetime() {
+    let doc = yaml::from_str("d: 2024-01-15").unwrap();
+    let result = run("set .t = type_of(.d)", &doc);
+    assert_eq!(
+        result.get_path(".t"),
+        Some(&Value::String("datetime".into()))
+    );
+}
+
+#[test]
+fn msgpack_timestamp_to_toml() {
+    let mut map = indexmap::IndexMap::new();
+    let at = DateTime::parse("2024-01-15T10:30:00.5Z").unwrap();
+    map.insert("at".to_string(), Value::DateTime(at));
+    let packed = msgpack::to_bytes(&Value::Map(map)).unwrap();
+    let value = msgpack::from_bytes(&packed).unwrap();
+    assert_eq!(
+        toml::to_string(&value).unwrap(),
+        "at = 2024-01-15T10:30:00.5Z\n"
+    );
+}
+
+#[test]
+fn cli_toml_to_yaml_keeps_native_dates() {
+    let output = morph()
+        .args(["-f", "toml", "-t", "yaml"])
+        .write_stdin("released = 2024-01-15\nupdated = 2024-01-16T08:00:00Z\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "released: 2024-01-15\nupdated: 2024-01-16T08:00:00Z\n"
+    );
+}
+
+#[test]
+fn cli_yaml_to_toml_writes_datetimes() {
+    let output = morph()
+        .args(["-f", "yaml", "-t", "toml"])
+        .write_stdin("released: 2024-01-15\nname: x\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "name = \"x\"\nreleased = 2024-01-15\n"
+    );
+}
+
+#[test]
+fn cli_yaml_with_multibyte_offset_stays_a_string() {
+    let output = morph()
+        .args(["-f", "yaml", "-t", "json"])
+        .write_stdin("a: 2024-01-15T10:30:00+a漢\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"a\":\"2024-01-15T10:30:00+a漢\"}"
+    );
+}
@@ -0,0 +1,83 @@
+//! Integration tests for `Value::Decimal`: exact JSON numbers, big integers,
+//! exact decimal arithmetic and per-format fallbacks.
+
+mod common;
+
+use common::{cli, run};
+use morph::value::Value;
+
+#[test]
+fn big_ids_survive_a_json_round_trip() {
+    let input = r#"{"id":12345678901234567890,"rate":0.123456789012345678901234}"#;
+    let out = cli(&["-f", "json", "-t", "json"], input);
+    assert_eq!(out.trim_end(), input);
+}
+
+#[test]
+fn decimal_arithmetic_is_exact() {
+    let result = run(
+        "cast .a as decimal\nset .total = .a + .b\nset .id = .id + 1",
+        r#"{"a": 0.1, "b": 0.2, "id": 12345678901234567890}"#,
+    );
+    assert_eq!(
+        result.get_path(".total"),
+        Some(&Value::Decimal("0.3".parse().unwrap()))
+    );
+    assert_eq!(
+        result.get_path(".id"),
+        Some(&Value::Decimal("12345678901234567891".parse().unwrap()))
+    );
+}
+
+#[test]
+fn float_arithmetic_is_unchanged() {
+    let result = run("set .total = .a + .b", r#"{"a": 0.1, "b": 0.2}"#);
+    assert_eq!(result.get_path(".total"), Some(&Value::Float(0.1 + 0.2)));
+}
+
+#[test]
+fn sort_and_compare_mixed_numbers() {
+    let result = run(
+        "sort .n\nwhere .n > 1",
+        r#"[{"n": 12345678901234567890}, {"n": 2.5}, {"n": 1}, {"n": 9007199254740993}]"#,
+    );
+    assert_eq!(
+        morph::formats::json::to_string(&result).unwrap(),
+        r#"[{"n":2.5},{"n":9007199254740993},{"n":12345678901234567890}]"#
+    );
+}
+
+#[test]
+fn cli_csv_keeps_exact_digits() {
+    let input = "id,amount\n12345678901234567890,1.10000000000000000001\n";
+    assert_eq!(cli(&["-f", "csv", "-t", "csv"], input), input);
+    assert_eq!(
+        cli(&["-f", "csv", "-t", "jsonl"], input),
+        "{\"id\":12345678901234567890,\"amount\":1.10000000000000000001}\n"
+    );
+}
+
+#[test]
+fn cli_toml_falls_back_to_strings() {
+    let out = cli(
+        &["-f", "json", "-t", "toml", "-e", "cast .price as decimal"],
+        r#"{"price": "19.99", "id": 12345678901234567890}"#,
+    );
+    assert_eq!(out, "id = \"12345678901234567890\"\nprice = 19.99\n");
+}
+
+#[test]
+fn large_exponents_stay_compact() {
+    let out = cli(&["-f", "json", "-t", "json"], r#"{"a":1.5e300}"#);
+    assert_eq!(out.trim_end(), r#"{"a":1.5e+300}"#);
+    let input = "a\n1.5e300\n";
+    assert_eq!(cli(&["-f", "csv", "-t", "csv"], input), input);
+}
+
+#[test]
+fn exponents_past_the_cap_read_as_floats() {
+    let result = run("set .b = .a + 1", r#"{"a": 1e30000000, "c": 1e1000000000}"#);
+    assert_eq!(result.get_path(".a"), Some(&Value::Float(f64::INFINITY)));
+    assert_eq!(result.get_path(".b"), Some(&Value::Float(f64::INFINITY)));
+    assert_eq!(result.get_path(".c"), Some(&Value::Float(f64::INFINITY)));
+}
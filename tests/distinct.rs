@@ -0,0 +1,232 @@
+#![allow(deprecated)]
+//! Integration tests for the `distinct` statement in batch and streaming mode.
+
+use assert_cmd::Command;
+use indexmap::IndexMap;
+use morph::cli::Format;
+use morph::formats::csv::CsvConfig;
+use morph::mapping::distinct::ApproxDistinct;
+use morph::mapping::{eval, parser};
+use morph::streaming::{self, StreamWriter};
+use morph::value::Value;
+
+fn run(mapping: &str, input: &Value) -> Value {
+    let program = parser::parse_str(mapping).unwrap();
+    eval::eval(&program, input).unwrap()
+}
+
+fn make_map(pairs: &[(&str, Value)]) -> Value {
+    let mut m = IndexMap::new();
+    for (k, v) in pairs {
+        m.insert((*k).to_string(), v.clone());
+    }
+    Value::Map(m)
+}
+
+fn s(v: &str) -> Value {
+    Value::String(v.into())
+}
+
+fn user(id: i64, email: &str, tenant: &str) -> Value {
+    make_map(&[
+        ("id", Value::Int(id)),
+        ("email", s(email)),
+        ("tenant", s(tenant)),
+    ])
+}
+
+fn users() -> Value {
+    Value::Array(vec![
+        user(1, "a@x", "t1"),
+        user(2, "b@x", "t1"),
+        user(3, "a@x", "t1"),
+        user(4, "a@x", "t2"),
+        user(5, "b@x", "t1"),
+    ])
+}
+
+fn ids(value: &Value) -> Vec<i64> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .map(|item| match item.get_path(".id") {
+                Some(Value::Int(id)) => *id,
+                other => panic!("expected int id, got: {other:?}"),
+            })
+            .collect(),
+        other => panic!("expected array, got: {other:?}"),
+    }
+}
+
+fn stream(program: &morph::mapping::ast::Program, input: &str) -> String {
+    stream_with(program, &eval::EvalOptions::default(), input)
+}
+
+fn stream_with(
+    program: &morph::mapping::ast::Program,
+    options: &eval::EvalOptions,
+    input: &str,
+) -> String {
+    let mut output = Vec::new();
+    {
+        let mut writer = StreamWriter::new(&mut output, Format::Jsonl, CsvConfig::default());
+        writer.begin().unwrap();
+        streaming::stream_jsonl(input.as_bytes(), &mut writer, Some((program, options))).unwrap();
+        writer.end().unwrap();
+    }
+    String::from_utf8(output).unwrap()
+}
+
+const USERS_JSONL: &str = "\
+{\"id\":1,\"email\":\"a@x\"}
+{\"id\":2,\"email\":\"b@x\"}
+{\"id\":3,\"email\":\"a@x\"}
+{\"id\":4,\"email\":\"c@x\"}
+{\"id\":5,\"email\":\"b@x\"}
+";
+
+// ---------------------------------------------------------------------------
+// Batch
+// ---------------------------------------------------------------------------
+
+#[test]
+fn distinct_by_keeps_first_by_default() {
+    let result = run("distinct by .email", &users());
+    assert_eq!(ids(&result), vec![1, 2]);
+}
+
+#[test]
+fn distinct_by_multiple_keys() {
+    let result = run("distinct by .email, .tenant", &users());
+    assert_eq!(ids(&result), vec![1, 2, 4]);
+}
+
+#[test]
+fn distinct_keep_last_uses_last_positions() {
+    let result = run("distinct by .email, .tenant keep last", &users());
+    assert_eq!(ids(&result), vec![3, 4, 5]);
+}
+
+#[test]
+fn distinct_whole_record() {
+    let input = Value::Array(vec![
+        make_map(&[("a", Value::Int(1)), ("b", Value::Int(2))]),
+        make_map(&[("b", Value::Int(2)), ("a", Value::Int(1))]),
+        make_map(&[("a", Value::Int(1)), ("b", Value::Float(2.0))]),
+    ]);
+    let result = run("distinct", &input);
+    match result {
+        // Key order does not matter, but Int and Float values differ.
+        Value::Array(items) => assert_eq!(items.len(), 2),
+        other => panic!("expected array, got: {other:?}"),
+    }
+}
+
+#[test]
+fn distinct_missing_key_counts_as_null() {
+    let input = Value::Array(vec![
+        make_map(&[("id", Value::Int(1))]),
+        make_map(&[("id", Value::Int(2)), ("email", Value::Null)]),
+        make_map(&[("id", Value::Int(3)), ("email", s("a@x"))]),
+    ]);
+    let result = run("distinct by .email", &input);
+    assert_eq!(ids(&result), vec![1, 3]);
+}
+
+#[test]
+fn distinct_on_single_record_is_noop() {
+    let input = user(1, "a@x", "t1");
+    assert_eq!(run("distinct by .email", &input), input);
+}
+
+// ---------------------------------------------------------------------------
+// Streaming
+// ---------------------------------------------------------------------------
+
+#[test]
+fn streaming_distinct_keep_first() {
+    let program = parser::parse_str("distinct by .email\nselect .id").unwrap();
+    assert_eq!(
+        stream(&program, USERS_JSONL),
+        "{\"id\":1}\n{\"id\":2}\n{\"id\":4}\n"
+    );
+}
+
+#[test]
+fn streaming_distinct_keep_last_releases_at_end() {
+    let program = parser::parse_str("distinct by .email keep last\nselect .id").unwrap();
+    assert_eq!(
+        stream(&program, USERS_JSONL),
+        "{\"id\":3}\n{\"id\":4}\n{\"id\":5}\n"
+    );
+}
+
+#[test]
+fn streaming_where_then_distinct_ignores_rejected_records() {
+    let program = parser::parse_str("where .id > 1\ndistinct by .email\nselect .id").unwrap();
+    assert_eq!(
+        stream(&program, USERS_JSONL),
+        "{\"id\":2}\n{\"id\":3}\n{\"id\":4}\n"
+    );
+    let program =
+        parser::parse_str("where .id > 1\ndistinct by .email keep last\nselect .id").unwrap();
+    assert_eq!(
+        stream(&program, USERS_JSONL),
+        "{\"id\":3}\n{\"id\":4}\n{\"id\":5}\n"
+    );
+}
+
+#[test]
+fn streaming_distinct_with_bounded_memory() {
+    let program = parser::parse_str("distinct by .email\nselect .id").unwrap();
+    let options = eval::EvalOptions {
+        approx_distinct: Some(ApproxDistinct::new(1000, 0.0001).unwrap()),
+        ..Default::default()
+    };
+    assert_eq!(
+        stream_with(&program, &options, USERS_JSONL),
+        "{\"id\":1}\n{\"id\":2}\n{\"id\":4}\n"
+    );
+}
+
+#[test]
+fn cli_distinct_fp_rate_flag() {
+    let output = Command::cargo_bin("morph")
+        .unwrap()
+        .args([
+            "-f",
+            "jsonl",
+            "-t",
+            "jsonl",
+            "--stream",
+            "--distinct-fp-rate",
+            "0.001",
+            "-e",
+            "distinct by .email",
+        ])
+        .write_stdin(USERS_JSONL)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap().lines().count(), 3);
+}
+
+#[test]
+fn cli_distinct_fp_rate_must_be_a_probability() {
+    Command::cargo_bin("morph")
+        .unwrap()
+        .args([
+            "-f",
+            "jsonl",
+            "-t",
+            "jsonl",
+            "--distinct-fp-rate",
+            "2",
+            "-e",
+            "distinct",
+        ])
+        .write_stdin(USERS_JSONL)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("between 0 and 1"));
+}
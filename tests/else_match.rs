@@ -0,0 +1,210 @@
+//! Integration tests for `else` / `else when` chains and `match` blocks.
+
+use indexmap::IndexMap;
+use morph::mapping::{eval, parser};
+use morph::value::Value;
+
+fn run(mapping: &str, input: &Value) -> Value {
+    let program = parser::parse_str(mapping).unwrap();
+    eval::eval(&program, input).unwrap()
+}
+
+fn make_map(pairs: &[(&str, Value)]) -> Value {
+    let mut m = IndexMap::new();
+    for (k, v) in pairs {
+        m.insert((*k).to_string(), v.clone());
+    }
+    Value::Map(m)
+}
+
+// ---------------------------------------------------------------------------
+// when ... else
+// ---------------------------------------------------------------------------
+
+#[test]
+fn else_runs_when_condition_false() {
+    let input = make_map(&[("age", Value::Int(10))]);
+    let result = run(
+        "when .age >= 18 { set .group = \"adult\" } else { set .group = \"minor\" }",
+        &input,
+    );
+    assert_eq!(
+        result.get_path(".group"),
+        Some(&Value::String("minor".into()))
+    );
+}
+
+#[test]
+fn else_skipped_when_condition_true() {
+    let input = make_map(&[("age", Value::Int(30))]);
+    let result = run(
+        "when .age >= 18 { set .group = \"adult\" } else { set .group = \"minor\" }",
+        &input,
+    );
+    assert_eq!(
+        result.get_path(".group"),
+        Some(&Value::String("adult".into()))
+    );
+}
+
+#[test]
+fn else_when_chain_picks_first_match() {
+    let mapping = "\
+when .score >= 90 {
+  set .grade = \"A\"
+} else when .score >= 80 {
+  set .grade = \"B\"
+} else when .score >= 70 {
+  set .grade = \"C\"
+} else {
+  set .grade = \"F\"
+}";
+    let grade = |score: i64| {
+        let result = run(mapping, &make_map(&[("score", Value::Int(score))]));
+        result.get_path(".grade").cloned()
+    };
+    assert_eq!(grade(95), Some(Value::String("A".into())));
+    assert_eq!(grade(85), Some(Value::String("B".into())));
+    assert_eq!(grade(75), Some(Value::String("C".into())));
+    assert_eq!(grade(10), Some(Value::String("F".into())));
+}
+
+#[test]
+fn else_when_without_final_else_is_noop() {
+    let input = make_map(&[("x", Value::Int(5))]);
+    let result = run(
+        "when .x == 1 { set .y = 1 } else when .x == 2 { set .y = 2 }",
+        &input,
+    );
+    assert_eq!(result, input);
+}
+
+#[test]
+fn branch_mutating_tested_field_does_not_trigger_else() {
+    // With two separate `when` blocks the second would fire after the first
+    // flips the status; an else chain evaluates only one branch.
+    let input = make_map(&[("status", Value::String("new".into()))]);
+    let result = run(
+        "when .status == \"new\" { set .status = \"open\" } else when .status == \"open\" { set .status = \"closed\" }",
+        &input,
+    );
+    assert_eq!(
+        result.get_path(".status"),
+        Some(&Value::String("open".into()))
+    );
+}
+
+#[test]
+fn else_inside_each() {
+    let input = make_map(&[(
+        "items",
+        Value::Array(vec![
+            make_map(&[("qty", Value::Int(0))]),
+            make_map(&[("qty", Value::Int(3))]),
+        ]),
+    )]);
+    let result = run(
+        "each .items { when .qty > 0 { set .in_stock = true } else { set .in_stock = false } }",
+        &input,
+    );
+    assert_eq!(
+        result.get_path(".items[0].in_stock"),
+        Some(&Value::Bool(false))
+    );
+    assert_eq!(
+        result.get_path(".items[1].in_stock"),
+        Some(&Value::Bool(true))
+    );
+}
+
+// ---------------------------------------------------------------------------
+// match
+// ---------------------------------------------------------------------------
+
+const MATCH_MAPPING: &str = "\
+match .kind {
+  \"a\" => { set .label = \"alpha\" },
+  \"b\" | \"c\" => { set .label = \"b-or-c\" },
+  _ => { set .label = \"other\" }
+}";
+
+fn label_for(kind: Value) -> Option<Value> {
+    let result = run(MATCH_MAPPING, &make_map(&[("kind", kind)]));
+    result.get_path(".label").cloned()
+}
+
+#[test]
+fn match_single_pattern() {
+    assert_eq!(
+        label_for(Value::String("a".into())),
+        Some(Value::String("alpha".into()))
+    );
+}
+
+#[test]
+fn match_alternative_patterns() {
+    assert_eq!(
+        label_for(Value::String("b".into())),
+        Some(Value::String("b-or-c".into()))
+    );
+    assert_eq!(
+        label_for(Value::String("c".into())),
+        Some(Value::String("b-or-c".into()))
+    );
+}
+
+#[test]
+fn match_wildcard_fallback() {
+    assert_eq!(
+        label_for(Value::String("z".into())),
+        Some(Value::String("other".into()))
+    );
+    assert_eq!(label_for(Value::Null), Some(Value::String("other".into())));
+}
+
+#[test]
+fn match_no_arm_is_noop() {
+    let input = make_map(&[("kind", Value::String("z".into()))]);
+    let result = run("match .kind { \"a\" => { set .label = 1 } }", &input);
+    assert_eq!(result, input);
+}
+
+#[test]
+fn match_first_arm_wins() {
+    let input = make_map(&[("kind", Value::String("a".into()))]);
+    let result = run(
+        "match .kind { \"a\" => { set .n = 1 } _ => { set .n = 2 } \"a\" => { set .n = 3 } }",
+        &input,
+    );
+    assert_eq!(result.get_path(".n"), Some(&Value::Int(1)));
+}
+
+#[test]
+fn match_numeric_patterns_compare_across_int_and_float() {
+    let input = make_map(&[("code", Value::Float(2.0))]);
+    let result = run(
+        "match .code { 1 => { set .s = \"one\" } 2 => { set .s = \"two\" } }",
+        &input,
+    );
+    assert_eq!(result.get_path(".s"), Some(&Value::String("two".into())));
+}
+
+#[test]
+fn match_subject_evaluated_once() {
+    let input = make_map(&[("state", Value::String("a".into()))]);
+    let result = run(
+        "match .state { \"a\" => { set .state = \"b\" } \"b\" => { set .state = \"c\" } }",
+        &input,
+    );
+    assert_eq!(result.get_path(".state"), Some(&Value::String("b".into())));
+}
+
+#[test]
+fn match_on_expression_subject() {
+    let input = make_map(&[("name", Value::String("ADMIN".into()))]);
+    let result = run(
+        "match lower(.name) { \"admin\" => { set .is_admin = true } _ => { set .is_admin = false } }",
+        &input,
+    );
+    assert_eq!(result.get_path(".is_admin"), Some(&Value::Bool(true)));
+}
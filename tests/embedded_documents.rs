@@ -0,0 +1,97 @@
+//! Integration tests for `parse_json`, `to_json` and the other embedded-document
+//! functions.
+
+mod common;
+
+use common::{json, morph, run};
+use morph::mapping::{eval, parser};
+use morph::value::Value;
+use predicates::prelude::*;
+
+#[test]
+fn parse_embedded_json_and_yaml() {
+    let result = run(
+        "set .payload = parse_json(.payload_raw)\nset .meta = parse_yaml(.meta)\ndrop .payload_raw",
+        r#"{"payload_raw": "{\"user\": {\"id\": 7}}", "meta": "tags: [a, b]"}"#,
+    );
+    assert_eq!(
+        result,
+        json(r#"{"meta": {"tags": ["a", "b"]}, "payload": {"user": {"id": 7}}}"#)
+    );
+}
+
+#[test]
+fn parsed_document_can_be_queried() {
+    let result = run(
+        "set .body = parse_json(.body)\nset .id = .body.user.id",
+        r#"{"body": "{\"user\": {\"id\": 7}}"}"#,
+    );
+    assert_eq!(result.get_path(".id"), Some(&Value::Int(7)));
+}
+
+#[test]
+fn serialize_subtree() {
+    let result = run(
+        "set .as_json = to_json(.cfg)\nset .as_toml = to_format(.cfg, \"toml\")",
+        r#"{"cfg": {"port": 80, "host": "x"}}"#,
+    );
+    assert_eq!(
+        result.get_path(".as_json"),
+        Some(&Value::String(r#"{"port":80,"host":"x"}"#.into()))
+    );
+    assert_eq!(
+        result.get_path(".as_toml"),
+        Some(&Value::String("host = \"x\"\nport = 80".into()))
+    );
+}
+
+#[test]
+fn round_trip_through_string() {
+    let input = r#"{"doc": {"a": [1, {"b": null}]}}"#;
+    let result = run("set .doc = parse_json(to_json(.doc))", input);
+    assert_eq!(result, json(input));
+}
+
+#[test]
+fn parse_error_names_the_field() {
+    let program = parser::parse_str("set .payload = parse_json(.raw)").unwrap();
+    let err = eval::eval(&program, &json(r#"{"raw": "{oops"}"#)).unwrap_err();
+    let message = err.to_string();
+    assert!(
+        message.contains("parse_json(): invalid json"),
+        "err: {message}"
+    );
+    assert!(message.contains("(in .raw)"), "err: {message}");
+}
+
+#[test]
+fn cli_parse_error_reports_field() {
+    morph()
+        .args([
+            "-f",
+            "json",
+            "-t",
+            "json",
+            "-e",
+            "set .p = parse_json(.raw)",
+        ])
+        .write_stdin(r#"{"raw": "[1,"}"#)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("in .raw"));
+}
+
+#[test]
+fn cli_streaming_parse_json() {
+    let output = morph()
+        .args(["-f", "jsonl", "-t", "jsonl", "--stream", "-e"])
+        .arg("set .p = parse_json(.raw)\ndrop .raw")
+        .write_stdin("{\"raw\":\"[1]\"}\n{\"raw\":null}\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"p\":[1]}\n{\"p\":null}\n"
+    );
+}
@@ -0,0 +1,170 @@
+//! Integration tests for error-tolerant mappings: `try`, `cast ... or`, and
+//! the `on error` directive.
+
+mod common;
+
+use common::{cli, json, morph, run};
+use morph::mapping::{eval, parser};
+
+/// `run`, as compact JSON text.
+fn run_json(mapping: &str, input: &str) -> String {
+    morph::formats::json::to_string(&run(mapping, input)).unwrap()
+}
+
+const DIRTY_CSV: &str = "name,age\nann,34\nbob,n/a\ncid,\n";
+
+#[test]
+fn try_falls_back_on_error() {
+    let out = run_json(
+        "set .n = try(to_int(.raw), -1)\nset .m = try(.raw * 2)\nset .ok = try(.x + 1, 0)",
+        r#"{"raw": "abc", "x": 1}"#,
+    );
+    assert_eq!(out, r#"{"raw":"abc","x":1,"n":-1,"m":null,"ok":2}"#);
+}
+
+#[test]
+fn try_inside_lambda() {
+    let out = run_json(
+        "set .ns = map(.xs, x => try(to_int(x), 0))",
+        r#"{"xs": ["1", "x", "3"]}"#,
+    );
+    assert_eq!(out, r#"{"xs":["1","x","3"],"ns":[1,0,3]}"#);
+}
+
+#[test]
+fn cast_fallbacks() {
+    let out = run_json("cast .age as int or null", r#"{"age": "n/a"}"#);
+    assert_eq!(out, r#"{"age":null}"#);
+    let out = run_json(
+        "cast .age as int or default .fallback",
+        r#"{"age": "n/a", "fallback": 0}"#,
+    );
+    assert_eq!(out, r#"{"age":0,"fallback":0}"#);
+    let out = run_json("cast .age as int or null", r#"{"age": "42"}"#);
+    assert_eq!(out, r#"{"age":42}"#);
+}
+
+#[test]
+fn cast_without_fallback_still_fails() {
+    let program = parser::parse_str("cast .age as int").unwrap();
+    assert!(eval::eval(&program, &json(r#"{"age": "n/a"}"#)).is_err());
+}
+
+#[test]
+fn eval_options_override_the_on_error_line() {
+    let program = parser::parse_str("on error fail\ncast .age as int").unwrap();
+    let options = eval::EvalOptions {
+        on_error: Some(morph::mapping::ast::OnError::Null),
+        ..Default::default()
+    };
+    let result = eval::eval_with(&program, &options, &json(r#"{"age": "n/a"}"#)).unwrap();
+    assert_eq!(result, json(r#"{"age": null}"#));
+}
+
+#[test]
+fn on_error_null_nulls_failing_expressions() {
+    let out = run_json(
+        "on error null\ncast .age as int\nset .next = .age + 1\nset .label = upper(.name)",
+        r#"{"name": "ann", "age": "n/a"}"#,
+    );
+    assert_eq!(
+        out,
+        r#"{"name":"ann","age":null,"next":null,"label":"ANN"}"#
+    );
+}
+
+#[test]
+fn on_error_skip_in_batch_drops_the_document() {
+    let out = run_json("on error skip\ncast .age as int", r#"{"age": "n/a"}"#);
+    assert_eq!(out, "[]");
+}
+
+#[test]
+fn on_error_skip_in_batch_drops_failing_array_records() {
+    let out = run_json(
+        "on error skip\nwhere .a * 2 > 1\nsort .a desc",
+        r#"[{"a":1},{"a":"z"},{"a":3}]"#,
+    );
+    assert_eq!(out, r#"[{"a":3},{"a":1}]"#);
+}
+
+#[test]
+fn cli_skip_matches_with_and_without_stream() {
+    let input = r#"[{"a":1},{"a":"z"},{"a":3}]"#;
+    let args = ["-e", "on error skip", "-e", "where .a * 2 > 1"];
+    let batch = cli(&[&["-f", "json", "-t", "jsonl"], &args[..]].concat(), input);
+    let streamed = cli(
+        &[&["-f", "json", "-t", "jsonl", "--stream"], &args[..]].concat(),
+        input,
+    );
+    assert_eq!(batch, "{\"a\":1}\n{\"a\":3}\n");
+    assert_eq!(batch, streamed);
+}
+
+#[test]
+fn cli_stream_skips_bad_csv_rows() {
+    let out = cli(
+        &[
+            "-f",
+            "csv",
+            "-t",
+            "jsonl",
+            "--stream",
+            "-e",
+            "on error skip\ncast .age as int",
+        ],
+        DIRTY_CSV,
+    );
+    assert_eq!(out, "{\"name\":\"ann\",\"age\":34}\n");
+}
+
+#[test]
+fn cli_stream_cleans_bad_csv_cells() {
+    let out = cli(
+        &[
+            "-f",
+            "csv",
+            "-t",
+            "csv",
+            "--stream",
+            "-e",
+            "cast .age as int or default 0",
+        ],
+        DIRTY_CSV,
+    );
+    assert_eq!(out, "name,age\nann,34\nbob,0\ncid,0\n");
+}
+
+#[test]
+fn cli_stream_skip_before_aggregate() {
+    let out = cli(
+        &[
+            "-f",
+            "csv",
+            "-t",
+            "jsonl",
+            "--stream",
+            "-e",
+            "on error skip\ncast .age as int\naggregate {\n  set .n = count()\n  set .total = sum(.age)\n}",
+        ],
+        DIRTY_CSV,
+    );
+    assert_eq!(out, "{\"n\":1,\"total\":34}\n");
+}
+
+#[test]
+fn cli_without_policy_fails() {
+    morph()
+        .args([
+            "-f",
+            "csv",
+            "-t",
+            "jsonl",
+            "--stream",
+            "-e",
+            "cast .age as int",
+        ])
+        .write_stdin(DIRTY_CSV)
+        .assert()
+        .failure();
+}
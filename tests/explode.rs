@@ -0,0 +1,162 @@
+//! Integration tests for the `explode` / `unnest` statement in batch and
+//! streaming mode.
+
+use indexmap::IndexMap;
+use morph::cli::Format;
+use morph::formats::csv::CsvConfig;
+use morph::mapping::{eval, parser};
+use morph::streaming::{self, StreamWriter};
+use morph::value::Value;
+
+fn run(mapping: &str, input: &Value) -> Value {
+    let program = parser::parse_str(mapping).unwrap();
+    eval::eval(&program, input).unwrap()
+}
+
+fn make_map(pairs: &[(&str, Value)]) -> Value {
+    let mut m = IndexMap::new();
+    for (k, v) in pairs {
+        m.insert((*k).to_string(), v.clone());
+    }
+    Value::Map(m)
+}
+
+fn s(v: &str) -> Value {
+    Value::String(v.into())
+}
+
+fn order(id: i64, items: Option<Vec<Value>>) -> Value {
+    let mut pairs = vec![("id", Value::Int(id))];
+    if let Some(items) = items {
+        pairs.push(("items", Value::Array(items)));
+    }
+    make_map(&pairs)
+}
+
+fn stream(mapping: &str, input: &str, out: Format) -> (usize, String) {
+    let program = parser::parse_str(mapping).unwrap();
+    let mut output = Vec::new();
+    let count;
+    {
+        let mut writer = StreamWriter::new(&mut output, out, CsvConfig::default());
+        writer.begin().unwrap();
+        count = streaming::stream_jsonl(
+            input.as_bytes(),
+            &mut writer,
+            Some((&program, &eval::EvalOptions::default())),
+        )
+        .unwrap();
+        writer.end().unwrap();
+    }
+    (count, String::from_utf8(output).unwrap())
+}
+
+// ---------------------------------------------------------------------------
+// Batch
+// ---------------------------------------------------------------------------
+
+#[test]
+fn explode_root_array_keeps_parent_fields() {
+    let input = Value::Array(vec![
+        order(1, Some(vec![s("a"), s("b")])),
+        order(2, Some(vec![s("c")])),
+    ]);
+    let result = run("explode .items as .item", &input);
+    assert_eq!(
+        result,
+        Value::Array(vec![
+            make_map(&[("id", Value::Int(1)), ("item", s("a"))]),
+            make_map(&[("id", Value::Int(1)), ("item", s("b"))]),
+            make_map(&[("id", Value::Int(2)), ("item", s("c"))]),
+        ])
+    );
+}
+
+#[test]
+fn explode_single_record_yields_array() {
+    let result = run(
+        "explode .items as .item",
+        &order(1, Some(vec![s("a"), s("b")])),
+    );
+    match result {
+        Value::Array(records) => assert_eq!(records.len(), 2),
+        other => panic!("expected array, got: {other:?}"),
+    }
+}
+
+#[test]
+fn unnest_without_target_replaces_array_in_place() {
+    let input = Value::Array(vec![order(1, Some(vec![s("a"), s("b")]))]);
+    let result = run("unnest .items", &input);
+    assert_eq!(
+        result,
+        Value::Array(vec![
+            make_map(&[("id", Value::Int(1)), ("items", s("a"))]),
+            make_map(&[("id", Value::Int(1)), ("items", s("b"))]),
+        ])
+    );
+}
+
+#[test]
+fn explode_empty_or_missing_array_keeps_one_record() {
+    let input = Value::Array(vec![order(1, Some(vec![])), order(2, None)]);
+    let result = run("explode .items as .item", &input);
+    assert_eq!(
+        result,
+        Value::Array(vec![
+            make_map(&[("id", Value::Int(1)), ("item", Value::Null)]),
+            make_map(&[("id", Value::Int(2)), ("item", Value::Null)]),
+        ])
+    );
+}
+
+#[test]
+fn explode_nested_path_into_nested_target() {
+    let input = make_map(&[(
+        "order",
+        make_map(&[
+            ("id", Value::Int(7)),
+            ("lines", Value::Array(vec![s("x"), s("y")])),
+        ]),
+    )]);
+    let result = run("explode .order.lines as .line.sku", &input);
+    assert_eq!(result.get_path("[1].line.sku"), Some(&s("y")));
+    assert_eq!(result.get_path("[1].order.id"), Some(&Value::Int(7)));
+    assert_eq!(result.get_path("[1].order.lines"), None);
+}
+
+#[test]
+fn where_after_explode_filters_exploded_records() {
+    let input = Value::Array(vec![order(1, Some(vec![s("a"), s("b")]))]);
+    let result = run("explode .items as .item\nwhere .item != \"a\"", &input);
+    assert_eq!(
+        result,
+        Value::Array(vec![make_map(&[("id", Value::Int(1)), ("item", s("b"))])])
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Streaming
+// ---------------------------------------------------------------------------
+
+#[test]
+fn streaming_explode_writes_one_element_per_item() {
+    let input = "{\"id\":1,\"items\":[\"a\",\"b\"]}\n{\"id\":2,\"items\":[]}\n";
+    let (count, out) = stream("explode .items as .item", input, Format::Jsonl);
+    assert_eq!(count, 3);
+    assert_eq!(
+        out,
+        "{\"id\":1,\"item\":\"a\"}\n{\"id\":1,\"item\":\"b\"}\n{\"id\":2,\"item\":null}\n"
+    );
+}
+
+#[test]
+fn streaming_statements_after_explode_run_per_record() {
+    let input = "{\"id\":1,\"items\":[{\"sku\":\"a\",\"qty\":2},{\"sku\":\"b\",\"qty\":1}]}\n";
+    let (_, out) = stream(
+        "explode .items as .line\nset .sku = .line.sku\nset .qty = .line.qty\ndrop .line",
+        input,
+        Format::Csv,
+    );
+    assert_eq!(out, "id,sku,qty\n1,a,2\n1,b,1\n");
+}
@@ -0,0 +1,187 @@
+//! Integration tests for `flatten all` and `unflatten all`.
+
+mod common;
+
+use common::{json, morph, run_value};
+use morph::mapping::{eval, parser};
+use morph::value::Value;
+
+const NESTED: &str = r#"{
+    "id": 1,
+    "user": {"name": "ada", "address": {"city": "Paris", "zip": "75001"}},
+    "tags": ["a", "b"],
+    "orders": [{"sku": "x", "qty": 2}],
+    "notes": [],
+    "meta": {}
+}"#;
+
+// ---------------------------------------------------------------------------
+// flatten all
+// ---------------------------------------------------------------------------
+
+#[test]
+fn flatten_all_with_index_keys() {
+    let result = run_value(r#"flatten all separator ".""#, &json(NESTED));
+    assert_eq!(
+        result,
+        json(
+            r#"{
+                "id": 1,
+                "user.name": "ada",
+                "user.address.city": "Paris",
+                "user.address.zip": "75001",
+                "tags.0": "a",
+                "tags.1": "b",
+                "orders.0.sku": "x",
+                "orders.0.qty": 2,
+                "notes": "[]",
+                "meta": "{}"
+            }"#
+        )
+    );
+}
+
+#[test]
+fn flatten_all_defaults_to_dot_separator() {
+    let a = run_value("flatten all", &json(NESTED));
+    let b = run_value(r#"flatten all separator "." arrays index"#, &json(NESTED));
+    assert_eq!(a, b);
+}
+
+#[test]
+fn flatten_all_custom_separator() {
+    let result = run_value(r#"flatten all separator "__""#, &json(NESTED));
+    assert_eq!(
+        result.get_path(".user__address__city"),
+        Some(&Value::String("Paris".into()))
+    );
+}
+
+#[test]
+fn flatten_all_joins_scalar_arrays() {
+    let result = run_value(r#"flatten all arrays join "|""#, &json(NESTED));
+    let Value::Map(map) = &result else {
+        panic!("expected map, got: {result:?}");
+    };
+    assert_eq!(map.get("tags[]"), Some(&Value::String("a|b".into())));
+    // Arrays of records still use index keys.
+    assert_eq!(map.get("orders.0.sku"), Some(&Value::String("x".into())));
+    assert_eq!(map.get("notes[]"), Some(&Value::String("".into())));
+    assert_eq!(map.get("meta"), Some(&Value::String("{}".into())));
+}
+
+#[test]
+fn flatten_all_each_record_of_root_array() {
+    let input = json(r#"[{"a": {"b": 1}}, {"a": {"b": 2}}]"#);
+    let result = run_value("flatten all", &input);
+    assert_eq!(result, json(r#"[{"a.b": 1}, {"a.b": 2}]"#));
+}
+
+// ---------------------------------------------------------------------------
+// unflatten all
+// ---------------------------------------------------------------------------
+
+#[test]
+fn unflatten_inverts_flatten() {
+    for mapping in [
+        "flatten all\nunflatten all",
+        "flatten all separator \"/\" arrays join \";\"\nunflatten all separator \"/\" arrays join \";\"",
+    ] {
+        assert_eq!(run_value(mapping, &json(NESTED)), json(NESTED), "mapping: {mapping}");
+    }
+}
+
+#[test]
+fn unflatten_only_contiguous_indexes_become_arrays() {
+    let input = json(r#"{"a.0": 1, "a.1": 2, "b.0": 1, "b.2": 3, "c.01": 1, "0": "root"}"#);
+    let result = run_value("unflatten all", &input);
+    assert_eq!(
+        result,
+        json(r#"{"a": [1, 2], "b": {"0": 1, "2": 3}, "c": {"01": 1}, "0": "root"}"#)
+    );
+}
+
+#[test]
+fn unflatten_restores_empty_containers() {
+    let input = json(r#"{"notes": "[]", "meta": "{}", "name": "[x]"}"#);
+    let result = run_value("unflatten all", &input);
+    assert_eq!(result, json(r#"{"notes": [], "meta": {}, "name": "[x]"}"#));
+}
+
+#[test]
+fn flatten_escapes_separators_and_markers() {
+    let input = json(r#"{"a.b": 1, "x": {"c.d": "[]", "e": "{}", "t[]": 2}}"#);
+    let flat = run_value("flatten all", &input);
+    assert_eq!(
+        flat,
+        json(r#"{"a\\.b": 1, "x.c\\.d": "\\[]", "x.e": "\\{}", "x.t\\[]": 2}"#)
+    );
+    for mapping in [
+        "flatten all\nunflatten all",
+        "flatten all arrays join \",\"\nunflatten all arrays join \",\"",
+    ] {
+        assert_eq!(run_value(mapping, &input), input, "mapping: {mapping}");
+    }
+}
+
+#[test]
+fn error_unflatten_conflicting_keys() {
+    let program = parser::parse_str("unflatten all").unwrap();
+    let err = eval::eval(&program, &json(r#"{"a": 1, "a.b": 2}"#)).unwrap_err();
+    assert!(err.to_string().contains("'a.b' conflicts"), "err: {err}");
+}
+
+// ---------------------------------------------------------------------------
+// JSON → CSV → JSON
+// ---------------------------------------------------------------------------
+
+fn convert(args: &[&str], input: &str) -> String {
+    let output = morph().args(args).write_stdin(input).output().unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn round_trip(input: &str, flatten: &str, unflatten: &str) {
+    let csv = convert(&["-f", "json", "-t", "csv", "-e", flatten], input);
+    let back = convert(
+        &["-f", "csv", "-t", "json", "--compact", "-e", unflatten],
+        &csv,
+    );
+    assert_eq!(json(&back), json(input), "csv:\n{csv}");
+}
+
+#[test]
+fn cli_round_trip_with_index_keys() {
+    let input = r#"[
+        {"id": 1, "user": {"name": "ada", "langs": ["en", "fr"]}, "scores": [1.5, 2.0], "tags": []},
+        {"id": 2, "user": {"name": "bob", "langs": ["de", "it"]}, "scores": [3.0, 4.5], "tags": []}
+    ]"#;
+    round_trip(input, "flatten all", "unflatten all");
+}
+
+#[test]
+fn cli_round_trip_with_joined_arrays() {
+    // Joined arrays may differ in length between records.
+    let input = r#"[
+        {"id": 1, "user": {"name": "ada", "langs": ["en", "fr"]}, "scores": [1.5, 2.0]},
+        {"id": 2, "user": {"name": "bob", "langs": ["de"]}, "scores": []}
+    ]"#;
+    round_trip(
+        input,
+        r#"flatten all arrays join ",""#,
+        r#"unflatten all arrays join ",""#,
+    );
+}
+
+#[test]
+fn cli_round_trip_with_separators_and_marker_strings() {
+    let input = r#"[
+        {"id": 1, "a.b": "[]", "meta": {"k.v": "{}", "raw": "\\[]"}, "notes": []},
+        {"id": 2, "a.b": "x", "meta": {"k.v": "y", "raw": "z"}, "notes": []}
+    ]"#;
+    round_trip(input, "flatten all", "unflatten all");
+}
@@ -0,0 +1,92 @@
+//! Integration tests for the hashing, encoding and identifier functions.
+
+mod common;
+
+use common::{json, morph, run};
+use morph::value::Value;
+
+#[test]
+fn pseudonymize_email() {
+    let result = run(
+        "set .email = hmac_sha256(\"secret\", lower(.email))\nset .id = sha256(.id)",
+        r#"{"email": "Bob@Example.com", "id": 7}"#,
+    );
+    let again = run(
+        "set .email = hmac_sha256(\"secret\", lower(.email))\nset .id = sha256(.id)",
+        r#"{"email": "bob@example.com", "id": 7}"#,
+    );
+    assert_eq!(result, again);
+    match result.get_path(".email") {
+        Some(Value::String(s)) => assert_eq!(s.len(), 64),
+        other => panic!("expected hex digest, got: {other:?}"),
+    }
+    assert_eq!(
+        result.get_path(".id"),
+        Some(&Value::String(
+            "7902699be42c8a8e46fbbb4501726517e86b22c56a189f7625a6da49081b2451".into()
+        ))
+    );
+}
+
+#[test]
+fn base64_decode_to_bytes_and_back_to_text() {
+    let result = run(
+        "set .raw = base64_decode(.b64)\nset .text = to_string(.raw)\nset .hex = hex(.raw)",
+        r#"{"b64": "aGVsbG8="}"#,
+    );
+    assert_eq!(
+        result.get_path(".raw"),
+        Some(&Value::Bytes(b"hello".to_vec()))
+    );
+    assert_eq!(
+        result.get_path(".text"),
+        Some(&Value::String("hello".into()))
+    );
+    assert_eq!(
+        result.get_path(".hex"),
+        Some(&Value::String("68656c6c6f".into()))
+    );
+}
+
+#[test]
+fn uuid_v5_is_a_stable_key() {
+    let mapping = "set .key = uuid_v5(\"url\", \"https://example.com/\" + .sku)";
+    let a = run(mapping, r#"{"sku": "A-1"}"#);
+    let b = run(mapping, r#"{"sku": "A-1"}"#);
+    let c = run(mapping, r#"{"sku": "A-2"}"#);
+    assert_eq!(a.get_path(".key"), b.get_path(".key"));
+    assert_ne!(a.get_path(".key"), c.get_path(".key"));
+}
+
+#[test]
+fn cli_bytes_are_written_as_base64() {
+    let output = morph()
+        .args(["-f", "json", "-t", "json", "-e"])
+        .arg("set .raw = base64_decode(.b64)\nset .q = url_encode(.s)")
+        .write_stdin(r#"{"b64": "3q2+7w==", "s": "a b"}"#)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        json(&String::from_utf8(output.stdout).unwrap()),
+        json(r#"{"b64": "3q2+7w==", "s": "a b", "raw": "3q2+7w==", "q": "a%20b"}"#)
+    );
+}
+
+#[test]
+fn cli_bytes_in_csv_and_yaml() {
+    let cases: [(&[&str], &str); 2] = [
+        (&["-f", "jsonl", "-t", "csv", "--stream"], "raw\nAQI=\n"),
+        (&["-f", "json", "-t", "yaml"], "raw: AQI=\n"),
+    ];
+    for (args, expected) in cases {
+        let output = morph()
+            .args(args)
+            .args(["-e", "set .raw = base64_decode(.b)\nselect .raw"])
+            .write_stdin("{\"b\": \"AQI=\"}\n")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+    }
+}
@@ -0,0 +1,315 @@
+//! Integration tests for lambda expressions and higher-order array functions.
+
+use indexmap::IndexMap;
+use morph::error::MorphError;
+use morph::mapping::{eval, parser};
+use morph::value::Value;
+
+fn run(mapping: &str, input: &Value) -> Value {
+    let program = parser::parse_str(mapping).unwrap();
+    eval::eval(&program, input).unwrap()
+}
+
+fn run_err(mapping: &str, input: &Value) -> String {
+    let err = match parser::parse_str(mapping) {
+        Ok(program) => eval::eval(&program, input).unwrap_err(),
+        Err(e) => e,
+    };
+    match err {
+        MorphError::Mapping { message, .. } => message,
+        other => panic!("expected Mapping error, got: {other:?}"),
+    }
+}
+
+fn make_map(pairs: &[(&str, Value)]) -> Value {
+    let mut m = IndexMap::new();
+    for (k, v) in pairs {
+        m.insert((*k).to_string(), v.clone());
+    }
+    Value::Map(m)
+}
+
+fn ints(values: &[i64]) -> Value {
+    Value::Array(values.iter().map(|i| Value::Int(*i)).collect())
+}
+
+fn line_items() -> Value {
+    make_map(&[
+        ("discount", Value::Int(1)),
+        (
+            "items",
+            Value::Array(vec![
+                make_map(&[
+                    ("sku", Value::String("a".into())),
+                    ("price", Value::Int(5)),
+                    ("qty", Value::Int(2)),
+                    ("active", Value::Bool(true)),
+                ]),
+                make_map(&[
+                    ("sku", Value::String("b".into())),
+                    ("price", Value::Int(3)),
+                    ("qty", Value::Int(1)),
+                    ("active", Value::Bool(false)),
+                ]),
+                make_map(&[
+                    ("sku", Value::String("c".into())),
+                    ("price", Value::Int(1)),
+                    ("qty", Value::Int(4)),
+                    ("active", Value::Bool(true)),
+                ]),
+            ]),
+        ),
+    ])
+}
+
+// ---------------------------------------------------------------------------
+// map / filter / reduce
+// ---------------------------------------------------------------------------
+
+#[test]
+fn map_computes_per_element() {
+    let result = run(
+        "set .totals = map(.items, i => i.price * i.qty)",
+        &line_items(),
+    );
+    assert_eq!(result.get_path(".totals"), Some(&ints(&[10, 3, 4])));
+}
+
+#[test]
+fn filter_keeps_truthy_elements() {
+    let result = run(
+        "set .skus = map(filter(.items, i => i.active), i => i.sku)",
+        &line_items(),
+    );
+    assert_eq!(
+        result.get_path(".skus"),
+        Some(&Value::Array(vec![
+            Value::String("a".into()),
+            Value::String("c".into()),
+        ]))
+    );
+}
+
+#[test]
+fn reduce_with_initial_value() {
+    let result = run(
+        "set .total = reduce(.items, (acc, i) => acc + i.price * i.qty, 0)",
+        &line_items(),
+    );
+    assert_eq!(result.get_path(".total"), Some(&Value::Int(17)));
+}
+
+#[test]
+fn reduce_without_initial_value_seeds_with_first_element() {
+    let input = make_map(&[("xs", ints(&[4, 9, 2]))]);
+    let result = run("set .max = reduce(.xs, (a, b) => if(a > b, a, b))", &input);
+    assert_eq!(result.get_path(".max"), Some(&Value::Int(9)));
+}
+
+#[test]
+fn reduce_empty_array_without_initial_value_is_null() {
+    let input = make_map(&[("xs", ints(&[]))]);
+    let result = run("set .sum = reduce(.xs, (a, b) => a + b)", &input);
+    assert_eq!(result.get_path(".sum"), Some(&Value::Null));
+}
+
+// ---------------------------------------------------------------------------
+// any / all / find
+// ---------------------------------------------------------------------------
+
+#[test]
+fn any_and_all() {
+    let result = run(
+        "set .some_inactive = any(.items, i => not i.active)\nset .all_priced = all(.items, i => i.price > 0)",
+        &line_items(),
+    );
+    assert_eq!(result.get_path(".some_inactive"), Some(&Value::Bool(true)));
+    assert_eq!(result.get_path(".all_priced"), Some(&Value::Bool(true)));
+}
+
+#[test]
+fn any_and_all_on_empty_array() {
+    let input = make_map(&[("xs", ints(&[]))]);
+    let result = run(
+        "set .any = any(.xs, x => x > 0)\nset .all = all(.xs, x => x > 0)",
+        &input,
+    );
+    assert_eq!(result.get_path(".any"), Some(&Value::Bool(false)));
+    assert_eq!(result.get_path(".all"), Some(&Value::Bool(true)));
+}
+
+#[test]
+fn find_returns_first_match_or_null() {
+    let result = run(
+        "set .cheap = find(.items, i => i.price < 4)\nset .none = find(.items, i => i.price > 100)",
+        &line_items(),
+    );
+    assert_eq!(
+        result.get_path(".cheap.sku"),
+        Some(&Value::String("b".into()))
+    );
+    assert_eq!(result.get_path(".none"), Some(&Value::Null));
+}
+
+// ---------------------------------------------------------------------------
+// sort_by / flat_map / group_by
+// ---------------------------------------------------------------------------
+
+#[test]
+fn sort_by_key() {
+    let result = run(
+        "set .by_price = map(sort_by(.items, i => i.price), i => i.sku)",
+        &line_items(),
+    );
+    assert_eq!(
+        result.get_path(".by_price"),
+        Some(&Value::Array(vec![
+            Value::String("c".into()),
+            Value::String("b".into()),
+            Value::String("a".into()),
+        ]))
+    );
+}
+
+#[test]
+fn sort_by_is_stable() {
+    let result = run(
+        "set .by_active = map(sort_by(.items, i => if(i.active, 0, 1)), i => i.sku)",
+        &line_items(),
+    );
+    assert_eq!(
+        result.get_path(".by_active"),
+        Some(&Value::Array(vec![
+            Value::String("a".into()),
+            Value::String("c".into()),
+            Value::String("b".into()),
+        ]))
+    );
+}
+
+#[test]
+fn flat_map_splices_arrays() {
+    let input = make_map(&[(
+        "posts",
+        Value::Array(vec![
+            make_map(&[(
+                "tags",
+                Value::Array(vec![Value::String("a".into()), Value::String("b".into())]),
+            )]),
+            make_map(&[("tags", Value::Array(vec![Value::String("c".into())]))]),
+        ]),
+    )]);
+    let result = run("set .tags = flat_map(.posts, p => p.tags)", &input);
+    assert_eq!(
+        result.get_path(".tags"),
+        Some(&Value::Array(vec![
+            Value::String("a".into()),
+            Value::String("b".into()),
+            Value::String("c".into()),
+        ]))
+    );
+}
+
+#[test]
+fn group_by_lambda() {
+    let result = run(
+        "set .groups = group_by(.items, i => if(i.active, \"on\", \"off\"))",
+        &line_items(),
+    );
+    let groups = result.get_path(".groups").unwrap();
+    match groups.get_path(".on") {
+        Some(Value::Array(items)) => assert_eq!(items.len(), 2),
+        other => panic!("expected array, got: {other:?}"),
+    }
+    match groups.get_path(".off") {
+        Some(Value::Array(items)) => assert_eq!(items.len(), 1),
+        other => panic!("expected array, got: {other:?}"),
+    }
+}
+
+#[test]
+fn group_by_field_name_still_supported() {
+    let result = run("set .groups = group_by(.items, \"sku\")", &line_items());
+    assert!(result.get_path(".groups.a").is_some());
+}
+
+// ---------------------------------------------------------------------------
+// Scoping
+// ---------------------------------------------------------------------------
+
+#[test]
+fn lambda_body_can_read_record_fields() {
+    let result = run(
+        "set .net = map(.items, i => i.price - .discount)",
+        &line_items(),
+    );
+    assert_eq!(result.get_path(".net"), Some(&ints(&[4, 2, 0])));
+}
+
+#[test]
+fn nested_lambdas_see_outer_params() {
+    let input = make_map(&[("xs", ints(&[1, 2])), ("ys", ints(&[10, 20]))]);
+    let result = run(
+        "set .pairs = flat_map(.xs, x => map(.ys, y => x + y))",
+        &input,
+    );
+    assert_eq!(result.get_path(".pairs"), Some(&ints(&[11, 21, 12, 22])));
+}
+
+#[test]
+fn lambda_param_in_string_interpolation() {
+    let result = run(
+        "set .labels = map(.items, i => \"{i.sku}x{i.qty}\")",
+        &line_items(),
+    );
+    assert_eq!(
+        result.get_path(".labels"),
+        Some(&Value::Array(vec![
+            Value::String("ax2".into()),
+            Value::String("bx1".into()),
+            Value::String("cx4".into()),
+        ]))
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Errors
+// ---------------------------------------------------------------------------
+
+#[test]
+fn error_lambda_passed_to_plain_function() {
+    let msg = run_err("set .x = lower(.items, i => i)", &line_items());
+    assert!(msg.contains("does not accept a lambda"), "msg: {msg}");
+}
+
+#[test]
+fn error_higher_order_function_without_lambda() {
+    let msg = run_err("set .x = map(.items, .sku)", &line_items());
+    assert!(msg.contains("expects a lambda"), "msg: {msg}");
+}
+
+#[test]
+fn error_wrong_lambda_arity() {
+    let msg = run_err("set .x = map(.items, (a, b) => a)", &line_items());
+    assert!(
+        msg.contains("expects a lambda with 1 parameter"),
+        "msg: {msg}"
+    );
+    let msg = run_err("set .x = reduce(.items, a => a, 0)", &line_items());
+    assert!(
+        msg.contains("expects a lambda with 2 parameter"),
+        "msg: {msg}"
+    );
+}
+
+#[test]
+fn error_non_array_argument() {
+    let msg = run_err("set .x = map(.discount, i => i)", &line_items());
+    assert!(msg.contains("expects an array"), "msg: {msg}");
+}
+
+#[test]
+fn error_bare_lambda() {
+    let msg = run_err("set .x = i => i", &line_items());
+    assert!(msg.contains("higher-order function"), "msg: {msg}");
+}
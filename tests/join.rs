@@ -0,0 +1,232 @@
+#![allow(deprecated)]
+//! Integration tests for `--lookup` tables and the `join` statement.
+
+use assert_cmd::Command;
+use indexmap::IndexMap;
+use morph::mapping::lookup::Lookups;
+use morph::mapping::{eval, parser};
+use morph::value::Value;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+fn make_map(pairs: &[(&str, Value)]) -> Value {
+    let mut m = IndexMap::new();
+    for (k, v) in pairs {
+        m.insert((*k).to_string(), v.clone());
+    }
+    Value::Map(m)
+}
+
+fn s(v: &str) -> Value {
+    Value::String(v.into())
+}
+
+fn users() -> Value {
+    Value::Array(vec![
+        make_map(&[
+            ("id", Value::Int(1)),
+            ("name", s("ada")),
+            ("tier", s("gold")),
+        ]),
+        make_map(&[
+            ("id", Value::Int(2)),
+            ("name", s("bob")),
+            ("tier", s("free")),
+        ]),
+    ])
+}
+
+fn orders() -> Value {
+    Value::Array(vec![
+        make_map(&[("order", Value::Int(10)), ("user_id", s("1"))]),
+        make_map(&[("order", Value::Int(11)), ("user_id", Value::Int(3))]),
+        make_map(&[("order", Value::Int(12)), ("user_id", Value::Int(2))]),
+    ])
+}
+
+fn run(mapping: &str, input: &Value) -> Value {
+    let program = parser::parse_str(mapping).unwrap();
+    let mut lookups = Lookups::new();
+    lookups.insert("users", users()).unwrap();
+    lookups.prepare(&program.statements).unwrap();
+    let options = eval::EvalOptions {
+        lookups,
+        ..Default::default()
+    };
+    eval::eval_with(&program, &options, input).unwrap()
+}
+
+fn morph() -> Command {
+    Command::cargo_bin("morph").unwrap()
+}
+
+fn temp_file(content: &str, suffix: &str) -> NamedTempFile {
+    let mut f = tempfile::Builder::new().suffix(suffix).tempfile().unwrap();
+    f.write_all(content.as_bytes()).unwrap();
+    f.flush().unwrap();
+    f
+}
+
+// ---------------------------------------------------------------------------
+// Batch evaluation
+// ---------------------------------------------------------------------------
+
+#[test]
+fn left_join_merges_matching_fields() {
+    let result = run("join users on .user_id = .id", &orders());
+    assert_eq!(result.get_path(".[0].name"), Some(&s("ada")));
+    assert_eq!(result.get_path(".[2].tier"), Some(&s("free")));
+    // No match: the record is kept unchanged.
+    assert_eq!(
+        result.get_path(".[1]"),
+        Some(&make_map(&[
+            ("order", Value::Int(11)),
+            ("user_id", Value::Int(3))
+        ]))
+    );
+}
+
+#[test]
+fn merge_keeps_existing_fields() {
+    let input = make_map(&[("user_id", Value::Int(1)), ("name", s("order name"))]);
+    let result = run("join users on .user_id = .id", &input);
+    assert_eq!(result.get_path(".name"), Some(&s("order name")));
+    assert_eq!(result.get_path(".tier"), Some(&s("gold")));
+}
+
+#[test]
+fn join_as_nests_match() {
+    let result = run("join users on .user_id = .id as .user", &orders());
+    assert_eq!(result.get_path(".[0].user.name"), Some(&s("ada")));
+    assert_eq!(result.get_path(".[1].user"), Some(&Value::Null));
+}
+
+#[test]
+fn inner_join_drops_unmatched() {
+    let result = run("inner join users on .user_id = .id as .user", &orders());
+    match result {
+        Value::Array(items) => {
+            let ids: Vec<_> = items
+                .iter()
+                .map(|i| i.get_path(".order").cloned())
+                .collect();
+            assert_eq!(ids, vec![Some(Value::Int(10)), Some(Value::Int(12))]);
+        }
+        other => panic!("expected array, got: {other:?}"),
+    }
+}
+
+#[test]
+fn explicit_left_join() {
+    let result = run("left join users on .user_id = .id as .user", &orders());
+    match result {
+        Value::Array(items) => assert_eq!(items.len(), 3),
+        other => panic!("expected array, got: {other:?}"),
+    }
+}
+
+#[test]
+fn null_key_never_matches() {
+    let input = make_map(&[("user_id", Value::Null)]);
+    let result = run("join users on .user_id = .id as .user", &input);
+    assert_eq!(result.get_path(".user"), Some(&Value::Null));
+}
+
+#[test]
+fn join_function_still_available() {
+    let input = make_map(&[("a", s("x")), ("b", s("y"))]);
+    let result = run("set .ab = join(.a, \"-\", .b)", &input);
+    assert_eq!(result.get_path(".ab"), Some(&s("x-y")));
+}
+
+#[test]
+fn error_unknown_table() {
+    let program = parser::parse_str("join accounts on .id = .id").unwrap();
+    let err = eval::eval(&program, &orders()).unwrap_err();
+    assert!(
+        err.to_string().contains("unknown lookup table 'accounts'"),
+        "err: {err}"
+    );
+}
+
+// ---------------------------------------------------------------------------
+// CLI
+// ---------------------------------------------------------------------------
+
+const USERS_CSV: &str = "id,name\n1,ada\n2,bob\n";
+const ORDERS_JSONL: &str =
+    "{\"order\":10,\"user_id\":1}\n{\"order\":11,\"user_id\":3}\n{\"order\":12,\"user_id\":2}\n";
+
+fn cli_join(mapping: &str, stream: bool) -> String {
+    let users = temp_file(USERS_CSV, ".csv");
+    let lookup = format!("users={}", users.path().to_str().unwrap());
+    let mut args = vec![
+        "-f", "jsonl", "-t", "jsonl", "--lookup", &lookup, "-e", mapping,
+    ];
+    if stream {
+        args.push("--stream");
+    }
+    let output = morph()
+        .args(&args)
+        .write_stdin(ORDERS_JSONL)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn cli_lookup_from_csv() {
+    let out = cli_join("join users on .user_id = .id as .user", false);
+    assert_eq!(
+        out,
+        "{\"order\":10,\"user_id\":1,\"user\":{\"id\":1,\"name\":\"ada\"}}\n\
+         {\"order\":11,\"user_id\":3,\"user\":null}\n\
+         {\"order\":12,\"user_id\":2,\"user\":{\"id\":2,\"name\":\"bob\"}}\n"
+    );
+}
+
+#[test]
+fn cli_inner_join_streaming_matches_batch() {
+    let mapping = "inner join users on .user_id = .id";
+    let batch = cli_join(mapping, false);
+    assert_eq!(
+        batch,
+        "{\"order\":10,\"user_id\":1,\"id\":1,\"name\":\"ada\"}\n\
+         {\"order\":12,\"user_id\":2,\"id\":2,\"name\":\"bob\"}\n"
+    );
+    assert_eq!(cli_join(mapping, true), batch);
+}
+
+#[test]
+fn cli_unknown_table_fails() {
+    morph()
+        .args(["-f", "json", "-t", "json", "-e", "join users on .id = .id"])
+        .write_stdin("[]")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("unknown lookup table 'users'"));
+}
+
+#[test]
+fn cli_malformed_lookup_flag_fails() {
+    morph()
+        .args([
+            "-f",
+            "json",
+            "-t",
+            "json",
+            "--lookup",
+            "users",
+            "-e",
+            "set .a = 1",
+        ])
+        .write_stdin("{}")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("expected NAME=PATH"));
+}
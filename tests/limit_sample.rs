@@ -0,0 +1,215 @@
+//! Integration tests for `limit`, `skip`, `tail` and `sample`, and their CLI flags.
+
+mod common;
+
+use common::{morph, run_value};
+use indexmap::IndexMap;
+use morph::cli::Format;
+use morph::formats::csv::CsvConfig;
+use morph::mapping::{eval, parser};
+use morph::streaming::{self, StreamWriter};
+use morph::value::Value;
+
+fn record(id: i64) -> Value {
+    let mut m = IndexMap::new();
+    m.insert("id".to_string(), Value::Int(id));
+    Value::Map(m)
+}
+
+fn records(n: i64) -> Value {
+    Value::Array((1..=n).map(record).collect())
+}
+
+fn ids(value: &Value) -> Vec<i64> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .map(|item| match item.get_path(".id") {
+                Some(Value::Int(id)) => *id,
+                other => panic!("expected int id, got: {other:?}"),
+            })
+            .collect(),
+        other => panic!("expected array, got: {other:?}"),
+    }
+}
+
+/// Stream JSONL through `mapping`, returning the record count (or error)
+/// and the output text.
+fn stream(mapping: &str, input: &str) -> (Result<usize, String>, String) {
+    let program = parser::parse_str(mapping).unwrap();
+    let mut output = Vec::new();
+    let result = {
+        let mut writer = StreamWriter::new(&mut output, Format::Jsonl, CsvConfig::default());
+        writer.begin().unwrap();
+        let result = streaming::stream_jsonl(
+            input.as_bytes(),
+            &mut writer,
+            Some((&program, &eval::EvalOptions::default())),
+        );
+        writer.end().unwrap();
+        result
+    };
+    (
+        result.map_err(|e| e.to_string()),
+        String::from_utf8(output).unwrap(),
+    )
+}
+
+const IDS_JSONL: &str = "{\"id\":1}\n{\"id\":2}\n{\"id\":3}\n{\"id\":4}\n{\"id\":5}\n";
+
+// ---------------------------------------------------------------------------
+// Batch evaluation
+// ---------------------------------------------------------------------------
+
+#[test]
+fn limit_and_head_keep_first_records() {
+    assert_eq!(ids(&run_value("limit 2", &records(5))), vec![1, 2]);
+    assert_eq!(ids(&run_value("head 10", &records(3))), vec![1, 2, 3]);
+    assert_eq!(ids(&run_value("limit 0", &records(3))), Vec::<i64>::new());
+}
+
+#[test]
+fn skip_then_limit_pages() {
+    assert_eq!(ids(&run_value("skip 2\nlimit 2", &records(10))), vec![3, 4]);
+    assert_eq!(ids(&run_value("skip 20", &records(3))), Vec::<i64>::new());
+}
+
+#[test]
+fn tail_keeps_last_records() {
+    assert_eq!(ids(&run_value("tail 2", &records(5))), vec![4, 5]);
+    assert_eq!(ids(&run_value("tail 9", &records(2))), vec![1, 2]);
+}
+
+#[test]
+fn limit_after_where_counts_matches() {
+    let result = run_value("where .id > 2\nlimit 2", &records(10));
+    assert_eq!(ids(&result), vec![3, 4]);
+}
+
+#[test]
+fn limit_on_single_record_is_noop() {
+    assert_eq!(run_value("limit 0", &record(1)), record(1));
+}
+
+#[test]
+fn sample_is_reproducible_and_ordered() {
+    let a = ids(&run_value("sample 5 seed 7", &records(100)));
+    let b = ids(&run_value("sample 5 seed 7", &records(100)));
+    assert_eq!(a, b);
+    assert_eq!(a.len(), 5);
+    assert!(a.windows(2).all(|w| w[0] < w[1]), "sample: {a:?}");
+    assert_ne!(a, ids(&run_value("sample 5 seed 8", &records(100))));
+}
+
+#[test]
+fn sample_uses_program_seed() {
+    let program = parser::parse_str("sample 3").unwrap();
+    let options = eval::EvalOptions {
+        seed: Some(7),
+        ..Default::default()
+    };
+    let a = eval::eval_with(&program, &options, &records(50)).unwrap();
+    let b = eval::eval_with(&program, &options, &records(50)).unwrap();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn sample_larger_than_input_keeps_all() {
+    assert_eq!(ids(&run_value("sample 10", &records(3))), vec![1, 2, 3]);
+}
+
+// ---------------------------------------------------------------------------
+// Streaming
+// ---------------------------------------------------------------------------
+
+#[test]
+fn streaming_limit_stops_reading_input() {
+    // The line after the limit is not valid JSON; it must never be parsed.
+    let input = "{\"id\":1}\n{\"id\":2}\nnot json\n";
+    let (count, out) = stream("limit 2", input);
+    assert_eq!(count, Ok(2));
+    assert_eq!(out, "{\"id\":1}\n{\"id\":2}\n");
+}
+
+#[test]
+fn streaming_skip_limit_and_tail_match_batch() {
+    let (_, out) = stream("skip 1\nlimit 3\ntail 2", IDS_JSONL);
+    assert_eq!(out, "{\"id\":3}\n{\"id\":4}\n");
+    assert_eq!(
+        ids(&run_value("skip 1\nlimit 3\ntail 2", &records(5))),
+        vec![3, 4]
+    );
+}
+
+#[test]
+fn streaming_sample_matches_batch() {
+    let (_, out) = stream("sample 2 seed 3", IDS_JSONL);
+    let batch = run_value("sample 2 seed 3", &records(5));
+    let expected: String = ids(&batch)
+        .iter()
+        .map(|id| format!("{{\"id\":{id}}}\n"))
+        .collect();
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn streaming_where_then_slicing_matches_batch() {
+    for mapping in [
+        "where .id > 2\nlimit 1",
+        "where .id > 2\nskip 1",
+        "where .id > 2\ntail 3",
+        "where .id > 2\nsample 2 seed 3",
+    ] {
+        let (_, out) = stream(mapping, IDS_JSONL);
+        let expected: String = ids(&run_value(mapping, &records(5)))
+            .iter()
+            .map(|id| format!("{{\"id\":{id}}}\n"))
+            .collect();
+        assert_eq!(out, expected, "mapping: {mapping}");
+        assert!(!out.contains("null"), "mapping: {mapping}");
+    }
+}
+
+// ---------------------------------------------------------------------------
+// CLI
+// ---------------------------------------------------------------------------
+
+fn cli(args: &[&str]) -> String {
+    let output = morph()
+        .args(["-f", "jsonl", "-t", "jsonl"])
+        .args(args)
+        .write_stdin(IDS_JSONL)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn cli_skip_and_limit_flags() {
+    let expected = "{\"id\":2}\n{\"id\":3}\n";
+    assert_eq!(cli(&["--skip", "1", "--limit", "2"]), expected);
+    assert_eq!(cli(&["--skip", "1", "--limit", "2", "--stream"]), expected);
+}
+
+#[test]
+fn cli_limit_applies_after_mapping() {
+    let out = cli(&["-e", "where .id > 2", "--limit", "1"]);
+    assert_eq!(out, "{\"id\":3}\n");
+}
+
+#[test]
+fn cli_tail_flag() {
+    assert_eq!(cli(&["--tail", "1"]), "{\"id\":5}\n");
+}
+
+#[test]
+fn cli_sample_with_seed_is_reproducible() {
+    let a = cli(&["--sample", "2", "--seed", "11"]);
+    assert_eq!(a.lines().count(), 2);
+    assert_eq!(a, cli(&["--sample", "2", "--seed", "11", "--stream"]));
+}
@@ -0,0 +1,320 @@
+#![allow(deprecated)]
+//! Integration tests for multiple `-i` inputs with `--merge` and `--concat`.
+
+use assert_cmd::Command;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+fn morph() -> Command {
+    Command::cargo_bin("morph").unwrap()
+}
+
+/// Create a temp file with the given content and extension.
+fn temp_file(content: &str, suffix: &str) -> NamedTempFile {
+    let mut f = tempfile::Builder::new().suffix(suffix).tempfile().unwrap();
+    f.write_all(content.as_bytes()).unwrap();
+    f.flush().unwrap();
+    f
+}
+
+fn run_ok(args: &[&str]) -> String {
+    let output = morph().args(args).output().unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+const BASE_YAML: &str = "\
+app:
+  name: shop
+  debug: true
+  db:
+    host: localhost
+    port: 5432
+servers:
+  - name: a
+    weight: 1
+  - name: b
+    weight: 1
+";
+
+const PROD_TOML: &str = "\
+[app]
+debug = false
+
+[app.db]
+host = \"db.prod\"
+
+[[servers]]
+name = \"b\"
+weight = 5
+
+[[servers]]
+name = \"c\"
+weight = 2
+";
+
+// ---------------------------------------------------------------------------
+// --merge
+// ---------------------------------------------------------------------------
+
+#[test]
+fn merge_deep_layers_mixed_formats() {
+    let base = temp_file(BASE_YAML, ".yaml");
+    let prod = temp_file(PROD_TOML, ".toml");
+    let out = run_ok(&[
+        "-i",
+        base.path().to_str().unwrap(),
+        "-i",
+        prod.path().to_str().unwrap(),
+        "--merge",
+        "-t",
+        "json",
+        "--compact",
+        "-e",
+        "select .app",
+    ]);
+    assert_eq!(
+        out.trim(),
+        r#"{"app":{"name":"shop","debug":false,"db":{"host":"db.prod","port":5432}}}"#
+    );
+}
+
+#[test]
+fn merge_arrays_replace_by_default() {
+    let base = temp_file(BASE_YAML, ".yaml");
+    let prod = temp_file(PROD_TOML, ".toml");
+    let out = run_ok(&[
+        "-i",
+        base.path().to_str().unwrap(),
+        "-i",
+        prod.path().to_str().unwrap(),
+        "--merge",
+        "deep",
+        "-t",
+        "json",
+        "--compact",
+        "-e",
+        "select .servers",
+    ]);
+    assert_eq!(
+        out.trim(),
+        r#"{"servers":[{"name":"b","weight":5},{"name":"c","weight":2}]}"#
+    );
+}
+
+#[test]
+fn merge_arrays_append() {
+    let base = temp_file(BASE_YAML, ".yaml");
+    let prod = temp_file(PROD_TOML, ".toml");
+    let out = run_ok(&[
+        "-i",
+        base.path().to_str().unwrap(),
+        "-i",
+        prod.path().to_str().unwrap(),
+        "--merge",
+        "--merge-arrays",
+        "append",
+        "-t",
+        "json",
+        "--compact",
+        "-e",
+        "set .n = len(.servers)",
+        "-e",
+        "select .n",
+    ]);
+    assert_eq!(out.trim(), r#"{"n":4}"#);
+}
+
+#[test]
+fn merge_arrays_by_key() {
+    let base = temp_file(BASE_YAML, ".yaml");
+    let prod = temp_file(PROD_TOML, ".toml");
+    let out = run_ok(&[
+        "-i",
+        base.path().to_str().unwrap(),
+        "-i",
+        prod.path().to_str().unwrap(),
+        "--merge",
+        "--merge-arrays",
+        "by-key:name",
+        "-t",
+        "json",
+        "--compact",
+        "-e",
+        "select .servers",
+    ]);
+    assert_eq!(
+        out.trim(),
+        r#"{"servers":[{"name":"a","weight":1},{"name":"b","weight":5},{"name":"c","weight":2}]}"#
+    );
+}
+
+#[test]
+fn merge_shallow_replaces_top_level_keys() {
+    let base = temp_file(BASE_YAML, ".yaml");
+    let prod = temp_file(PROD_TOML, ".toml");
+    let out = run_ok(&[
+        "-i",
+        base.path().to_str().unwrap(),
+        "-i",
+        prod.path().to_str().unwrap(),
+        "--merge",
+        "shallow",
+        "-t",
+        "json",
+        "--compact",
+        "-e",
+        "select .app",
+    ]);
+    assert_eq!(
+        out.trim(),
+        r#"{"app":{"db":{"host":"db.prod"},"debug":false}}"#
+    );
+}
+
+// ---------------------------------------------------------------------------
+// --concat
+// ---------------------------------------------------------------------------
+
+#[test]
+fn concat_appends_root_arrays_with_source() {
+    let first = temp_file(r#"[{"id":1},{"id":2}]"#, ".json");
+    let second = temp_file("id\n3\n", ".csv");
+    let first_name = first.path().to_str().unwrap();
+    let second_name = second.path().to_str().unwrap();
+    let out = run_ok(&[
+        "-i",
+        first_name,
+        "-i",
+        second_name,
+        "--concat",
+        "--with-source",
+        "-t",
+        "jsonl",
+    ]);
+    let expected = format!(
+        "{{\"id\":1,\"$source\":\"{first_name}\"}}\n\
+         {{\"id\":2,\"$source\":\"{first_name}\"}}\n\
+         {{\"id\":3,\"$source\":\"{second_name}\"}}\n"
+    );
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn concat_wraps_non_array_documents() {
+    let first = temp_file(r#"{"id":1}"#, ".json");
+    let second = temp_file(r#"[{"id":2}]"#, ".json");
+    let out = run_ok(&[
+        "-i",
+        first.path().to_str().unwrap(),
+        "-i",
+        second.path().to_str().unwrap(),
+        "--concat",
+        "-t",
+        "json",
+        "--compact",
+    ]);
+    assert_eq!(out.trim(), r#"[{"id":1},{"id":2}]"#);
+}
+
+#[test]
+fn stream_concat_reads_inputs_in_turn() {
+    let first = temp_file(r#"{"id":1}"#, ".json");
+    let second = temp_file("id\n2\n3\n", ".csv");
+    let third = temp_file("{\"id\":4}\n", ".jsonl");
+    let names = [&first, &second, &third].map(|f| f.path().to_str().unwrap().to_string());
+    let args = [
+        "-i",
+        &names[0],
+        "-i",
+        &names[1],
+        "-i",
+        &names[2],
+        "--concat",
+        "--with-source",
+        "-t",
+        "jsonl",
+    ];
+    let batch = run_ok(&args);
+    let streamed = run_ok(&[&args[..], &["--stream"]].concat());
+    assert_eq!(streamed, batch);
+    assert_eq!(streamed.lines().count(), 4);
+
+    let limited = run_ok(&[&args[..], &["--stream", "--limit", "2", "-e", "select .id"]].concat());
+    assert_eq!(limited, "{\"id\":1}\n{\"id\":2}\n");
+}
+
+// ---------------------------------------------------------------------------
+// Errors
+// ---------------------------------------------------------------------------
+
+#[test]
+fn multiple_inputs_require_mode() {
+    let first = temp_file("{}", ".json");
+    let second = temp_file("{}", ".json");
+    morph()
+        .args([
+            "-i",
+            first.path().to_str().unwrap(),
+            "-i",
+            second.path().to_str().unwrap(),
+            "-t",
+            "json",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("--merge or --concat"));
+}
+
+#[test]
+fn unknown_array_strategy_fails() {
+    let first = temp_file("{}", ".json");
+    morph()
+        .args([
+            "-i",
+            first.path().to_str().unwrap(),
+            "--merge",
+            "--merge-arrays",
+            "zip",
+            "-t",
+            "json",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("unknown array merge strategy"));
+}
+
+#[test]
+fn merge_and_concat_conflict() {
+    morph()
+        .args(["-f", "json", "-t", "json", "--merge", "--concat"])
+        .write_stdin("{}")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn stream_and_merge_conflict() {
+    let first = temp_file(r#"{"a":1}"#, ".json");
+    let second = temp_file(r#"{"b":2}"#, ".json");
+    morph()
+        .args([
+            "-i",
+            first.path().to_str().unwrap(),
+            "-i",
+            second.path().to_str().unwrap(),
+            "--merge",
+            "--stream",
+            "-t",
+            "json",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "--stream cannot be used with --merge",
+        ));
+}
@@ -0,0 +1,182 @@
+//! Integration tests for `??`, `in` / `not in`, `=~`, `cond ? a : b` and
+//! short-circuit evaluation of `and` / `or`.
+
+mod common;
+
+use common::{json, morph, run, run_err};
+use morph::value::Value;
+
+// ---------------------------------------------------------------------------
+// ??
+// ---------------------------------------------------------------------------
+
+#[test]
+fn coalesce_falls_back_on_null_only() {
+    let result = run(
+        "set .a = .missing ?? \"x\"\nset .b = .zero ?? 1\nset .c = .n ?? .missing ?? 3",
+        r#"{"zero": 0, "n": null}"#,
+    );
+    assert_eq!(
+        result,
+        json(r#"{"zero": 0, "n": null, "a": "x", "b": 0, "c": 3}"#)
+    );
+}
+
+#[test]
+fn coalesce_skips_right_side() {
+    let result = run("set .r = .v ?? 1 / 0", r#"{"v": 5}"#);
+    assert_eq!(result, json(r#"{"v": 5, "r": 5}"#));
+}
+
+// ---------------------------------------------------------------------------
+// in / not in
+// ---------------------------------------------------------------------------
+
+#[test]
+fn in_array_literal_and_path() {
+    let result = run(
+        "set .a = .s in [\"new\", \"open\"]\nset .b = .s not in .closed\nset .c = 2 in [1, 2.0]",
+        r#"{"s": "open", "closed": ["done", "open"]}"#,
+    );
+    assert_eq!(result.get_path(".a"), Some(&Value::Bool(true)));
+    assert_eq!(result.get_path(".b"), Some(&Value::Bool(false)));
+    assert_eq!(result.get_path(".c"), Some(&Value::Bool(true)));
+}
+
+#[test]
+fn in_string_map_and_null() {
+    let result = run(
+        "set .a = \"ell\" in .s\nset .b = \"k\" in .m\nset .c = 1 in .missing",
+        r#"{"s": "hello", "m": {"k": 1}}"#,
+    );
+    assert_eq!(result.get_path(".a"), Some(&Value::Bool(true)));
+    assert_eq!(result.get_path(".b"), Some(&Value::Bool(true)));
+    assert_eq!(result.get_path(".c"), Some(&Value::Bool(false)));
+}
+
+#[test]
+fn in_rejects_scalar_right_side() {
+    let err = run_err("set .a = 1 in .n", r#"{"n": 5}"#);
+    assert!(err.contains("'in' expects"), "err: {err}");
+}
+
+#[test]
+fn where_not_in_filters_records() {
+    let result = run(
+        "where .status not in [\"deleted\", \"archived\"]",
+        r#"[{"status": "ok"}, {"status": "deleted"}, {"status": "new"}]"#,
+    );
+    assert_eq!(result, json(r#"[{"status": "ok"}, {"status": "new"}]"#));
+}
+
+// ---------------------------------------------------------------------------
+// =~
+// ---------------------------------------------------------------------------
+
+#[test]
+fn regex_match_operator() {
+    let result = run(
+        "set .a = .email =~ \"@example\\\\.com$\"\nset .b = .missing =~ \".*\"\nset .c = .n =~ \"^4\"",
+        r#"{"email": "bob@example.com", "n": 42}"#,
+    );
+    assert_eq!(result.get_path(".a"), Some(&Value::Bool(true)));
+    assert_eq!(result.get_path(".b"), Some(&Value::Bool(false)));
+    assert_eq!(result.get_path(".c"), Some(&Value::Bool(true)));
+}
+
+#[test]
+fn regex_match_dynamic_pattern() {
+    let result = run(
+        "set .ok = .code =~ .pattern",
+        r#"{"code": "AB-12", "pattern": "^[A-Z]+-\\d+$"}"#,
+    );
+    assert_eq!(result.get_path(".ok"), Some(&Value::Bool(true)));
+    let err = run_err(
+        "set .ok = .code =~ .pattern",
+        r#"{"code": "x", "pattern": "("}"#,
+    );
+    assert!(err.contains("invalid regex"), "err: {err}");
+}
+
+// ---------------------------------------------------------------------------
+// cond ? a : b
+// ---------------------------------------------------------------------------
+
+#[test]
+fn conditional_selects_branch() {
+    let mapping = "set .group = .age >= 18 ? (.age >= 65 ? \"senior\" : \"adult\") : \"minor\"";
+    for (age, group) in [(10, "minor"), (30, "adult"), (70, "senior")] {
+        let result = run(mapping, &format!(r#"{{"age": {age}}}"#));
+        assert_eq!(
+            result.get_path(".group"),
+            Some(&Value::String(group.into()))
+        );
+    }
+}
+
+#[test]
+fn conditional_evaluates_only_selected_branch() {
+    let result = run("set .r = .d == 0 ? 0 : .n / .d", r#"{"n": 4, "d": 0}"#);
+    assert_eq!(result.get_path(".r"), Some(&Value::Int(0)));
+}
+
+// ---------------------------------------------------------------------------
+// Short-circuit and / or
+// ---------------------------------------------------------------------------
+
+#[test]
+fn and_or_short_circuit() {
+    let result = run(
+        "set .a = .d != 0 and .n / .d > 1\nset .b = .d == 0 or .n / .d > 1",
+        r#"{"n": 4, "d": 0}"#,
+    );
+    assert_eq!(result.get_path(".a"), Some(&Value::Bool(false)));
+    assert_eq!(result.get_path(".b"), Some(&Value::Bool(true)));
+}
+
+#[test]
+fn and_still_evaluates_right_when_needed() {
+    let err = run_err("set .a = .d == 0 and .n / .d > 1", r#"{"n": 4, "d": 0}"#);
+    assert!(err.contains("division by zero"), "err: {err}");
+}
+
+// ---------------------------------------------------------------------------
+// CLI
+// ---------------------------------------------------------------------------
+
+#[test]
+fn cli_where_in() {
+    let output = morph()
+        .args([
+            "-f",
+            "jsonl",
+            "-t",
+            "jsonl",
+            "-e",
+            "where .tag in [\"a\", \"b\"]",
+        ])
+        .write_stdin("{\"tag\":\"a\"}\n{\"tag\":\"c\"}\n{\"tag\":\"b\"}\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"tag\":\"a\"}\n{\"tag\":\"b\"}\n"
+    );
+}
+
+#[test]
+fn cli_coalesce_and_conditional() {
+    let output = morph()
+        .args(["-f", "json", "-t", "json", "-e"])
+        .arg("set .label = .name ?? \"anon\"\nset .kind = .n > 1 ? \"many\" : \"one\"")
+        .write_stdin(r#"{"name": null, "n": 3}"#)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let result = json(&String::from_utf8(output.stdout).unwrap());
+    assert_eq!(
+        result,
+        json(r#"{"name": null, "n": 3, "label": "anon", "kind": "many"}"#)
+    );
+}
@@ -0,0 +1,332 @@
+//! Integration tests for recursive descent, map wildcards, filter predicates,
+//! slices and negative indexes in paths.
+
+use indexmap::IndexMap;
+use morph::mapping::{eval, parser};
+use morph::value::Value;
+
+fn run(mapping: &str, input: &Value) -> Value {
+    let program = parser::parse_str(mapping).unwrap();
+    eval::eval(&program, input).unwrap()
+}
+
+fn make_map(pairs: &[(&str, Value)]) -> Value {
+    let mut m = IndexMap::new();
+    for (k, v) in pairs {
+        m.insert((*k).to_string(), v.clone());
+    }
+    Value::Map(m)
+}
+
+fn s(v: &str) -> Value {
+    Value::String(v.into())
+}
+
+/// A document with `password` at three different depths.
+fn secrets() -> Value {
+    make_map(&[
+        ("password", s("top")),
+        (
+            "user",
+            make_map(&[
+                ("name", s("ann")),
+                ("password", s("mid")),
+                (
+                    "sessions",
+                    Value::Array(vec![make_map(&[
+                        ("id", Value::Int(1)),
+                        ("password", s("deep")),
+                    ])]),
+                ),
+            ]),
+        ),
+    ])
+}
+
+fn order() -> Value {
+    make_map(&[
+        (
+            "items",
+            Value::Array(vec![
+                make_map(&[("sku", s("a")), ("qty", Value::Int(0))]),
+                make_map(&[("sku", s("b")), ("qty", Value::Int(2))]),
+                make_map(&[("sku", s("c")), ("qty", Value::Int(5))]),
+            ]),
+        ),
+        (
+            "totals",
+            make_map(&[("net", Value::Int(10)), ("tax", Value::Int(2))]),
+        ),
+    ])
+}
+
+// ---------------------------------------------------------------------------
+// Recursive descent
+// ---------------------------------------------------------------------------
+
+#[test]
+fn descent_collects_matches_in_document_order() {
+    let result = run("set .found = ..password", &secrets());
+    assert_eq!(
+        result.get_path(".found"),
+        Some(&Value::Array(vec![s("top"), s("mid"), s("deep")]))
+    );
+}
+
+#[test]
+fn descent_missing_key_is_null() {
+    let result = run("set .found = ..nope", &secrets());
+    assert_eq!(result.get_path(".found"), Some(&Value::Null));
+}
+
+#[test]
+fn drop_descent_scrubs_every_depth() {
+    let result = run("drop ..password", &secrets());
+    let expected = make_map(&[(
+        "user",
+        make_map(&[
+            ("name", s("ann")),
+            (
+                "sessions",
+                Value::Array(vec![make_map(&[("id", Value::Int(1))])]),
+            ),
+        ]),
+    )]);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn set_descent_only_touches_existing_keys() {
+    let result = run("set ..password = \"***\"", &secrets());
+    assert_eq!(result.get_path(".password"), Some(&s("***")));
+    assert_eq!(result.get_path(".user.password"), Some(&s("***")));
+    assert_eq!(
+        result.get_path(".user.sessions[0].password"),
+        Some(&s("***"))
+    );
+    assert_eq!(result.get_path(".user.name"), Some(&s("ann")));
+}
+
+#[test]
+fn descent_below_a_field() {
+    let result = run("set .found = .user..password", &secrets());
+    assert_eq!(
+        result.get_path(".found"),
+        Some(&Value::Array(vec![s("mid"), s("deep")]))
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Map wildcard
+// ---------------------------------------------------------------------------
+
+#[test]
+fn dot_star_reads_map_values() {
+    let result = run("set .all = .totals.*", &order());
+    assert_eq!(
+        result.get_path(".all"),
+        Some(&Value::Array(vec![Value::Int(10), Value::Int(2)]))
+    );
+}
+
+#[test]
+fn dot_star_sets_every_map_value() {
+    let result = run("set .totals.* = 0", &order());
+    assert_eq!(
+        result.get_path(".totals"),
+        Some(&make_map(&[("net", Value::Int(0)), ("tax", Value::Int(0))]))
+    );
+}
+
+#[test]
+fn drop_dot_star_clears_map() {
+    let result = run("drop .totals.*", &order());
+    assert_eq!(result.get_path(".totals"), Some(&make_map(&[])));
+}
+
+// ---------------------------------------------------------------------------
+// Filter predicates
+// ---------------------------------------------------------------------------
+
+#[test]
+fn predicate_in_expression() {
+    let result = run("set .in_stock = .items[?(.qty > 0)].sku", &order());
+    assert_eq!(
+        result.get_path(".in_stock"),
+        Some(&Value::Array(vec![s("b"), s("c")]))
+    );
+}
+
+#[test]
+fn predicate_with_no_matches_is_empty_array() {
+    let result = run("set .n = len(.items[?(.qty > 100)])", &order());
+    assert_eq!(result.get_path(".n"), Some(&Value::Int(0)));
+}
+
+#[test]
+fn set_through_predicate() {
+    let result = run("set .items[?(.qty >= 5)].bulk = true", &order());
+    assert_eq!(result.get_path(".items[0].bulk"), None);
+    assert_eq!(result.get_path(".items[1].bulk"), None);
+    assert_eq!(result.get_path(".items[2].bulk"), Some(&Value::Bool(true)));
+}
+
+#[test]
+fn drop_predicate_removes_matching_elements() {
+    let result = run("drop .items[?(.qty == 0)]", &order());
+    match result.get_path(".items") {
+        Some(Value::Array(items)) => {
+            assert_eq!(items.len(), 2);
+            assert_eq!(items[0].get_path(".sku"), Some(&s("b")));
+        }
+        other => panic!("expected array, got: {other:?}"),
+    }
+}
+
+#[test]
+fn drop_field_inside_predicate_matches() {
+    let result = run("drop .items[?(.qty == 0)].sku", &order());
+    assert_eq!(result.get_path(".items[0].sku"), None);
+    assert_eq!(result.get_path(".items[1].sku"), Some(&s("b")));
+}
+
+#[test]
+fn select_predicate() {
+    let result = run("select .items[?(.sku == \"c\")]", &order());
+    let expected = make_map(&[(
+        "items",
+        Value::Array(vec![make_map(&[("sku", s("c")), ("qty", Value::Int(5))])]),
+    )]);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn predicate_inside_lambda_sees_parameters() {
+    let input = make_map(&[
+        ("limits", Value::Array(vec![Value::Int(1), Value::Int(4)])),
+        ("items", order().get_path(".items").cloned().unwrap()),
+    ]);
+    let result = run(
+        "set .counts = map(.limits, n => len(.items[?(.qty > n)]))",
+        &input,
+    );
+    assert_eq!(
+        result.get_path(".counts"),
+        Some(&Value::Array(vec![Value::Int(2), Value::Int(1)]))
+    );
+}
+
+#[test]
+fn predicate_error_propagates() {
+    let program = parser::parse_str("set .x = .items[?(.qty / 0 > 1)]").unwrap();
+    let err = eval::eval(&program, &order()).unwrap_err();
+    assert!(err.to_string().contains("division by zero"), "err: {err}");
+}
+
+// ---------------------------------------------------------------------------
+// Slices and negative indexes
+// ---------------------------------------------------------------------------
+
+fn numbers() -> Value {
+    make_map(&[("n", Value::Array((0..6).map(Value::Int).collect()))])
+}
+
+fn ints(values: &[i64]) -> Value {
+    Value::Array(values.iter().map(|i| Value::Int(*i)).collect())
+}
+
+fn run_err(mapping: &str, input: &Value) -> String {
+    let program = parser::parse_str(mapping).unwrap();
+    eval::eval(&program, input).unwrap_err().to_string()
+}
+
+#[test]
+fn read_slices() {
+    let result = run(
+        "set .a = .n[1:3]\nset .b = .n[-2:]\nset .c = .n[::2]\nset .d = .n[::-1]",
+        &numbers(),
+    );
+    assert_eq!(result.get_path(".a"), Some(&ints(&[1, 2])));
+    assert_eq!(result.get_path(".b"), Some(&ints(&[4, 5])));
+    assert_eq!(result.get_path(".c"), Some(&ints(&[0, 2, 4])));
+    assert_eq!(result.get_path(".d"), Some(&ints(&[5, 4, 3, 2, 1, 0])));
+}
+
+#[test]
+fn slice_bounds_are_clamped() {
+    let result = run("set .a = .n[-100:2]\nset .b = .n[4:100]", &numbers());
+    assert_eq!(result.get_path(".a"), Some(&ints(&[0, 1])));
+    assert_eq!(result.get_path(".b"), Some(&ints(&[4, 5])));
+}
+
+#[test]
+fn slice_then_field() {
+    let input = make_map(&[(
+        "rows",
+        Value::Array(vec![
+            make_map(&[("id", Value::Int(1))]),
+            make_map(&[("id", Value::Int(2))]),
+            make_map(&[("id", Value::Int(3))]),
+        ]),
+    )]);
+    let result = run("set .ids = .rows[-2:].id", &input);
+    assert_eq!(result.get_path(".ids"), Some(&ints(&[2, 3])));
+}
+
+#[test]
+fn set_slice_updates_each_selected_element() {
+    let result = run("set .n[1:3] = 9", &numbers());
+    assert_eq!(result.get_path(".n"), Some(&ints(&[0, 9, 9, 3, 4, 5])));
+}
+
+#[test]
+fn drop_slice_removes_selected_elements() {
+    let result = run("drop .n[::2]", &numbers());
+    assert_eq!(result.get_path(".n"), Some(&ints(&[1, 3, 5])));
+}
+
+#[test]
+fn each_over_slice_edits_in_place() {
+    let input = make_map(&[(
+        "rows",
+        Value::Array(vec![
+            make_map(&[("id", Value::Int(1))]),
+            make_map(&[("id", Value::Int(2))]),
+            make_map(&[("id", Value::Int(3))]),
+        ]),
+    )]);
+    let result = run("each .rows[1:] { set .tail = true }", &input);
+    assert_eq!(result.get_path(".rows[0].tail"), None);
+    assert_eq!(result.get_path(".rows[1].tail"), Some(&Value::Bool(true)));
+    assert_eq!(result.get_path(".rows[2].tail"), Some(&Value::Bool(true)));
+}
+
+#[test]
+fn negative_index_set_and_drop() {
+    let result = run("set .n[-1] = 50\ndrop .n[-2]", &numbers());
+    assert_eq!(result.get_path(".n"), Some(&ints(&[0, 1, 2, 3, 50])));
+}
+
+#[test]
+fn read_out_of_range_index_is_null() {
+    let result = run("set .a = .n[10]\nset .b = .n[-10]", &numbers());
+    assert_eq!(result.get_path(".a"), Some(&Value::Null));
+    assert_eq!(result.get_path(".b"), Some(&Value::Null));
+}
+
+#[test]
+fn set_out_of_range_index_errors_instead_of_growing() {
+    let msg = run_err("set .n[6] = 1", &numbers());
+    assert!(
+        msg.contains("index 6 is out of range for array of length 6"),
+        "msg: {msg}"
+    );
+    let msg = run_err("set .n[-7] = 1", &numbers());
+    assert!(msg.contains("index -7 is out of range"), "msg: {msg}");
+}
+
+#[test]
+fn drop_out_of_range_index_errors() {
+    let msg = run_err("drop .n[9]", &numbers());
+    assert!(msg.contains("index 9 is out of range"), "msg: {msg}");
+}
@@ -0,0 +1,228 @@
+//! Integration tests for the `pivot` and `unpivot` statements in batch and
+//! streaming mode.
+
+use indexmap::IndexMap;
+use morph::cli::Format;
+use morph::formats::csv::CsvConfig;
+use morph::mapping::{eval, parser};
+use morph::streaming::{self, StreamWriter};
+use morph::value::Value;
+
+fn run(mapping: &str, input: &Value) -> Value {
+    let program = parser::parse_str(mapping).unwrap();
+    eval::eval(&program, input).unwrap()
+}
+
+fn make_map(pairs: &[(&str, Value)]) -> Value {
+    let mut m = IndexMap::new();
+    for (k, v) in pairs {
+        m.insert((*k).to_string(), v.clone());
+    }
+    Value::Map(m)
+}
+
+fn s(v: &str) -> Value {
+    Value::String(v.into())
+}
+
+fn reading(date: &str, metric: &str, value: i64) -> Value {
+    make_map(&[
+        ("date", s(date)),
+        ("metric", s(metric)),
+        ("value", Value::Int(value)),
+    ])
+}
+
+fn long_table() -> Value {
+    Value::Array(vec![
+        reading("d1", "temp", 20),
+        reading("d1", "rain", 3),
+        reading("d2", "temp", 22),
+        reading("d3", "wind", 7),
+    ])
+}
+
+fn stream(mapping: &str, input: &str) -> String {
+    let program = parser::parse_str(mapping).unwrap();
+    let mut output = Vec::new();
+    {
+        let mut writer = StreamWriter::new(&mut output, Format::Csv, CsvConfig::default());
+        writer.begin().unwrap();
+        streaming::stream_jsonl(
+            input.as_bytes(),
+            &mut writer,
+            Some((&program, &eval::EvalOptions::default())),
+        )
+        .unwrap();
+        writer.end().unwrap();
+    }
+    String::from_utf8(output).unwrap()
+}
+
+// ---------------------------------------------------------------------------
+// pivot
+// ---------------------------------------------------------------------------
+
+#[test]
+fn pivot_long_to_wide() {
+    let result = run("pivot key .metric value .value by .date", &long_table());
+    assert_eq!(
+        result,
+        Value::Array(vec![
+            make_map(&[
+                ("date", s("d1")),
+                ("temp", Value::Int(20)),
+                ("rain", Value::Int(3)),
+                ("wind", Value::Null),
+            ]),
+            make_map(&[
+                ("date", s("d2")),
+                ("temp", Value::Int(22)),
+                ("rain", Value::Null),
+                ("wind", Value::Null),
+            ]),
+            make_map(&[
+                ("date", s("d3")),
+                ("temp", Value::Null),
+                ("rain", Value::Null),
+                ("wind", Value::Int(7)),
+            ]),
+        ])
+    );
+}
+
+#[test]
+fn pivot_without_by_yields_single_row() {
+    let result = run("pivot key .metric value .value", &long_table());
+    assert_eq!(
+        result,
+        Value::Array(vec![make_map(&[
+            ("temp", Value::Int(22)),
+            ("rain", Value::Int(3)),
+            ("wind", Value::Int(7)),
+        ])])
+    );
+}
+
+#[test]
+fn pivot_skips_null_keys_and_stringifies_numbers() {
+    let input = Value::Array(vec![
+        make_map(&[
+            ("id", Value::Int(1)),
+            ("year", Value::Int(2024)),
+            ("n", Value::Int(5)),
+        ]),
+        make_map(&[
+            ("id", Value::Int(1)),
+            ("year", Value::Null),
+            ("n", Value::Int(9)),
+        ]),
+    ]);
+    let result = run("pivot key .year value .n by .id", &input);
+    assert_eq!(
+        result,
+        Value::Array(vec![make_map(&[
+            ("id", Value::Int(1)),
+            ("2024", Value::Int(5))
+        ])])
+    );
+}
+
+#[test]
+fn statements_after_pivot_see_wide_rows() {
+    let result = run(
+        "pivot key .metric value .value by .date\nwhere .temp != null",
+        &long_table(),
+    );
+    match result {
+        Value::Array(rows) => assert_eq!(rows.len(), 2),
+        other => panic!("expected array, got: {other:?}"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// unpivot
+// ---------------------------------------------------------------------------
+
+#[test]
+fn unpivot_wide_to_long() {
+    let input = Value::Array(vec![make_map(&[
+        ("region", s("north")),
+        ("jan", Value::Int(1)),
+        ("feb", Value::Int(2)),
+        ("mar", Value::Null),
+    ])]);
+    let result = run("unpivot .jan, .feb, .mar into .month, .amount", &input);
+    let row = |month: &str, amount: Value| {
+        make_map(&[
+            ("region", s("north")),
+            ("month", s(month)),
+            ("amount", amount),
+        ])
+    };
+    assert_eq!(
+        result,
+        Value::Array(vec![
+            row("jan", Value::Int(1)),
+            row("feb", Value::Int(2)),
+            row("mar", Value::Null),
+        ])
+    );
+}
+
+#[test]
+fn unpivot_skips_missing_fields() {
+    let input = make_map(&[("region", s("south")), ("feb", Value::Int(4))]);
+    let result = run("unpivot .jan, .feb into .month, .amount", &input);
+    assert_eq!(
+        result,
+        Value::Array(vec![make_map(&[
+            ("region", s("south")),
+            ("month", s("feb")),
+            ("amount", Value::Int(4)),
+        ])])
+    );
+}
+
+#[test]
+fn unpivot_then_pivot_round_trips() {
+    let wide = Value::Array(vec![
+        make_map(&[
+            ("id", Value::Int(1)),
+            ("a", Value::Int(10)),
+            ("b", Value::Int(20)),
+        ]),
+        make_map(&[
+            ("id", Value::Int(2)),
+            ("a", Value::Int(30)),
+            ("b", Value::Int(40)),
+        ]),
+    ]);
+    let result = run(
+        "unpivot .a, .b into .k, .v\npivot key .k value .v by .id",
+        &wide,
+    );
+    assert_eq!(result, wide);
+}
+
+// ---------------------------------------------------------------------------
+// Streaming
+// ---------------------------------------------------------------------------
+
+#[test]
+fn streaming_pivot_writes_rectangular_csv() {
+    let input = "\
+{\"date\":\"d1\",\"metric\":\"temp\",\"value\":20}
+{\"date\":\"d1\",\"metric\":\"rain\",\"value\":3}
+{\"date\":\"d2\",\"metric\":\"wind\",\"value\":7}
+";
+    let out = stream("pivot key .metric value .value by .date", input);
+    assert_eq!(out, "date,temp,rain,wind\nd1,20,3,\nd2,,,7\n");
+}
+
+#[test]
+fn streaming_unpivot_runs_per_record() {
+    let input = "{\"id\":1,\"q1\":5,\"q2\":6}\n{\"id\":2,\"q1\":7}\n";
+    let out = stream("unpivot .q1, .q2 into .quarter, .sales", input);
+    assert_eq!(out, "id,quarter,sales\n1,q1,5\n1,q2,6\n2,q1,7\n");
+}
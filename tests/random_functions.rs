@@ -0,0 +1,98 @@
+#![allow(deprecated)]
+//! Integration tests for the random functions: `--seed` reproducibility,
+//! injecting a generator, and masking records with `fake`.
+
+use assert_cmd::Command;
+use morph::mapping::random::{self, Rng};
+use morph::mapping::{eval, parser};
+use morph::value::Value;
+
+fn morph() -> Command {
+    Command::cargo_bin("morph").unwrap()
+}
+
+fn cli(args: &[&str], input: &str) -> String {
+    let output = morph()
+        .args(args)
+        .write_stdin(input.to_string())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+const MASK: &str =
+    "set .name = fake(\"name\")\nset .email = fake(\"email\")\nset .pin = random_int(1000, 9999)";
+
+const USERS: &str = "{\"id\":1,\"name\":\"Real Person\",\"email\":\"real@corp.test\"}\n\
+                     {\"id\":2,\"name\":\"Other Person\",\"email\":\"other@corp.test\"}\n";
+
+#[test]
+fn seeded_stream_runs_are_reproducible() {
+    let args = [
+        "-f", "jsonl", "-t", "jsonl", "--stream", "--seed", "42", "-e", MASK,
+    ];
+    let first = cli(&args, USERS);
+    assert_eq!(cli(&args, USERS), first);
+    assert!(!first.contains("corp.test"), "not masked: {first}");
+    assert_eq!(first.lines().count(), 2);
+    // Each record draws new values from the same generator.
+    let lines: Vec<&str> = first.lines().collect();
+    assert_ne!(
+        lines[0].replace("\"id\":1", ""),
+        lines[1].replace("\"id\":2", "")
+    );
+
+    let other_seed = [
+        "-f", "jsonl", "-t", "jsonl", "--stream", "--seed", "43", "-e", MASK,
+    ];
+    assert_ne!(cli(&other_seed, USERS), first);
+}
+
+#[test]
+fn seeded_batch_runs_are_reproducible() {
+    let args = [
+        "-f",
+        "json",
+        "-t",
+        "json",
+        "--seed",
+        "7",
+        "-e",
+        "set .order = shuffle(range(10))",
+    ];
+    let first = cli(&args, "{}");
+    assert_eq!(cli(&args, "{}"), first);
+}
+
+#[test]
+fn injected_generator_drives_eval() {
+    let program = parser::parse_str("set .roll = random_int(1, 1000000)").unwrap();
+    let input = Value::Map(Default::default());
+    let roll = |seed| {
+        random::with_rng(&mut Rng::new(seed), || {
+            eval::eval(&program, &input).unwrap()
+        })
+    };
+    assert_eq!(roll(1), roll(1));
+    assert_ne!(roll(1), roll(2));
+}
+
+#[test]
+fn uuid_v4_ignores_the_seed() {
+    let args = [
+        "-f",
+        "json",
+        "-t",
+        "json",
+        "--seed",
+        "42",
+        "-e",
+        "set .id = uuid_v4()",
+    ];
+    assert_ne!(cli(&args, "{}"), cli(&args, "{}"));
+}
@@ -0,0 +1,150 @@
+#![allow(deprecated)]
+//! Integration tests for `rename keys` and the case-conversion functions.
+
+use assert_cmd::Command;
+use morph::mapping::{eval, parser};
+use morph::value::Value;
+
+fn json(text: &str) -> Value {
+    morph::formats::json::from_str(text).unwrap()
+}
+
+fn run(mapping: &str, input: &str) -> Value {
+    let program = parser::parse_str(mapping).unwrap();
+    eval::eval(&program, &json(input)).unwrap()
+}
+
+fn morph() -> Command {
+    Command::cargo_bin("morph").unwrap()
+}
+
+const PAYLOAD: &str = r#"{
+    "userId": 1,
+    "homeAddress": {"streetName": "Main", "zipCode": "123"},
+    "recentOrders": [{"orderId": 7, "lineItems": [{"skuCode": "A"}]}]
+}"#;
+
+#[test]
+fn recursive_snake_case() {
+    let result = run("rename keys recursive using snake_case", PAYLOAD);
+    assert_eq!(
+        result,
+        json(
+            r#"{
+                "user_id": 1,
+                "home_address": {"street_name": "Main", "zip_code": "123"},
+                "recent_orders": [{"order_id": 7, "line_items": [{"sku_code": "A"}]}]
+            }"#
+        )
+    );
+}
+
+#[test]
+fn non_recursive_renames_top_level_only() {
+    let result = run("rename keys using kebab_case", PAYLOAD);
+    assert_eq!(
+        result.get_path(".home-address.streetName"),
+        Some(&Value::String("Main".into()))
+    );
+}
+
+#[test]
+fn rename_keys_at_path() {
+    let result = run(
+        "rename keys .homeAddress using screaming_snake_case",
+        PAYLOAD,
+    );
+    assert_eq!(
+        result.get_path(".homeAddress.STREET_NAME"),
+        Some(&Value::String("Main".into()))
+    );
+    assert!(result.get_path(".userId").is_some());
+}
+
+#[test]
+fn root_array_renames_each_record() {
+    let result = run(
+        "rename keys using pascal_case",
+        r#"[{"first_name": "a"}, {"first_name": "b"}]"#,
+    );
+    assert_eq!(result, json(r#"[{"FirstName": "a"}, {"FirstName": "b"}]"#));
+}
+
+#[test]
+fn regex_rename_with_captures() {
+    let result = run(
+        // `{` starts string interpolation, so `${rest}` is written `$\{rest}`.
+        r#"rename keys recursive matching "^x_(?P<rest>.*)$" -> "$\{rest}_ext""#,
+        r#"{"x_id": 1, "name": "n", "inner": {"x_tag": true}}"#,
+    );
+    assert_eq!(
+        result,
+        json(r#"{"id_ext": 1, "name": "n", "inner": {"tag_ext": true}}"#)
+    );
+}
+
+#[test]
+fn regex_rename_with_numbered_capture() {
+    let result = run(
+        r#"rename keys matching "^(.*)_at$" -> "when_$1""#,
+        r#"{"created_at": 1, "id": 2}"#,
+    );
+    assert_eq!(result, json(r#"{"when_created": 1, "id": 2}"#));
+}
+
+#[test]
+fn error_when_two_keys_collide() {
+    let program = parser::parse_str("rename keys using snake_case").unwrap();
+    let err = eval::eval(&program, &json(r#"{"fooBar": 1, "foo_bar": 2}"#)).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("'fooBar' and 'foo_bar' both become 'foo_bar'"),
+        "err: {err}"
+    );
+}
+
+#[test]
+fn rename_keys_inside_each() {
+    let result = run(
+        "each .items { rename keys using camel_case }",
+        r#"{"items": [{"item_id": 1}], "other_key": 2}"#,
+    );
+    assert_eq!(
+        result,
+        json(r#"{"items": [{"itemId": 1}], "other_key": 2}"#)
+    );
+}
+
+#[test]
+fn case_functions_on_values() {
+    let result = run(
+        "set .a = snake_case(.name)\nset .b = camel_case(.name)",
+        r#"{"name": "Hello World"}"#,
+    );
+    assert_eq!(
+        result.get_path(".a"),
+        Some(&Value::String("hello_world".into()))
+    );
+    assert_eq!(
+        result.get_path(".b"),
+        Some(&Value::String("helloWorld".into()))
+    );
+}
+
+#[test]
+fn cli_yaml_to_toml_with_snake_case_keys() {
+    morph()
+        .args([
+            "-f",
+            "json",
+            "-t",
+            "yaml",
+            "-e",
+            "rename keys recursive using snake_case",
+        ])
+        .write_stdin(r#"{"serverConfig": {"maxConnections": 10}}"#)
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("server_config:"))
+        .stdout(predicates::str::contains("max_connections: 10"));
+}
@@ -292,3 +292,57 @@ fn chained_replace_upper() {
         Some(&Value::String("HELLO RUST".into()))
     );
 }
+
+// ---------------------------------------------------------------------------
+// Text formatting
+// ---------------------------------------------------------------------------
+
+#[test]
+fn fixed_width_report_columns() {
+    let input = make_map(&[
+        ("name", Value::String("Zoë Ångström-Lindqvist".into())),
+        ("amount", Value::Float(1234.5)),
+    ]);
+    let result = run(
+        r##"set .line = join(pad_right(truncate(.name, 12, "…"), 14), pad_left(format_number(.amount, "#,##0.00"), 10))"##,
+        &input,
+    );
+    assert_eq!(
+        result.get_path(".line"),
+        Some(&Value::String("Zoë Ångströ…    1,234.50".into()))
+    );
+}
+
+#[test]
+fn parse_and_reformat_german_amounts() {
+    let input = make_map(&[("betrag", Value::String("1.234,56".into()))]);
+    let result = run(
+        r##"set .amount = parse_number(.betrag, "de")
+set .label = format_number(.amount, "#,##0.0", "en")"##,
+        &input,
+    );
+    assert_eq!(result.get_path(".amount"), Some(&Value::Float(1234.56)));
+    assert_eq!(
+        result.get_path(".label"),
+        Some(&Value::String("1,234.6".into()))
+    );
+}
+
+#[test]
+fn index_functions_count_combining_marks_with_their_base() {
+    let input = make_map(&[("s", Value::String("e\u{301}e\u{301}x".into()))]);
+    let result = run(
+        "set .all = substring(.s, 0, len(.s))\nset .tail = slice(.s, -2)\nset .at = index_of(.s, \"x\")\nset .mark = index_of(.s, \"\u{301}\")",
+        &input,
+    );
+    assert_eq!(
+        result.get_path(".all"),
+        Some(&Value::String("e\u{301}e\u{301}x".into()))
+    );
+    assert_eq!(
+        result.get_path(".tail"),
+        Some(&Value::String("e\u{301}x".into()))
+    );
+    assert_eq!(result.get_path(".at"), Some(&Value::Int(2)));
+    assert_eq!(result.get_path(".mark"), Some(&Value::Null));
+}
@@ -0,0 +1,103 @@
+#![allow(deprecated)]
+//! Integration tests for the canonical `Value` ordering: sorting and
+//! deduplicating columns that mix types.
+
+use morph::mapping::{eval, parser};
+use morph::value::Value;
+
+fn json(text: &str) -> Value {
+    morph::formats::json::from_str(text).unwrap()
+}
+
+fn run(mapping: &str, input: &str) -> Value {
+    let program = parser::parse_str(mapping).unwrap();
+    eval::eval(&program, &json(input)).unwrap()
+}
+
+fn to_json(value: &Value) -> String {
+    morph::formats::json::to_string(value).unwrap()
+}
+
+#[test]
+fn sort_mixed_column_by_type_then_value() {
+    let result = run(
+        "sort .v",
+        r#"[{"v": "b"}, {"v": null}, {"v": 2}, {"v": true}, {"v": "a"}, {"v": 1.5}, {"v": [1]}]"#,
+    );
+    assert_eq!(
+        to_json(&result),
+        r#"[{"v":true},{"v":1.5},{"v":2},{"v":"a"},{"v":"b"},{"v":[1]},{"v":null}]"#
+    );
+}
+
+#[test]
+fn sort_mixed_column_descending_keeps_nulls_last() {
+    let result = run(
+        "sort .v desc",
+        r#"[{"v": 1}, {"v": null}, {"v": "x"}, {}, {"v": false}]"#,
+    );
+    assert_eq!(
+        to_json(&result),
+        r#"[{"v":"x"},{"v":1},{"v":false},{"v":null},{}]"#
+    );
+}
+
+#[test]
+fn sort_by_orders_mixed_arrays() {
+    let result = run(
+        "set .s = sort_by(.xs, x => x)",
+        r#"{"xs": ["b", 3, null, 1, "a"]}"#,
+    );
+    assert_eq!(
+        to_json(result.get_path(".s").unwrap()),
+        r#"[1,3,"a","b",null]"#
+    );
+}
+
+#[test]
+fn unique_treats_types_and_key_order_canonically() {
+    let result = run(
+        "set .u = unique(.xs)",
+        r#"{"xs": [1, "1", 1, {"a": 1, "b": 2}, {"b": 2, "a": 1}, 1.0]}"#,
+    );
+    assert_eq!(
+        to_json(result.get_path(".u").unwrap()),
+        r#"[1,"1",{"a":1,"b":2},1.0]"#
+    );
+}
+
+#[test]
+fn min_and_max_over_mixed_types() {
+    let result = run(
+        "set .lo = min(3, \"a\", 2.5)\nset .hi = max(3, \"a\", 2.5)",
+        "{}",
+    );
+    assert_eq!(to_json(&result), r#"{"lo":2.5,"hi":"a"}"#);
+}
+
+#[test]
+fn aggregate_min_and_max_over_mixed_types() {
+    let result = run(
+        "aggregate {\n  set .lo = min(.a)\n  set .hi = max(.a)\n}",
+        r#"[{"a": 1}, {"a": "x"}, {"a": 2.5}, {"a": null}]"#,
+    );
+    assert_eq!(to_json(&result), r#"[{"lo":1,"hi":"x"}]"#);
+}
+
+#[test]
+fn mixed_type_comparisons_follow_the_total_order() {
+    let result = run(
+        "set .lt = 1 < \"a\"\nset .gt = [1] > \"a\"\nset .n = null < 1",
+        "{}",
+    );
+    assert_eq!(to_json(&result), r#"{"lt":true,"gt":true,"n":false}"#);
+}
+
+#[test]
+fn min_and_max_skip_nulls_like_aggregates() {
+    let result = run(
+        "set .lo = min(3, .missing)\nset .hi = max(null, 1, \"a\")\nset .none = min(null, .missing)",
+        "{}",
+    );
+    assert_eq!(to_json(&result), r#"{"lo":3,"hi":"a","none":null}"#);
+}
@@ -0,0 +1,288 @@
+//! Integration tests for window functions in `set` statements.
+
+mod common;
+
+use common::{morph, run_value};
+use indexmap::IndexMap;
+use morph::mapping::{eval, parser};
+use morph::value::Value;
+
+fn make_map(pairs: &[(&str, Value)]) -> Value {
+    let mut m = IndexMap::new();
+    for (k, v) in pairs {
+        m.insert((*k).to_string(), v.clone());
+    }
+    Value::Map(m)
+}
+
+fn s(v: &str) -> Value {
+    Value::String(v.into())
+}
+
+fn entry(account: &str, date: &str, amount: i64) -> Value {
+    make_map(&[
+        ("account", s(account)),
+        ("date", s(date)),
+        ("amount", Value::Int(amount)),
+    ])
+}
+
+/// Entries deliberately out of date order, across two accounts.
+fn ledger() -> Value {
+    Value::Array(vec![
+        entry("a", "2024-01-03", 30),
+        entry("b", "2024-01-01", 5),
+        entry("a", "2024-01-01", 10),
+        entry("a", "2024-01-02", 20),
+        entry("b", "2024-01-02", 7),
+    ])
+}
+
+/// The value at `path` in each record of an array.
+fn column(value: &Value, path: &str) -> Vec<Value> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .map(|item| item.get_path(path).cloned().unwrap_or(Value::Null))
+            .collect(),
+        other => panic!("expected array, got: {other:?}"),
+    }
+}
+
+fn ints(values: &[i64]) -> Vec<Value> {
+    values.iter().map(|v| Value::Int(*v)).collect()
+}
+
+// ---------------------------------------------------------------------------
+// Running aggregates
+// ---------------------------------------------------------------------------
+
+#[test]
+fn running_sum_by_partition_and_order() {
+    let result = run_value(
+        "set .cum = running_sum(.amount) partition by .account order by .date",
+        &ledger(),
+    );
+    // Records keep their input order.
+    assert_eq!(column(&result, ".cum"), ints(&[60, 5, 10, 30, 12]));
+}
+
+#[test]
+fn running_sum_without_clauses_uses_input_order() {
+    let result = run_value("set .cum = running_sum(.amount)", &ledger());
+    assert_eq!(column(&result, ".cum"), ints(&[30, 35, 45, 65, 72]));
+}
+
+#[test]
+fn running_count_avg_min_max() {
+    let mapping = "set .n = running_count() order by .date\n\
+                   set .avg = running_avg(.amount) partition by .account order by .date\n\
+                   set .low = running_min(.amount) order by .amount desc\n\
+                   set .high = running_max(.amount)";
+    let result = run_value(mapping, &ledger());
+    // Ties on .date keep input order: b-01 then a-01.
+    assert_eq!(column(&result, ".n"), ints(&[5, 1, 2, 3, 4]));
+    assert_eq!(
+        column(&result, ".avg"),
+        vec![
+            Value::Float(20.0),
+            Value::Float(5.0),
+            Value::Float(10.0),
+            Value::Float(15.0),
+            Value::Float(6.0),
+        ]
+    );
+    assert_eq!(column(&result, ".low"), ints(&[30, 5, 10, 20, 7]));
+    assert_eq!(column(&result, ".high"), ints(&[30, 30, 30, 30, 30]));
+}
+
+#[test]
+fn running_sum_skips_nulls() {
+    let input = Value::Array(vec![
+        make_map(&[("v", Value::Int(1))]),
+        make_map(&[("v", Value::Null)]),
+        make_map(&[("v", Value::Int(2))]),
+    ]);
+    let result = run_value("set .cum = running_sum(.v)", &input);
+    assert_eq!(column(&result, ".cum"), ints(&[1, 1, 3]));
+}
+
+// ---------------------------------------------------------------------------
+// lag / lead
+// ---------------------------------------------------------------------------
+
+#[test]
+fn lag_computes_deltas() {
+    let result = run_value(
+        "set .delta = .amount - lag(.amount, 1, .amount) partition by .account order by .date",
+        &ledger(),
+    );
+    assert_eq!(column(&result, ".delta"), ints(&[10, 0, 0, 10, 2]));
+}
+
+#[test]
+fn lag_and_lead_default_to_null() {
+    let result = run_value(
+        "set .prev = lag(.date) order by .date\nset .next = lead(.amount, 2) order by .date",
+        &ledger(),
+    );
+    assert_eq!(
+        column(&result, ".prev"),
+        vec![
+            s("2024-01-02"),
+            Value::Null,
+            s("2024-01-01"),
+            s("2024-01-01"),
+            s("2024-01-02"),
+        ]
+    );
+    assert_eq!(
+        column(&result, ".next"),
+        vec![
+            Value::Null,
+            Value::Int(20),
+            Value::Int(7),
+            Value::Int(30),
+            Value::Null
+        ]
+    );
+}
+
+#[test]
+fn lag_sees_values_from_before_the_statement() {
+    let input = Value::Array(vec![
+        make_map(&[("v", Value::Int(1))]),
+        make_map(&[("v", Value::Int(2))]),
+    ]);
+    let result = run_value("set .v = lag(.v, 1, 0)", &input);
+    assert_eq!(column(&result, ".v"), ints(&[0, 1]));
+}
+
+#[test]
+fn error_negative_offset() {
+    let program = parser::parse_str("set .p = lag(.amount, -1)").unwrap();
+    let err = eval::eval(&program, &ledger()).unwrap_err();
+    assert!(
+        err.to_string().contains("non-negative integer"),
+        "err: {err}"
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Numbering
+// ---------------------------------------------------------------------------
+
+#[test]
+fn row_number_per_partition() {
+    let result = run_value(
+        "set .n = row_number() partition by .account order by .date",
+        &ledger(),
+    );
+    assert_eq!(column(&result, ".n"), ints(&[3, 1, 1, 2, 2]));
+}
+
+#[test]
+fn rank_and_dense_rank_share_ties() {
+    let input = Value::Array(
+        [90, 80, 90, 70]
+            .iter()
+            .map(|score| make_map(&[("score", Value::Int(*score))]))
+            .collect(),
+    );
+    let result = run_value(
+        "set .rank = rank() order by .score desc\nset .dense = dense_rank() order by .score desc",
+        &input,
+    );
+    assert_eq!(column(&result, ".rank"), ints(&[1, 3, 1, 4]));
+    assert_eq!(column(&result, ".dense"), ints(&[1, 2, 1, 3]));
+}
+
+#[test]
+fn window_on_single_record() {
+    let result = run_value("set .n = row_number()", &entry("a", "2024-01-01", 1));
+    assert_eq!(result.get_path(".n"), Some(&Value::Int(1)));
+}
+
+#[test]
+fn error_window_function_outside_set() {
+    let program = parser::parse_str("where row_number() < 3").unwrap();
+    let err = eval::eval(&program, &ledger()).unwrap_err();
+    assert!(
+        err.to_string().contains("only be used in a top-level set"),
+        "err: {err}"
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Streaming and CLI
+// ---------------------------------------------------------------------------
+
+const LEDGER_JSONL: &str = "\
+{\"account\":\"a\",\"amount\":30,\"date\":\"2024-01-03\"}
+{\"account\":\"b\",\"amount\":5,\"date\":\"2024-01-01\"}
+{\"account\":\"a\",\"amount\":10,\"date\":\"2024-01-01\"}
+";
+
+fn cli(mapping: &str, stream: bool) -> String {
+    let mut args = vec!["-f", "jsonl", "-t", "jsonl", "-e", mapping];
+    if stream {
+        args.push("--stream");
+    }
+    let output = morph()
+        .args(&args)
+        .write_stdin(LEDGER_JSONL)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn cli_streaming_matches_batch() {
+    let mapping = "set .cum = running_sum(.amount) partition by .account order by .date";
+    let batch = cli(mapping, false);
+    assert_eq!(
+        batch,
+        "{\"account\":\"a\",\"amount\":30,\"date\":\"2024-01-03\",\"cum\":40}\n\
+         {\"account\":\"b\",\"amount\":5,\"date\":\"2024-01-01\",\"cum\":5}\n\
+         {\"account\":\"a\",\"amount\":10,\"date\":\"2024-01-01\",\"cum\":10}\n"
+    );
+    assert_eq!(cli(mapping, true), batch);
+}
+
+#[test]
+fn cli_streaming_where_before_window() {
+    let mapping = "where .amount > 5\nset .n = row_number()";
+    let batch = cli(mapping, false);
+    assert_eq!(
+        batch,
+        "{\"account\":\"a\",\"amount\":30,\"date\":\"2024-01-03\",\"n\":1}\n\
+         {\"account\":\"a\",\"amount\":10,\"date\":\"2024-01-01\",\"n\":2}\n"
+    );
+    assert_eq!(cli(mapping, true), batch);
+}
+
+#[test]
+fn window_leaves_non_record_values_alone() {
+    let result = run_value(
+        "set .n = row_number()",
+        &Value::Array(vec![s("x"), entry("a", "2024-01-01", 1), Value::Null]),
+    );
+    assert_eq!(
+        result,
+        Value::Array(vec![
+            s("x"),
+            make_map(&[
+                ("account", s("a")),
+                ("date", s("2024-01-01")),
+                ("amount", Value::Int(1)),
+                ("n", Value::Int(1)),
+            ]),
+            Value::Null,
+        ])
+    );
+}